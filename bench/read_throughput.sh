@@ -0,0 +1,71 @@
+#!/bin/sh
+
+# Measures how fast a large value can be read back out of a mount: we make a
+# JSON document holding a single SIZE_MB string, mount it, and time reading
+# that file NUM_RUNS times.
+
+SIZE_MB_DEFAULT=100
+NUM_RUNS_DEFAULT=10
+usage() {
+    exec >&2
+    printf "Usage: %s [-s SIZE_MB] [-n NUM_RUNS]\n\n" "$(basename $0)"
+    printf "       -s SIZE_MB     the size of the value, in megabytes (defaults to $SIZE_MB_DEFAULT)\n"
+    printf "       -n NUM_RUNS    the number of reads (defaults to $NUM_RUNS_DEFAULT)\n"
+    exit 2
+}
+
+SIZE_MB=$SIZE_MB_DEFAULT
+NUM_RUNS=$NUM_RUNS_DEFAULT
+while getopts ":s:n:h" opt
+do
+    case "$opt" in
+        (s) SIZE_MB=$OPTARG;;
+        (n) NUM_RUNS=$OPTARG;;
+        (h) usage;;
+        (*) printf "Unrecognized argument '%s'\n\n" "$OPTARG"
+            usage
+            ;;
+    esac
+done
+
+if ! which ffs >/dev/null 2>&1
+then
+    echo "Couldn't find ffs on PATH." >&2
+    exit 3
+fi
+
+MNT=$(mktemp -d)
+DOC=$(mktemp)
+cleanup() {
+    umount "$MNT" >/dev/null 2>&1
+    rmdir "$MNT" >/dev/null 2>&1
+    rm "$DOC" >/dev/null 2>&1
+}
+trap 'cleanup' EXIT
+trap 'echo "Interrupted!"; cleanup; exit' INT
+
+printf '{"big":"' >"$DOC"
+head -c $((SIZE_MB * 1024 * 1024)) /dev/zero | tr '\0' x >>"$DOC"
+printf '"}' >>"$DOC"
+
+ffs --no-output -m "$MNT" "$DOC" &
+PID=$!
+sleep 2
+[ -f "$MNT"/big ] || { echo "Couldn't mount $DOC." >&2; exit 1; }
+
+START=$(date +%s.%N)
+i=0
+while [ $i -lt $NUM_RUNS ]
+do
+    cat "$MNT"/big >/dev/null
+    i=$((i + 1))
+done
+END=$(date +%s.%N)
+
+umount "$MNT"
+wait $PID
+
+echo "$SIZE_MB $NUM_RUNS $START $END" | awk '{
+    secs = $4 - $3;
+    printf "read %d MB %d times in %.3fs: %.1f MB/s\n", $1, $2, secs, ($1 * $2) / secs
+}'
@@ -0,0 +1,26 @@
+# An example Terraform configuration
+region = "us-east-1"
+replicas = 3
+
+tags = {
+  Name = "web"
+  Team = "infra"
+}
+
+resource "aws_instance" "web" {
+  ami = "ami-0123456789"
+  instance_type = "t2.micro"
+
+  ingress {
+    port = 80
+  }
+
+  ingress {
+    port = 443
+  }
+}
+
+resource "aws_instance" "db" {
+  ami = "ami-9876543210"
+  instance_type = "t2.large"
+}
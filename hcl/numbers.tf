@@ -0,0 +1,10 @@
+small    = 1e3
+fraction = 2.5e-3
+big      = 1e20
+negative = -1e20
+wide     = 12345678901234567890
+
+limits = {
+  x = 1e20
+  y = 12345678901234567890
+}
@@ -0,0 +1,6 @@
+
+Michael Greenbergmgree@example.commichael@example.org"
+json"
+ffs
+(2
+Hoboken07030
\ No newline at end of file
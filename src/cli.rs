@@ -1,11 +1,63 @@
 use clap::{App, Arg};
 
 /// The possible formats.
-pub const POSSIBLE_FORMATS: &[&str] = &["json", "toml", "yaml"];
+pub const POSSIBLE_FORMATS: &[&str] = &[
+    "auto",
+    "bson",
+    "edn",
+    "env",
+    "hcl",
+    "ini",
+    "json",
+    "json5",
+    "ndjson",
+    "plist",
+    "properties",
+    "protobuf",
+    "ron",
+    "toml",
+    "yaml",
+];
 
 /// The possible name munging policies.
 pub const MUNGE_POLICIES: &[&str] = &["filter", "rename"];
 
+/// The possible ways of handling keys with '/' in them.
+pub const SLASH_POLICIES: &[&str] = &["escape", "reject", "split"];
+
+/// The possible formats for logging.
+pub const LOG_FORMATS: &[&str] = &["json", "text"];
+
+/// The possible indent characters.
+pub const INDENT_CHARS: &[&str] = &["space", "tab"];
+
+/// The possible text encodings.
+pub const ENCODINGS: &[&str] = &["utf-8", "utf-8-bom", "utf-16le", "utf-16be"];
+
+/// The possible ways of loading YAML aliases.
+pub const YAML_ALIASES: &[&str] = &["expand", "link"];
+
+/// The possible ways of mounting nulls.
+pub const NULL_AS: &[&str] = &["empty_file", "missing", "symlink"];
+
+/// The possible ways of mounting an empty input.
+pub const EMPTY_AS: &[&str] = &["error", "empty_object", "empty_array"];
+
+/// The possible ways of ending the output.
+pub const FINAL_NEWLINE: &[&str] = &["auto", "always", "never"];
+
+/// The possible ways renaming one list element onto another reorders a list.
+pub const LIST_RENAME: &[&str] = &["shift", "swap"];
+
+/// The possible kinds of directory `mkdir` makes.
+pub const DIR_TYPES: &[&str] = &["named", "list"];
+
+/// The possible ways of merging lists under `--merge`.
+pub const MERGE_ARRAYS: &[&str] = &["replace", "concat"];
+
+/// The possible policies for conflicts under `--watch`.
+pub const WATCH_CONFLICT: &[&str] = &["file", "mount"];
+
 pub fn app() -> App<'static, 'static> {
     App::new("ffs")
         .version(env!("CARGO_PKG_VERSION"))
@@ -24,6 +76,21 @@ pub fn app() -> App<'static, 'static> {
                 .long("quiet")
                 .short("q")
                 .overrides_with("DEBUG")
+                .overrides_with("VERBOSE")
+        )
+        .arg(
+            Arg::with_name("VERBOSE")
+                .help("Gives more output on stderr: -v for progress, -vv for debugging, -vvv for tracing")
+                .short("v")
+                .multiple(true)
+        )
+        .arg(
+            Arg::with_name("LOG_FORMAT")
+                .help("Sets the format of output on stderr")
+                .long("log-format")
+                .takes_value(true)
+                .default_value("text")
+                .possible_values(LOG_FORMATS)
         )
         .arg(
             Arg::with_name("TIMING")
@@ -55,6 +122,24 @@ pub fn app() -> App<'static, 'static> {
                 .long("gid")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("ALLOW_OTHER")
+                .help("Lets any user use the mount, not just the owner (requires user_allow_other in /etc/fuse.conf)")
+                .long("allow-other")
+        )
+        .arg(
+            Arg::with_name("ALLOW_UID")
+                .help("Lets the given user use the mount, too (implies --allow-other, but only listed users are allowed; may be repeated)")
+                .long("allow-uid")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("PRIVATE")
+                .help("Refuses reads by everyone but the owner, root, and users allowed by --allow-uid")
+                .long("private")
+        )
         .arg(
             Arg::with_name("FILEMODE")
                 .help("Sets the default mode of files (parsed as octal)")
@@ -74,6 +159,20 @@ pub fn app() -> App<'static, 'static> {
                 .help("Don't add newlines to the end of values that don't already have them (or strip them when loading)")
                 .long("exact")
         )
+        .arg(
+            Arg::with_name("FINAL_NEWLINE")
+                .help("Sets whether text output ends in a newline: 'auto' matches the input file; 'always' and 'never' override it")
+                .long("final-newline")
+                .value_name("MODE")
+                .takes_value(true)
+                .default_value("auto")
+                .possible_values(FINAL_NEWLINE)
+        )
+        .arg(
+            Arg::with_name("TRIM")
+                .help("Strips leading and trailing whitespace from files when saving, so ' 42 ' is saved as the number 42")
+                .long("trim-whitespace")
+        )
         .arg(
             Arg::with_name("NOXATTR")
                 .help("Don't use extended attributes to track metadata (see `man xattr`)")
@@ -92,19 +191,198 @@ pub fn app() -> App<'static, 'static> {
                 .default_value("rename")
                 .possible_values(MUNGE_POLICIES)
         )
+        .arg(
+            Arg::with_name("CASE_INSENSITIVE")
+                .help("Looks up names ignoring case (e.g., 'NAME' finds 'name'); names that differ only in case get underscores appended")
+                .long("case-insensitive")
+        )
+        .arg(
+            Arg::with_name("SLASH_POLICY")
+                .help("Set how to handle keys with '/' in them: escape them per --munge, refuse to mount, or split them into nested directories (e.g., 'a/b' becomes the file 'b' in the directory 'a')")
+                .long("slash-policy")
+                .takes_value(true)
+                .default_value("escape")
+                .possible_values(SLASH_POLICIES)
+        )
+        .arg(
+            Arg::with_name("SLASH_ESCAPE")
+                .help("Sets what replaces each '/' in a key under '--slash-policy escape' and '--munge rename' (e.g., '%2F')")
+                .long("slash-escape")
+                .value_name("TEXT")
+                .takes_value(true)
+                .default_value("_SLASH_")
+        )
+        .arg(
+            Arg::with_name("FLATTEN")
+                .help("Collapse objects and lists with a single child into their parent, joining names with dots (e.g., 'a.b' for {\"a\": {\"b\": 1}})")
+                .long("flatten")
+        )
+        .arg(
+            Arg::with_name("TYPED_LEAVES")
+                .help("Mount each number, boolean, string, and null as a directory holding a 'value' file and a 'type' file")
+                .long("typed-leaves")
+        )
+        .arg(
+            Arg::with_name("EXCLUDE")
+                .help("Leaves paths matching the given glob (e.g., '**/password') out of the mount; they're saved back unchanged (may be repeated)")
+                .long("exclude")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("NAME_MAP")
+                .help("Mounts the key at a JSON Pointer under another name, e.g., '/servers/primary.example.com=primary'; it keeps its original name when saved (may be repeated)")
+                .long("name-map")
+                .value_name("POINTER=NAME")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("IGNORE_FILE")
+                .help("Leaves paths matching the rules in this gitignore-style file out of the mount, as for --exclude (defaults to .ffsignore next to the input, if there is one)")
+                .long("ignore-file")
+                .value_name("FILE")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("BLOB")
+                .help("Mounts the base64-encoded strings at paths matching the given glob (e.g., '**/image') as files holding the decoded bytes; they're encoded again when saving (may be repeated)")
+                .long("blob")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("INCLUDE_TAG")
+                .help("Replaces strings holding TAG and a path (e.g., '!include other.yaml') with the contents of that file, which is saved back on its own")
+                .long("include-tag")
+                .value_name("TAG")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("STRICT")
+                .help("Refuses to mount JSON with duplicate keys in an object (by default, ffs warns and keeps the last value)")
+                .long("strict")
+        )
+        .arg(
+            Arg::with_name("STRICT_TYPES")
+                .help("Refuses to mount input holding values the output format can't represent (by default, ffs warns and coerces them)")
+                .long("strict-types")
+        )
+        .arg(
+            Arg::with_name("JSON5_OUTPUT")
+                .help("Saves JSON5 output as JSON5, with unquoted keys and trailing commas (by default, it's saved as plain JSON)")
+                .long("json5-output")
+        )
+        .arg(
+            Arg::with_name("NUMBERS_AS_STRINGS")
+                .help("Keeps JSON numbers digit for digit, even those too big or precise for 64 bits (by default, they're rounded)")
+                .long("numbers-as-strings")
+        )
+        .arg(
+            Arg::with_name("NUMBER_SUFFIX")
+                .help("Keeps JSON integers and floats apart: floats keep their decimal point (e.g., `1.0`), even when edited to look like integers")
+                .long("number-suffix")
+        )
+        .arg(
+            Arg::with_name("CANONICAL_NUMBERS")
+                .help("Writes JSON numbers in one canonical form (e.g., `1e3`, `1000.0`, and `1000` all save as `1000`), so saves don't churn diffs")
+                .long("canonical-numbers")
+        )
+        .arg(
+            Arg::with_name("WATCH")
+                .help("Reloads the input file when it changes on disk")
+                .long("watch")
+        )
+        .arg(
+            Arg::with_name("STREAM")
+                .help("Appends each new element of an NDJSON list to the output as soon as it's closed or fsynced (by default, output is saved at unmount)")
+                .long("stream")
+        )
+        .arg(
+            Arg::with_name("WATCH_CONFLICT")
+                .help("Sets which side wins when the input file changes while the mount has unsaved edits: 'mount' keeps the edits; 'file' reloads the file, discarding them")
+                .long("watch-conflict")
+                .takes_value(true)
+                .default_value("mount")
+                .possible_values(WATCH_CONFLICT)
+        )
+        .arg(
+            Arg::with_name("INI_SPLIT_DOTS")
+                .help("Nest dotted keys in INI and .properties files (e.g., 'a.b.c') into subdirectories")
+                .long("ini-split-dots")
+        )
+        .arg(
+            Arg::with_name("YAML_ALIASES")
+                .help("Set how YAML aliases are loaded: 'expand' copies the anchored value; 'link' makes a symbolic link to it")
+                .long("yaml-aliases")
+                .takes_value(true)
+                .default_value("expand")
+                .possible_values(YAML_ALIASES)
+        )
+        .arg(
+            Arg::with_name("NULL_AS")
+                .help("Set how nulls are mounted: 'empty_file' makes an empty file; 'missing' leaves them out; 'symlink' makes a dangling symbolic link")
+                .long("null-as")
+                .takes_value(true)
+                .default_value("empty_file")
+                .possible_values(NULL_AS)
+        )
+        .arg(
+            Arg::with_name("EMPTY_AS")
+                .help("Set how an empty input is mounted: 'error' parses it like any other input (an error for most formats); 'empty_object' mounts an empty named directory; 'empty_array' an empty list directory")
+                .long("empty-as")
+                .value_name("MODE")
+                .takes_value(true)
+                .default_value("error")
+                .possible_values(EMPTY_AS)
+        )
+        .arg(
+            Arg::with_name("LIST_RENAME")
+                .help("Sets how renaming a list element onto another reorders the list: 'shift' moves it there, shifting the elements in between; 'swap' exchanges the two")
+                .long("list-rename")
+                .value_name("MODE")
+                .takes_value(true)
+                .default_value("shift")
+                .possible_values(LIST_RENAME)
+        )
+        .arg(
+            Arg::with_name("NEW_DIR_TYPE")
+                .help("Sets the kind of directory mkdir makes: 'named' directories are saved as maps (e.g., {} in JSON), 'list' directories as lists (e.g., [] in JSON)")
+                .long("new-dir-type")
+                .value_name("TYPE")
+                .takes_value(true)
+                .default_value("named")
+                .possible_values(DIR_TYPES)
+        )
         .arg(
             Arg::with_name("UNPADDED")
                 .help("Don't pad the numeric names of list elements with zeroes; will not sort properly")
                 .long("unpadded")
         )
+        .arg(
+            Arg::with_name("INDEX_WIDTH")
+                .help("Pads the numeric names of list elements with zeroes to at least this many digits, so lists that grow while mounted still sort properly")
+                .long("index-width")
+                .value_name("WIDTH")
+                .takes_value(true)
+                .conflicts_with("UNPADDED")
+        )
         .arg(
             Arg::with_name("READONLY")
                 .help("Mounted filesystem will be readonly")
                 .long("readonly")
         )
+        .arg(
+            Arg::with_name("NOATIME")
+                .help("Don't update files' access times when they're read")
+                .long("noatime")
+        )
         .arg(
             Arg::with_name("OUTPUT")
-                .help("Sets the output file for saving changes (defaults to stdout)")
+                .help("Sets the output file for saving changes (defaults to stdout, which '-' also names)")
                 .long("output")
                 .short("o")
                 .takes_value(true)
@@ -123,9 +401,43 @@ pub fn app() -> App<'static, 'static> {
                 .overrides_with("OUTPUT")            
                 .overrides_with("NOOUTPUT")
         )
+        .arg(
+            Arg::with_name("DRYRUN")
+                .help("Don't save changes; instead, print a unified diff of the input against what would have been saved on stderr")
+                .long("dry-run")
+        )
+        .arg(
+            Arg::with_name("COMPRESS_OUTPUT")
+                .help("Gzip the output (the default when the output file ends in .gz, or when a gzipped input is saved in place)")
+                .long("compress-output")
+        )
+        .arg(
+            Arg::with_name("NO_CLOBBER")
+                .help("Refuses to mount if the output file already exists (unless it's the input file)")
+                .long("no-clobber")
+        )
+        .arg(
+            Arg::with_name("FORCE")
+                .help("Overwrites an existing output file, even after --no-clobber")
+                .long("force")
+                .overrides_with("NO_CLOBBER")
+        )
+        .arg(
+            Arg::with_name("NO_SYNC_ON_CLOSE")
+                .help("Only save changes at unmount, rather than shortly after a file that was written is closed")
+                .long("no-sync-on-close")
+        )
+        .arg(
+            Arg::with_name("SYNC_DELAY")
+                .help("Sets how long (in seconds) to wait after a written file is closed before saving; closes in the meantime share the save")
+                .long("sync-delay")
+                .value_name("SECONDS")
+                .takes_value(true)
+                .default_value("1")
+        )
         .arg(
             Arg::with_name("SOURCE_FORMAT")
-                .help("Specify the source format explicitly (by default, automatically inferred from filename extension)")
+                .help("Specify the source format explicitly (by default, automatically inferred from filename extension, or else from the input's contents)")
                 .long("source")
                 .short("s")
                 .takes_value(true)
@@ -141,11 +453,82 @@ pub fn app() -> App<'static, 'static> {
         )
         .arg(
             Arg::with_name("PRETTY")
-                .help("Pretty-print output (may increase size)")
+                .help("Pretty-print output (may increase size); by default, JSON output is pretty printed when the JSON input is")
                 .long("pretty")
                 .overrides_with("NOOUTPUT")
                 .overrides_with("QUIET")
         )
+        .arg(
+            Arg::with_name("COMPACT")
+                .help("Don't pretty-print output, even if the input is pretty printed")
+                .long("compact")
+                .conflicts_with("PRETTY")
+                .conflicts_with("INDENT")
+        )
+        .arg(
+            Arg::with_name("INDENT")
+                .help("Sets the indent width for pretty-printed JSON output (implies --pretty; defaults to the input's indent or 2)")
+                .long("indent")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("INDENT_CHAR")
+                .help("Sets the character used to indent pretty-printed output (implies --pretty; defaults to the input's, or 'space')")
+                .long("indent-char")
+                .takes_value(true)
+                .possible_values(INDENT_CHARS)
+                .conflicts_with("COMPACT")
+        )
+        .arg(
+            Arg::with_name("OUTPUT_ENCODING")
+                .help("Sets the text encoding of the output (defaults to the input's, as determined by its byte order mark)")
+                .long("output-encoding")
+                .value_name("ENCODING")
+                .takes_value(true)
+                .possible_values(ENCODINGS)
+        )
+        .arg(
+            Arg::with_name("PLIST_BINARY")
+                .help("Saves plists in the binary format, rather than XML")
+                .long("plist-binary")
+        )
+        .arg(
+            Arg::with_name("PROTO_SCHEMA")
+                .help("Reads and writes protobuf according to this .proto schema")
+                .long("proto-schema")
+                .value_name("FILE")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("PROTO_MESSAGE")
+                .help("The fully qualified name of the protobuf message to read and write (defaults to the schema's only message)")
+                .long("proto-message")
+                .value_name("NAME")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("SCHEMA")
+                .help("Checks the document against this JSON Schema when loading and saving, warning about violations")
+                .long("schema")
+                .value_name("FILE")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("SCHEMA_ENFORCE")
+                .help("Refuses to save a document that violates the --schema")
+                .long("schema-enforce")
+                .requires("SCHEMA")
+        )
+        .arg(
+            Arg::with_name("SORT_KEYS")
+                .help("Sorts the keys of named directories (i.e., objects and maps) when saving, for deterministic output")
+                .long("sort-keys")
+        )
+        .arg(
+            Arg::with_name("PRUNE_EMPTY")
+                .help("Leaves out keys whose values are empty directories (e.g., {} or []) when saving, recursively")
+                .long("prune-empty")
+        )
         .arg(
             Arg::with_name("MOUNT")
                 .help("Sets the mountpoint; will be inferred when using a file, but must be specified when running on stdin")
@@ -153,6 +536,155 @@ pub fn app() -> App<'static, 'static> {
                 .short("m")
                 .takes_value(true)
         )
+        .arg(
+            Arg::with_name("OVERLAY")
+                .help("Merges the overlay FILE (in the same format as the input) into the input before mounting: maps are merged key by key, with the overlay winning")
+                .long("merge")
+                .value_name("FILE")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("MERGE_ARRAYS")
+                .help("Sets whether lists in the overlay replace or are appended to lists in the input")
+                .long("merge-arrays")
+                .takes_value(true)
+                .default_value("replace")
+                .possible_values(MERGE_ARRAYS)
+        )
+        .arg(
+            Arg::with_name("WRITE_OVERLAY_ONLY")
+                .help("Saves only the differences from the input back to the overlay FILE, leaving the input alone")
+                .long("write-overlay-only")
+                .requires("OVERLAY")
+                .conflicts_with_all(&["OUTPUT", "INPLACE", "NOOUTPUT"])
+        )
+        .arg(
+            Arg::with_name("POINTER")
+                .help("Mounts only the subtree named by an RFC 6901 JSON Pointer (e.g., '/users/0/settings'); edits are spliced back into the whole document when saving")
+                .long("pointer")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("ELEMENTS_AS_FILES")
+                .help("Mounts the object and list elements of lists at depth DEPTH or deeper (the root is at depth 0) as files holding the serialized element")
+                .long("elements-as-files")
+                .value_name("DEPTH")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("MAX_INODES")
+                .help("Refuses to load documents (or create files) that would need more than this many inodes")
+                .long("max-inodes")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("MAX_DEPTH")
+                .help("Refuses to load documents whose lists and maps nest more than this deeply")
+                .long("max-depth")
+                .default_value("1024")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("ANALYZE")
+                .help("Prints how many files and directories the input would need, how deeply it nests, and its largest list and map, and exits without mounting")
+                .long("analyze")
+                .conflicts_with("NEW")
+        )
+        .arg(
+            Arg::with_name("ANALYZE_JSON")
+                .help("Like --analyze, but prints the statistics as JSON")
+                .long("analyze-json")
+                .conflicts_with("NEW")
+        )
+        .arg(
+            Arg::with_name("CONVERT")
+                .help("Saves the input in the output format (on stdout, unless --output is given) and exits without mounting")
+                .long("convert")
+                .conflicts_with("NEW")
+                .conflicts_with("ANALYZE")
+                .conflicts_with("ANALYZE_JSON")
+        )
+        .arg(
+            Arg::with_name("MAX_FILE_SIZE")
+                .help("Refuses to grow any file beyond this many bytes")
+                .long("max-file-size")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("NAME_MAX")
+                .help("Refuses to create or rename files to names longer than this many bytes")
+                .long("name-max")
+                .takes_value(true)
+                .default_value("255")
+        )
+        .arg(
+            Arg::with_name("MAX_TOTAL_BYTES")
+                .help("Refuses to load documents whose strings and binary data add up to more than this many bytes")
+                .long("max-total-bytes")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("SIZE")
+                .help("Caps the total size of the files in the filesystem, like tmpfs's `size=` option; takes K, M, and G suffixes")
+                .long("size")
+                .value_name("SIZE")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("TTL")
+                .help("Sets how long (in seconds) the kernel may cache attributes and directory entries; 0 disables caching")
+                .long("ttl")
+                .takes_value(true)
+                .default_value("1")
+        )
+        .arg(
+            Arg::with_name("MOUNT_RETRIES")
+                .help("Sets how many more times to try mounting on a busy mountpoint (e.g., one left mounted by a crashed ffs) before giving up")
+                .long("mount-retries")
+                .value_name("N")
+                .takes_value(true)
+                .default_value("3")
+        )
+        .arg(
+            Arg::with_name("MOUNT_RETRY_DELAY")
+                .help("Sets how long (in seconds) to wait between tries at a busy mountpoint")
+                .long("mount-retry-delay")
+                .value_name("SECONDS")
+                .takes_value(true)
+                .default_value("0.5")
+        )
+        .arg(
+            Arg::with_name("FORCE_UNMOUNT")
+                .help("Unmounts a stale FUSE mount (e.g., one left by a crashed ffs) on a busy mountpoint (with fusermount -u or umount) before retrying")
+                .long("force-unmount")
+        )
+        .arg(
+            Arg::with_name("NO_SAVE_ON_SIGNAL")
+                .help("Exits immediately on SIGINT or SIGTERM, without unmounting or saving")
+                .long("no-save-on-signal")
+        )
+        .arg(
+            Arg::with_name("ALLOW_PRIMITIVE_ROOT")
+                .help("Allows mounting a primitive value (e.g., a number or string) as a directory holding a single file")
+                .long("allow-primitive-root")
+        )
+        .arg(
+            Arg::with_name("PRIMITIVE_NAME")
+                .help("Sets the name of the file holding a primitive root (implies --allow-primitive-root) [default: value]")
+                .long("primitive-name")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("LIST_LENGTH_FILE")
+                .help("Adds a read-only file with this name to every list directory, holding the list's length (e.g., '.length'); it is never saved")
+                .long("list-length-file")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("EXPOSE_INFO")
+                .help("Adds a read-only '.ffs-info' file to the root describing the mount's configuration; it is never saved")
+                .long("expose-info")
+        )
         .arg(
             Arg::with_name("NEW")
                 .help("Mounts an empty filesystem, inferring a mountpoint and output format")
@@ -161,11 +693,13 @@ pub fn app() -> App<'static, 'static> {
                 .conflicts_with("INPLACE")
                 .conflicts_with("SOURCE_FORMAT")
                 .conflicts_with("OUTPUT")
+                .conflicts_with("POINTER")
         )
         .arg(
             Arg::with_name("INPUT")
-                .help("Sets the input file ('-' means STDIN)")
+                .help("Sets the input file ('-' means STDIN); several files are mounted side by side, each saved back to itself; a directory's files are mounted as one document")
                 .default_value("-")
+                .multiple(true)
                 .index(1),
         )
 }
@@ -1,21 +1,29 @@
 use std::fs::File;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::time::Duration;
 
-use tracing::{debug, error, warn};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+use tracing::{debug, error, info, warn};
 use tracing_subscriber::prelude::*;
 use tracing_subscriber::{filter::EnvFilter, fmt};
 
-use fuser::FileType;
+use fuser::{FileType, MountOption};
 
 use super::format;
 use super::format::Format;
+use super::fs::DirType;
 
 use super::cli;
 
 pub const ERROR_STATUS_FUSE: i32 = 1;
 pub const ERROR_STATUS_CLI: i32 = 2;
 
+/// The name of the file at the root describing the mount (see
+/// `Config::expose_info`).
+pub const INFO_FILE: &str = ".ffs-info";
+
 /// Configuration information
 ///
 /// See `cli.rs` for information on the actual command-line options; see
@@ -23,36 +31,242 @@ pub const ERROR_STATUS_CLI: i32 = 2;
 ///
 /// NB I know this arrangement sucks, but `clap`'s automatic stuff isn't
 /// adequate to express what I want here. Command-line interfaces are hard. 😢
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Config {
     pub input_format: Format,
     pub output_format: Format,
     pub eager: bool,
     pub uid: u32,
     pub gid: u32,
+    /// Lets users other than `uid` (and root) use the mount; needs FUSE's
+    /// `allow_other`, i.e., `user_allow_other` in `/etc/fuse.conf`.
+    pub allow_other: bool,
+    /// When `allow_other` is set, the uids allowed to use the mount; empty
+    /// means everyone.
+    pub allowed_uids: Vec<u32>,
+    /// Only let the owner, root, and `allowed_uids` read the mount (look up,
+    /// list, read, or get attributes); others get `EACCES`, even under
+    /// `allow_other`.
+    pub private: bool,
     pub filemode: u16,
     pub dirmode: u16,
     pub add_newlines: bool,
+    /// Strip leading and trailing whitespace from files' contents when
+    /// saving (after removing the newline `add_newlines` adds).
+    pub trim: bool,
+    /// Whether text output ends in a newline; see `FinalNewlineWriter`.
+    pub final_newline: FinalNewline,
     pub pad_element_names: bool,
+    /// The least width list element names are padded to (see
+    /// `Config::element_name`). Without it, the width is just enough for the
+    /// list's length when it's loaded, so a list that grows while mounted
+    /// ends up with names of different widths.
+    pub index_width: Option<usize>,
+    pub ini_split_dots: bool,
+    /// Collapse directories with a single child into their parent, joining
+    /// the names with dots; see `FS::flatten_entries`.
+    pub flatten_single: bool,
+    /// Mount each primitive as a directory holding a `value` file and a
+    /// `type` file; see `FS::typed_leaf`.
+    pub typed_leaves: bool,
+    /// Glob patterns for paths to leave out of the mount; excluded values are
+    /// saved back unchanged. See `Config::excluded`.
+    pub exclude: Vec<String>,
+    /// A gitignore-style file of paths to leave out of the mount, like
+    /// `exclude`; by default, `.ffsignore` next to the input (if it exists).
+    pub ignore_file: Option<PathBuf>,
+    /// The rules read from `ignore_file`.
+    pub ignore: Option<Gitignore>,
+    /// Glob patterns (as for `exclude`) for paths whose strings hold base64
+    /// data, which is mounted as raw bytes; see `FS::decode_blob`.
+    pub blob_keys: Vec<String>,
+    /// Renames: a JSON Pointer to a key in the document, and the name to
+    /// mount it under. The key keeps its original name when saved.
+    pub name_map: Vec<(String, String)>,
+    /// Strings holding this tag followed by a path (e.g., `!include
+    /// other.yaml`) are replaced by the contents of that file; see
+    /// `FS::splice_include`.
+    pub include_tag: Option<String>,
+    /// Reject inputs with duplicate keys, rather than warning and keeping the
+    /// last value.
+    pub strict: bool,
+    /// When saving in a different format than the input's, refuse to mount
+    /// input holding values the output format can't represent (see
+    /// `format::unrepresentable`), rather than warning and coercing them.
+    pub strict_conversion: bool,
+    /// Save JSON5 as JSON5 (unquoted keys, trailing commas), rather than as
+    /// plain JSON.
+    pub json5_output: bool,
+    /// Keep JSON numbers exactly as written, rather than as the nearest
+    /// `i64`, `u64`, or `f64`.
+    pub arbitrary_precision: bool,
+    /// Keep JSON integers and floats apart: integers are typed `integer`
+    /// rather than `float`, and floats always have a decimal point (or an
+    /// exponent), even once edited to look like integers.
+    pub number_suffix: bool,
+    /// Spell JSON numbers one way, however they were written: integral
+    /// values as integers (`1e3` and `1000.0` are both `1000`), everything
+    /// else as the shortest decimal that reads back as the same `f64`.
+    /// Ignored when `arbitrary_precision` is set, which keeps numbers as
+    /// written instead.
+    pub canonical_numbers: bool,
+    /// Save maps with their keys in sorted order, rather than the order they
+    /// were loaded in.
+    pub sort_keys: bool,
+    /// Leave empty named and list directories (e.g., `{}` and `[]`) out of
+    /// named directories when saving; see `fs::is_empty_dir`.
+    pub prune_empty: bool,
+    /// Save plists in the binary format, rather than XML.
+    pub plist_binary: bool,
+    /// The `.proto` schema protobuf input and output are read and written
+    /// with; see `format::protobuf::message_descriptor`.
+    pub proto_schema: Option<PathBuf>,
+    /// The fully qualified name of the message protobuf input and output
+    /// hold; it may be left out when the schema defines just one.
+    pub proto_message: Option<String>,
+    /// A JSON Schema the document is checked against when it's loaded and
+    /// saved; see `format::json::schema`. Violations are logged as warnings.
+    pub schema: Option<PathBuf>,
+    /// Refuse to save a document that violates `Config::schema`.
+    pub schema_enforce: bool,
+    /// Reload the input file when it changes on disk.
+    pub watch: bool,
+    /// What to do when the input file changes on disk while the mount has
+    /// unsaved edits (see `Config::watch`).
+    pub watch_conflict: WatchConflict,
+    /// Append each new element of an NDJSON list to the output file as soon
+    /// as it's closed or `fsync`ed, rather than only saving at unmount.
+    pub stream: bool,
     pub base64: base64::Config,
     pub try_decode_base64: bool,
     pub allow_xattr: bool,
     pub keep_macos_xattr_file: bool,
     pub munge: Munge,
+    /// What to do with keys that have a `/` in them, which can't be filenames.
+    pub slash_policy: SlashPolicy,
+    /// What replaces each `/` in a key under `SlashPolicy::Escape` and
+    /// `Munge::Rename`.
+    pub slash_escape: String,
+    /// Look up names ignoring case. Names in the input that differ only in
+    /// case get underscores appended until they don't.
+    pub case_insensitive: bool,
+    pub yaml_aliases: YamlAliases,
+    pub null_as: NullAs,
+    /// How to mount an input with nothing in it; see `format::read_input`.
+    pub empty_as: EmptyAs,
+    pub list_rename: ListRename,
+    /// The kind of directory `mkdir` makes; either kind can be changed later
+    /// by setting its `user.type` extended attribute.
+    pub new_dir_type: DirType,
     pub read_only: bool,
+    /// Don't update a file's atime when it's read.
+    pub noatime: bool,
     pub input: Input,
+    pub root_pointer: Option<String>,
+    /// A document, in the input format, merged into the input when loading.
+    pub overlay: Option<PathBuf>,
+    /// How lists in the `overlay` combine with those in the input.
+    pub merge_arrays: MergeArrays,
+    /// Save the differences from the input to the `overlay` rather than
+    /// writing the merged document to the output.
+    pub write_overlay_only: bool,
+    /// Mount a primitive (non-directory) root as a directory holding a single
+    /// file named `primitive_root_name`.
+    pub allow_primitive_root: bool,
+    pub primitive_root_name: String,
+    /// Mount the compound (list or map) elements of lists this deep (the
+    /// root is at depth 0) or deeper as files holding the serialized element,
+    /// rather than as directories; see `FS::element_file`.
+    pub elements_as_files: Option<usize>,
+    /// Refuse to load documents (or create files) that would need more inodes
+    pub max_inodes: Option<usize>,
+    /// Refuse to load documents whose lists and maps nest deeper than this
+    pub max_depth: usize,
+    /// Refuse to grow any file beyond this many bytes
+    pub max_file_size: Option<u64>,
+    /// The longest name (in bytes) a new file may have; reported by `statfs`
+    pub name_max: u32,
+    /// Refuse to load documents whose strings and binary data add up to more
+    /// than this many bytes, however few inodes they need
+    pub max_total_bytes: Option<u64>,
+    /// The most bytes of file contents the filesystem may hold, like tmpfs's
+    /// `size=` option. Writes that would go beyond it fail with `ENOSPC`, and
+    /// `statfs` reports it as the size of the filesystem.
+    pub capacity: Option<u64>,
+    /// Print statistics about the input's structure (see `format::Stats`)
+    /// and exit, rather than mounting it
+    pub analyze: bool,
+    /// Print those statistics as JSON
+    pub analyze_json: bool,
+    /// Save the input in the output format and exit, rather than mounting it
+    pub convert: bool,
+    /// How long the kernel may cache attributes and directory entries.
+    pub cache_ttl: Duration,
+    /// The name of a read-only file in each list directory holding the
+    /// list's length; it is never saved.
+    pub list_length_file: Option<String>,
+    /// Adds a read-only `INFO_FILE` to the root describing the mount; it is
+    /// never saved.
+    pub expose_info: bool,
     pub output: Output,
     pub pretty: bool,
+    /// How many `indent_char`s make up one level of pretty-printed output.
+    pub indent: usize,
+    pub indent_char: IndentChar,
+    /// The text encoding of the input, as determined by its byte order mark
+    /// when it's read (see `Config::input_reader`).
+    pub input_encoding: TextEncoding,
+    /// Overrides `input_encoding` as the text encoding of the output.
+    pub output_encoding: Option<TextEncoding>,
+    /// Gzip the output. Set by `--compress-output`, by an output file ending
+    /// in `.gz`, or when saving a gzipped input back over itself.
+    pub compress_output: bool,
+    /// Refuse to mount when the output file already exists and isn't the
+    /// input file, rather than overwriting it when saving. `--force` turns
+    /// this back off.
+    pub no_clobber: bool,
+    pub dry_run: bool,
+    /// Save changes shortly after a file that was opened for writing is
+    /// closed, rather than only at unmount. Closes within `sync_delay` of
+    /// each other share one save.
+    pub sync_on_close: bool,
+    /// How long after a close `sync_on_close` waits before saving.
+    pub sync_delay: Duration,
     pub timing: bool,
     pub mount: Option<PathBuf>,
     pub cleanup_mount: bool,
+    /// How many more times to try mounting on a busy mountpoint (e.g., one a
+    /// crashed ffs never unmounted) before giving up; see
+    /// `ffs::mount_session`.
+    pub mount_retries: usize,
+    /// How long to wait between tries at a busy mountpoint.
+    pub mount_retry_delay: Duration,
+    /// Unmount a stale FUSE mount on a busy mountpoint before retrying.
+    pub force_unmount: bool,
+    /// Unmount (and so save) on `SIGINT` and `SIGTERM`, rather than dying
+    /// with the mount; see `ffs::unmount_on_signal`.
+    pub save_on_signal: bool,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum Input {
     Stdin,
     File(PathBuf),
     Empty,
+    /// Several files, mounted side by side in the root.
+    Siblings(Vec<Sibling>),
+    /// A directory, whose files (and subdirectories) are mounted as one
+    /// document, keyed by their stems (and names).
+    Directory(PathBuf),
+}
+
+/// One of several input files (see `Input::Siblings`). It's mounted as the
+/// directory `name` in the root, and saved back to `file` in `format`.
+#[derive(Clone, Debug)]
+pub struct Sibling {
+    pub name: String,
+    pub file: PathBuf,
+    pub format: Format,
 }
 
 impl std::fmt::Display for Input {
@@ -60,19 +274,30 @@ impl std::fmt::Display for Input {
         match self {
             Input::Empty => write!(f, "<empty>"),
             Input::Stdin => write!(f, "<stdin>"),
-            Input::File(file) => write!(f, "{}", file.display()),
+            Input::File(file) | Input::Directory(file) => write!(f, "{}", file.display()),
+            Input::Siblings(siblings) => {
+                let files = siblings
+                    .iter()
+                    .map(|sibling| sibling.file.display().to_string())
+                    .collect::<Vec<_>>();
+                write!(f, "{}", files.join(","))
+            }
         }
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Output {
     Quiet,
     Stdout,
     File(PathBuf),
+    /// Save each of the `Input::Siblings` back to its own file.
+    Siblings,
+    /// Save the tree back out to the files of the `Input::Directory`.
+    Directory,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum Munge {
     Rename,
     Filter,
@@ -103,6 +328,409 @@ impl FromStr for Munge {
     }
 }
 
+/// How to mount keys with a `/` in them (see `Config::slash_policy`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlashPolicy {
+    /// Replace each `/` with `Config::slash_escape`, restoring the original
+    /// key when saving (or, under `Munge::Filter`, drop the key).
+    Escape,
+    /// Refuse to mount input with such keys.
+    Reject,
+    /// Split the key into nested directories, e.g., `{"a/b": 1}` becomes the
+    /// file `b` in the directory `a`. Keys sharing a prefix share the
+    /// directory, and the directory's entries are saved back as keys of its
+    /// parent.
+    Split,
+}
+
+impl std::fmt::Display for SlashPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            SlashPolicy::Escape => write!(f, "escape"),
+            SlashPolicy::Reject => write!(f, "reject"),
+            SlashPolicy::Split => write!(f, "split"),
+        }
+    }
+}
+
+impl FromStr for SlashPolicy {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let s = s.trim().to_lowercase();
+
+        if s == "escape" {
+            Ok(SlashPolicy::Escape)
+        } else if s == "reject" {
+            Ok(SlashPolicy::Reject)
+        } else if s == "split" {
+            Ok(SlashPolicy::Split)
+        } else {
+            Err(())
+        }
+    }
+}
+
+/// How lists combine under `Config::overlay`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeArrays {
+    /// The overlay's list replaces the input's.
+    Replace,
+    /// The overlay's elements are appended to the input's.
+    Concat,
+}
+
+impl std::fmt::Display for MergeArrays {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            MergeArrays::Replace => write!(f, "replace"),
+            MergeArrays::Concat => write!(f, "concat"),
+        }
+    }
+}
+
+impl FromStr for MergeArrays {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let s = s.trim().to_lowercase();
+
+        if s == "replace" {
+            Ok(MergeArrays::Replace)
+        } else if s == "concat" {
+            Ok(MergeArrays::Concat)
+        } else {
+            Err(())
+        }
+    }
+}
+
+/// Which side wins when the input file changes on disk while the mount has
+/// unsaved edits (see `Config::watch`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchConflict {
+    /// Keep the edits made through the mount; they'll overwrite the file when
+    /// saved.
+    Mount,
+    /// Reload the file, discarding the edits made through the mount.
+    File,
+}
+
+impl std::fmt::Display for WatchConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            WatchConflict::Mount => write!(f, "mount"),
+            WatchConflict::File => write!(f, "file"),
+        }
+    }
+}
+
+impl FromStr for WatchConflict {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let s = s.trim().to_lowercase();
+
+        if s == "mount" {
+            Ok(WatchConflict::Mount)
+        } else if s == "file" {
+            Ok(WatchConflict::File)
+        } else {
+            Err(())
+        }
+    }
+}
+
+/// The character used to indent pretty-printed output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndentChar {
+    Space,
+    Tab,
+}
+
+impl IndentChar {
+    fn as_char(&self) -> char {
+        match self {
+            IndentChar::Space => ' ',
+            IndentChar::Tab => '\t',
+        }
+    }
+}
+
+impl std::fmt::Display for IndentChar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            IndentChar::Space => write!(f, "space"),
+            IndentChar::Tab => write!(f, "tab"),
+        }
+    }
+}
+
+impl FromStr for IndentChar {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let s = s.trim().to_lowercase();
+
+        if s == "space" {
+            Ok(IndentChar::Space)
+        } else if s == "tab" {
+            Ok(IndentChar::Tab)
+        } else {
+            Err(())
+        }
+    }
+}
+
+/// The text encodings we read and write. Input in any of them is transcoded
+/// to UTF-8 for parsing, and back again for saving.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextEncoding {
+    Utf8,
+    /// UTF-8 with a byte order mark.
+    Utf8Bom,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl TextEncoding {
+    /// Encodes `text`, with a byte order mark if the encoding has one.
+    fn encode(&self, text: &str) -> Vec<u8> {
+        match self {
+            TextEncoding::Utf8 => text.as_bytes().to_vec(),
+            TextEncoding::Utf8Bom => [b"\xEF\xBB\xBF", text.as_bytes()].concat(),
+            TextEncoding::Utf16Le => std::iter::once(0xFEFF)
+                .chain(text.encode_utf16())
+                .flat_map(u16::to_le_bytes)
+                .collect(),
+            TextEncoding::Utf16Be => std::iter::once(0xFEFF)
+                .chain(text.encode_utf16())
+                .flat_map(u16::to_be_bytes)
+                .collect(),
+        }
+    }
+}
+
+impl std::fmt::Display for TextEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            TextEncoding::Utf8 => write!(f, "utf-8"),
+            TextEncoding::Utf8Bom => write!(f, "utf-8-bom"),
+            TextEncoding::Utf16Le => write!(f, "utf-16le"),
+            TextEncoding::Utf16Be => write!(f, "utf-16be"),
+        }
+    }
+}
+
+impl FromStr for TextEncoding {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let s = s.trim().to_lowercase();
+
+        if s == "utf-8" || s == "utf8" {
+            Ok(TextEncoding::Utf8)
+        } else if s == "utf-8-bom" || s == "utf8-bom" {
+            Ok(TextEncoding::Utf8Bom)
+        } else if s == "utf-16le" || s == "utf16le" {
+            Ok(TextEncoding::Utf16Le)
+        } else if s == "utf-16be" || s == "utf16be" {
+            Ok(TextEncoding::Utf16Be)
+        } else {
+            Err(())
+        }
+    }
+}
+
+/// How to load YAML aliases (e.g., `*base` after `&base`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum YamlAliases {
+    /// Copy the anchored value to each alias.
+    Expand,
+    /// Make each alias a symbolic link to the anchored value.
+    Link,
+}
+
+impl std::fmt::Display for YamlAliases {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            YamlAliases::Expand => write!(f, "expand"),
+            YamlAliases::Link => write!(f, "link"),
+        }
+    }
+}
+
+impl FromStr for YamlAliases {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let s = s.trim().to_lowercase();
+
+        if s == "expand" {
+            Ok(YamlAliases::Expand)
+        } else if s == "link" {
+            Ok(YamlAliases::Link)
+        } else {
+            Err(())
+        }
+    }
+}
+
+/// Whether text output ends in a newline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FinalNewline {
+    /// However the output format's serializer leaves it. `Config::from_args`
+    /// replaces this with `Always` or `Never` when the input is a file in
+    /// the output format.
+    Auto,
+    /// Exactly one newline.
+    Always,
+    /// No newlines.
+    Never,
+}
+
+impl std::fmt::Display for FinalNewline {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            FinalNewline::Auto => write!(f, "auto"),
+            FinalNewline::Always => write!(f, "always"),
+            FinalNewline::Never => write!(f, "never"),
+        }
+    }
+}
+
+impl FromStr for FinalNewline {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let s = s.trim().to_lowercase();
+
+        if s == "auto" {
+            Ok(FinalNewline::Auto)
+        } else if s == "always" {
+            Ok(FinalNewline::Always)
+        } else if s == "never" {
+            Ok(FinalNewline::Never)
+        } else {
+            Err(())
+        }
+    }
+}
+
+/// How renaming one list element onto another reorders the list; see
+/// `FS::reorder_list`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListRename {
+    /// Move the element to the target's position, shifting the ones in
+    /// between (and the target) over by one.
+    Shift,
+    /// Exchange the two elements.
+    Swap,
+}
+
+impl std::fmt::Display for ListRename {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            ListRename::Shift => write!(f, "shift"),
+            ListRename::Swap => write!(f, "swap"),
+        }
+    }
+}
+
+impl FromStr for ListRename {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let s = s.trim().to_lowercase();
+
+        if s == "shift" {
+            Ok(ListRename::Shift)
+        } else if s == "swap" {
+            Ok(ListRename::Swap)
+        } else {
+            Err(())
+        }
+    }
+}
+
+/// How to mount an empty input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmptyAs {
+    /// Parse it like any other input; only some formats allow it.
+    Error,
+    /// An empty named directory.
+    EmptyObject,
+    /// An empty list directory.
+    EmptyArray,
+}
+
+impl std::fmt::Display for EmptyAs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            EmptyAs::Error => write!(f, "error"),
+            EmptyAs::EmptyObject => write!(f, "empty_object"),
+            EmptyAs::EmptyArray => write!(f, "empty_array"),
+        }
+    }
+}
+
+impl FromStr for EmptyAs {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let s = s.trim().to_lowercase();
+
+        if s == "error" {
+            Ok(EmptyAs::Error)
+        } else if s == "empty_object" {
+            Ok(EmptyAs::EmptyObject)
+        } else if s == "empty_array" {
+            Ok(EmptyAs::EmptyArray)
+        } else {
+            Err(())
+        }
+    }
+}
+
+/// How to mount null values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NullAs {
+    /// An empty file.
+    EmptyFile,
+    /// Nothing at all: nulls are left out of the mount, and saved back as is.
+    Missing,
+    /// A dangling symbolic link.
+    Symlink,
+}
+
+impl std::fmt::Display for NullAs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            NullAs::EmptyFile => write!(f, "empty_file"),
+            NullAs::Missing => write!(f, "missing"),
+            NullAs::Symlink => write!(f, "symlink"),
+        }
+    }
+}
+
+impl FromStr for NullAs {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let s = s.trim().to_lowercase();
+
+        if s == "empty_file" {
+            Ok(NullAs::EmptyFile)
+        } else if s == "missing" {
+            Ok(NullAs::Missing)
+        } else if s == "symlink" {
+            Ok(NullAs::Symlink)
+        } else {
+            Err(())
+        }
+    }
+}
+
 impl Config {
     /// Parses arguments from `std::env::Args`, via `cli::app().get_matches()`
     pub fn from_args() -> Self {
@@ -130,66 +758,397 @@ impl Config {
             std::process::exit(0);
         }
 
-        // logging
+        // logging: RUST_LOG wins over the flags
         if !args.is_present("QUIET") {
-            let filter_layer = EnvFilter::try_from_default_env().unwrap_or_else(|_e| {
-                if args.is_present("DEBUG") {
-                    EnvFilter::new("ffs=debug")
-                } else {
-                    EnvFilter::new("ffs=warn")
+            let level = if args.is_present("DEBUG") {
+                "debug"
+            } else {
+                match args.occurrences_of("VERBOSE") {
+                    0 => "warn",
+                    1 => "info",
+                    2 => "debug",
+                    _ => "trace",
                 }
-            });
-            let fmt_layer = fmt::layer().with_writer(std::io::stderr);
-            tracing_subscriber::registry()
-                .with(filter_layer)
-                .with(fmt_layer)
-                .init();
+            };
+            let filter_layer = EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_e| EnvFilter::new(format!("ffs={}", level)));
+            let registry = tracing_subscriber::registry().with(filter_layer);
+            if args.value_of("LOG_FORMAT") == Some("json") {
+                registry
+                    .with(fmt::layer().json().with_writer(std::io::stderr))
+                    .init();
+            } else {
+                registry
+                    .with(fmt::layer().with_writer(std::io::stderr))
+                    .init();
+            }
         }
 
         // simple flags
         config.timing = args.is_present("TIMING");
         config.eager = args.is_present("EAGER");
         config.add_newlines = !args.is_present("EXACT");
+        config.trim = args.is_present("TRIM");
         config.pad_element_names = !args.is_present("UNPADDED");
-        config.read_only = args.is_present("READONLY");
-        config.allow_xattr = !args.is_present("NOXATTR");
-        config.keep_macos_xattr_file = args.is_present("KEEPMACOSDOT");
-        config.pretty = args.is_present("PRETTY");
-
-        // munging policy
-        config.munge = match args.value_of("MUNGE") {
-            None => Munge::Filter,
-            Some(s) => match str::parse(s) {
-                Ok(munge) => munge,
-                Err(_) => {
-                    warn!("Invalid `--munge` mode '{}', using 'rename'.", s);
-                    Munge::Rename
+        if let Some(width) = args.value_of("INDEX_WIDTH") {
+            config.index_width = match width.parse::<usize>() {
+                Ok(width) => Some(width),
+                Err(e) => {
+                    error!("Couldn't parse `--index-width {}`: {}.", width, e);
+                    std::process::exit(ERROR_STATUS_CLI)
                 }
-            },
-        };
-
-        // perms
-        config.filemode = match u16::from_str_radix(args.value_of("FILEMODE").unwrap(), 8) {
-            Ok(filemode) => filemode,
-            Err(e) => {
+            };
+        }
+        config.ini_split_dots = args.is_present("INI_SPLIT_DOTS");
+        config.flatten_single = args.is_present("FLATTEN");
+        config.typed_leaves = args.is_present("TYPED_LEAVES");
+        config.strict = args.is_present("STRICT");
+        config.strict_conversion = args.is_present("STRICT_TYPES");
+        config.analyze = args.is_present("ANALYZE") || args.is_present("ANALYZE_JSON");
+        config.analyze_json = args.is_present("ANALYZE_JSON");
+        config.convert = args.is_present("CONVERT");
+        // converting saves everything, so munging and filtering should see it all
+        config.eager |= config.convert;
+        config.json5_output = args.is_present("JSON5_OUTPUT");
+        config.arbitrary_precision = args.is_present("NUMBERS_AS_STRINGS");
+        config.number_suffix = args.is_present("NUMBER_SUFFIX");
+        config.canonical_numbers = args.is_present("CANONICAL_NUMBERS");
+        config.sort_keys = args.is_present("SORT_KEYS");
+        config.prune_empty = args.is_present("PRUNE_EMPTY");
+        config.plist_binary = args.is_present("PLIST_BINARY");
+        config.proto_schema = args.value_of("PROTO_SCHEMA").map(PathBuf::from);
+        config.proto_message = args.value_of("PROTO_MESSAGE").map(String::from);
+        config.schema = args.value_of("SCHEMA").map(PathBuf::from);
+        config.schema_enforce = args.is_present("SCHEMA_ENFORCE");
+        config.watch = args.is_present("WATCH");
+        config.stream = args.is_present("STREAM");
+        if let Some(patterns) = args.values_of("EXCLUDE") {
+            config.exclude.extend(patterns.map(String::from));
+        }
+        if let Some(patterns) = args.values_of("BLOB") {
+            config.blob_keys.extend(patterns.map(String::from));
+        }
+        for mapping in args.values_of("NAME_MAP").into_iter().flatten() {
+            let (pointer, name) = match mapping.rsplit_once('=') {
+                Some(split) => split,
+                None => {
+                    error!("Invalid `--name-map {}`: expected POINTER=NAME.", mapping);
+                    std::process::exit(ERROR_STATUS_CLI);
+                }
+            };
+            if pointer.is_empty() || format::pointer_tokens(pointer).is_none() {
                 error!(
-                    "Couldn't parse `--mode {}`: {}.",
-                    args.value_of("FILEMODE").unwrap(),
-                    e
+                    "Invalid `--name-map {}`: '{}' isn't a JSON Pointer to a key.",
+                    mapping, pointer
                 );
-                std::process::exit(ERROR_STATUS_CLI)
+                std::process::exit(ERROR_STATUS_CLI);
             }
-        };
-        if args.occurrences_of("FILEMODE") > 0 && args.occurrences_of("DIRMODE") == 0 {
-            // wherever a read bit is set, the dirmode should have an execute bit, too
-            config.dirmode = config.filemode;
-            if config.dirmode & 0o400 != 0 {
-                config.dirmode |= 0o100;
+            if !config.valid_name(name) {
+                error!(
+                    "Invalid `--name-map {}`: '{}' isn't a valid filename.",
+                    mapping, name
+                );
+                std::process::exit(ERROR_STATUS_CLI);
             }
-            if config.dirmode & 0o040 != 0 {
-                config.dirmode |= 0o010;
+            if config.name_map.iter().any(|(other, _)| other == pointer) {
+                error!("`--name-map` renames '{}' more than once.", pointer);
+                std::process::exit(ERROR_STATUS_CLI);
             }
-            if config.dirmode & 0o004 != 0 {
+            config.name_map.push((pointer.into(), name.into()));
+        }
+        config.include_tag = args.value_of("INCLUDE_TAG").map(String::from);
+        config.read_only = args.is_present("READONLY");
+        config.noatime = args.is_present("NOATIME");
+        config.allow_xattr = !args.is_present("NOXATTR");
+        config.keep_macos_xattr_file = args.is_present("KEEPMACOSDOT");
+        config.dry_run = args.is_present("DRYRUN");
+        // a dry run's diff is only worth reading once, at unmount
+        config.sync_on_close = !args.is_present("NO_SYNC_ON_CLOSE") && !config.dry_run;
+        if let Some(delay) = args.value_of("SYNC_DELAY") {
+            config.sync_delay = match delay.parse::<f64>() {
+                Ok(secs) if secs.is_finite() && secs >= 0.0 => Duration::from_secs_f64(secs),
+                Ok(_) => {
+                    error!(
+                        "Invalid `--sync-delay {}`: must be a non-negative number of seconds.",
+                        delay
+                    );
+                    std::process::exit(ERROR_STATUS_CLI)
+                }
+                Err(e) => {
+                    error!("Couldn't parse `--sync-delay {}`: {}.", delay, e);
+                    std::process::exit(ERROR_STATUS_CLI)
+                }
+            };
+        }
+        if let Some(name) = args.value_of("PRIMITIVE_NAME") {
+            if !config.valid_name(name) {
+                error!("Invalid `--primitive-name {}`: not a valid filename.", name);
+                std::process::exit(ERROR_STATUS_CLI);
+            }
+            config.primitive_root_name = name.into();
+        }
+        config.allow_primitive_root =
+            args.is_present("ALLOW_PRIMITIVE_ROOT") || args.is_present("PRIMITIVE_NAME");
+
+        if let Some(name) = args.value_of("LIST_LENGTH_FILE") {
+            if !config.valid_name(name) {
+                error!(
+                    "Invalid `--list-length-file {}`: not a valid filename.",
+                    name
+                );
+                std::process::exit(ERROR_STATUS_CLI);
+            }
+            config.list_length_file = Some(name.into());
+        }
+        config.expose_info = args.is_present("EXPOSE_INFO");
+
+        if let Some(depth) = args.value_of("ELEMENTS_AS_FILES") {
+            config.elements_as_files = match depth.parse::<usize>() {
+                Ok(depth) => Some(depth),
+                Err(e) => {
+                    error!("Couldn't parse `--elements-as-files {}`: {}.", depth, e);
+                    std::process::exit(ERROR_STATUS_CLI)
+                }
+            };
+        }
+
+        if let Some(max) = args.value_of("MAX_INODES") {
+            config.max_inodes = match max.parse::<usize>() {
+                Ok(max) => Some(max),
+                Err(e) => {
+                    error!("Couldn't parse `--max-inodes {}`: {}.", max, e);
+                    std::process::exit(ERROR_STATUS_CLI)
+                }
+            };
+        }
+
+        if let Some(max) = args.value_of("MAX_DEPTH") {
+            config.max_depth = match max.parse::<usize>() {
+                Ok(max) => max,
+                Err(e) => {
+                    error!("Couldn't parse `--max-depth {}`: {}.", max, e);
+                    std::process::exit(ERROR_STATUS_CLI)
+                }
+            };
+        }
+
+        if let Some(ttl) = args.value_of("TTL") {
+            config.cache_ttl = match ttl.parse::<f64>() {
+                Ok(secs) if secs.is_finite() && secs >= 0.0 => Duration::from_secs_f64(secs),
+                Ok(_) => {
+                    error!(
+                        "Invalid `--ttl {}`: must be a non-negative number of seconds.",
+                        ttl
+                    );
+                    std::process::exit(ERROR_STATUS_CLI)
+                }
+                Err(e) => {
+                    error!("Couldn't parse `--ttl {}`: {}.", ttl, e);
+                    std::process::exit(ERROR_STATUS_CLI)
+                }
+            };
+        }
+
+        if let Some(retries) = args.value_of("MOUNT_RETRIES") {
+            config.mount_retries = match retries.parse::<usize>() {
+                Ok(retries) => retries,
+                Err(e) => {
+                    error!("Couldn't parse `--mount-retries {}`: {}.", retries, e);
+                    std::process::exit(ERROR_STATUS_CLI)
+                }
+            };
+        }
+        if let Some(delay) = args.value_of("MOUNT_RETRY_DELAY") {
+            config.mount_retry_delay = match delay.parse::<f64>() {
+                Ok(secs) if secs.is_finite() && secs >= 0.0 => Duration::from_secs_f64(secs),
+                Ok(_) => {
+                    error!(
+                        "Invalid `--mount-retry-delay {}`: must be a non-negative number of seconds.",
+                        delay
+                    );
+                    std::process::exit(ERROR_STATUS_CLI)
+                }
+                Err(e) => {
+                    error!("Couldn't parse `--mount-retry-delay {}`: {}.", delay, e);
+                    std::process::exit(ERROR_STATUS_CLI)
+                }
+            };
+        }
+        config.force_unmount = args.is_present("FORCE_UNMOUNT");
+        config.save_on_signal = !args.is_present("NO_SAVE_ON_SIGNAL");
+
+        if let Some(max) = args.value_of("MAX_FILE_SIZE") {
+            config.max_file_size = match max.parse::<u64>() {
+                Ok(max) => Some(max),
+                Err(e) => {
+                    error!("Couldn't parse `--max-file-size {}`: {}.", max, e);
+                    std::process::exit(ERROR_STATUS_CLI)
+                }
+            };
+        }
+
+        if let Some(max) = args.value_of("NAME_MAX") {
+            config.name_max = match max.parse::<u32>() {
+                Ok(max) if max > 0 => max,
+                Ok(_) => {
+                    error!(
+                        "Invalid `--name-max {}`: names must be allowed at least one byte.",
+                        max
+                    );
+                    std::process::exit(ERROR_STATUS_CLI)
+                }
+                Err(e) => {
+                    error!("Couldn't parse `--name-max {}`: {}.", max, e);
+                    std::process::exit(ERROR_STATUS_CLI)
+                }
+            };
+        }
+
+        if let Some(size) = args.value_of("SIZE") {
+            config.capacity = match parse_size(size) {
+                Ok(size) => Some(size),
+                Err(e) => {
+                    error!("Couldn't parse `--size {}`: {}.", size, e);
+                    std::process::exit(ERROR_STATUS_CLI)
+                }
+            };
+        }
+
+        if let Some(max) = args.value_of("MAX_TOTAL_BYTES") {
+            config.max_total_bytes = match max.parse::<u64>() {
+                Ok(max) => Some(max),
+                Err(e) => {
+                    error!("Couldn't parse `--max-total-bytes {}`: {}.", max, e);
+                    std::process::exit(ERROR_STATUS_CLI)
+                }
+            };
+        }
+
+        // munging policy
+        config.munge = match args.value_of("MUNGE") {
+            None => Munge::Filter,
+            Some(s) => match str::parse(s) {
+                Ok(munge) => munge,
+                Err(_) => {
+                    warn!("Invalid `--munge` mode '{}', using 'rename'.", s);
+                    Munge::Rename
+                }
+            },
+        };
+
+        // slash policy
+        config.slash_policy = match args.value_of("SLASH_POLICY") {
+            None => SlashPolicy::Escape,
+            Some(s) => match str::parse(s) {
+                Ok(policy) => policy,
+                Err(_) => {
+                    warn!("Invalid `--slash-policy` '{}', using 'escape'.", s);
+                    SlashPolicy::Escape
+                }
+            },
+        };
+        if let Some(escape) = args.value_of("SLASH_ESCAPE") {
+            if escape.is_empty() || escape.contains('/') || escape.contains('\0') {
+                warn!(
+                    "Invalid `--slash-escape` '{}': it must be non-empty and can't contain '/' or NUL; using '{}'.",
+                    escape, config.slash_escape
+                );
+            } else {
+                config.slash_escape = escape.into();
+            }
+        }
+
+        config.case_insensitive = args.is_present("CASE_INSENSITIVE");
+
+        // YAML aliases
+        config.watch_conflict = match args.value_of("WATCH_CONFLICT") {
+            None => WatchConflict::Mount,
+            Some(s) => match str::parse(s) {
+                Ok(watch_conflict) => watch_conflict,
+                Err(_) => {
+                    warn!("Invalid `--watch-conflict` policy '{}', using 'mount'.", s);
+                    WatchConflict::Mount
+                }
+            },
+        };
+
+        config.yaml_aliases = match args.value_of("YAML_ALIASES") {
+            None => YamlAliases::Expand,
+            Some(s) => match str::parse(s) {
+                Ok(yaml_aliases) => yaml_aliases,
+                Err(_) => {
+                    warn!("Invalid `--yaml-aliases` mode '{}', using 'expand'.", s);
+                    YamlAliases::Expand
+                }
+            },
+        };
+
+        config.null_as = match args.value_of("NULL_AS") {
+            None => NullAs::EmptyFile,
+            Some(s) => match str::parse(s) {
+                Ok(null_as) => null_as,
+                Err(_) => {
+                    warn!("Invalid `--null-as` mode '{}', using 'empty_file'.", s);
+                    NullAs::EmptyFile
+                }
+            },
+        };
+
+        config.empty_as = match args.value_of("EMPTY_AS") {
+            None => EmptyAs::Error,
+            Some(s) => match str::parse(s) {
+                Ok(empty_as) => empty_as,
+                Err(_) => {
+                    warn!("Invalid `--empty-as` mode '{}', using 'error'.", s);
+                    EmptyAs::Error
+                }
+            },
+        };
+
+        config.list_rename = match args.value_of("LIST_RENAME") {
+            None => ListRename::Shift,
+            Some(s) => match str::parse(s) {
+                Ok(list_rename) => list_rename,
+                Err(_) => {
+                    warn!("Invalid `--list-rename` mode '{}', using 'shift'.", s);
+                    ListRename::Shift
+                }
+            },
+        };
+
+        config.new_dir_type = match args.value_of("NEW_DIR_TYPE") {
+            None => DirType::Named,
+            Some(s) => match str::parse(s) {
+                Ok(new_dir_type) => new_dir_type,
+                Err(_) => {
+                    warn!("Invalid `--new-dir-type` '{}', using 'named'.", s);
+                    DirType::Named
+                }
+            },
+        };
+
+        // perms
+        config.filemode = match u16::from_str_radix(args.value_of("FILEMODE").unwrap(), 8) {
+            Ok(filemode) => filemode,
+            Err(e) => {
+                error!(
+                    "Couldn't parse `--mode {}`: {}.",
+                    args.value_of("FILEMODE").unwrap(),
+                    e
+                );
+                std::process::exit(ERROR_STATUS_CLI)
+            }
+        };
+        if args.occurrences_of("FILEMODE") > 0 && args.occurrences_of("DIRMODE") == 0 {
+            // wherever a read bit is set, the dirmode should have an execute bit, too
+            config.dirmode = config.filemode;
+            if config.dirmode & 0o400 != 0 {
+                config.dirmode |= 0o100;
+            }
+            if config.dirmode & 0o040 != 0 {
+                config.dirmode |= 0o010;
+            }
+            if config.dirmode & 0o004 != 0 {
                 config.dirmode |= 0o001;
             }
         } else {
@@ -236,6 +1195,27 @@ impl Config {
             None => config.gid = unsafe { libc::getegid() },
         }
 
+        // other users
+        if let Some(uids) = args.values_of("ALLOW_UID") {
+            for uid_string in uids {
+                match uid_string.parse() {
+                    Ok(uid) => config.allowed_uids.push(uid),
+                    Err(e) => {
+                        error!("Couldn't parse `--allow-uid {}`: {}.", uid_string, e);
+                        std::process::exit(ERROR_STATUS_CLI)
+                    }
+                }
+            }
+        }
+        config.allow_other = args.is_present("ALLOW_OTHER") || !config.allowed_uids.is_empty();
+        config.private = args.is_present("PRIVATE");
+        if config.allow_other && unsafe { libc::geteuid() } != 0 && !fuse_allows_other() {
+            error!(
+                "Letting other users use the mount requires `user_allow_other` in /etc/fuse.conf."
+            );
+            std::process::exit(ERROR_STATUS_CLI)
+        }
+
         // two modes: with `--new` flag (infer most stuff) or without (parse other args)
         //
         // TODO 2021-07-06 maybe this would all be better with subcommands. but all that is so _complex_ :(
@@ -271,7 +1251,7 @@ impl Config {
                                 debug!("Inferring output format from input.")
                             }
                         };
-                        match output
+                        match without_gzip(&output)
                             .extension()
                             .and_then(|s| s.to_str())
                             .ok_or(format::ParseFormatError::NoFormatProvided)
@@ -291,7 +1271,7 @@ impl Config {
                 let mount = match args.value_of("MOUNT") {
                     Some(mount_point) => {
                         let mount_point = PathBuf::from(mount_point);
-                        if !mount_point.exists() {
+                        if !mount_point_exists(&mount_point) {
                             error!("Mount point {} does not exist.", mount_point.display());
                             std::process::exit(ERROR_STATUS_FUSE);
                         }
@@ -300,7 +1280,8 @@ impl Config {
                     }
                     None => {
                         // If the output is to a file foo.EXT, then try to make a directory foo.
-                        let stem = output.file_stem().unwrap_or_else(|| {
+                        let output_name = without_gzip(&output);
+                        let stem = output_name.file_stem().unwrap_or_else(|| {
                             error!("Couldn't infer the mountpoint from output '{}'. Use `--mount MOUNT` to specify a mountpoint.", output.display());
                             std::process::exit(ERROR_STATUS_FUSE);
                         });
@@ -334,7 +1315,11 @@ impl Config {
                 // no `--new` flag... so parse everything
 
                 // configure input
+                let inputs = args.values_of("INPUT").map_or(0, |inputs| inputs.len());
                 config.input = match args.value_of("INPUT") {
+                    Some(_) if inputs > 1 => {
+                        Input::Siblings(siblings(args.values_of("INPUT").unwrap(), &args))
+                    }
                     Some(input_source) => {
                         if input_source == "-" {
                             Input::Stdin
@@ -344,15 +1329,112 @@ impl Config {
                                 error!("Input file {} does not exist.", input_source.display());
                                 std::process::exit(ERROR_STATUS_FUSE);
                             }
-                            Input::File(input_source)
+                            if input_source.is_dir() {
+                                Input::Directory(input_source)
+                            } else {
+                                Input::File(input_source)
+                            }
                         }
                     }
                     None => Input::Stdin,
                 };
+                if config.analyze && matches!(config.input, Input::Siblings(_)) {
+                    error!("`--analyze` can't be used with several input files.");
+                    std::process::exit(ERROR_STATUS_CLI);
+                }
+                if config.analyze && matches!(config.input, Input::Directory(_)) {
+                    error!("`--analyze` can't be used with a directory.");
+                    std::process::exit(ERROR_STATUS_CLI);
+                }
+
+                // configure the mounted subtree
+                if let Some(pointer) = args.value_of("POINTER") {
+                    if format::pointer_tokens(pointer).is_none() {
+                        error!(
+                            "Invalid `--pointer {}`: JSON Pointers must be empty or start with '/'.",
+                            pointer
+                        );
+                        std::process::exit(ERROR_STATUS_CLI);
+                    }
+                    if config.yaml_aliases == YamlAliases::Link {
+                        error!("`--yaml-aliases link` can't be used with `--pointer`, since links may point outside of the mounted subtree.");
+                        std::process::exit(ERROR_STATUS_CLI);
+                    }
+                    if let Input::Siblings(_) = config.input {
+                        error!("`--pointer` can't be used with several input files.");
+                        std::process::exit(ERROR_STATUS_CLI);
+                    }
+                    if let Input::Directory(_) = config.input {
+                        error!("`--pointer` can't be used with a directory.");
+                        std::process::exit(ERROR_STATUS_CLI);
+                    }
+                    config.root_pointer = Some(pointer.into());
+                }
+
+                // configure the overlay
+                if let Some(overlay) = args.value_of("OVERLAY") {
+                    match config.input {
+                        Input::Siblings(_) => {
+                            error!("`--merge` can't be used with several input files.");
+                            std::process::exit(ERROR_STATUS_CLI);
+                        }
+                        Input::Directory(_) => {
+                            error!("`--merge` can't be used with a directory.");
+                            std::process::exit(ERROR_STATUS_CLI);
+                        }
+                        Input::Empty => {
+                            error!("`--merge` needs an input to merge into.");
+                            std::process::exit(ERROR_STATUS_CLI);
+                        }
+                        Input::Stdin | Input::File(_) => (),
+                    }
+                    let overlay = PathBuf::from(overlay);
+                    if !overlay.exists() {
+                        error!("Overlay file {} does not exist.", overlay.display());
+                        std::process::exit(ERROR_STATUS_FUSE);
+                    }
+                    config.overlay = Some(overlay);
+                }
+                config.merge_arrays = match args.value_of("MERGE_ARRAYS") {
+                    None => MergeArrays::Replace,
+                    Some(s) => match str::parse(s) {
+                        Ok(merge_arrays) => merge_arrays,
+                        Err(_) => {
+                            warn!("Invalid `--merge-arrays` '{}', using 'replace'.", s);
+                            MergeArrays::Replace
+                        }
+                    },
+                };
+                config.write_overlay_only = args.is_present("WRITE_OVERLAY_ONLY");
 
                 // configure output
                 config.output = if let Some(output) = args.value_of("OUTPUT") {
-                    Output::File(PathBuf::from(output))
+                    if let Input::Siblings(_) = config.input {
+                        error!("`--output` can't be used with several input files; each is saved back to itself.");
+                        std::process::exit(ERROR_STATUS_CLI);
+                    }
+                    if let Input::Directory(_) = config.input {
+                        error!("`--output` can't be used with a directory; each file is saved back to itself.");
+                        std::process::exit(ERROR_STATUS_CLI);
+                    }
+                    if output == "-" {
+                        Output::Stdout
+                    } else {
+                        Output::File(PathBuf::from(output))
+                    }
+                } else if matches!(config.input, Input::Siblings(_))
+                    && !args.is_present("NOOUTPUT")
+                    && !args.is_present("QUIET")
+                {
+                    Output::Siblings
+                } else if matches!(config.input, Input::Directory(_))
+                    && !args.is_present("NOOUTPUT")
+                    && !args.is_present("QUIET")
+                {
+                    Output::Directory
+                } else if config.write_overlay_only {
+                    // see `FS::save_overlay`
+                    Output::File(config.overlay.clone().unwrap())
                 } else if args.is_present("INPLACE") {
                     match &config.input {
                         Input::Stdin => {
@@ -368,6 +1450,8 @@ impl Config {
                             Output::Stdout
                         }
                         Input::File(input_source) => Output::File(input_source.clone()),
+                        Input::Siblings(_) => Output::Siblings,
+                        Input::Directory(_) => Output::Directory,
                     }
                 } else if args.is_present("NOOUTPUT") || args.is_present("QUIET") {
                     Output::Quiet
@@ -379,13 +1463,15 @@ impl Config {
                 config.mount = match args.value_of("MOUNT") {
                     Some(mount_point) => {
                         let mount_point = PathBuf::from(mount_point);
-                        if !mount_point.exists() {
+                        if !mount_point_exists(&mount_point) {
                             error!("Mount point {} does not exist.", mount_point.display());
                             std::process::exit(ERROR_STATUS_FUSE);
                         }
                         config.cleanup_mount = false;
                         Some(mount_point)
                     }
+                    // nothing to mount
+                    None if config.analyze || config.convert => None,
                     None => {
                         match &config.input {
                             Input::Stdin => {
@@ -398,9 +1484,20 @@ impl Config {
                                 );
                                 std::process::exit(ERROR_STATUS_CLI);
                             }
+                            Input::Siblings(_) => {
+                                error!(
+                                    "You must specify a mount point when reading several files."
+                                );
+                                std::process::exit(ERROR_STATUS_CLI);
+                            }
+                            Input::Directory(_) => {
+                                error!("You must specify a mount point when reading a directory.");
+                                std::process::exit(ERROR_STATUS_CLI);
+                            }
                             Input::File(file) => {
                                 // If the input is from a file foo.EXT, then try to make a directory foo.
-                                let stem = file.file_stem().unwrap_or_else(|| {
+                                let file_name = without_gzip(file);
+                                let stem = file_name.file_stem().unwrap_or_else(|| {
                                     error!("Couldn't infer the mountpoint from input '{}'. Use `--mount MOUNT` to specify a mountpoint.", file.display());
                                     std::process::exit(ERROR_STATUS_FUSE);
                                 });
@@ -429,7 +1526,7 @@ impl Config {
                         }
                     }
                 };
-                assert!(config.mount.is_some());
+                assert!(config.analyze || config.convert || config.mount.is_some());
 
                 // try to autodetect the input format.
                 //
@@ -454,22 +1551,28 @@ impl Config {
                             }
                         };
                         match &config.input {
-                            Input::Stdin => Format::Json,
+                            Input::Stdin => sniff_format(&config.input).unwrap_or(Format::Json),
                             Input::Empty => Format::Json,
-                            Input::File(input_source) => match input_source
+                            Input::Siblings(siblings) => siblings[0].format,
+                            // each file is read in its own format
+                            Input::Directory(_) => Format::Json,
+                            Input::File(input_source) => match without_gzip(input_source)
                                 .extension()
                                 .and_then(|s| s.to_str())
                                 .ok_or(format::ParseFormatError::NoFormatProvided)
                                 .and_then(|s| s.parse::<Format>())
                             {
                                 Ok(format) => format,
-                                Err(_) => {
-                                    warn!(
-                                        "Unrecognized format {}, defaulting to JSON.",
-                                        input_source.display()
-                                    );
-                                    Format::Json
-                                }
+                                Err(_) => match sniff_format(&config.input) {
+                                    Some(format) => format,
+                                    None => {
+                                        warn!(
+                                            "Unrecognized format {}, defaulting to JSON.",
+                                            input_source.display()
+                                        );
+                                        Format::Json
+                                    }
+                                },
                             },
                         }
                     }
@@ -482,6 +1585,10 @@ impl Config {
                 // then see if we can pull it out of the extension (if specified)
                 //
                 // then give up and use the input format
+                if let Input::Siblings(siblings) = &config.input {
+                    // each file is saved in its own format
+                    config.input_format = siblings[0].format;
+                }
                 config.output_format = match args
                     .value_of("TARGET_FORMAT")
                     .ok_or(format::ParseFormatError::NoFormatProvided)
@@ -502,18 +1609,29 @@ impl Config {
                         };
                         match args
                             .value_of("OUTPUT")
-                            .and_then(|s| Path::new(s).extension())
+                            .map(|s| without_gzip(Path::new(s)))
+                            .as_deref()
+                            .and_then(Path::extension)
                             .and_then(|s| s.to_str())
                         {
                             Some(s) => match s.parse::<Format>() {
                                 Ok(format) => format,
-                                Err(_) => {
+                                // an existing file (e.g., from `mktemp`) is
+                                // taken to hold the input's format
+                                Err(_) if Path::new(args.value_of("OUTPUT").unwrap()).exists() => {
                                     warn!(
                                         "Unrecognized format {}, defaulting to input format '{}'.",
                                         s, config.input_format
                                     );
                                     config.input_format
                                 }
+                                Err(_) => {
+                                    error!(
+                                        "Unrecognized output format '{}'; use --target or a known extension to specify a format.",
+                                        s
+                                    );
+                                    std::process::exit(ERROR_STATUS_CLI);
+                                }
                             },
                             None => config.input_format,
                         }
@@ -522,31 +1640,190 @@ impl Config {
             }
         };
 
-        if config.pretty && !config.output_format.can_be_pretty() {
-            warn!(
-                "There is no pretty printing routine for {}.",
-                config.output_format
-            )
-        }
+        config.compress_output = args.is_present("COMPRESS_OUTPUT")
+            || matches!(&config.output, Output::File(file) if is_gzip_path(file));
 
-        config
-    }
+        // an overlay is meant to be overwritten, as is the input
+        config.no_clobber = args.is_present("NO_CLOBBER");
+        if config.no_clobber && !config.write_overlay_only {
+            if let Output::File(output) = &config.output {
+                let same_as_input = match &config.input {
+                    Input::File(input) => {
+                        input == output
+                            || matches!((input.canonicalize(), output.canonicalize()), (Ok(i), Ok(o)) if i == o)
+                    }
+                    _ => false,
+                };
+                if output.exists() && !same_as_input {
+                    error!(
+                        "Output file {} already exists; use --force to overwrite it.",
+                        output.display()
+                    );
+                    std::process::exit(ERROR_STATUS_CLI);
+                }
+            }
+        }
 
-    pub fn valid_name(&self, s: &str) -> bool {
-        s != "." && s != ".." && !s.contains('\0') && !s.contains('/')
-    }
+        // protobuf can't be read or written without its schema
+        if config.input_format == Format::Protobuf || config.output_format == Format::Protobuf {
+            if let Err(e) = format::protobuf::message_descriptor(&config) {
+                error!("Couldn't use the protobuf schema: {}.", e);
+                std::process::exit(ERROR_STATUS_CLI);
+            }
+        }
 
-    pub fn normalize_name(&self, s: String) -> String {
-        if s == "." {
-            "_.".into()
-        } else if s == ".." {
-            "_..".into()
-        } else {
-            s.replace("\0", "_NUL_").replace("/", "_SLASH_")
+        // fail early on an unreadable JSON Schema, rather than at every save
+        if let Err(e) = format::json::schema(&config) {
+            error!("Couldn't use the JSON Schema: {}.", e);
+            std::process::exit(ERROR_STATUS_CLI);
         }
-    }
 
-    #[cfg(target_os = "macos")]
+        // final newlines: explicit modes win, otherwise we try to match the input
+        config.final_newline = match args.value_of("FINAL_NEWLINE") {
+            None => FinalNewline::Auto,
+            Some(s) => match str::parse(s) {
+                Ok(final_newline) => final_newline,
+                Err(_) => {
+                    warn!("Invalid `--final-newline` mode '{}', using 'auto'.", s);
+                    FinalNewline::Auto
+                }
+            },
+        };
+        if config.final_newline == FinalNewline::Auto
+            && config.input_format == config.output_format
+            && config.input_format.is_text()
+        {
+            if let Input::File(file) = &config.input {
+                match sniff_final_newline(file) {
+                    Some(true) => config.final_newline = FinalNewline::Always,
+                    Some(false) => config.final_newline = FinalNewline::Never,
+                    None => (),
+                }
+            }
+        }
+
+        // pretty printing: explicit flags win, otherwise we try to match the input
+        if let Some(indent) = args.value_of("INDENT") {
+            config.indent = match indent.parse::<usize>() {
+                Ok(indent) => indent,
+                Err(e) => {
+                    error!("Couldn't parse `--indent {}`: {}.", indent, e);
+                    std::process::exit(ERROR_STATUS_CLI)
+                }
+            };
+        }
+        if let Some(s) = args.value_of("OUTPUT_ENCODING") {
+            config.output_encoding = match str::parse(s) {
+                Ok(encoding) => Some(encoding),
+                Err(_) => {
+                    warn!("Invalid `--output-encoding` '{}', using the input's.", s);
+                    None
+                }
+            };
+        }
+        if let Some(s) = args.value_of("INDENT_CHAR") {
+            config.indent_char = match str::parse(s) {
+                Ok(indent_char) => indent_char,
+                Err(_) => {
+                    warn!("Invalid `--indent-char` '{}', using 'space'.", s);
+                    IndentChar::Space
+                }
+            };
+        }
+        config.pretty = if args.is_present("PRETTY")
+            || args.is_present("INDENT")
+            || args.is_present("INDENT_CHAR")
+        {
+            true
+        } else if args.is_present("COMPACT") {
+            false
+        } else if config.input_format == Format::Json && config.output_format == Format::Json {
+            match &config.input {
+                Input::File(file) => match sniff_json_indent(file) {
+                    Some((indent_char, indent)) => {
+                        debug!(
+                            "input is pretty printed with indent {} ({})",
+                            indent, indent_char
+                        );
+                        // explicit flags still win
+                        if !args.is_present("INDENT_CHAR") {
+                            config.indent_char = indent_char;
+                        }
+                        config.indent = indent;
+                        true
+                    }
+                    None => false,
+                },
+                Input::Stdin | Input::Empty | Input::Siblings(_) | Input::Directory(_) => false,
+            }
+        } else {
+            false
+        };
+
+        if config.pretty && !config.output_format.can_be_pretty() {
+            warn!(
+                "There is no pretty printing routine for {}.",
+                config.output_format
+            )
+        }
+        if config.pretty
+            && config.indent_char == IndentChar::Tab
+            && config.output_format == Format::Yaml
+        {
+            warn!("YAML can't be indented with tabs; using spaces.");
+        }
+
+        if config.watch && !matches!(config.input, Input::File(_)) {
+            error!("`--watch` needs an input file to watch.");
+            std::process::exit(ERROR_STATUS_CLI);
+        }
+
+        if config.stream
+            && (config.input_format != Format::Ndjson
+                || config.output_format != Format::Ndjson
+                || !matches!(config.output, Output::File(_))
+                || config.encoding() != TextEncoding::Utf8
+                || config.root_pointer.is_some()
+                || config.write_overlay_only
+                || config.dry_run)
+        {
+            warn!("`--stream` only works when saving NDJSON to a UTF-8 file as a whole; saving at unmount instead.");
+            config.stream = false;
+        }
+
+        config.ignore_file = match args.value_of("IGNORE_FILE") {
+            Some(file) => Some(PathBuf::from(file)),
+            None => match &config.input {
+                Input::File(file) => Some(file.with_file_name(IGNORE_FILE))
+                    .filter(|ignore_file| ignore_file.is_file()),
+                Input::Directory(dir) => {
+                    Some(dir.join(IGNORE_FILE)).filter(|ignore_file| ignore_file.is_file())
+                }
+                Input::Stdin | Input::Empty | Input::Siblings(_) => None,
+            },
+        };
+        config.ignore = config.ignore_file.as_deref().map(read_ignore_file);
+
+        config
+    }
+
+    pub fn valid_name(&self, s: &str) -> bool {
+        !s.is_empty() && s != "." && s != ".." && !s.contains('\0') && !s.contains('/')
+    }
+
+    pub fn normalize_name(&self, s: String) -> String {
+        if s.is_empty() {
+            "_EMPTY_".into()
+        } else if s == "." {
+            "_.".into()
+        } else if s == ".." {
+            "_..".into()
+        } else {
+            s.replace("\0", "_NUL_").replace("/", &self.slash_escape)
+        }
+    }
+
+    #[cfg(target_os = "macos")]
     fn platform_ignored_file(&self, s: &str) -> bool {
         !self.keep_macos_xattr_file && s.starts_with("._")
     }
@@ -566,6 +1843,52 @@ impl Config {
         s == "." || s == ".." || self.platform_ignored_file(s)
     }
 
+    /// Returns `true` when `path`, the keys leading to a value from the root,
+    /// matches one of the `exclude` patterns or is ignored by `ignore_file`.
+    /// Some ignore rules only match directories, hence `is_dir`.
+    pub fn excluded<S>(&self, path: &[S], is_dir: bool) -> bool
+    where
+        S: AsRef<str>,
+    {
+        if glob_match_any(&self.exclude, path) {
+            return true;
+        }
+
+        match &self.ignore {
+            Some(ignore) => {
+                let path = path.iter().map(AsRef::as_ref).collect::<Vec<_>>();
+                ignore.matched(path.join("/"), is_dir).is_ignore()
+            }
+            None => false,
+        }
+    }
+
+    /// Returns `true` when `path`, the keys leading to a value from the root,
+    /// matches one of the `blob_keys` patterns.
+    pub fn blob<S>(&self, path: &[S]) -> bool
+    where
+        S: AsRef<str>,
+    {
+        glob_match_any(&self.blob_keys, path)
+    }
+
+    /// The name `--name-map` gives the key at `path` (the keys leading to it
+    /// from the root), if any.
+    pub fn mapped_name<S>(&self, path: &[S]) -> Option<&str>
+    where
+        S: AsRef<str>,
+    {
+        self.name_map
+            .iter()
+            .find(|(pointer, _)| {
+                format::pointer_tokens(pointer).is_some_and(|tokens| {
+                    tokens.len() == path.len()
+                        && tokens.iter().zip(path).all(|(t, k)| t == k.as_ref())
+                })
+            })
+            .map(|(_, name)| name.as_str())
+    }
+
     /// Determines the default mode of a file
     pub fn mode(&self, kind: FileType) -> u16 {
         if kind == FileType::Directory {
@@ -575,42 +1898,774 @@ impl Config {
         }
     }
 
+    /// The FUSE mount options for this configuration.
+    pub fn mount_options(&self) -> Vec<MountOption> {
+        let mut options = vec![MountOption::FSName(format!("{}", self.input))];
+        if self.read_only {
+            options.push(MountOption::RO);
+        }
+        if self.noatime {
+            options.push(MountOption::NoAtime);
+        }
+        if self.allow_other {
+            options.push(MountOption::AllowOther);
+        }
+        options
+    }
+
+    /// The filename of element `index` of a list with `len` elements: its
+    /// index, padded with zeroes so that the names sort in order (unless
+    /// `Config::pad_element_names` is off).
+    pub fn element_name(&self, index: usize, len: usize) -> String {
+        if !self.pad_element_names {
+            return format!("{}", index);
+        }
+
+        let width = (len as f64).log10().ceil() as usize;
+        let width = width.max(self.index_width.unwrap_or(0));
+        format!("{:0width$}", index, width = width)
+    }
+
+    /// One level of indentation for pretty-printed output (see
+    /// `Config::indent` and `Config::indent_char`).
+    pub fn indent_string(&self) -> String {
+        self.indent_char.as_char().to_string().repeat(self.indent)
+    }
+
     /// Generate a reader for input
     ///
     /// A return of `None` means to start from an empty named directory
-    pub fn input_reader(&self) -> Option<Box<dyn std::io::Read>> {
-        match &self.input {
-            Input::Stdin => Some(Box::new(std::io::stdin())),
+    ///
+    /// Text input is transcoded to UTF-8 (see `Config::decode_input`), which
+    /// sets `input_encoding`.
+    pub fn input_reader(&mut self) -> Option<Box<dyn std::io::Read>> {
+        let reader: Box<dyn std::io::Read> = match &self.input {
+            Input::Stdin => Box::new(std::io::stdin()),
             Input::File(file) => {
                 let fmt = self.input_format;
                 let file = std::fs::File::open(&file).unwrap_or_else(|e| {
                     error!("Unable to open {} for {} input: {}", file.display(), fmt, e);
                     std::process::exit(ERROR_STATUS_FUSE);
                 });
-                Some(Box::new(file))
+                Box::new(file)
             }
-            Input::Empty => None,
+            Input::Empty => return None,
+            Input::Siblings(_) => unreachable!("siblings are read one at a time"),
+            Input::Directory(_) => unreachable!("directories are read one file at a time"),
+        };
+        let reader = self.gunzip_input(reader);
+
+        if self.input_format.is_text() {
+            Some(self.decode_input(reader))
+        } else {
+            Some(reader)
         }
     }
 
-    /// Generate a writer for output
+    /// Decompresses `reader` if it starts with the gzip magic number. Saving a
+    /// gzipped file back over itself keeps it gzipped.
+    fn gunzip_input(&mut self, mut reader: Box<dyn std::io::Read>) -> Box<dyn std::io::Read> {
+        use std::io::{Cursor, Read};
+
+        let mut magic = Vec::with_capacity(GZIP_MAGIC.len());
+        if let Err(e) = reader
+            .by_ref()
+            .take(GZIP_MAGIC.len() as u64)
+            .read_to_end(&mut magic)
+        {
+            error!("Unable to read {}: {}", self.input, e);
+            std::process::exit(ERROR_STATUS_FUSE);
+        }
+
+        let compressed = magic == GZIP_MAGIC;
+        let reader = Box::new(Cursor::new(magic).chain(reader));
+        if !compressed {
+            return reader;
+        }
+
+        debug!("input is gzipped");
+        if let (Input::File(input), Output::File(output)) = (&self.input, &self.output) {
+            if input == output {
+                self.compress_output = true;
+            }
+        }
+        Box::new(flate2::read::GzDecoder::new(reader))
+    }
+
+    /// Determines the encoding of `reader` from its byte order mark (if any),
+    /// recording it in `input_encoding`, and gives its contents as UTF-8.
+    fn decode_input(&mut self, mut reader: Box<dyn std::io::Read>) -> Box<dyn std::io::Read> {
+        use std::io::{Cursor, Read};
+
+        let mut bom = Vec::with_capacity(3);
+        if let Err(e) = reader.by_ref().take(3).read_to_end(&mut bom) {
+            error!("Unable to read {}: {}", self.input, e);
+            std::process::exit(ERROR_STATUS_FUSE);
+        }
+
+        let (encoding, bom_len) = match encoding_rs::Encoding::for_bom(&bom) {
+            None => return Box::new(Cursor::new(bom).chain(reader)),
+            Some((encoding, bom_len)) if encoding == encoding_rs::UTF_8 => {
+                self.input_encoding = TextEncoding::Utf8Bom;
+                return Box::new(Cursor::new(bom.split_off(bom_len)).chain(reader));
+            }
+            Some((encoding, bom_len)) => (encoding, bom_len),
+        };
+        self.input_encoding = if encoding == encoding_rs::UTF_16LE {
+            TextEncoding::Utf16Le
+        } else {
+            TextEncoding::Utf16Be
+        };
+        debug!("input is {}", self.input_encoding);
+
+        // UTF-16 isn't self-synchronizing, so we transcode all at once
+        let mut bytes = bom.split_off(bom_len);
+        if let Err(e) = reader.read_to_end(&mut bytes) {
+            error!("Unable to read {}: {}", self.input, e);
+            std::process::exit(ERROR_STATUS_FUSE);
+        }
+        let (text, malformed) = encoding.decode_without_bom_handling(&bytes);
+        if malformed {
+            warn!(
+                "{} isn't entirely valid {}; replaced the invalid parts with U+FFFD.",
+                self.input, self.input_encoding
+            );
+        }
+        Box::new(Cursor::new(text.into_owned().into_bytes()))
+    }
+
+    /// The text encoding to save in: `output_encoding`, or else the input's.
+    fn encoding(&self) -> TextEncoding {
+        self.output_encoding.unwrap_or(self.input_encoding)
+    }
+
+    /// Generate a writer for output, which must be committed (see `Commit`)
     ///
     /// A return of `None` means no output should be provided
-    pub fn output_writer(&self) -> Option<Box<dyn std::io::Write>> {
+    pub fn output_writer(&self) -> Option<Box<dyn Commit>> {
+        if self.dry_run && self.output != Output::Quiet {
+            debug!("dry run, reporting output as a diff on STDERR");
+            let dry_run = Box::new(DryRun::new(&self.input, &self.output));
+            return Some(self.final_newline_writer(dry_run));
+        }
+
         match &self.output {
             Output::Stdout => {
                 debug!("outputting on STDOUT");
-                Some(Box::new(std::io::stdout()))
+                let stdout = self.compressor(Box::new(Stdout));
+                Some(self.final_newline_writer(self.encoder(stdout)))
             }
             Output::File(path) => {
                 debug!("output {}", path.display());
-                Some(Box::new(File::create(path).unwrap()))
+                let file = self.compressor(Box::new(AtomicFile::new(path)));
+                Some(self.final_newline_writer(self.encoder(file)))
             }
             Output::Quiet => {
                 debug!("no output path, skipping");
                 None
             }
+            Output::Siblings => {
+                debug!("saving each sibling to its own file");
+                None
+            }
+            Output::Directory => {
+                debug!("saving each file in the directory back to itself");
+                None
+            }
+        }
+    }
+
+    /// Wraps `writer` in a `FinalNewlineWriter`, unless `final_newline` leaves
+    /// the output alone (or the output isn't text).
+    fn final_newline_writer(&self, writer: Box<dyn Commit>) -> Box<dyn Commit> {
+        let newline = match self.final_newline {
+            FinalNewline::Auto => return writer,
+            _ if !self.output_format.is_text() => return writer,
+            FinalNewline::Always => true,
+            FinalNewline::Never => false,
+        };
+
+        debug!("output ends with a newline: {}", newline);
+        Box::new(FinalNewlineWriter {
+            inner: writer,
+            newline,
+            text: Vec::new(),
+        })
+    }
+
+    /// Wraps `writer` in a gzip encoder if `compress_output` is set. The gzip
+    /// trailer is written when the encoder is committed.
+    fn compressor(&self, writer: Box<dyn Commit>) -> Box<dyn Commit> {
+        if !self.compress_output {
+            return writer;
+        }
+
+        debug!("gzipping output");
+        Box::new(flate2::write::GzEncoder::new(
+            writer,
+            flate2::Compression::default(),
+        ))
+    }
+
+    /// Wraps `writer` in an `Encoder`, unless the output is UTF-8 anyway (or
+    /// isn't text).
+    fn encoder(&self, writer: Box<dyn Commit>) -> Box<dyn Commit> {
+        let encoding = self.encoding();
+        if encoding == TextEncoding::Utf8 || !self.output_format.is_text() {
+            return writer;
+        }
+
+        debug!("encoding output as {}", encoding);
+        Box::new(Encoder {
+            inner: writer,
+            encoding,
+            text: Vec::new(),
+        })
+    }
+
+    /// Generate a writer for a file that's saved back on its own, like one of
+    /// the `Input::Siblings` or an included file (see `Config::include_tag`).
+    pub fn file_writer(&self, file: &Path) -> Box<dyn Commit> {
+        if self.dry_run {
+            let file = file.to_path_buf();
+            return Box::new(DryRun::new(&Input::File(file.clone()), &Output::File(file)));
+        }
+
+        debug!("output {}", file.display());
+        Box::new(AtomicFile::new(file))
+    }
+}
+
+/// Builds the `Input::Siblings` for several input files. Each file is named
+/// by its stem; its format comes from `--source` or its extension.
+fn siblings<'a>(inputs: clap::Values<'a>, args: &clap::ArgMatches<'a>) -> Vec<Sibling> {
+    let source_format = args
+        .value_of("SOURCE_FORMAT")
+        .and_then(|s| s.parse::<Format>().ok());
+
+    let mut siblings: Vec<Sibling> = Vec::new();
+    for input in inputs {
+        let file = PathBuf::from(input);
+        if input == "-" {
+            error!("STDIN can't be one of several input files.");
+            std::process::exit(ERROR_STATUS_CLI);
+        }
+        if !file.exists() {
+            error!("Input file {} does not exist.", file.display());
+            std::process::exit(ERROR_STATUS_FUSE);
+        }
+
+        let name = match file.file_stem().and_then(|s| s.to_str()) {
+            Some(name) => name.to_string(),
+            None => {
+                error!("Couldn't name the subtree for input '{}'.", file.display());
+                std::process::exit(ERROR_STATUS_CLI);
+            }
+        };
+        if let Some(other) = siblings.iter().find(|sibling| sibling.name == name) {
+            error!(
+                "Input files {} and {} would both be mounted as '{}'.",
+                other.file.display(),
+                file.display(),
+                name
+            );
+            std::process::exit(ERROR_STATUS_CLI);
         }
+
+        let format = source_format.unwrap_or_else(|| {
+            match file
+                .extension()
+                .and_then(|s| s.to_str())
+                .ok_or(format::ParseFormatError::NoFormatProvided)
+                .and_then(|s| s.parse::<Format>())
+            {
+                Ok(format) => format,
+                Err(_) => {
+                    warn!(
+                        "Unrecognized format {}, defaulting to JSON.",
+                        file.display()
+                    );
+                    Format::Json
+                }
+            }
+        });
+
+        siblings.push(Sibling { name, file, format });
+    }
+
+    siblings
+}
+
+/// Checks whether `/etc/fuse.conf` lets non-root users mount with
+/// `allow_other`.
+fn fuse_allows_other() -> bool {
+    match std::fs::read_to_string("/etc/fuse.conf") {
+        Ok(conf) => conf.lines().any(|line| line.trim() == "user_allow_other"),
+        Err(_) => false,
+    }
+}
+
+/// Parses a size in bytes, with an optional `K`, `M`, or `G` suffix (in
+/// powers of 1024, as for tmpfs's `size=` option).
+fn parse_size(size: &str) -> Result<u64, String> {
+    let (digits, scale) = match size.chars().last() {
+        Some('k' | 'K') => (&size[..size.len() - 1], 1 << 10),
+        Some('m' | 'M') => (&size[..size.len() - 1], 1 << 20),
+        Some('g' | 'G') => (&size[..size.len() - 1], 1 << 30),
+        _ => (size, 1),
+    };
+    let n = digits.parse::<u64>().map_err(|e| e.to_string())?;
+    n.checked_mul(scale)
+        .ok_or_else(|| "size too large".to_string())
+}
+
+/// The default `Config::ignore_file`, looked for next to the input.
+const IGNORE_FILE: &str = ".ffsignore";
+
+/// Reads the gitignore-style rules in `file`; paths are matched relative to
+/// the document root.
+fn read_ignore_file(file: &Path) -> Gitignore {
+    let mut builder = GitignoreBuilder::new("");
+    if let Some(e) = builder.add(file) {
+        error!("Couldn't read the ignore file {}: {}.", file.display(), e);
+        std::process::exit(ERROR_STATUS_CLI);
+    }
+    match builder.build() {
+        Ok(ignore) => {
+            debug!(
+                "ignoring {} paths per {}",
+                ignore.num_ignores(),
+                file.display()
+            );
+            ignore
+        }
+        Err(e) => {
+            error!("Couldn't read the ignore file {}: {}.", file.display(), e);
+            std::process::exit(ERROR_STATUS_CLI);
+        }
+    }
+}
+
+/// Does any of the globs in `patterns` match `path`?
+fn glob_match_any<S>(patterns: &[String], path: &[S]) -> bool
+where
+    S: AsRef<str>,
+{
+    patterns.iter().any(|pattern| {
+        let pattern = pattern
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<_>>();
+        glob_match(&pattern, path)
+    })
+}
+
+/// Matches a glob `pattern`, split on `/`, against `path`: `**` matches any
+/// number of path segments, and within a segment `*` matches any run of
+/// characters and `?` matches any one character.
+fn glob_match<S>(pattern: &[&str], path: &[S]) -> bool
+where
+    S: AsRef<str>,
+{
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| glob_match(rest, &path[skip..])),
+        Some((segment, rest)) => match path.split_first() {
+            Some((name, path)) => {
+                let segment = segment.chars().collect::<Vec<_>>();
+                let name = name.as_ref().chars().collect::<Vec<_>>();
+                segment_match(&segment, &name) && glob_match(rest, path)
+            }
+            None => false,
+        },
+    }
+}
+
+fn segment_match(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|skip| segment_match(rest, &name[skip..])),
+        Some(('?', rest)) => !name.is_empty() && segment_match(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && segment_match(rest, &name[1..]),
+    }
+}
+
+/// Guesses the format of `input` from the start of its contents (see
+/// `Format::sniff`). We only peek at STDIN's buffer, so the bytes we look at
+/// are still there to be read.
+fn sniff_format(input: &Input) -> Option<Format> {
+    use std::io::{BufRead, Read};
+
+    let mut prefix = Vec::with_capacity(4096);
+    match input {
+        Input::Stdin => {
+            let mut stdin = std::io::stdin().lock();
+            prefix.extend_from_slice(stdin.fill_buf().ok()?);
+        }
+        Input::File(file) => {
+            File::open(file)
+                .ok()?
+                .take(4096)
+                .read_to_end(&mut prefix)
+                .ok()?;
+        }
+        _ => return None,
+    };
+    if prefix.starts_with(&GZIP_MAGIC) {
+        // the prefix is truncated, so decoding it runs into an early end;
+        // whatever came out before then is enough to go on
+        let mut decoded = Vec::with_capacity(4096);
+        let _ = flate2::read::GzDecoder::new(&prefix[..])
+            .take(4096)
+            .read_to_end(&mut decoded);
+        prefix = decoded;
+    }
+    let format = Format::sniff(&prefix)?;
+    info!(
+        "Guessing that {} is {} from its contents; use `--source` to override.",
+        input, format
+    );
+    Some(format)
+}
+
+/// The first two bytes of every gzip stream.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Does `path` name a gzipped file?
+fn is_gzip_path(path: &Path) -> bool {
+    path.extension() == Some("gz".as_ref())
+}
+
+/// `path` without its `.gz` extension (if any), so `foo.json.gz` is read as
+/// JSON and mounted on `foo`.
+fn without_gzip(path: &Path) -> PathBuf {
+    if is_gzip_path(path) {
+        path.with_extension("")
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// Determines whether `mount` exists, counting a mountpoint whose ffs died
+/// without unmounting it (so it's `ENOTCONN`), which `ffs::mount_session`
+/// can retry.
+fn mount_point_exists(mount: &Path) -> bool {
+    match std::fs::metadata(mount) {
+        Ok(_) => true,
+        Err(e) => e.raw_os_error() == Some(libc::ENOTCONN),
+    }
+}
+
+/// Determines whether `file` ends in a newline. Gzipped files (and files we
+/// can't read) give `None`.
+fn sniff_final_newline(file: &Path) -> Option<bool> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let mut file = File::open(file).ok()?;
+    let mut magic = [0; 2];
+    if file.read_exact(&mut magic).is_ok() && magic == GZIP_MAGIC {
+        return None;
+    }
+
+    let mut last = [0; 1];
+    file.seek(SeekFrom::End(-1)).ok()?;
+    file.read_exact(&mut last).ok()?;
+    Some(last[0] == b'\n')
+}
+
+/// Guesses whether a JSON file was pretty printed, returning its indent
+/// character and width.
+///
+/// We only look at the start of the file: the first indented line determines
+/// the indent; if there are no indented lines, the file is compact.
+fn sniff_json_indent(file: &Path) -> Option<(IndentChar, usize)> {
+    use std::io::{BufRead, BufReader, Read};
+
+    let file = File::open(file).ok()?;
+    BufReader::new(file.take(1 << 16))
+        .lines()
+        .skip(1)
+        .map_while(Result::ok)
+        .find_map(|line| {
+            let indent_char = if line.starts_with('\t') {
+                IndentChar::Tab
+            } else {
+                IndentChar::Space
+            };
+            let indent = line.len() - line.trim_start_matches(indent_char.as_char()).len();
+            if indent > 0 {
+                Some((indent_char, indent))
+            } else {
+                None
+            }
+        })
+}
+
+/// A writer for saving the output. Nothing is saved until it's committed:
+/// some writers (e.g., `Encoder`) buffer everything, and `AtomicFile` only
+/// replaces its target at the end. Dropping one without committing it
+/// abandons the output.
+pub trait Commit: std::io::Write {
+    /// Finishes saving, reporting whether it worked.
+    fn commit(self: Box<Self>) -> std::io::Result<()>;
+}
+
+/// A writer for `--dry-run`: buffers output, and when committed (i.e., when
+/// saving is done) reports a unified diff of the input against the output on
+/// STDERR.
+struct DryRun {
+    original: String,
+    original_name: String,
+    output: Vec<u8>,
+    output_name: String,
+}
+
+impl DryRun {
+    fn new(input: &Input, output: &Output) -> Self {
+        let original = match input {
+            Input::File(file) => std::fs::read(file)
+                .and_then(|bytes| {
+                    if !bytes.starts_with(&GZIP_MAGIC) {
+                        return Ok(bytes);
+                    }
+                    use std::io::Read;
+
+                    let mut decoded = Vec::new();
+                    flate2::read::GzDecoder::new(&bytes[..]).read_to_end(&mut decoded)?;
+                    Ok(decoded)
+                })
+                .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+                .unwrap_or_else(|e| {
+                    warn!("Couldn't re-read {} for --dry-run: {}", file.display(), e);
+                    String::new()
+                }),
+            Input::Stdin | Input::Empty | Input::Siblings(_) | Input::Directory(_) => String::new(),
+        };
+
+        let output_name = match output {
+            Output::File(file) => file.display().to_string(),
+            Output::Stdout | Output::Quiet | Output::Siblings | Output::Directory => {
+                "<stdout>".into()
+            }
+        };
+
+        DryRun {
+            original,
+            original_name: input.to_string(),
+            output: Vec::new(),
+            output_name,
+        }
+    }
+}
+
+impl std::io::Write for DryRun {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.output.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Commit for DryRun {
+    fn commit(self: Box<Self>) -> std::io::Result<()> {
+        let output = String::from_utf8_lossy(&self.output);
+        let diff = similar::TextDiff::from_lines(self.original.as_str(), &*output);
+        eprint!(
+            "{}",
+            diff.unified_diff()
+                .header(&self.original_name, &self.output_name)
+        );
+        Ok(())
+    }
+}
+
+/// A writer for saving to a file: the output goes to a temporary file in the
+/// same directory, which is renamed over the target once it's complete (when
+/// the writer is committed). If anything goes wrong, the target is untouched.
+///
+/// Write errors are recorded (and reported by `AtomicFile::commit`) rather
+/// than returned, so the first one is what gets reported.
+struct AtomicFile {
+    target: PathBuf,
+    temp: PathBuf,
+    file: Option<File>,
+    error: Option<std::io::Error>,
+    committed: bool,
+}
+
+impl AtomicFile {
+    fn new(target: &Path) -> Self {
+        // write through symbolic links, rather than replacing them
+        let target = std::fs::canonicalize(target).unwrap_or_else(|_| target.to_path_buf());
+        let name = target
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let temp = target.with_file_name(format!(".{}.ffs-{}", name, std::process::id()));
+
+        let (file, error) = match File::create(&temp) {
+            Ok(file) => {
+                // keep the target's permissions
+                if let Ok(metadata) = std::fs::metadata(&target) {
+                    let _ = file.set_permissions(metadata.permissions());
+                }
+                (Some(file), None)
+            }
+            Err(e) => (None, Some(e)),
+        };
+
+        AtomicFile {
+            target,
+            temp,
+            file,
+            error,
+            committed: false,
+        }
+    }
+
+    /// Moves the temporary file into place, unless something went wrong
+    /// writing it.
+    fn commit(&mut self) -> std::io::Result<()> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+
+        let file = self.file.take().unwrap();
+        file.sync_all()?;
+        std::fs::rename(&self.temp, &self.target)?;
+        self.committed = true;
+        Ok(())
+    }
+}
+
+impl Commit for AtomicFile {
+    fn commit(mut self: Box<Self>) -> std::io::Result<()> {
+        AtomicFile::commit(&mut self)
+    }
+}
+
+impl std::io::Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match (&mut self.file, &self.error) {
+            (Some(file), None) => match file.write(buf) {
+                Err(e) if e.kind() != std::io::ErrorKind::Interrupted => {
+                    self.error = Some(e);
+                    Ok(buf.len())
+                }
+                res => res,
+            },
+            // once something's gone wrong, the rest is moot
+            _ => Ok(buf.len()),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        // the target is untouched; just clean up
+        if !self.committed {
+            self.file = None;
+            let _ = std::fs::remove_file(&self.temp);
+        }
+    }
+}
+
+/// A writer that transcodes the (UTF-8) output to another encoding (see
+/// `Config::encoding`). Since UTF-16 needs whole characters, it buffers the
+/// output, writing it all out when it's committed.
+struct Encoder {
+    inner: Box<dyn Commit>,
+    encoding: TextEncoding,
+    text: Vec<u8>,
+}
+
+impl std::io::Write for Encoder {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.text.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Commit for Encoder {
+    fn commit(mut self: Box<Self>) -> std::io::Result<()> {
+        let text = String::from_utf8_lossy(&self.text);
+        let encoded = self.encoding.encode(&text);
+        self.inner.write_all(&encoded)?;
+        self.inner.commit()
+    }
+}
+
+/// A writer that makes the output end in exactly one newline, or none at all,
+/// to match the input (see `Config::final_newline`). Like `Encoder`, it
+/// buffers the whole output and writes it when committed.
+struct FinalNewlineWriter {
+    inner: Box<dyn Commit>,
+    newline: bool,
+    text: Vec<u8>,
+}
+
+impl std::io::Write for FinalNewlineWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.text.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Commit for FinalNewlineWriter {
+    fn commit(mut self: Box<Self>) -> std::io::Result<()> {
+        while self.text.last() == Some(&b'\n') {
+            self.text.pop();
+            if self.text.last() == Some(&b'\r') {
+                self.text.pop();
+            }
+        }
+        if self.newline && !self.text.is_empty() {
+            self.text.push(b'\n');
+        }
+        self.inner.write_all(&self.text)?;
+        self.inner.commit()
+    }
+}
+
+/// The gzip trailer is written when the encoder is finished.
+impl Commit for flate2::write::GzEncoder<Box<dyn Commit>> {
+    fn commit(self: Box<Self>) -> std::io::Result<()> {
+        self.finish()?.commit()
+    }
+}
+
+/// A writer for saving to STDOUT. What's written can't be taken back, so if
+/// saving fails partway, the output is incomplete.
+struct Stdout;
+
+impl std::io::Write for Stdout {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        std::io::stdout().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        std::io::Write::flush(&mut std::io::stdout())
+    }
+}
+
+impl Commit for Stdout {
+    fn commit(self: Box<Self>) -> std::io::Result<()> {
+        std::io::Write::flush(&mut std::io::stdout())
     }
 }
 
@@ -622,22 +2677,94 @@ impl Default for Config {
             eager: false,
             uid: 501,
             gid: 501,
+            allow_other: false,
+            allowed_uids: Vec::new(),
+            private: false,
             filemode: 0o644,
             dirmode: 0o755,
             add_newlines: true,
+            trim: false,
             pad_element_names: true,
+            index_width: None,
+            ini_split_dots: false,
+            flatten_single: false,
+            typed_leaves: false,
+            exclude: Vec::new(),
+            ignore_file: None,
+            ignore: None,
+            blob_keys: Vec::new(),
+            name_map: Vec::new(),
+            include_tag: None,
+            strict: false,
+            strict_conversion: false,
+            json5_output: false,
+            arbitrary_precision: false,
+            number_suffix: false,
+            canonical_numbers: false,
+            sort_keys: false,
+            prune_empty: false,
+            plist_binary: false,
+            proto_schema: None,
+            proto_message: None,
+            schema: None,
+            schema_enforce: false,
+            watch: false,
+            watch_conflict: WatchConflict::Mount,
+            stream: false,
             base64: base64::STANDARD,
             try_decode_base64: false,
             allow_xattr: true,
             keep_macos_xattr_file: false,
             munge: Munge::Rename,
+            slash_policy: SlashPolicy::Escape,
+            slash_escape: "_SLASH_".into(),
+            case_insensitive: false,
+            yaml_aliases: YamlAliases::Expand,
+            null_as: NullAs::EmptyFile,
+            empty_as: EmptyAs::Error,
+            list_rename: ListRename::Shift,
+            final_newline: FinalNewline::Auto,
+            new_dir_type: DirType::Named,
             read_only: false,
+            noatime: false,
             input: Input::Stdin,
+            root_pointer: None,
+            overlay: None,
+            merge_arrays: MergeArrays::Replace,
+            write_overlay_only: false,
+            allow_primitive_root: false,
+            primitive_root_name: "value".into(),
+            elements_as_files: None,
+            max_inodes: None,
+            max_depth: 1024,
+            analyze: false,
+            analyze_json: false,
+            convert: false,
+            max_file_size: None,
+            name_max: 255,
+            max_total_bytes: None,
+            capacity: None,
+            cache_ttl: Duration::from_secs(1),
+            list_length_file: None,
+            expose_info: false,
             output: Output::Stdout,
             pretty: false,
+            indent: 2,
+            indent_char: IndentChar::Space,
+            input_encoding: TextEncoding::Utf8,
+            output_encoding: None,
+            compress_output: false,
+            no_clobber: false,
+            dry_run: false,
+            sync_on_close: true,
+            sync_delay: Duration::from_secs(1),
             timing: false,
             mount: None,
             cleanup_mount: false,
+            mount_retries: 3,
+            mount_retry_delay: Duration::from_millis(500),
+            force_unmount: false,
+            save_on_signal: true,
         }
     }
 }
@@ -1,12 +1,17 @@
 use std::collections::HashMap;
 use std::str::FromStr;
 
-use tracing::debug;
+use tracing::{debug, warn};
 
 use fuser::FileType;
 
-use super::config::Config;
+use super::config::{Config, EmptyAs, MergeArrays};
 
+use ::bson as bson_rs;
+use ::hcl as hcl_rs;
+use ::json5 as json5_rs;
+use ::plist as plist_rs;
+use ::ron as ron_rs;
 use ::toml as serde_toml;
 
 #[macro_export]
@@ -32,8 +37,23 @@ macro_rules! time_ns {
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Format {
     Json,
+    Json5,
+    Ndjson,
     Toml,
     Yaml,
+    Ini,
+    /// Java `.properties` files.
+    Properties,
+    Hcl,
+    Ron,
+    Bson,
+    Env,
+    Plist,
+    /// Binary protocol buffers, read and written according to a schema (see
+    /// `Config::proto_schema`).
+    Protobuf,
+    /// Extensible Data Notation, as used by Clojure.
+    Edn,
 }
 
 /// Types classifying string data.
@@ -47,6 +67,14 @@ pub enum Typ {
     Datetime,
     String,
     Bytes,
+    /// A BSON `ObjectId`, shown as 24 hex digits.
+    ObjectId,
+    /// A BSON `Decimal128`.
+    Decimal,
+    /// An EDN keyword, shown with its colon (e.g., `:active`).
+    Keyword,
+    /// An EDN symbol.
+    Symbol,
 }
 
 impl std::fmt::Display for Format {
@@ -56,8 +84,19 @@ impl std::fmt::Display for Format {
             "{}",
             match self {
                 Format::Json => "json",
+                Format::Json5 => "json5",
+                Format::Ndjson => "ndjson",
                 Format::Toml => "toml",
                 Format::Yaml => "yaml",
+                Format::Ini => "ini",
+                Format::Properties => "properties",
+                Format::Hcl => "hcl",
+                Format::Ron => "ron",
+                Format::Bson => "bson",
+                Format::Env => "env",
+                Format::Plist => "plist",
+                Format::Protobuf => "protobuf",
+                Format::Edn => "edn",
             }
         )
     }
@@ -77,6 +116,10 @@ impl std::fmt::Display for Typ {
                 Typ::Float => "float",
                 Typ::Integer => "integer",
                 Typ::String => "string",
+                Typ::ObjectId => "objectid",
+                Typ::Decimal => "decimal",
+                Typ::Keyword => "keyword",
+                Typ::Symbol => "symbol",
             }
         )
     }
@@ -94,12 +137,37 @@ impl FromStr for Format {
     fn from_str(s: &str) -> Result<Self, ParseFormatError> {
         let s = s.trim().to_lowercase();
 
-        if s == "json" {
+        // `--source auto` asks us to infer the format, as if it weren't given
+        if s == "auto" {
+            Err(ParseFormatError::NoFormatProvided)
+        } else if s == "json" {
             Ok(Format::Json)
+        } else if s == "json5" {
+            Ok(Format::Json5)
+        } else if s == "ndjson" || s == "jsonl" {
+            Ok(Format::Ndjson)
         } else if s == "toml" {
             Ok(Format::Toml)
         } else if s == "yaml" || s == "yml" {
             Ok(Format::Yaml)
+        } else if s == "ini" {
+            Ok(Format::Ini)
+        } else if s == "properties" {
+            Ok(Format::Properties)
+        } else if s == "hcl" || s == "tf" {
+            Ok(Format::Hcl)
+        } else if s == "ron" {
+            Ok(Format::Ron)
+        } else if s == "bson" {
+            Ok(Format::Bson)
+        } else if s == "env" || s == "dotenv" {
+            Ok(Format::Env)
+        } else if s == "plist" {
+            Ok(Format::Plist)
+        } else if s == "protobuf" || s == "pb" || s == "binpb" {
+            Ok(Format::Protobuf)
+        } else if s == "edn" {
+            Ok(Format::Edn)
         } else {
             Err(ParseFormatError::NoSuchFormat(s))
         }
@@ -122,12 +190,20 @@ impl FromStr for Typ {
             Ok(Typ::Bytes)
         } else if s == "datetime" || s == "date" || s == "time" {
             Ok(Typ::Datetime)
-        } else if s == "float" || s == "double" || s == "real" {
+        } else if s == "float" || s == "double" || s == "real" || s == "number" {
             Ok(Typ::Float)
         } else if s == "integer" || s == "int" {
             Ok(Typ::Integer)
         } else if s == "string" {
             Ok(Typ::String)
+        } else if s == "objectid" || s == "oid" {
+            Ok(Typ::ObjectId)
+        } else if s == "decimal" || s == "decimal128" {
+            Ok(Typ::Decimal)
+        } else if s == "keyword" || s == "kw" {
+            Ok(Typ::Keyword)
+        } else if s == "symbol" || s == "sym" {
+            Ok(Typ::Symbol)
         } else {
             Err(())
         }
@@ -135,14 +211,145 @@ impl FromStr for Typ {
 }
 
 impl Format {
+    /// Is this a text format? Only text formats are transcoded (see
+    /// `Config::input_encoding`); the others handle their own encodings.
+    pub fn is_text(&self) -> bool {
+        !matches!(self, Format::Bson | Format::Plist | Format::Protobuf)
+    }
+
+    /// Can saving keep the input's comments and formatting (see
+    /// `Nodelike::to_writer_preserving`)?
+    pub fn keeps_formatting(&self) -> bool {
+        matches!(self, Format::Toml)
+    }
+
     pub fn can_be_pretty(&self) -> bool {
         match self {
-            Format::Json | Format::Toml => true,
-            Format::Yaml => false,
+            Format::Json | Format::Json5 | Format::Toml | Format::Ron => true,
+            Format::Ndjson
+            | Format::Yaml
+            | Format::Ini
+            | Format::Properties
+            | Format::Hcl
+            | Format::Bson
+            | Format::Env
+            | Format::Plist
+            | Format::Protobuf
+            | Format::Edn => false,
+        }
+    }
+
+    /// Guesses the format of a document from its first few bytes: a leading
+    /// `{` or `[` means JSON (unless it's a TOML table header, like
+    /// `[section]`), `---` or `key: value` means YAML, and `key = value`
+    /// means TOML. Blank lines and `#` comments are skipped.
+    pub fn sniff(prefix: &[u8]) -> Option<Format> {
+        let text = String::from_utf8_lossy(prefix);
+        let first = text
+            .trim_start_matches('\u{feff}')
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with('#'))?;
+
+        let bare_key = |key: &str| {
+            key.starts_with(|c: char| c.is_alphabetic() || c == '_')
+                && key
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.')
+        };
+
+        if first.starts_with('{') {
+            Some(Format::Json)
+        } else if first.starts_with('[') {
+            let header = first.trim_start_matches('[').trim_end_matches(']');
+            if first.ends_with(']') && bare_key(header.trim()) {
+                Some(Format::Toml)
+            } else {
+                Some(Format::Json)
+            }
+        } else if first == "---" || first.starts_with("--- ") || first.starts_with("- ") {
+            Some(Format::Yaml)
+        } else {
+            match first.find([':', '=']) {
+                Some(i) if bare_key(first[..i].trim()) => {
+                    if first[i..].starts_with('=') {
+                        Some(Format::Toml)
+                    } else {
+                        Some(Format::Yaml)
+                    }
+                }
+                _ => None,
+            }
+        }
+    }
+
+    /// Can this format hold `construct` as such, rather than as something
+    /// else (e.g., binary data as a base64 string)? INI and dotenv files
+    /// hold every scalar as text, so only constructs with no textual form
+    /// count against them.
+    pub fn represents(&self, construct: Construct) -> bool {
+        match construct {
+            Construct::Null => matches!(
+                self,
+                Format::Json
+                    | Format::Json5
+                    | Format::Ndjson
+                    | Format::Yaml
+                    | Format::Hcl
+                    | Format::Ron
+                    | Format::Bson
+                    | Format::Edn
+            ),
+            Construct::NonStringKey => matches!(self, Format::Yaml | Format::Ron),
+            Construct::SpecialFloat => !matches!(self, Format::Json | Format::Ndjson | Format::Hcl),
+            Construct::WideNumber => *self != Format::Hcl,
+            Construct::Bytes => matches!(self, Format::Bson | Format::Plist),
+            Construct::Datetime => matches!(
+                self,
+                Format::Toml
+                    | Format::Bson
+                    | Format::Plist
+                    | Format::Ini
+                    | Format::Properties
+                    | Format::Env
+                    | Format::Edn
+            ),
         }
     }
 }
 
+/// Things a value can hold that not every format can represent (see
+/// `Format::represents` and `Config::strict_conversion`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Construct {
+    Null,
+    NonStringKey,
+    /// NaN or an infinity.
+    SpecialFloat,
+    /// A whole number outside the range of 64-bit integers (see
+    /// `is_wide_number`).
+    WideNumber,
+    Bytes,
+    Datetime,
+}
+
+impl std::fmt::Display for Construct {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "{}",
+            match self {
+                Construct::Null => "null",
+                Construct::NonStringKey => "a non-string key",
+                Construct::SpecialFloat => "NaN or infinity",
+                Construct::WideNumber => "a whole number too big for 64 bits",
+                Construct::Bytes => "binary data",
+                Construct::Datetime => "a datetime",
+            }
+        )
+    }
+}
+
 /// The ffs data model; it represents just one layer---lists and maps are
 /// parameterized over the underlying value type V.
 pub enum Node<V> {
@@ -156,6 +363,9 @@ pub enum Node<V> {
     /// It's a little bit annoying that, e.g., serde_json and toml use different
     /// maps internally. :(
     Map(Vec<(String, V)>),
+    /// A symbolic link to another value in the document, named by an RFC 6901
+    /// JSON Pointer (see `Config::yaml_aliases`).
+    Link(String),
 }
 
 /// Values that can be converted to a `Node`, which can be in turn processed by
@@ -168,6 +378,10 @@ where
     /// number of inodes needed to accommodate the value.
     fn size(&self) -> usize;
 
+    /// How deeply compound values nest in the given value: scalars have depth
+    /// 0, and an empty list or map has depth 1. See `Config::max_depth`.
+    fn depth(&self) -> usize;
+
     /// Predicts filetypes (directory vs. regular file) for values.
     ///
     /// Since FUSE filesystems need to have directories at the root, it's
@@ -189,19 +403,717 @@ where
     fn from_list_dir(files: Vec<Self>, config: &Config) -> Self;
     fn from_named_dir(files: HashMap<String, Self>, config: &Config) -> Self;
 
-    /// Loading
-    fn from_reader(reader: Box<dyn std::io::Read>) -> Self;
+    /// Loading; parse errors should mention where in the input they occurred
+    fn from_reader(
+        reader: Box<dyn std::io::Read>,
+        config: &Config,
+    ) -> Result<Self, Box<dyn std::error::Error>>;
+
+    /// Saving, with optional pretty printing (see `Config::pretty`). Fails
+    /// if `writer` does, or if the value can't be saved in this format (see
+    /// `save_error`).
+    fn to_writer(&self, writer: &mut dyn std::io::Write, config: &Config) -> std::io::Result<()>;
+
+    /// Saving over `source`, the text of the input, keeping its comments and
+    /// formatting where the values haven't changed. Only called for formats
+    /// where `Format::keeps_formatting`; by default, this is `to_writer`.
+    fn to_writer_preserving(
+        &self,
+        writer: &mut dyn std::io::Write,
+        _source: &str,
+        config: &Config,
+    ) -> std::io::Result<()> {
+        self.to_writer(writer, config)
+    }
+
+    /// Swaps the subtree named by an RFC 6901 JSON Pointer (e.g.,
+    /// `/users/0/settings`) for `v`, returning the old subtree. Returns `None`
+    /// (and leaves `self` alone) when the pointer doesn't resolve.
+    fn replace_at(&mut self, pointer: &str, v: Self) -> Option<Self>;
+
+    /// Sorts the keys of every map in the value, recursively (see
+    /// `Config::sort_keys`).
+    fn sort_keys(&mut self);
+
+    /// The keys of the outermost value, if it's a map, that aren't strings,
+    /// as they're named by `Nodelike::node`. Only formats whose keys can be
+    /// something else need to override this.
+    fn non_string_keys(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// A tag on the outermost value that `Nodelike::node` can't show, e.g.,
+    /// an EDN tagged literal's `inst`. The mount keeps it on the value's
+    /// inode and restores it with `Nodelike::with_tag` when saving.
+    fn tag(&self) -> Option<String> {
+        None
+    }
+
+    /// Restores a tag found by `Nodelike::tag`.
+    fn with_tag(self, _tag: &str) -> Self {
+        self
+    }
+}
+
+/// Deep-merges `overlay` into `base` (see `Config::overlay`): maps are merged
+/// key by key, lists are combined per `Config::merge_arrays`, and otherwise
+/// the overlay wins.
+pub fn merge<V>(base: V, overlay: V, config: &Config) -> V
+where
+    V: Nodelike,
+{
+    if base.kind() != FileType::Directory || overlay.kind() != FileType::Directory {
+        return overlay;
+    }
+
+    match (base.node(config), overlay.clone().node(config)) {
+        (Node::Map(fvs), Node::Map(overlay_fvs)) => {
+            let mut files = fvs.into_iter().collect::<HashMap<_, _>>();
+            for (key, v) in overlay_fvs {
+                let v = match files.remove(&key) {
+                    Some(base) => merge(base, v, config),
+                    None => v,
+                };
+                files.insert(key, v);
+            }
+            V::from_named_dir(files, config)
+        }
+        (Node::List(mut vs), Node::List(overlay_vs))
+            if config.merge_arrays == MergeArrays::Concat =>
+        {
+            vs.extend(overlay_vs);
+            V::from_list_dir(vs, config)
+        }
+        _ => overlay,
+    }
+}
+
+/// The overlay that `merge` would apply to `base` to get `v`, or `None` if
+/// they're the same. Overlays can't remove keys, so keys missing from `v`
+/// are ignored.
+pub fn diff<V>(base: V, v: V, config: &Config) -> Option<V>
+where
+    V: Nodelike,
+{
+    match (base.node(config), v.clone().node(config)) {
+        (Node::Map(base_fvs), Node::Map(fvs)) => {
+            let mut base_files = base_fvs.into_iter().collect::<HashMap<_, _>>();
+            let mut files = HashMap::new();
+            for (key, v) in fvs {
+                let changed = match base_files.remove(&key) {
+                    Some(base) => diff(base, v, config),
+                    None => Some(v),
+                };
+                if let Some(v) = changed {
+                    files.insert(key, v);
+                }
+            }
+            for key in base_files.keys() {
+                warn!("'{}' was removed, but overlays can't remove keys.", key);
+            }
+
+            if files.is_empty() {
+                None
+            } else {
+                Some(V::from_named_dir(files, config))
+            }
+        }
+        (Node::List(base_vs), Node::List(vs)) => {
+            let same = |(base, v): (&V, &V)| diff(base.clone(), v.clone(), config).is_none();
+            let prefix = base_vs.len() <= vs.len() && base_vs.iter().zip(vs.iter()).all(same);
+
+            if prefix && base_vs.len() == vs.len() {
+                None
+            } else if prefix && config.merge_arrays == MergeArrays::Concat {
+                Some(V::from_list_dir(vs[base_vs.len()..].to_vec(), config))
+            } else {
+                if config.merge_arrays == MergeArrays::Concat {
+                    warn!("A list changed other than at its end; under `--merge-arrays concat`, the input's elements will appear twice.");
+                }
+                Some(v)
+            }
+        }
+        (Node::String(base_typ, base_s), Node::String(typ, s))
+            if base_typ == typ && base_s == s =>
+        {
+            None
+        }
+        (Node::Bytes(base_b), Node::Bytes(b)) if base_b == b => None,
+        (Node::Link(base_pointer), Node::Link(pointer)) if base_pointer == pointer => None,
+        _ => Some(v),
+    }
+}
+
+/// Finds a key with a `/` in it anywhere in `v` (see
+/// `SlashPolicy::Reject`).
+pub fn slashed_key<V>(v: V, config: &Config) -> Option<String>
+where
+    V: Nodelike,
+{
+    let mut stack = vec![v];
+    while let Some(v) = stack.pop() {
+        match v.node(config) {
+            Node::List(vs) => stack.extend(vs),
+            Node::Map(fvs) => {
+                for (key, v) in fvs {
+                    if key.contains('/') {
+                        return Some(key);
+                    }
+                    stack.push(v);
+                }
+            }
+            Node::String(..) | Node::Bytes(_) | Node::Link(_) => (),
+        }
+    }
+    None
+}
+
+/// An error for `Nodelike::to_writer` when the value can't be saved, e.g.,
+/// because the format has no way to write it.
+pub fn save_error<E>(e: E) -> std::io::Error
+where
+    E: std::fmt::Display,
+{
+    std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string())
+}
+
+/// The number of bytes in the leaves of `v`, i.e., its strings and binary
+/// data (see `Config::max_total_bytes`).
+pub fn leaf_bytes<V>(v: V, config: &Config) -> u64
+where
+    V: Nodelike,
+{
+    let mut bytes = 0;
+    let mut stack = vec![v];
+    while let Some(v) = stack.pop() {
+        match v.node(config) {
+            Node::List(vs) => stack.extend(vs),
+            Node::Map(fvs) => stack.extend(fvs.into_iter().map(|(_, v)| v)),
+            Node::String(_, s) => bytes += s.len() as u64,
+            Node::Bytes(b) => bytes += b.len() as u64,
+            Node::Link(_) => (),
+        }
+    }
+    bytes
+}
+
+/// Finds the values in `v` that `target` can't represent (see
+/// `Config::strict_conversion`), as JSON Pointers along with what's wrong.
+pub fn unrepresentable<V>(v: V, target: Format, config: &Config) -> Vec<(String, Construct)>
+where
+    V: Nodelike,
+{
+    let mut found = Vec::new();
+    let mut stack = vec![(Vec::new(), v)];
+    while let Some((tokens, v)) = stack.pop() {
+        if !target.represents(Construct::NonStringKey) {
+            for key in v.non_string_keys() {
+                let mut tokens = tokens.clone();
+                tokens.push(key);
+                found.push((pointer_from_tokens(&tokens), Construct::NonStringKey));
+            }
+        }
+
+        let construct = match v.node(config) {
+            Node::List(vs) => {
+                for (i, v) in vs.into_iter().enumerate().rev() {
+                    let mut tokens = tokens.clone();
+                    tokens.push(i.to_string());
+                    stack.push((tokens, v));
+                }
+                continue;
+            }
+            Node::Map(fvs) => {
+                for (key, v) in fvs.into_iter().rev() {
+                    let mut tokens = tokens.clone();
+                    tokens.push(key);
+                    stack.push((tokens, v));
+                }
+                continue;
+            }
+            Node::String(Typ::Null, _) => Construct::Null,
+            Node::String(Typ::Datetime, _) => Construct::Datetime,
+            Node::String(Typ::Float, s) if is_special_float(&s) => Construct::SpecialFloat,
+            Node::String(Typ::Float | Typ::Integer, s) if is_wide_number(&s) => {
+                Construct::WideNumber
+            }
+            Node::Bytes(_) => Construct::Bytes,
+            Node::String(..) | Node::Link(_) => continue,
+        };
+        if !target.represents(construct) {
+            found.push((pointer_from_tokens(&tokens), construct));
+        }
+    }
+    found
+}
+
+/// Is `s` a whole number that doesn't fit in a 64-bit integer, e.g., `1e20`?
+/// HCL turns whole floats into integers, clamping ones like these.
+fn is_wide_number(s: &str) -> bool {
+    let s = s.trim();
+    if i64::from_str(s).is_ok() || u64::from_str(s).is_ok() {
+        return false;
+    }
+
+    match f64::from_str(s) {
+        Ok(f) if f.is_finite() && f.fract() == 0.0 => f < i64::MIN as f64 || f >= i64::MAX as f64,
+        _ => false,
+    }
+}
+
+/// Is `s` NaN or an infinity, as any of our formats would write it (e.g.,
+/// `NaN`, `-inf`, YAML's `.nan`, or EDN's `##Inf`)?
+fn is_special_float(s: &str) -> bool {
+    let s = s
+        .trim()
+        .trim_start_matches('#')
+        .trim_start_matches(&['+', '-'][..]);
+    let s = s.strip_prefix('.').unwrap_or(s).to_lowercase();
+    s == "nan" || s == "inf" || s == "infinity"
+}
+
+/// Splits an RFC 6901 JSON Pointer into its (unescaped) reference tokens.
+///
+/// The empty pointer refers to the whole document; every other pointer must
+/// start with `/`.
+pub fn pointer_tokens(pointer: &str) -> Option<Vec<String>> {
+    if pointer.is_empty() {
+        return Some(Vec::new());
+    }
+
+    if !pointer.starts_with('/') {
+        return None;
+    }
+
+    Some(
+        pointer[1..]
+            .split('/')
+            .map(|token| token.replace("~1", "/").replace("~0", "~"))
+            .collect(),
+    )
+}
+
+/// Parses the input from `reader`. An input with nothing in it is mounted
+/// according to `Config::empty_as`: under `EmptyAs::Error`, it's parsed as
+/// usual, so formats with empty documents (e.g., TOML) still load it.
+pub fn read_input<V>(
+    mut reader: Box<dyn std::io::Read>,
+    config: &Config,
+) -> Result<V, Box<dyn std::error::Error>>
+where
+    V: Nodelike,
+{
+    use std::io::{Cursor, Read};
+
+    let mut first = Vec::with_capacity(1);
+    reader.by_ref().take(1).read_to_end(&mut first)?;
+    if !first.is_empty() {
+        return V::from_reader(Box::new(Cursor::new(first).chain(reader)), config);
+    }
+
+    match config.empty_as {
+        EmptyAs::Error => V::from_reader(Box::new(std::io::empty()), config).map_err(|e| {
+            format!(
+                "{} (the input is empty; use `--empty-as empty_object` or `--empty-as empty_array` to mount it anyway)",
+                e
+            )
+            .into()
+        }),
+        EmptyAs::EmptyObject => Ok(V::from_named_dir(HashMap::new(), config)),
+        EmptyAs::EmptyArray => Ok(V::from_list_dir(Vec::new(), config)),
+    }
+}
+
+/// Converts a value in one format to another without building a filesystem,
+/// following the same conventions as saving an `FS`.
+pub fn convert<V, U>(v: V, config: &Config) -> U
+where
+    V: Nodelike,
+    U: Nodelike,
+{
+    match v.node(config) {
+        Node::Bytes(bytes) => U::from_bytes(bytes, config),
+        Node::String(typ, mut contents) => {
+            if config.add_newlines && contents.ends_with('\n') {
+                contents.truncate(contents.len() - 1);
+            }
+            U::from_string(typ, contents, config)
+        }
+        Node::List(vs) => {
+            U::from_list_dir(vs.into_iter().map(|v| convert(v, config)).collect(), config)
+        }
+        Node::Map(fvs) => U::from_named_dir(
+            fvs.into_iter()
+                .map(|(field, v)| (field, convert(v, config)))
+                .collect(),
+            config,
+        ),
+        Node::Link(pointer) => {
+            warn!("Can't convert the link to '{}'; using null.", pointer);
+            U::from_string(Typ::Null, String::new(), config)
+        }
+    }
+}
+
+/// Reads a value in `format` and converts it to a `V`, e.g., to mount files
+/// of several formats side by side (see `config::Input::Siblings`).
+pub fn read_as<V>(
+    format: Format,
+    reader: Box<dyn std::io::Read>,
+    config: &Config,
+) -> Result<V, Box<dyn std::error::Error>>
+where
+    V: Nodelike,
+{
+    fn read<U, V>(
+        reader: Box<dyn std::io::Read>,
+        config: &Config,
+    ) -> Result<V, Box<dyn std::error::Error>>
+    where
+        U: Nodelike,
+        V: Nodelike,
+    {
+        Ok(convert(U::from_reader(reader, config)?, config))
+    }
+
+    match format {
+        Format::Json => read::<json::Value, V>(reader, config),
+        Format::Json5 => read::<json5::Value, V>(reader, config),
+        Format::Ndjson => read::<ndjson::Value, V>(reader, config),
+        Format::Toml => read::<toml::Value, V>(reader, config),
+        Format::Yaml => read::<yaml::Value, V>(reader, config),
+        Format::Ini => read::<ini::Value, V>(reader, config),
+        Format::Properties => read::<properties::Value, V>(reader, config),
+        Format::Hcl => read::<hcl::Value, V>(reader, config),
+        Format::Ron => read::<ron::Value, V>(reader, config),
+        Format::Bson => read::<bson::Value, V>(reader, config),
+        Format::Env => read::<env::Value, V>(reader, config),
+        Format::Plist => read::<plist::Value, V>(reader, config),
+        Format::Protobuf => read::<protobuf::Value, V>(reader, config),
+        Format::Edn => read::<edn::Value, V>(reader, config),
+    }
+}
+
+/// Statistics about a value's structure, as printed by `--analyze`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// The number of inodes needed (as in `Nodelike::size`)
+    pub inodes: usize,
+    /// Regular files (and symlinks)
+    pub files: usize,
+    /// Lists and maps
+    pub directories: usize,
+    /// How deeply lists and maps nest (as in `Nodelike::depth`)
+    pub max_depth: usize,
+    /// The most elements in any one list
+    pub largest_list: usize,
+    /// The most fields in any one map
+    pub largest_map: usize,
+}
+
+impl Stats {
+    /// Measures `v`, without recursing (cf. `measure`).
+    pub fn of<V>(v: V, config: &Config) -> Self
+    where
+        V: Nodelike,
+    {
+        let mut stats = Stats {
+            inodes: v.size(),
+            max_depth: v.depth(),
+            ..Stats::default()
+        };
+
+        let mut stack = vec![v];
+        while let Some(v) = stack.pop() {
+            match v.node(config) {
+                Node::List(vs) => {
+                    stats.directories += 1;
+                    stats.largest_list = std::cmp::max(stats.largest_list, vs.len());
+                    stack.extend(vs);
+                }
+                Node::Map(fvs) => {
+                    stats.directories += 1;
+                    stats.largest_map = std::cmp::max(stats.largest_map, fvs.len());
+                    stack.extend(fvs.into_iter().map(|(_, v)| v));
+                }
+                Node::String(..) | Node::Bytes(_) | Node::Link(_) => stats.files += 1,
+            }
+        }
+
+        stats
+    }
+
+    /// The statistics as a JSON object
+    pub fn to_json(&self) -> json::Value {
+        serde_json::json!({
+            "inodes": self.inodes,
+            "files": self.files,
+            "directories": self.directories,
+            "max_depth": self.max_depth,
+            "largest_list": self.largest_list,
+            "largest_map": self.largest_map,
+        })
+    }
+}
+
+impl std::fmt::Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        writeln!(f, "inodes: {}", self.inodes)?;
+        writeln!(f, "files: {}", self.files)?;
+        writeln!(f, "directories: {}", self.directories)?;
+        writeln!(f, "max depth: {}", self.max_depth)?;
+        writeln!(f, "largest list: {}", self.largest_list)?;
+        write!(f, "largest map: {}", self.largest_map)
+    }
+}
+
+/// Builds an RFC 6901 JSON Pointer from (unescaped) reference tokens; the
+/// inverse of `pointer_tokens`.
+pub fn pointer_from_tokens<S>(tokens: &[S]) -> String
+where
+    S: AsRef<str>,
+{
+    tokens
+        .iter()
+        .map(|token| format!("/{}", token.as_ref().replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
+
+/// Interprets a JSON Pointer reference token as a list index. Per RFC 6901,
+/// indices are unsigned decimals without leading zeros.
+pub fn pointer_index(token: &str) -> Option<usize> {
+    if token.is_empty()
+        || !token.bytes().all(|b| b.is_ascii_digit())
+        || (token.len() > 1 && token.starts_with('0'))
+    {
+        return None;
+    }
+
+    token.parse().ok()
+}
 
-    /// Saving, with optional pretty printing
-    fn to_writer(&self, writer: Box<dyn std::io::Write>, pretty: bool);
+/// The sort key for a name in a list directory, which puts the elements in
+/// order: names starting with a number sort by it (padded or not, so `2`
+/// comes before `10`), and ahead of any that don't.
+pub fn element_key(name: &str) -> (bool, usize, &str) {
+    let digits = name.bytes().take_while(u8::is_ascii_digit).count();
+    match name[..digits].parse() {
+        Ok(index) => (false, index, name),
+        Err(_) => (true, 0, name),
+    }
+}
+
+/// Counts the nodes in `v` and measures its depth (see `Nodelike::size` and
+/// `Nodelike::depth`); `children` should return `None` for scalars. We keep an
+/// explicit stack rather than recursing, so that adversarially deep documents
+/// can't overflow the call stack.
+fn measure<'a, T>(v: &'a T, children: impl Fn(&'a T) -> Option<Vec<&'a T>>) -> (usize, usize) {
+    let mut size = 0;
+    let mut depth = 0;
+    let mut stack = vec![(v, 0)];
+
+    while let Some((v, d)) = stack.pop() {
+        size += 1;
+        if let Some(vs) = children(v) {
+            depth = depth.max(d + 1);
+            stack.extend(vs.into_iter().map(|v| (v, d + 1)));
+        }
+    }
+
+    (size, depth)
 }
 
 ////////////////////////////////////////////////////////////////////////////////
 /// JSON Nodelike implementation
 pub mod json {
     use super::*;
+    use serde::de::{Deserialize, Deserializer, Error, MapAccess, SeqAccess, Visitor};
+    use serde::Serialize;
     pub use serde_json::Value;
 
+    /// A parsed value along with the duplicate keys in it, as `(pointer,
+    /// dropped value)` pairs. Like `serde_json::Value`, the last value for a
+    /// key wins.
+    pub(super) struct Checked {
+        pub(super) value: Value,
+        pub(super) duplicates: Vec<(String, Value)>,
+    }
+
+    impl From<Value> for Checked {
+        fn from(value: Value) -> Self {
+            Checked {
+                value,
+                duplicates: Vec::new(),
+            }
+        }
+    }
+
+    /// How serde_json's `arbitrary_precision` feature passes numbers to
+    /// `Visitor::visit_map`: as a map from this key to the number's digits.
+    const NUMBER_TOKEN: &str = "$serde_json::private::Number";
+
+    /// Parses a number, rounding it to the nearest `i64`, `u64`, or `f64`
+    /// unless `config.arbitrary_precision` is set.
+    fn parse_number(s: &str, config: &Config) -> Option<serde_json::Number> {
+        let n = serde_json::Number::from_str(s).ok()?;
+
+        if config.arbitrary_precision {
+            Some(n)
+        } else {
+            Some(round_number(&n, config))
+        }
+    }
+
+    /// The nearest `i64`, `u64`, or `f64` to `n`. With
+    /// `config.canonical_numbers`, an integral `f64` that fits in an `i64`
+    /// becomes that integer (unless `config.number_suffix` asks floats to
+    /// stay floats), so every spelling of a number saves the same way.
+    fn round_number(n: &serde_json::Number, config: &Config) -> serde_json::Number {
+        if let Some(i) = n.as_i64() {
+            i.into()
+        } else if let Some(u) = n.as_u64() {
+            u.into()
+        } else {
+            match n.as_f64() {
+                Some(f)
+                    if config.canonical_numbers
+                        && !config.number_suffix
+                        && f.fract() == 0.0
+                        && f >= i64::MIN as f64
+                        && f < i64::MAX as f64 =>
+                {
+                    (f as i64).into()
+                }
+                f => f
+                    .and_then(serde_json::Number::from_f64)
+                    .unwrap_or_else(|| n.clone()),
+            }
+        }
+    }
+
+    /// Rounds every number in `v` (see `round_number`).
+    pub(super) fn round_numbers(v: &mut Value, config: &Config) {
+        let mut stack = vec![v];
+        while let Some(v) = stack.pop() {
+            match v {
+                Value::Number(n) => *n = round_number(n, config),
+                Value::Array(vs) => stack.extend(vs.iter_mut()),
+                Value::Object(fvs) => stack.extend(fvs.values_mut()),
+                _ => (),
+            }
+        }
+    }
+
+    fn json_measure(v: &Value) -> (usize, usize) {
+        measure(v, |v| match v {
+            Value::Array(vs) => Some(vs.iter().collect()),
+            Value::Object(fvs) => Some(fvs.values().collect()),
+            _ => None,
+        })
+    }
+
+    struct CheckedVisitor;
+
+    impl<'de> Visitor<'de> for CheckedVisitor {
+        type Value = Checked;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str("any valid JSON value")
+        }
+
+        fn visit_bool<E>(self, b: bool) -> Result<Checked, E> {
+            Ok(Value::Bool(b).into())
+        }
+
+        fn visit_i64<E>(self, n: i64) -> Result<Checked, E> {
+            Ok(Value::from(n).into())
+        }
+
+        fn visit_u64<E>(self, n: u64) -> Result<Checked, E> {
+            Ok(Value::from(n).into())
+        }
+
+        fn visit_f64<E>(self, n: f64) -> Result<Checked, E> {
+            Ok(Value::from(n).into())
+        }
+
+        fn visit_str<E>(self, s: &str) -> Result<Checked, E> {
+            Ok(Value::String(s.into()).into())
+        }
+
+        fn visit_string<E>(self, s: String) -> Result<Checked, E> {
+            Ok(Value::String(s).into())
+        }
+
+        fn visit_unit<E>(self) -> Result<Checked, E> {
+            Ok(Value::Null.into())
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> Result<Checked, A::Error>
+        where
+            A: SeqAccess<'de>,
+        {
+            let mut vs = Vec::new();
+            let mut duplicates = Vec::new();
+            while let Some(elt) = seq.next_element::<Checked>()? {
+                let prefix = pointer_from_tokens(&[vs.len().to_string()]);
+                duplicates.extend(
+                    elt.duplicates
+                        .into_iter()
+                        .map(|(pointer, v)| (format!("{}{}", prefix, pointer), v)),
+                );
+                vs.push(elt.value);
+            }
+
+            Ok(Checked {
+                value: Value::Array(vs),
+                duplicates,
+            })
+        }
+
+        fn visit_map<A>(self, mut map: A) -> Result<Checked, A::Error>
+        where
+            A: MapAccess<'de>,
+        {
+            let mut next = map.next_key::<String>()?;
+            if next.as_deref() == Some(NUMBER_TOKEN) {
+                let digits = map.next_value::<String>()?;
+                let n = serde_json::Number::from_str(&digits).map_err(A::Error::custom)?;
+                return Ok(Value::Number(n).into());
+            }
+
+            let mut fvs = serde_json::Map::new();
+            let mut duplicates = Vec::new();
+            while let Some(field) = next.take() {
+                let v = map.next_value::<Checked>()?;
+                let prefix = pointer_from_tokens(&[&field]);
+                duplicates.extend(
+                    v.duplicates
+                        .into_iter()
+                        .map(|(pointer, v)| (format!("{}{}", prefix, pointer), v)),
+                );
+                if let Some(old) = fvs.insert(field, v.value) {
+                    duplicates.push((prefix, old));
+                }
+                next = map.next_key::<String>()?;
+            }
+
+            Ok(Checked {
+                value: Value::Object(fvs),
+                duplicates,
+            })
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Checked {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            deserializer.deserialize_any(CheckedVisitor)
+        }
+    }
+
     impl Nodelike for Value {
         /// `Value::Object` and `Value::Array` map to directories; everything else is a
         /// regular file.
@@ -213,11 +1125,11 @@ pub mod json {
         }
 
         fn size(&self) -> usize {
-            match self {
-                Value::Null | Value::Bool(_) | Value::Number(_) | Value::String(_) => 1,
-                Value::Array(vs) => vs.iter().map(|v| v.size()).sum::<usize>() + 1,
-                Value::Object(fvs) => fvs.iter().map(|(_, v)| v.size()).sum::<usize>() + 1,
-            }
+            json_measure(self).0
+        }
+
+        fn depth(&self) -> usize {
+            json_measure(self).1
         }
 
         fn node(self, config: &Config) -> Node<Self> {
@@ -226,7 +1138,14 @@ pub mod json {
             match self {
                 Value::Null => Node::String(Typ::Null, "".into()), // always empty
                 Value::Bool(b) => Node::String(Typ::Boolean, format!("{}{}", b, nl)),
-                Value::Number(n) => Node::String(Typ::Float, format!("{}{}", n, nl)),
+                Value::Number(n) => {
+                    let typ = if config.number_suffix && (n.is_i64() || n.is_u64()) {
+                        Typ::Integer
+                    } else {
+                        Typ::Float
+                    };
+                    Node::String(typ, format!("{}{}", n, nl))
+                }
                 Value::String(s) => {
                     if config.try_decode_base64 {
                         if let Ok(bytes) = base64::decode_config(&s, config.base64) {
@@ -241,7 +1160,7 @@ pub mod json {
             }
         }
 
-        fn from_string(typ: Typ, contents: String, _config: &Config) -> Self {
+        fn from_string(typ: Typ, contents: String, config: &Config) -> Self {
             match typ {
                 Typ::Auto => {
                     if contents.is_empty() {
@@ -250,7 +1169,7 @@ pub mod json {
                         Value::Bool(true)
                     } else if contents == "false" {
                         Value::Bool(false)
-                    } else if let Ok(n) = serde_json::Number::from_str(&contents) {
+                    } else if let Some(n) = parse_number(&contents, config) {
                         Value::Number(n)
                     } else {
                         Value::String(contents)
@@ -269,7 +1188,16 @@ pub mod json {
                 Typ::Bytes => panic!("from_string called at typ::bytes"),
                 Typ::Datetime => Value::String(contents),
                 Typ::Float => {
-                    if let Ok(n) = serde_json::Number::from_str(&contents) {
+                    // a float that looks like an integer gets its `.0` back
+                    let contents = if config.number_suffix
+                        && !contents.contains(['.', 'e', 'E'])
+                        && parse_number(&contents, config).is_some()
+                    {
+                        contents + ".0"
+                    } else {
+                        contents
+                    };
+                    if let Some(n) = parse_number(&contents, config) {
                         Value::Number(n)
                     } else {
                         debug!("string '{}' tagged as float", contents);
@@ -277,7 +1205,7 @@ pub mod json {
                     }
                 }
                 Typ::Integer => {
-                    if let Ok(n) = serde_json::Number::from_str(&contents) {
+                    if let Some(n) = parse_number(&contents, config) {
                         Value::Number(n)
                     } else {
                         debug!("string '{}' tagged as float", contents);
@@ -292,7 +1220,9 @@ pub mod json {
                         Value::String(contents)
                     }
                 }
-                Typ::String => Value::String(contents),
+                Typ::String | Typ::ObjectId | Typ::Decimal | Typ::Keyword | Typ::Symbol => {
+                    Value::String(contents)
+                }
             }
         }
 
@@ -308,18 +1238,423 @@ pub mod json {
         }
 
         fn from_named_dir(files: HashMap<String, Self>, _config: &Config) -> Self {
+            // `bson` turns on serde_json's `preserve_order`, so `Map` keeps
+            // whatever order it's given; sort, like a plain `Map` would
+            let mut files = files.into_iter().collect::<Vec<_>>();
+            files.sort_unstable_by(|(k1, _), (k2, _)| k1.cmp(k2));
             Value::Object(files.into_iter().collect())
         }
 
-        fn to_writer(&self, writer: Box<dyn std::io::Write>, pretty: bool) {
-            if pretty {
-                serde_json::to_writer_pretty(writer, self).unwrap();
+        fn to_writer(
+            &self,
+            writer: &mut dyn std::io::Write,
+            config: &Config,
+        ) -> std::io::Result<()> {
+            if config.pretty {
+                let indent = config.indent_string();
+                let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+                let mut serializer = serde_json::Serializer::with_formatter(writer, formatter);
+                self.serialize(&mut serializer)?;
+            } else {
+                serde_json::to_writer(writer, self)?;
+            }
+            Ok(())
+        }
+        fn from_reader(
+            reader: std::boxed::Box<dyn std::io::Read>,
+            config: &Config,
+        ) -> Result<Self, Box<dyn std::error::Error>> {
+            let Checked {
+                mut value,
+                duplicates,
+            } = serde_json::from_reader(reader)?;
+
+            // serde_json keeps the last value for a key, so earlier ones are lost
+            for (pointer, dropped) in duplicates.iter() {
+                if config.strict {
+                    return Err(format!("duplicate key at '{}'", pointer).into());
+                }
+                warn!(
+                    "Duplicate key at '{}'; dropping the earlier value {}.",
+                    pointer, dropped
+                );
+            }
+
+            if !config.arbitrary_precision {
+                round_numbers(&mut value, config);
+            }
+
+            Ok(value)
+        }
+
+        fn replace_at(&mut self, pointer: &str, v: Self) -> Option<Self> {
+            self.pointer_mut(pointer)
+                .map(|subtree| std::mem::replace(subtree, v))
+        }
+
+        fn sort_keys(&mut self) {
+            match self {
+                Value::Array(vs) => vs.iter_mut().for_each(Nodelike::sort_keys),
+                Value::Object(fvs) => {
+                    let mut sorted = std::mem::take(fvs).into_iter().collect::<Vec<_>>();
+                    sorted.sort_unstable_by(|(k1, _), (k2, _)| k1.cmp(k2));
+                    for (k, mut v) in sorted {
+                        v.sort_keys();
+                        fvs.insert(k, v);
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Compiles the JSON Schema at `Config::schema`, if there is one.
+    pub fn schema(
+        config: &Config,
+    ) -> Result<Option<jsonschema::JSONSchema>, Box<dyn std::error::Error>> {
+        let path = match &config.schema {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+        let file = std::fs::File::open(path)
+            .map_err(|e| format!("couldn't open {}: {}", path.display(), e))?;
+        let schema: Value = serde_json::from_reader(std::io::BufReader::new(file))
+            .map_err(|e| format!("{} isn't valid JSON: {}", path.display(), e))?;
+        let compiled = jsonschema::JSONSchema::compile(&schema)
+            .map_err(|e| format!("{} isn't a valid JSON Schema: {}", path.display(), e))?;
+        Ok(Some(compiled))
+    }
+
+    /// The ways `v` violates `schema`, as `(pointer, problem)` pairs, where
+    /// the JSON Pointer locates the offending value (`""` is the root).
+    pub fn schema_violations(schema: &jsonschema::JSONSchema, v: &Value) -> Vec<(String, String)> {
+        match schema.validate(v) {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors
+                .map(|e| (e.instance_path.to_string(), e.to_string()))
+                .collect(),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+/// JSON5 Nodelike implementation
+///
+/// JSON5 (comments, trailing commas, unquoted keys, single-quoted strings,
+/// etc.) is loaded into the same tree as JSON. Comments aren't kept. Saving
+/// writes plain JSON, unless `config.json5_output` is set.
+pub mod json5 {
+    use super::json::{round_numbers, Checked};
+    use super::*;
+
+    #[derive(Clone, Debug, Default, PartialEq)]
+    pub struct Value(serde_json::Value);
+
+    impl std::fmt::Display for Value {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+            write!(f, "{}", self.0)
+        }
+    }
+
+    /// Can `key` be written without quotes, i.e., is it an (ASCII) identifier?
+    fn is_identifier(key: &str) -> bool {
+        let mut chars = key.chars();
+        match chars.next() {
+            Some(c) if c.is_ascii_alphabetic() || c == '_' || c == '$' => {
+                chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+            }
+            _ => false,
+        }
+    }
+
+    /// Writes `v` as JSON5. When pretty printing (i.e., `indent` is given),
+    /// every element and field is followed by a comma.
+    fn write_json5(
+        writer: &mut dyn std::io::Write,
+        v: &serde_json::Value,
+        indent: Option<&str>,
+        level: usize,
+    ) -> std::io::Result<()> {
+        use serde_json::Value as Json;
+
+        let (open, close, len) = match v {
+            Json::Array(vs) => ("[", "]", vs.len()),
+            Json::Object(fvs) => ("{", "}", fvs.len()),
+            v => return write!(writer, "{}", v),
+        };
+
+        write!(writer, "{}", open)?;
+        if len == 0 {
+            return write!(writer, "{}", close);
+        }
+
+        let children: Box<dyn Iterator<Item = (Option<&String>, &Json)>> = match v {
+            Json::Array(vs) => Box::new(vs.iter().map(|v| (None, v))),
+            Json::Object(fvs) => Box::new(fvs.iter().map(|(f, v)| (Some(f), v))),
+            _ => unreachable!(),
+        };
+        for (i, (field, v)) in children.enumerate() {
+            match indent {
+                Some(indent) => write!(writer, "\n{}", indent.repeat(level + 1))?,
+                None if i > 0 => write!(writer, ",")?,
+                None => (),
+            }
+
+            if let Some(field) = field {
+                if is_identifier(field) {
+                    write!(writer, "{}:", field)?;
+                } else {
+                    write!(writer, "{}:", Json::String(field.clone()))?;
+                }
+                if indent.is_some() {
+                    write!(writer, " ")?;
+                }
+            }
+
+            write_json5(writer, v, indent, level + 1)?;
+
+            if indent.is_some() {
+                write!(writer, ",")?;
+            }
+        }
+        if let Some(indent) = indent {
+            write!(writer, "\n{}", indent.repeat(level))?;
+        }
+        write!(writer, "{}", close)
+    }
+
+    impl Nodelike for Value {
+        fn kind(&self) -> FileType {
+            self.0.kind()
+        }
+
+        fn size(&self) -> usize {
+            self.0.size()
+        }
+
+        fn depth(&self) -> usize {
+            self.0.depth()
+        }
+
+        fn node(self, config: &Config) -> Node<Self> {
+            match self.0.node(config) {
+                Node::String(t, s) => Node::String(t, s),
+                Node::Bytes(b) => Node::Bytes(b),
+                Node::Link(target) => Node::Link(target),
+                Node::List(vs) => Node::List(vs.into_iter().map(Value).collect()),
+                Node::Map(fvs) => Node::Map(fvs.into_iter().map(|(f, v)| (f, Value(v))).collect()),
+            }
+        }
+
+        fn from_string(typ: Typ, contents: String, config: &Config) -> Self {
+            Value(serde_json::Value::from_string(typ, contents, config))
+        }
+
+        fn from_bytes<T>(contents: T, config: &Config) -> Self
+        where
+            T: AsRef<[u8]>,
+        {
+            Value(serde_json::Value::from_bytes(contents, config))
+        }
+
+        fn from_list_dir(files: Vec<Self>, _config: &Config) -> Self {
+            Value(serde_json::Value::Array(
+                files.into_iter().map(|v| v.0).collect(),
+            ))
+        }
+
+        fn from_named_dir(files: HashMap<String, Self>, config: &Config) -> Self {
+            Value(serde_json::Value::from_named_dir(
+                files.into_iter().map(|(f, v)| (f, v.0)).collect(),
+                config,
+            ))
+        }
+
+        fn to_writer(
+            &self,
+            writer: &mut dyn std::io::Write,
+            config: &Config,
+        ) -> std::io::Result<()> {
+            if !config.json5_output {
+                return self.0.to_writer(writer, config);
+            }
+
+            let indent = if config.pretty {
+                Some(config.indent_string())
             } else {
-                serde_json::to_writer(writer, self).unwrap();
+                None
+            };
+            write_json5(writer, &self.0, indent.as_deref(), 0)?;
+            if config.pretty {
+                writeln!(writer)?;
+            }
+            Ok(())
+        }
+
+        fn from_reader(
+            mut reader: std::boxed::Box<dyn std::io::Read>,
+            config: &Config,
+        ) -> Result<Self, Box<dyn std::error::Error>> {
+            let mut text = String::new();
+            reader.read_to_string(&mut text)?;
+            let Checked {
+                mut value,
+                duplicates,
+            } = json5_rs::from_str(&text)?;
+
+            for (pointer, dropped) in duplicates.iter() {
+                if config.strict {
+                    return Err(format!("duplicate key at '{}'", pointer).into());
+                }
+                warn!(
+                    "Duplicate key at '{}'; dropping the earlier value {}.",
+                    pointer, dropped
+                );
+            }
+
+            if !config.arbitrary_precision {
+                round_numbers(&mut value, config);
+            }
+
+            Ok(Value(value))
+        }
+
+        fn replace_at(&mut self, pointer: &str, v: Self) -> Option<Self> {
+            self.0.replace_at(pointer, v.0).map(Value)
+        }
+
+        fn sort_keys(&mut self) {
+            self.0.sort_keys()
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+/// Newline-delimited JSON (NDJSON, a/k/a JSON Lines) Nodelike implementation
+///
+/// A stream of JSON values is mounted as a list, one element per value. Values
+/// are usually one per line, but any whitespace (including blank lines) may
+/// separate them. Saving writes each element of the root list as compact JSON
+/// on its own line; any other root is written as a single line.
+pub mod ndjson {
+    use super::json::Checked;
+    use super::*;
+
+    #[derive(Clone, Debug, Default, PartialEq)]
+    pub struct Value(serde_json::Value);
+
+    impl std::fmt::Display for Value {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+            write!(f, "{}", self.0)
+        }
+    }
+
+    impl Nodelike for Value {
+        fn kind(&self) -> FileType {
+            self.0.kind()
+        }
+
+        fn size(&self) -> usize {
+            self.0.size()
+        }
+
+        fn depth(&self) -> usize {
+            self.0.depth()
+        }
+
+        fn node(self, config: &Config) -> Node<Self> {
+            match self.0.node(config) {
+                Node::String(t, s) => Node::String(t, s),
+                Node::Bytes(b) => Node::Bytes(b),
+                Node::Link(target) => Node::Link(target),
+                Node::List(vs) => Node::List(vs.into_iter().map(Value).collect()),
+                Node::Map(fvs) => Node::Map(fvs.into_iter().map(|(f, v)| (f, Value(v))).collect()),
+            }
+        }
+
+        fn from_string(typ: Typ, contents: String, config: &Config) -> Self {
+            Value(serde_json::Value::from_string(typ, contents, config))
+        }
+
+        fn from_bytes<T>(contents: T, config: &Config) -> Self
+        where
+            T: AsRef<[u8]>,
+        {
+            Value(serde_json::Value::from_bytes(contents, config))
+        }
+
+        fn from_list_dir(files: Vec<Self>, _config: &Config) -> Self {
+            Value(serde_json::Value::Array(
+                files.into_iter().map(|v| v.0).collect(),
+            ))
+        }
+
+        fn from_named_dir(files: HashMap<String, Self>, config: &Config) -> Self {
+            Value(serde_json::Value::from_named_dir(
+                files.into_iter().map(|(f, v)| (f, v.0)).collect(),
+                config,
+            ))
+        }
+
+        fn to_writer(
+            &self,
+            writer: &mut dyn std::io::Write,
+            _config: &Config,
+        ) -> std::io::Result<()> {
+            match &self.0 {
+                serde_json::Value::Array(vs) => {
+                    for v in vs {
+                        serde_json::to_writer(&mut *writer, v)?;
+                        writeln!(writer)?;
+                    }
+                }
+                v => {
+                    warn!("The root isn't a list, so it will be saved as a single line.");
+                    serde_json::to_writer(&mut *writer, v)?;
+                    writeln!(writer)?;
+                }
+            }
+            Ok(())
+        }
+
+        fn from_reader(
+            reader: std::boxed::Box<dyn std::io::Read>,
+            config: &Config,
+        ) -> Result<Self, Box<dyn std::error::Error>> {
+            let mut vs = Vec::new();
+            for checked in serde_json::Deserializer::from_reader(reader).into_iter::<Checked>() {
+                let Checked {
+                    mut value,
+                    duplicates,
+                } = checked?;
+
+                for (pointer, dropped) in duplicates.iter() {
+                    let pointer = format!("/{}{}", vs.len(), pointer);
+                    if config.strict {
+                        return Err(format!("duplicate key at '{}'", pointer).into());
+                    }
+                    warn!(
+                        "Duplicate key at '{}'; dropping the earlier value {}.",
+                        pointer, dropped
+                    );
+                }
+
+                if !config.arbitrary_precision {
+                    super::json::round_numbers(&mut value, config);
+                }
+
+                vs.push(value);
             }
+
+            Ok(Value(serde_json::Value::Array(vs)))
         }
-        fn from_reader(reader: std::boxed::Box<dyn std::io::Read>) -> Self {
-            serde_json::from_reader(reader).expect("JSON")
+
+        fn replace_at(&mut self, pointer: &str, v: Self) -> Option<Self> {
+            self.0.replace_at(pointer, v.0).map(Value)
+        }
+
+        fn sort_keys(&mut self) {
+            self.0.sort_keys()
         }
     }
 }
@@ -345,16 +1680,205 @@ pub mod toml {
         }
     }
 
-    fn toml_size(v: &Toml) -> usize {
+    fn toml_sort_keys(v: &mut Toml) {
+        match v {
+            Toml::Array(vs) => vs.iter_mut().for_each(toml_sort_keys),
+            Toml::Table(fvs) => {
+                let mut sorted = std::mem::replace(fvs, serde_toml::value::Table::new())
+                    .into_iter()
+                    .collect::<Vec<_>>();
+                sorted.sort_unstable_by(|(k1, _), (k2, _)| k1.cmp(k2));
+                for (k, mut v) in sorted {
+                    toml_sort_keys(&mut v);
+                    fvs.insert(k, v);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Updates `table` to hold `fvs`, leaving unchanged entries (and their
+    /// comments) alone.
+    fn edit_table(table: &mut toml_edit::Table, fvs: &serde_toml::value::Table) {
+        let removed = table
+            .iter()
+            .map(|(k, _)| k.to_string())
+            .filter(|k| !fvs.contains_key(k))
+            .collect::<Vec<_>>();
+        for k in removed {
+            table.remove(&k);
+        }
+
+        for (k, v) in fvs {
+            match table.get_mut(k) {
+                Some(item) if !item.is_none() => edit_item(item, v),
+                _ => {
+                    table.insert(k, new_item(v));
+                }
+            }
+        }
+    }
+
+    fn edit_item(item: &mut toml_edit::Item, v: &Toml) {
+        match (item, v) {
+            (toml_edit::Item::Table(table), Toml::Table(fvs)) => edit_table(table, fvs),
+            (toml_edit::Item::Value(value), v) => edit_value(value, v),
+            (toml_edit::Item::ArrayOfTables(tables), Toml::Array(vs))
+                if tables.len() == vs.len() && vs.iter().all(Toml::is_table) =>
+            {
+                for (i, v) in vs.iter().enumerate() {
+                    if let (Some(table), Toml::Table(fvs)) = (tables.get_mut(i), v) {
+                        edit_table(table, fvs);
+                    }
+                }
+            }
+            (item, v) => *item = new_item(v),
+        }
+    }
+
+    fn edit_value(value: &mut toml_edit::Value, v: &Toml) {
+        use toml_edit::Value as Edit;
+
+        let same = match (&*value, v) {
+            (Edit::String(s), Toml::String(t)) => s.value() == t,
+            (Edit::Integer(n), Toml::Integer(m)) => n.value() == m,
+            (Edit::Float(n), Toml::Float(m)) => n.value() == m,
+            (Edit::Boolean(b), Toml::Boolean(c)) => b.value() == c,
+            (Edit::Datetime(d), Toml::Datetime(e)) => d.value().to_string() == e.to_string(),
+            _ => false,
+        };
+        if same {
+            return;
+        }
+
+        match (value, v) {
+            (Edit::Array(vs), Toml::Array(new_vs)) if vs.len() == new_vs.len() => {
+                for (i, v) in new_vs.iter().enumerate() {
+                    if let Some(value) = vs.get_mut(i) {
+                        edit_value(value, v);
+                    }
+                }
+            }
+            (Edit::InlineTable(table), Toml::Table(fvs)) => {
+                let removed = table
+                    .iter()
+                    .map(|(k, _)| k.to_string())
+                    .filter(|k| !fvs.contains_key(k))
+                    .collect::<Vec<_>>();
+                for k in removed {
+                    table.remove(&k);
+                }
+
+                for (k, v) in fvs {
+                    match table.get_mut(k) {
+                        Some(value) => edit_value(value, v),
+                        None => {
+                            table.insert(k, new_value(v));
+                        }
+                    }
+                }
+            }
+            (value, v) => {
+                // keep the spacing and any trailing comment
+                let decor = value.decor().clone();
+                *value = new_value(v);
+                *value.decor_mut() = decor;
+            }
+        }
+    }
+
+    fn new_item(v: &Toml) -> toml_edit::Item {
+        match v {
+            Toml::Table(fvs) => {
+                let mut table = toml_edit::Table::new();
+                table.set_implicit(true);
+                edit_table(&mut table, fvs);
+                toml_edit::Item::Table(table)
+            }
+            Toml::Array(vs) if !vs.is_empty() && vs.iter().all(Toml::is_table) => {
+                let mut tables = toml_edit::ArrayOfTables::new();
+                for v in vs {
+                    if let Toml::Table(fvs) = v {
+                        let mut table = toml_edit::Table::new();
+                        edit_table(&mut table, fvs);
+                        tables.push(table);
+                    }
+                }
+                toml_edit::Item::ArrayOfTables(tables)
+            }
+            v => toml_edit::Item::Value(new_value(v)),
+        }
+    }
+
+    fn new_value(v: &Toml) -> toml_edit::Value {
         match v {
-            Toml::Boolean(_)
-            | Toml::Datetime(_)
-            | Toml::Float(_)
-            | Toml::Integer(_)
-            | Toml::String(_) => 1,
-            Toml::Array(vs) => vs.iter().map(|v| toml_size(v)).sum::<usize>() + 1,
-            Toml::Table(fvs) => fvs.iter().map(|(_, v)| toml_size(v)).sum::<usize>() + 1,
+            Toml::String(s) => s.as_str().into(),
+            Toml::Integer(n) => (*n).into(),
+            Toml::Float(n) => (*n).into(),
+            Toml::Boolean(b) => (*b).into(),
+            Toml::Datetime(d) => match d.to_string().parse::<toml_edit::Datetime>() {
+                Ok(d) => d.into(),
+                Err(_) => d.to_string().into(),
+            },
+            Toml::Array(vs) => toml_edit::Value::Array(vs.iter().map(new_value).collect()),
+            Toml::Table(fvs) => toml_edit::Value::InlineTable(
+                fvs.iter().map(|(k, v)| (k.clone(), new_value(v))).collect(),
+            ),
+        }
+    }
+
+    /// Writes the multi-line strings that saving added as literal strings,
+    /// like `serde_toml::to_string_pretty` does. Strings from the input keep
+    /// however they were written.
+    struct PrettyStrings;
+
+    impl toml_edit::visit_mut::VisitMut for PrettyStrings {
+        fn visit_value_mut(&mut self, node: &mut toml_edit::Value) {
+            let literal = match &*node {
+                toml_edit::Value::String(s)
+                    if s.as_repr().is_none()
+                        && s.value().contains('\n')
+                        && !s.value().contains("'''")
+                        && !s
+                            .value()
+                            .chars()
+                            .any(|c| c.is_control() && c != '\n' && c != '\t') =>
+                {
+                    format!("'''\n{}'''", s.value())
+                        .parse::<toml_edit::Value>()
+                        .ok()
+                }
+                _ => None,
+            };
+
+            match literal {
+                Some(mut literal) => {
+                    *literal.decor_mut() = node.decor().clone();
+                    *node = literal;
+                }
+                None => toml_edit::visit_mut::visit_value_mut(self, node),
+            }
+        }
+    }
+
+    fn toml_measure(v: &Toml) -> (usize, usize) {
+        measure(v, |v| match v {
+            Toml::Array(vs) => Some(vs.iter().collect()),
+            Toml::Table(fvs) => Some(fvs.values().collect()),
+            _ => None,
+        })
+    }
+
+    fn toml_pointer_mut<'a>(mut v: &'a mut Toml, pointer: &str) -> Option<&'a mut Toml> {
+        for token in pointer_tokens(pointer)? {
+            v = match v {
+                Toml::Table(fvs) => fvs.get_mut(&token)?,
+                Toml::Array(vs) => vs.get_mut(pointer_index(&token)?)?,
+                _ => return None,
+            };
         }
+
+        Some(v)
     }
 
     impl Nodelike for Value {
@@ -366,7 +1890,11 @@ pub mod toml {
         }
 
         fn size(&self) -> usize {
-            toml_size(&self.0)
+            toml_measure(&self.0).0
+        }
+
+        fn depth(&self) -> usize {
+            toml_measure(&self.0).1
         }
 
         fn node(self, config: &Config) -> Node<Self> {
@@ -455,7 +1983,9 @@ pub mod toml {
                         Toml::String(contents)
                     }
                 }
-                Typ::String => Toml::String(contents),
+                Typ::String | Typ::ObjectId | Typ::Decimal | Typ::Keyword | Typ::Symbol => {
+                    Toml::String(contents)
+                }
             };
 
             Value(v)
@@ -478,19 +2008,63 @@ pub mod toml {
             ))
         }
 
-        fn from_reader(mut reader: Box<dyn std::io::Read>) -> Self {
+        fn from_reader(
+            mut reader: Box<dyn std::io::Read>,
+            _config: &Config,
+        ) -> Result<Self, Box<dyn std::error::Error>> {
             let mut text = String::new();
-            let _len = reader.read_to_string(&mut text).unwrap();
-            Value(serde_toml::from_str(&text).expect("TOML"))
+            let _len = reader.read_to_string(&mut text)?;
+            Ok(Value(serde_toml::from_str(&text)?))
         }
 
-        fn to_writer(&self, mut writer: Box<dyn std::io::Write>, pretty: bool) {
-            let text = if pretty {
-                serde_toml::to_string_pretty(&self.0).unwrap()
+        fn to_writer(
+            &self,
+            writer: &mut dyn std::io::Write,
+            config: &Config,
+        ) -> std::io::Result<()> {
+            let text = if config.pretty {
+                serde_toml::to_string_pretty(&self.0).map_err(save_error)?
             } else {
-                serde_toml::to_string(&self.0).unwrap()
+                serde_toml::to_string(&self.0).map_err(save_error)?
+            };
+            writer.write_all(text.as_bytes())
+        }
+
+        fn to_writer_preserving(
+            &self,
+            writer: &mut dyn std::io::Write,
+            source: &str,
+            config: &Config,
+        ) -> std::io::Result<()> {
+            let fvs = match &self.0 {
+                Toml::Table(fvs) => fvs,
+                _ => return self.to_writer(writer, config),
             };
-            writer.write_all(text.as_bytes()).unwrap();
+            let mut doc = match source.parse::<toml_edit::Document>() {
+                Ok(doc) => doc,
+                Err(e) => {
+                    warn!(
+                        "Couldn't reparse the input as TOML ({}); comments will be lost.",
+                        e
+                    );
+                    return self.to_writer(writer, config);
+                }
+            };
+
+            edit_table(doc.as_table_mut(), fvs);
+            if config.pretty {
+                toml_edit::visit_mut::visit_document_mut(&mut PrettyStrings, &mut doc);
+            }
+            writer.write_all(doc.to_string().as_bytes())
+        }
+
+        fn replace_at(&mut self, pointer: &str, v: Self) -> Option<Self> {
+            toml_pointer_mut(&mut self.0, pointer)
+                .map(|subtree| Value(std::mem::replace(subtree, v.0)))
+        }
+
+        fn sort_keys(&mut self) {
+            toml_sort_keys(&mut self.0)
         }
     }
 }
@@ -500,47 +2074,101 @@ pub mod toml {
 pub mod yaml {
     use super::*;
     use std::hash::{Hash, Hasher};
+    use std::sync::Arc;
+    use yaml_rust::parser::{Event, MarkedEventReceiver, Parser};
+    use yaml_rust::scanner::{Marker, TScalarStyle};
     use yaml_rust::Yaml;
 
+    use crate::config::YamlAliases;
+
+    /// A YAML value, along with the targets of any aliases in it.
+    ///
+    /// Under `YamlAliases::Link`, each alias after the first is loaded as
+    /// `Yaml::Alias(n)`, where `n` indexes the alias table: the JSON Pointer
+    /// of the anchored value and its expansion, which is used when saving.
     #[derive(Clone, Debug)]
-    pub struct Value(Yaml);
+    pub struct Value(Yaml, Arc<Vec<(String, Yaml)>>);
 
-    impl std::fmt::Display for Value {
-        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
-            let mut emitter = yaml_rust::YamlEmitter::new(f);
-            emitter.dump(&self.0).map_err(|e| match e {
-                yaml_rust::EmitError::FmtError(e) => e,
-                yaml_rust::EmitError::BadHashmapKey => {
-                    panic!("unrecoverable YAML display error: BadHashmapKey")
-                }
-            })
+    impl Value {
+        fn plain(v: Yaml) -> Self {
+            Value(v, Arc::default())
         }
-    }
 
-    impl Default for Value {
-        fn default() -> Self {
-            Value(Yaml::Null)
+        /// The underlying YAML, with every alias expanded.
+        fn into_expanded(self) -> Yaml {
+            if self.1.is_empty() {
+                self.0
+            } else {
+                expand_aliases(self.0, &self.1)
+            }
         }
     }
 
-    fn yaml_size(v: &Yaml) -> usize {
+    fn expand_aliases(v: Yaml, aliases: &[(String, Yaml)]) -> Yaml {
         match v {
-            Yaml::Real(_)
-            | Yaml::Integer(_)
-            | Yaml::String(_)
-            | Yaml::Boolean(_)
-            | Yaml::Null
-            | Yaml::BadValue
-            | Yaml::Alias(_) => 1,
-            Yaml::Array(vs) => vs.iter().map(|v| yaml_size(v)).sum::<usize>() + 1,
-            Yaml::Hash(fvs) => fvs.iter().map(|(_, v)| yaml_size(v)).sum::<usize>() + 1,
+            Yaml::Alias(n) => match aliases.get(n) {
+                Some((_, target)) => target.clone(),
+                None => Yaml::BadValue,
+            },
+            Yaml::Array(vs) => {
+                Yaml::Array(vs.into_iter().map(|v| expand_aliases(v, aliases)).collect())
+            }
+            Yaml::Hash(fvs) => Yaml::Hash(
+                fvs.into_iter()
+                    .map(|(k, v)| (k, expand_aliases(v, aliases)))
+                    .collect(),
+            ),
+            v => v,
         }
     }
 
-    fn yaml_key_to_string(v: Yaml) -> String {
-        match v {
-            Yaml::Boolean(b) => format!("{}", b),
-            Yaml::Real(s) => s,
+    impl std::fmt::Display for Value {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+            let mut emitter = yaml_rust::YamlEmitter::new(f);
+            emitter
+                .dump(&self.clone().into_expanded())
+                .map_err(|e| match e {
+                    yaml_rust::EmitError::FmtError(e) => e,
+                    yaml_rust::EmitError::BadHashmapKey => {
+                        panic!("unrecoverable YAML display error: BadHashmapKey")
+                    }
+                })
+        }
+    }
+
+    impl Default for Value {
+        fn default() -> Self {
+            Value::plain(Yaml::Null)
+        }
+    }
+
+    fn yaml_sort_keys(v: &mut Yaml) {
+        match v {
+            Yaml::Array(vs) => vs.iter_mut().for_each(yaml_sort_keys),
+            Yaml::Hash(fvs) => {
+                let mut sorted = std::mem::take(fvs).into_iter().collect::<Vec<_>>();
+                sorted.sort_unstable_by(|(k1, _), (k2, _)| k1.cmp(k2));
+                for (k, mut v) in sorted {
+                    yaml_sort_keys(&mut v);
+                    fvs.insert(k, v);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn yaml_measure(v: &Yaml) -> (usize, usize) {
+        measure(v, |v| match v {
+            Yaml::Array(vs) => Some(vs.iter().collect()),
+            Yaml::Hash(fvs) => Some(fvs.values().collect()),
+            _ => None,
+        })
+    }
+
+    fn yaml_key_to_string(v: Yaml) -> String {
+        match v {
+            Yaml::Boolean(b) => format!("{}", b),
+            Yaml::Real(s) => s,
             Yaml::Integer(n) => format!("{}", n),
             Yaml::String(s) => s,
             Yaml::Alias(n) => format!("alias{}", n),
@@ -559,20 +2187,159 @@ pub mod yaml {
         }
     }
 
+    fn yaml_pointer_mut<'a>(mut v: &'a mut Yaml, pointer: &str) -> Option<&'a mut Yaml> {
+        for token in pointer_tokens(pointer)? {
+            v = match v {
+                Yaml::Hash(fvs) => fvs
+                    .iter_mut()
+                    .find(|(k, _)| yaml_key_to_string((*k).clone()) == token)
+                    .map(|(_, v)| v)?,
+                Yaml::Array(vs) => vs.get_mut(pointer_index(&token)?)?,
+                _ => return None,
+            };
+        }
+
+        Some(v)
+    }
+
+    /// Where `AliasFinder` is inside of a collection.
+    enum Frame {
+        /// In a sequence, at the given index.
+        Seq(usize),
+        /// In a mapping: `None` while reading a key, and then the key (if it
+        /// is a scalar) while reading its value.
+        Map(Option<Option<String>>),
+    }
+
+    /// Records the paths of the anchors and aliases in a YAML stream. Paths
+    /// start with the document index; nodes inside of complex keys have no
+    /// path.
+    #[derive(Default)]
+    struct AliasFinder {
+        documents: usize,
+        stack: Vec<(Option<Vec<String>>, Frame)>,
+        anchors: HashMap<usize, Option<Vec<String>>>,
+        aliases: Vec<(Option<Vec<String>>, usize)>,
+    }
+
+    impl AliasFinder {
+        /// The path of the node that's about to start.
+        fn next_path(&self) -> Option<Vec<String>> {
+            let (path, token) = match self.stack.last() {
+                None => return Some(vec![self.documents.to_string()]),
+                Some((path, Frame::Seq(i))) => (path, i.to_string()),
+                Some((path, Frame::Map(Some(Some(key))))) => (path, key.clone()),
+                Some((_, Frame::Map(_))) => return None,
+            };
+
+            let mut path = path.clone()?;
+            path.push(token);
+            Some(path)
+        }
+
+        fn start(&mut self, anchor: usize, frame: Frame) {
+            let path = self.next_path();
+            if anchor > 0 {
+                self.anchors.insert(anchor, path.clone());
+            }
+            self.stack.push((path, frame));
+        }
+
+        /// Moves past a node that just ended; `key` is its text if it can be
+        /// used as a key.
+        fn finish(&mut self, key: Option<String>) {
+            if let Some((_, frame)) = self.stack.last_mut() {
+                *frame = match frame {
+                    Frame::Seq(i) => Frame::Seq(*i + 1),
+                    Frame::Map(None) => Frame::Map(Some(key)),
+                    Frame::Map(Some(_)) => Frame::Map(None),
+                };
+            }
+        }
+    }
+
+    impl MarkedEventReceiver for AliasFinder {
+        fn on_event(&mut self, ev: Event, _mark: Marker) {
+            match ev {
+                Event::DocumentEnd => self.documents += 1,
+                Event::SequenceStart(anchor) => self.start(anchor, Frame::Seq(0)),
+                Event::MappingStart(anchor) => self.start(anchor, Frame::Map(None)),
+                Event::SequenceEnd | Event::MappingEnd => {
+                    self.stack.pop();
+                    self.finish(None);
+                }
+                Event::Scalar(s, style, anchor, _tag) => {
+                    if anchor > 0 {
+                        self.anchors.insert(anchor, self.next_path());
+                    }
+                    // match how YamlLoader reads keys
+                    let key = if style == TScalarStyle::Plain {
+                        yaml_key_to_string(Yaml::from_str(&s))
+                    } else {
+                        s
+                    };
+                    self.finish(Some(key));
+                }
+                Event::Alias(anchor) => {
+                    self.aliases.push((self.next_path(), anchor));
+                    self.finish(None);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Replaces the aliases in `v`, which was loaded from `text` with its
+    /// aliases expanded, with links to the values they refer to.
+    fn link_aliases(
+        text: &str,
+        mut v: Yaml,
+    ) -> Result<(Yaml, Vec<(String, Yaml)>), yaml_rust::ScanError> {
+        let mut finder = AliasFinder::default();
+        Parser::new(text.chars()).load(&mut finder, true)?;
+
+        // a single document is the root, not the first of a list
+        let skip = if finder.documents == 1 { 1 } else { 0 };
+        let mut aliases = Vec::new();
+        for (path, anchor) in finder.aliases {
+            let (path, target) = match (path, finder.anchors.get(&anchor)) {
+                (Some(path), Some(Some(target))) => (path, target),
+                _ => {
+                    warn!("Expanding a YAML alias in a complex key.");
+                    continue;
+                }
+            };
+
+            let target = pointer_from_tokens(&target[skip..]);
+            if let Some(subtree) = yaml_pointer_mut(&mut v, &pointer_from_tokens(&path[skip..])) {
+                let expanded = std::mem::replace(subtree, Yaml::Alias(aliases.len()));
+                aliases.push((target, expanded));
+            }
+        }
+
+        Ok((v, aliases))
+    }
+
     impl Nodelike for Value {
         fn kind(&self) -> FileType {
             match &self.0 {
                 Yaml::Array(_) | Yaml::Hash(_) => FileType::Directory,
+                Yaml::Alias(n) if *n < self.1.len() => FileType::Symlink,
                 _ => FileType::RegularFile,
             }
         }
 
         fn size(&self) -> usize {
-            yaml_size(&self.0)
+            yaml_measure(&self.0).0
+        }
+
+        fn depth(&self) -> usize {
+            yaml_measure(&self.0).1
         }
 
         fn node(self, config: &Config) -> Node<Self> {
             let nl = if config.add_newlines { "\n" } else { "" };
+            let aliases = self.1;
 
             match self.0 {
                 Yaml::Null => Node::String(Typ::Null, "".into()),
@@ -588,14 +2355,18 @@ pub mod yaml {
 
                     Node::String(Typ::String, if s.ends_with('\n') { s } else { s + nl })
                 }
-                Yaml::Array(vs) => Node::List(vs.into_iter().map(Value).collect()),
+                Yaml::Array(vs) => {
+                    Node::List(vs.into_iter().map(|v| Value(v, aliases.clone())).collect())
+                }
                 Yaml::Hash(fvs) => Node::Map(
                     fvs.into_iter()
-                        .map(|(k, v)| (yaml_key_to_string(k), Value(v)))
+                        .map(|(k, v)| (yaml_key_to_string(k), Value(v, aliases.clone())))
                         .collect(),
                 ),
-                // ??? 2021-06-21 support aliases w/hard links?
-                Yaml::Alias(n) => Node::Bytes(format!("alias{}{}", n, nl).into_bytes()),
+                Yaml::Alias(n) => match aliases.get(n) {
+                    Some((pointer, _)) => Node::Link(pointer.clone()),
+                    None => Node::Bytes(format!("alias{}{}", n, nl).into_bytes()),
+                },
                 Yaml::BadValue => Node::Bytes("bad YAML value".into()),
             }
         }
@@ -604,56 +2375,58 @@ pub mod yaml {
             match typ {
                 Typ::Auto => {
                     if contents.is_empty() {
-                        Value(Yaml::Null)
+                        Value::plain(Yaml::Null)
                     } else if contents == "true" {
-                        Value(Yaml::Boolean(true))
+                        Value::plain(Yaml::Boolean(true))
                     } else if contents == "false" {
-                        Value(Yaml::Boolean(false))
+                        Value::plain(Yaml::Boolean(false))
                     } else if let Ok(n) = i64::from_str(&contents) {
-                        Value(Yaml::Integer(n))
+                        Value::plain(Yaml::Integer(n))
                     } else if let Ok(_n) = f64::from_str(&contents) {
-                        Value(Yaml::Real(contents))
+                        Value::plain(Yaml::Real(contents))
                     } else {
-                        Value(Yaml::String(contents))
+                        Value::plain(Yaml::String(contents))
                     }
                 }
                 Typ::Boolean => {
                     if contents == "true" {
-                        Value(Yaml::Boolean(true))
+                        Value::plain(Yaml::Boolean(true))
                     } else if contents == "false" {
-                        Value(Yaml::Boolean(false))
+                        Value::plain(Yaml::Boolean(false))
                     } else {
                         debug!("string '{}' tagged as boolean", contents);
-                        Value(Yaml::String(contents))
+                        Value::plain(Yaml::String(contents))
                     }
                 }
                 Typ::Bytes => panic!("from_string called at typ::bytes"),
-                Typ::Datetime => Value(Yaml::String(contents)),
+                Typ::Datetime => Value::plain(Yaml::String(contents)),
                 Typ::Float => {
                     if let Ok(_n) = f64::from_str(&contents) {
-                        Value(Yaml::Real(contents))
+                        Value::plain(Yaml::Real(contents))
                     } else {
                         debug!("string '{}' tagged as float", contents);
-                        Value(Yaml::String(contents))
+                        Value::plain(Yaml::String(contents))
                     }
                 }
                 Typ::Integer => {
                     if let Ok(n) = i64::from_str(&contents) {
-                        Value(Yaml::Integer(n))
+                        Value::plain(Yaml::Integer(n))
                     } else {
                         debug!("string '{}' tagged as float", contents);
-                        Value(Yaml::String(contents))
+                        Value::plain(Yaml::String(contents))
                     }
                 }
                 Typ::Null => {
                     if contents.is_empty() {
-                        Value(Yaml::Null)
+                        Value::plain(Yaml::Null)
                     } else {
                         debug!("string '{}' tagged as null", contents);
-                        Value(Yaml::String(contents))
+                        Value::plain(Yaml::String(contents))
                     }
                 }
-                Typ::String => Value(Yaml::String(contents)),
+                Typ::String | Typ::ObjectId | Typ::Decimal | Typ::Keyword | Typ::Symbol => {
+                    Value::plain(Yaml::String(contents))
+                }
             }
         }
 
@@ -661,40 +2434,3237 @@ pub mod yaml {
         where
             T: AsRef<[u8]>,
         {
-            Value(Yaml::String(base64::encode_config(contents, config.base64)))
+            Value::plain(Yaml::String(base64::encode_config(contents, config.base64)))
         }
 
         fn from_list_dir(vs: Vec<Self>, _config: &Config) -> Self {
-            Value(Yaml::Array(vs.into_iter().map(|v| v.0).collect()))
+            Value::plain(Yaml::Array(
+                vs.into_iter().map(|v| v.into_expanded()).collect(),
+            ))
         }
 
         fn from_named_dir(fvs: HashMap<String, Self>, config: &Config) -> Self {
-            Value(Yaml::Hash(
+            Value::plain(Yaml::Hash(
                 fvs.into_iter()
-                    .map(|(k, v)| (Value::from_string(Typ::String, k, config).0, v.0))
+                    .map(|(k, v)| {
+                        (
+                            Value::from_string(Typ::String, k, config).0,
+                            v.into_expanded(),
+                        )
+                    })
                     .collect(),
             ))
         }
 
-        fn from_reader(mut reader: Box<dyn std::io::Read>) -> Self {
+        fn from_reader(
+            mut reader: Box<dyn std::io::Read>,
+            config: &Config,
+        ) -> Result<Self, Box<dyn std::error::Error>> {
             let mut text = String::new();
-            let _len = reader.read_to_string(&mut text).unwrap();
-            yaml_rust::YamlLoader::load_from_str(&text)
-                .map(|vs| {
-                    Value(if vs.len() == 1 {
-                        vs.into_iter().next().unwrap()
-                    } else {
-                        Yaml::Array(vs)
-                    })
-                })
-                .expect("YAML")
+            let _len = reader.read_to_string(&mut text)?;
+            let vs = yaml_rust::YamlLoader::load_from_str(&text)?;
+            let v = if vs.len() == 1 {
+                vs.into_iter().next().unwrap()
+            } else {
+                Yaml::Array(vs)
+            };
+
+            match config.yaml_aliases {
+                YamlAliases::Expand => Ok(Value::plain(v)),
+                YamlAliases::Link => {
+                    let (v, aliases) = link_aliases(&text, v)?;
+                    Ok(Value(v, Arc::new(aliases)))
+                }
+            }
         }
 
-        fn to_writer(&self, mut writer: Box<dyn std::io::Write>, _pretty: bool) {
+        fn to_writer(
+            &self,
+            writer: &mut dyn std::io::Write,
+            _config: &Config,
+        ) -> std::io::Result<()> {
             let mut text = String::new();
             let mut emitter = yaml_rust::YamlEmitter::new(&mut text);
-            emitter.dump(&self.0).unwrap();
-            writer.write_all(text.as_bytes()).unwrap();
+            emitter
+                .dump(&self.clone().into_expanded())
+                .map_err(save_error)?;
+            writer.write_all(text.as_bytes())
+        }
+
+        fn replace_at(&mut self, pointer: &str, v: Self) -> Option<Self> {
+            let aliases = self.1.clone();
+            yaml_pointer_mut(&mut self.0, pointer)
+                .map(|subtree| Value(std::mem::replace(subtree, v.into_expanded()), aliases))
+        }
+
+        fn sort_keys(&mut self) {
+            yaml_sort_keys(&mut self.0)
+        }
+
+        fn non_string_keys(&self) -> Vec<String> {
+            match &self.0 {
+                Yaml::Hash(fvs) => fvs
+                    .keys()
+                    .filter(|k| !matches!(k, Yaml::String(_)))
+                    .map(|k| yaml_key_to_string(k.clone()))
+                    .collect(),
+                _ => Vec::new(),
+            }
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+/// INI/properties Nodelike implementation
+///
+/// An INI document is a named directory: keys outside of any section are files
+/// at the root, and each `[section]` is a named directory of keys. Java
+/// `.properties` files are INI documents without sections. Values are untyped
+/// strings, so they use `Typ::Auto`.
+pub mod ini {
+    use super::*;
+    use std::collections::HashSet;
+    use std::fmt::Write;
+    use tracing::warn;
+
+    #[derive(Clone, Debug)]
+    pub enum Value {
+        String(String),
+        /// A section, or (under `Config::ini_split_dots`) a group of keys
+        /// sharing a dotted prefix inside of one.
+        Map(Vec<(String, Value)>),
+        /// Under `Config::ini_split_dots`, a group of keys outside of any
+        /// section sharing a dotted prefix. It's tagged (see `DOTTED_TAG`), so
+        /// it's saved as dotted keys again rather than as a section.
+        Dotted(Vec<(String, Value)>),
+        /// A whole INI document, as loaded.
+        Document(Vec<(String, Value)>),
+    }
+
+    /// The tag `Nodelike::tag` gives `Value::Dotted`.
+    const DOTTED_TAG: &str = "dotted";
+
+    impl Default for Value {
+        fn default() -> Self {
+            Value::String(String::new())
+        }
+    }
+
+    impl std::fmt::Display for Value {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+            let fvs = match self {
+                Value::String(s) => return write!(f, "{}", s),
+                Value::Map(fvs) | Value::Dotted(fvs) | Value::Document(fvs) => fvs,
+            };
+
+            // keys outside of any section have to come first
+            let global = |v: &Value| matches!(v, Value::String(_) | Value::Dotted(_));
+            let mut wrote_globals = false;
+            for (key, v) in fvs.iter() {
+                if global(v) {
+                    write_entries(f, key, v)?;
+                    wrote_globals = true;
+                }
+            }
+
+            let mut sections = fvs.iter().filter(|(_, v)| !global(v)).peekable();
+            if wrote_globals && sections.peek().is_some() {
+                writeln!(f)?;
+            }
+            while let Some((section, v)) = sections.next() {
+                writeln!(f, "[{}]", section)?;
+                write_entries(f, "", v)?;
+                if sections.peek().is_some() {
+                    writeln!(f)?;
+                }
+            }
+
+            Ok(())
+        }
+    }
+
+    /// Writes `key = value` lines, flattening nested maps into dotted keys.
+    fn write_entries(
+        f: &mut std::fmt::Formatter<'_>,
+        prefix: &str,
+        v: &Value,
+    ) -> std::result::Result<(), std::fmt::Error> {
+        match v {
+            Value::String(s) => writeln!(f, "{} = {}", prefix, quote(prefix, s)),
+            Value::Map(fvs) | Value::Dotted(fvs) | Value::Document(fvs) => {
+                for (key, v) in fvs.iter() {
+                    if prefix.is_empty() {
+                        write_entries(f, key, v)?;
+                    } else {
+                        write_entries(f, &format!("{}.{}", prefix, key), v)?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Quotes values that wouldn't survive being parsed back, i.e., those with
+    /// surrounding whitespace or quotes.
+    fn quote(key: &str, s: &str) -> String {
+        let s = if s.contains('\n') {
+            warn!(
+                "INI values can't span lines; joining the lines of '{}'",
+                key
+            );
+            s.lines().collect::<Vec<_>>().join(" ")
+        } else {
+            s.into()
+        };
+
+        if s.trim() != s || (s.len() >= 2 && s.starts_with('"') && s.ends_with('"')) {
+            let mut quoted = String::with_capacity(s.len() + 2);
+            let _ = write!(quoted, "\"{}\"", s);
+            quoted
+        } else {
+            s
+        }
+    }
+
+    fn unquote(s: &str) -> String {
+        if s.len() >= 2 && s.starts_with('"') && s.ends_with('"') {
+            s[1..s.len() - 1].into()
+        } else {
+            s.into()
+        }
+    }
+
+    fn parse(text: &str) -> Value {
+        let mut document: Vec<(String, Value)> = Vec::new();
+        let mut section: Option<usize> = None;
+        // sections named like a key outside of any section, and their new names
+        let mut renamed: HashMap<String, String> = HashMap::new();
+
+        for (lineno, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+                continue;
+            }
+
+            if line.starts_with('[') && line.ends_with(']') {
+                let mut name = line[1..line.len() - 1].trim().to_string();
+                if let Some(new_name) = renamed.get(&name) {
+                    name = new_name.clone();
+                } else if document
+                    .iter()
+                    .any(|(k, v)| *k == name && matches!(v, Value::String(_)))
+                {
+                    let mut new_name = name.clone();
+                    while document.iter().any(|(k, _)| *k == new_name) {
+                        new_name.push('_');
+                    }
+                    warn!(
+                        "INI section [{}] on line {} has the same name as a key outside of any section; calling it '{}'",
+                        name,
+                        lineno + 1,
+                        new_name
+                    );
+                    renamed.insert(name, new_name.clone());
+                    name = new_name;
+                }
+
+                section = match document
+                    .iter()
+                    .position(|(k, v)| *k == name && matches!(v, Value::Map(_)))
+                {
+                    Some(idx) => Some(idx),
+                    None => {
+                        document.push((name, Value::Map(Vec::new())));
+                        Some(document.len() - 1)
+                    }
+                };
+                continue;
+            }
+
+            let (key, value) = match line.find(['=', ':']) {
+                Some(idx) => (line[..idx].trim(), unquote(line[idx + 1..].trim())),
+                None => (line, String::new()),
+            };
+
+            let entries = match section {
+                None => &mut document,
+                Some(idx) => match &mut document[idx].1 {
+                    Value::Map(fvs) => fvs,
+                    _ => unreachable!("INI section is not a map"),
+                },
+            };
+            match entries.iter_mut().find(|(k, _)| k == key) {
+                Some(entry) => {
+                    warn!(
+                        "duplicate INI key '{}' on line {}; keeping the last value",
+                        key,
+                        lineno + 1
+                    );
+                    entry.1 = Value::String(value);
+                }
+                None => entries.push((key.into(), Value::String(value))),
+            }
+        }
+
+        Value::Document(document)
+    }
+
+    /// Nests dotted keys (`a.b.c`) into maps---`Value::Dotted` ones at the
+    /// `root` of the document, since maps there are sections. Keys whose prefix
+    /// is already taken by another entry are left alone.
+    fn split_dots(fvs: Vec<(String, Value)>, root: bool) -> Vec<(String, Value)> {
+        let taken: HashSet<String> = fvs
+            .iter()
+            .filter(|(k, v)| !k.contains('.') || !matches!(v, Value::String(_)))
+            .map(|(k, _)| k.clone())
+            .collect();
+
+        let mut nested: Vec<(String, Value)> = Vec::with_capacity(fvs.len());
+        for (key, v) in fvs.into_iter() {
+            let split = match (&v, key.split_once('.')) {
+                (Value::String(_), Some((first, rest)))
+                    if !first.is_empty() && !rest.is_empty() && !taken.contains(first) =>
+                {
+                    Some((first.to_string(), rest.to_string()))
+                }
+                _ => None,
+            };
+
+            match split {
+                None => nested.push((key, v)),
+                Some((first, rest)) => match nested.iter_mut().find(|(k, _)| *k == first) {
+                    Some((_, Value::Map(children) | Value::Dotted(children))) => {
+                        children.push((rest, v))
+                    }
+                    Some(_) => unreachable!("prefix '{}' is taken", first),
+                    None if root => nested.push((first, Value::Dotted(vec![(rest, v)]))),
+                    None => nested.push((first, Value::Map(vec![(rest, v)]))),
+                },
+            }
+        }
+
+        nested
+    }
+
+    fn ini_pointer_mut<'a>(mut v: &'a mut Value, pointer: &str) -> Option<&'a mut Value> {
+        for token in pointer_tokens(pointer)? {
+            v = match v {
+                Value::Map(fvs) | Value::Dotted(fvs) | Value::Document(fvs) => {
+                    fvs.iter_mut().find(|(k, _)| *k == token).map(|(_, v)| v)?
+                }
+                Value::String(_) => return None,
+            };
+        }
+
+        Some(v)
+    }
+
+    fn ini_measure(v: &Value) -> (usize, usize) {
+        measure(v, |v| match v {
+            Value::String(_) => None,
+            Value::Map(fvs) | Value::Dotted(fvs) | Value::Document(fvs) => {
+                Some(fvs.iter().map(|(_, v)| v).collect())
+            }
+        })
+    }
+
+    impl Nodelike for Value {
+        fn kind(&self) -> FileType {
+            match self {
+                Value::String(_) => FileType::RegularFile,
+                Value::Map(_) | Value::Dotted(_) | Value::Document(_) => FileType::Directory,
+            }
+        }
+
+        fn size(&self) -> usize {
+            ini_measure(self).0
+        }
+
+        fn depth(&self) -> usize {
+            ini_measure(self).1
+        }
+
+        fn node(self, config: &Config) -> Node<Self> {
+            let nl = if config.add_newlines { "\n" } else { "" };
+
+            match self {
+                Value::String(s) => {
+                    if config.try_decode_base64 {
+                        if let Ok(bytes) = base64::decode_config(&s, config.base64) {
+                            return Node::Bytes(bytes);
+                        }
+                    }
+
+                    Node::String(Typ::Auto, if s.ends_with('\n') { s } else { s + nl })
+                }
+                Value::Map(fvs) | Value::Dotted(fvs) => {
+                    if config.ini_split_dots {
+                        Node::Map(split_dots(fvs, false))
+                    } else {
+                        Node::Map(fvs)
+                    }
+                }
+                Value::Document(fvs) => {
+                    if config.ini_split_dots {
+                        Node::Map(split_dots(fvs, true))
+                    } else {
+                        Node::Map(fvs)
+                    }
+                }
+            }
+        }
+
+        fn from_string(_typ: Typ, contents: String, _config: &Config) -> Self {
+            Value::String(contents)
+        }
+
+        fn from_bytes<T>(contents: T, config: &Config) -> Self
+        where
+            T: AsRef<[u8]>,
+        {
+            Value::String(base64::encode_config(contents, config.base64))
+        }
+
+        fn from_list_dir(files: Vec<Self>, _config: &Config) -> Self {
+            debug!("INI has no lists; using indices as keys");
+            Value::Map(
+                files
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, v)| (i.to_string(), v))
+                    .collect(),
+            )
+        }
+
+        fn from_named_dir(files: HashMap<String, Self>, _config: &Config) -> Self {
+            let mut fvs = files.into_iter().collect::<Vec<_>>();
+            fvs.sort_unstable_by(|(k1, _), (k2, _)| k1.cmp(k2));
+            Value::Map(fvs)
+        }
+
+        fn from_reader(
+            mut reader: Box<dyn std::io::Read>,
+            _config: &Config,
+        ) -> Result<Self, Box<dyn std::error::Error>> {
+            let mut text = String::new();
+            let _len = reader.read_to_string(&mut text)?;
+            Ok(parse(&text))
+        }
+
+        fn to_writer(
+            &self,
+            writer: &mut dyn std::io::Write,
+            _config: &Config,
+        ) -> std::io::Result<()> {
+            writer.write_all(self.to_string().as_bytes())
+        }
+
+        fn replace_at(&mut self, pointer: &str, v: Self) -> Option<Self> {
+            ini_pointer_mut(self, pointer).map(|subtree| std::mem::replace(subtree, v))
+        }
+
+        fn sort_keys(&mut self) {
+            if let Value::Map(fvs) | Value::Dotted(fvs) | Value::Document(fvs) = self {
+                fvs.sort_by(|(k1, _), (k2, _)| k1.cmp(k2));
+                for (_, v) in fvs.iter_mut() {
+                    v.sort_keys();
+                }
+            }
+        }
+
+        fn tag(&self) -> Option<String> {
+            match self {
+                Value::Dotted(_) => Some(DOTTED_TAG.into()),
+                _ => None,
+            }
+        }
+
+        fn with_tag(self, tag: &str) -> Self {
+            match (tag, self) {
+                (DOTTED_TAG, Value::Map(fvs)) => Value::Dotted(fvs),
+                (_, v) => v,
+            }
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+/// Java `.properties` Nodelike implementation
+///
+/// A `.properties` file is an INI document without sections (see `ini`), with
+/// its own syntax: `#` and `!` comments; `key=value`, `key:value`, or `key
+/// value` lines; backslash escapes (e.g., `\=`, `\:`, and `\uXXXX`); and
+/// lines continued by a trailing backslash. Directories are saved as dotted
+/// keys.
+pub mod properties {
+    use super::*;
+    use tracing::warn;
+
+    #[derive(Clone, Debug, Default)]
+    pub struct Value(ini::Value);
+
+    impl std::fmt::Display for Value {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+            let fvs = match &self.0 {
+                ini::Value::String(s) => return write!(f, "{}", s),
+                ini::Value::Map(fvs) | ini::Value::Dotted(fvs) | ini::Value::Document(fvs) => fvs,
+            };
+
+            for (key, v) in fvs.iter() {
+                if let ini::Value::Map(_) = v {
+                    warn!(
+                        "Properties files have no sections; saving the directory '{}' as dotted keys.",
+                        key
+                    );
+                }
+                write_entries(f, key, v)?;
+            }
+            Ok(())
+        }
+    }
+
+    /// Writes `key = value` lines, flattening nested maps into dotted keys.
+    fn write_entries(
+        f: &mut std::fmt::Formatter<'_>,
+        prefix: &str,
+        v: &ini::Value,
+    ) -> std::result::Result<(), std::fmt::Error> {
+        match v {
+            ini::Value::String(s) => writeln!(f, "{} = {}", escape(prefix, true), escape(s, false)),
+            ini::Value::Map(fvs) | ini::Value::Dotted(fvs) | ini::Value::Document(fvs) => {
+                for (key, v) in fvs.iter() {
+                    write_entries(f, &format!("{}.{}", prefix, key), v)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Escapes what wouldn't survive being parsed back: backslashes and line
+    /// breaks anywhere, separators and spaces in keys, a comment character
+    /// starting a key, and a space starting a value.
+    fn escape(s: &str, key: bool) -> String {
+        let mut escaped = String::with_capacity(s.len());
+        for (idx, c) in s.chars().enumerate() {
+            match c {
+                '\\' => escaped.push_str("\\\\"),
+                '\n' => escaped.push_str("\\n"),
+                '\r' => escaped.push_str("\\r"),
+                '\t' => escaped.push_str("\\t"),
+                '\x0c' => escaped.push_str("\\f"),
+                '=' | ':' if key => {
+                    escaped.push('\\');
+                    escaped.push(c);
+                }
+                '#' | '!' if key && idx == 0 => {
+                    escaped.push('\\');
+                    escaped.push(c);
+                }
+                ' ' if key || idx == 0 => escaped.push_str("\\ "),
+                c => escaped.push(c),
+            }
+        }
+        escaped
+    }
+
+    /// Reads the four hex digits of a `\uXXXX` escape.
+    fn hex4(chars: &mut std::str::Chars) -> Option<u32> {
+        let hex = chars.clone().take(4).collect::<String>();
+        let n = u32::from_str_radix(&hex, 16)
+            .ok()
+            .filter(|_| hex.len() == 4)?;
+        chars.nth(3);
+        Some(n)
+    }
+
+    /// Undoes escapes: `\t`, `\n`, `\r`, `\f`, and `\uXXXX` (UTF-16, so a
+    /// surrogate pair takes two); a backslash before anything else stands for
+    /// that character (e.g., `\=`).
+    fn unescape(s: &str, lineno: usize) -> Result<String, String> {
+        let mut unescaped = String::with_capacity(s.len());
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                unescaped.push(c);
+                continue;
+            }
+
+            match chars.next() {
+                Some('t') => unescaped.push('\t'),
+                Some('n') => unescaped.push('\n'),
+                Some('r') => unescaped.push('\r'),
+                Some('f') => unescaped.push('\x0c'),
+                Some('u') => {
+                    let n = hex4(&mut chars).ok_or_else(|| {
+                        format!("malformed \\uXXXX escape on line {}", lineno + 1)
+                    })?;
+                    let c = if (0xD800..0xDC00).contains(&n) {
+                        let mut rest = chars.clone();
+                        let low = match (rest.next(), rest.next()) {
+                            (Some('\\'), Some('u')) => hex4(&mut rest),
+                            _ => None,
+                        };
+                        match low {
+                            Some(low) if (0xDC00..0xE000).contains(&low) => {
+                                chars = rest;
+                                char::from_u32(0x10000 + ((n - 0xD800) << 10) + (low - 0xDC00))
+                            }
+                            _ => None,
+                        }
+                    } else {
+                        char::from_u32(n)
+                    };
+                    unescaped.push(c.unwrap_or(char::REPLACEMENT_CHARACTER));
+                }
+                Some(c) => unescaped.push(c),
+                None => (),
+            }
+        }
+        Ok(unescaped)
+    }
+
+    /// Splits `text` into logical lines, numbered by where they start.
+    /// Comments and blank lines are dropped, and a line ending in an odd number
+    /// of backslashes goes on with the next one, less its leading whitespace.
+    fn logical_lines(text: &str) -> Vec<(usize, String)> {
+        let mut lines = Vec::new();
+        let mut continued: Option<(usize, String)> = None;
+        for (lineno, line) in text.lines().enumerate() {
+            let line = line.trim_start();
+            let (start, mut logical) = match continued.take() {
+                Some((start, logical)) => (start, logical + line),
+                None if line.is_empty() || line.starts_with('#') || line.starts_with('!') => {
+                    continue
+                }
+                None => (lineno, line.to_string()),
+            };
+
+            if logical.chars().rev().take_while(|c| *c == '\\').count() % 2 == 1 {
+                logical.pop();
+                continued = Some((start, logical));
+            } else {
+                lines.push((start, logical));
+            }
+        }
+        lines.extend(continued);
+        lines
+    }
+
+    fn parse(text: &str) -> Result<ini::Value, String> {
+        let mut fvs: Vec<(String, ini::Value)> = Vec::new();
+
+        for (lineno, line) in logical_lines(text) {
+            // the key runs up to the first unescaped separator or whitespace
+            let mut end = line.len();
+            let mut escaped = false;
+            for (idx, c) in line.char_indices() {
+                if escaped {
+                    escaped = false;
+                } else if c == '\\' {
+                    escaped = true;
+                } else if c == '=' || c == ':' || c.is_whitespace() {
+                    end = idx;
+                    break;
+                }
+            }
+
+            let key = unescape(&line[..end], lineno)?;
+            let rest = line[end..].trim_start();
+            let rest = rest.strip_prefix(['=', ':']).map_or(rest, str::trim_start);
+            let value = ini::Value::String(unescape(rest, lineno)?);
+
+            match fvs.iter_mut().find(|(k, _)| *k == key) {
+                Some(entry) => {
+                    warn!(
+                        "duplicate key '{}' on line {}; keeping the last value",
+                        key,
+                        lineno + 1
+                    );
+                    entry.1 = value;
+                }
+                None => fvs.push((key, value)),
+            }
+        }
+
+        Ok(ini::Value::Document(fvs))
+    }
+
+    impl Nodelike for Value {
+        fn kind(&self) -> FileType {
+            self.0.kind()
+        }
+
+        fn size(&self) -> usize {
+            self.0.size()
+        }
+
+        fn depth(&self) -> usize {
+            self.0.depth()
+        }
+
+        fn node(self, config: &Config) -> Node<Self> {
+            match self.0.node(config) {
+                Node::String(t, s) => Node::String(t, s),
+                Node::Bytes(b) => Node::Bytes(b),
+                Node::Link(target) => Node::Link(target),
+                Node::List(vs) => Node::List(vs.into_iter().map(Value).collect()),
+                Node::Map(fvs) => Node::Map(fvs.into_iter().map(|(f, v)| (f, Value(v))).collect()),
+            }
+        }
+
+        fn from_string(typ: Typ, contents: String, config: &Config) -> Self {
+            Value(ini::Value::from_string(typ, contents, config))
+        }
+
+        fn from_bytes<T>(contents: T, config: &Config) -> Self
+        where
+            T: AsRef<[u8]>,
+        {
+            Value(ini::Value::from_bytes(contents, config))
+        }
+
+        fn from_list_dir(files: Vec<Self>, config: &Config) -> Self {
+            Value(ini::Value::from_list_dir(
+                files.into_iter().map(|v| v.0).collect(),
+                config,
+            ))
+        }
+
+        fn from_named_dir(files: HashMap<String, Self>, config: &Config) -> Self {
+            Value(ini::Value::from_named_dir(
+                files.into_iter().map(|(f, v)| (f, v.0)).collect(),
+                config,
+            ))
+        }
+
+        fn from_reader(
+            mut reader: Box<dyn std::io::Read>,
+            _config: &Config,
+        ) -> Result<Self, Box<dyn std::error::Error>> {
+            let mut text = String::new();
+            let _len = reader.read_to_string(&mut text)?;
+            Ok(Value(parse(&text)?))
+        }
+
+        fn to_writer(
+            &self,
+            writer: &mut dyn std::io::Write,
+            _config: &Config,
+        ) -> std::io::Result<()> {
+            writer.write_all(self.to_string().as_bytes())
+        }
+
+        fn replace_at(&mut self, pointer: &str, v: Self) -> Option<Self> {
+            self.0.replace_at(pointer, v.0).map(Value)
+        }
+
+        fn sort_keys(&mut self) {
+            self.0.sort_keys()
+        }
+
+        fn tag(&self) -> Option<String> {
+            self.0.tag()
+        }
+
+        fn with_tag(self, tag: &str) -> Self {
+            Value(self.0.with_tag(tag))
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+/// HCL Nodelike implementation
+///
+/// Attributes are files and blocks are directories: a block's labels become
+/// nested directories (so `resource "aws_instance" "web" { ... }` lives at
+/// `resource/aws_instance/web`), and repeated blocks with the same type and
+/// labels form a list. Expressions other than literals (variables, function
+/// calls, templates, etc.) aren't supported: they're loaded as strings.
+///
+/// Blocks can't be told apart from object attributes after they've been
+/// mounted, so saving writes every directory as an object attribute; only
+/// attribute-only files round-trip exactly.
+pub mod hcl {
+    use super::*;
+    use hcl_rs::edit::visit_mut::{visit_expr_mut, VisitMut};
+    use hcl_rs::{edit, Expression, Identifier, ObjectKey, Structure, TemplateExpr};
+    use tracing::warn;
+
+    type Hcl = hcl_rs::Value;
+
+    #[derive(Clone, Debug)]
+    pub struct Value(Hcl);
+
+    impl std::fmt::Display for Value {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+            self.0.fmt(f)
+        }
+    }
+
+    impl Default for Value {
+        fn default() -> Self {
+            Value(Hcl::Null)
+        }
+    }
+
+    fn hcl_sort_keys(v: &mut Hcl) {
+        match v {
+            Hcl::Array(vs) => vs.iter_mut().for_each(hcl_sort_keys),
+            Hcl::Object(fvs) => {
+                let mut sorted = std::mem::take(fvs).into_iter().collect::<Vec<_>>();
+                sorted.sort_unstable_by(|(k1, _), (k2, _)| k1.cmp(k2));
+                for (k, mut v) in sorted {
+                    hcl_sort_keys(&mut v);
+                    fvs.insert(k, v);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn hcl_measure(v: &Hcl) -> (usize, usize) {
+        measure(v, |v| match v {
+            Hcl::Array(vs) => Some(vs.iter().collect()),
+            Hcl::Object(fvs) => Some(fvs.values().collect()),
+            _ => None,
+        })
+    }
+
+    fn hcl_pointer_mut<'a>(mut v: &'a mut Hcl, pointer: &str) -> Option<&'a mut Hcl> {
+        for token in pointer_tokens(pointer)? {
+            v = match v {
+                Hcl::Object(fvs) => fvs.get_mut(&token)?,
+                Hcl::Array(vs) => vs.get_mut(pointer_index(&token)?)?,
+                _ => return None,
+            };
+        }
+
+        Some(v)
+    }
+
+    fn parse_number(s: &str) -> Option<hcl_rs::Number> {
+        if let Ok(n) = i64::from_str(s) {
+            Some(n.into())
+        } else if let Ok(n) = u64::from_str(s) {
+            Some(n.into())
+        } else if is_wide_number(s) {
+            warn!(
+                "HCL numbers can't hold {} exactly; keeping it as a string.",
+                s
+            );
+            None
+        } else {
+            f64::from_str(s).ok().and_then(hcl_rs::Number::from_f64)
+        }
+    }
+
+    /// Finds the numbers the HCL parser clamped (see `is_wide_number`),
+    /// turning each back into a string of how it was written.
+    #[derive(Default)]
+    struct WideNumbers(Vec<String>);
+
+    impl VisitMut for WideNumbers {
+        fn visit_expr_mut(&mut self, expr: &mut edit::expr::Expression) {
+            let n = match expr {
+                edit::expr::Expression::Number(n) => n,
+                _ => return visit_expr_mut(self, expr),
+            };
+            // a negative number's text may have spaces after the sign
+            let text = match n.as_repr() {
+                Some(repr) => repr.split_whitespace().collect::<String>(),
+                None => return,
+            };
+            if is_wide_number(&text) {
+                *expr = edit::expr::Expression::String(edit::Decorated::new(text.clone()));
+                self.0.push(text);
+            }
+        }
+    }
+
+    /// Converts a body to an object, turning blocks into nested objects.
+    fn body_value(body: hcl_rs::Body) -> Hcl {
+        let mut fvs = hcl_rs::Map::new();
+
+        for structure in body.into_inner() {
+            match structure {
+                Structure::Attribute(attr) => {
+                    let key = attr.key.into_inner();
+                    if fvs.contains_key(&key) {
+                        warn!("Duplicate HCL attribute '{}'; keeping the last one.", key);
+                    }
+                    fvs.insert(key, expr_value(attr.expr));
+                }
+                Structure::Block(block) => {
+                    let mut path = vec![block.identifier.into_inner()];
+                    path.extend(block.labels.into_iter().map(|l| l.into_inner()));
+                    insert_block(&mut fvs, &path, body_value(block.body));
+                }
+            }
+        }
+
+        Hcl::Object(fvs)
+    }
+
+    /// Puts a block's body at its path (its type followed by its labels);
+    /// repeated blocks at the same path form a list.
+    fn insert_block(fvs: &mut hcl_rs::Map<String, Hcl>, path: &[String], v: Hcl) {
+        let (key, rest) = match path.split_first() {
+            Some(split) => split,
+            None => unreachable!("blocks always have a type"),
+        };
+
+        if rest.is_empty() {
+            match fvs.get_mut(key) {
+                None => {
+                    fvs.insert(key.clone(), v);
+                }
+                Some(Hcl::Array(vs)) => vs.push(v),
+                Some(existing) => {
+                    let first = std::mem::take(existing);
+                    *existing = Hcl::Array(vec![first, v]);
+                }
+            }
+            return;
+        }
+
+        match fvs
+            .entry(key.clone())
+            .or_insert_with(|| Hcl::Object(hcl_rs::Map::new()))
+        {
+            Hcl::Object(inner) => insert_block(inner, rest, v),
+            _ => warn!(
+                "HCL block '{}' conflicts with an attribute or unlabeled block of the same name; dropping it.",
+                path.join(" ")
+            ),
+        }
+    }
+
+    fn expr_value(expr: Expression) -> Hcl {
+        match expr {
+            Expression::Null => Hcl::Null,
+            Expression::Bool(b) => Hcl::Bool(b),
+            Expression::Number(n) => Hcl::Number(n),
+            Expression::String(s) => Hcl::String(s),
+            Expression::Array(vs) => Hcl::Array(vs.into_iter().map(expr_value).collect()),
+            Expression::Object(fvs) => Hcl::Object(
+                fvs.into_iter()
+                    .map(|(k, v)| {
+                        let k = match k {
+                            ObjectKey::Identifier(ident) => ident.into_inner(),
+                            ObjectKey::Expression(Expression::String(s)) => s,
+                            key => {
+                                warn!(
+                                    "HCL object keys like `{}` aren't supported; using it as a string.",
+                                    key
+                                );
+                                key.to_string()
+                            }
+                        };
+                        (k, expr_value(v))
+                    })
+                    .collect(),
+            ),
+            Expression::Parenthesis(expr) => expr_value(*expr),
+            Expression::TemplateExpr(template) => {
+                let template = match *template {
+                    TemplateExpr::QuotedString(s) => s,
+                    TemplateExpr::Heredoc(heredoc) => heredoc.template,
+                };
+                warn!(
+                    "HCL template interpolation isn't supported; using \"{}\" as a string.",
+                    template
+                );
+                Hcl::String(template)
+            }
+            expr => {
+                warn!(
+                    "HCL expressions like `{}` aren't supported; using it as a string.",
+                    expr
+                );
+                Hcl::String(expr.to_string())
+            }
+        }
+    }
+
+    impl Nodelike for Value {
+        fn kind(&self) -> FileType {
+            match self.0 {
+                Hcl::Object(_) | Hcl::Array(_) => FileType::Directory,
+                _ => FileType::RegularFile,
+            }
+        }
+
+        fn size(&self) -> usize {
+            hcl_measure(&self.0).0
+        }
+
+        fn depth(&self) -> usize {
+            hcl_measure(&self.0).1
+        }
+
+        fn node(self, config: &Config) -> Node<Self> {
+            let nl = if config.add_newlines { "\n" } else { "" };
+
+            match self.0 {
+                Hcl::Null => Node::String(Typ::Null, "".into()), // always empty
+                Hcl::Bool(b) => Node::String(Typ::Boolean, format!("{}{}", b, nl)),
+                Hcl::Number(n) => {
+                    let typ = if n.is_f64() { Typ::Float } else { Typ::Integer };
+                    Node::String(typ, format!("{}{}", n, nl))
+                }
+                Hcl::String(s) => {
+                    if config.try_decode_base64 {
+                        if let Ok(bytes) = base64::decode_config(&s, config.base64) {
+                            return Node::Bytes(bytes);
+                        }
+                    }
+
+                    Node::String(Typ::String, if s.ends_with('\n') { s } else { s + nl })
+                }
+                Hcl::Array(vs) => Node::List(vs.into_iter().map(Value).collect()),
+                Hcl::Object(fvs) => {
+                    Node::Map(fvs.into_iter().map(|(f, v)| (f, Value(v))).collect())
+                }
+            }
+        }
+
+        fn from_string(typ: Typ, contents: String, _config: &Config) -> Self {
+            let v = match typ {
+                Typ::Auto => {
+                    if contents.is_empty() {
+                        Hcl::Null
+                    } else if contents == "true" {
+                        Hcl::Bool(true)
+                    } else if contents == "false" {
+                        Hcl::Bool(false)
+                    } else if let Some(n) = parse_number(&contents) {
+                        Hcl::Number(n)
+                    } else {
+                        Hcl::String(contents)
+                    }
+                }
+                Typ::Boolean => {
+                    if contents == "true" {
+                        Hcl::Bool(true)
+                    } else if contents == "false" {
+                        Hcl::Bool(false)
+                    } else {
+                        debug!("string '{}' tagged as boolean", contents);
+                        Hcl::String(contents)
+                    }
+                }
+                Typ::Bytes => panic!("from_string called at typ::bytes"),
+                Typ::Datetime => Hcl::String(contents),
+                Typ::Float | Typ::Integer => match parse_number(&contents) {
+                    Some(n) => Hcl::Number(n),
+                    None => {
+                        debug!("string '{}' tagged as {}", contents, typ);
+                        Hcl::String(contents)
+                    }
+                },
+                Typ::Null => {
+                    if contents.is_empty() {
+                        Hcl::Null
+                    } else {
+                        debug!("string '{}' tagged as null", contents);
+                        Hcl::String(contents)
+                    }
+                }
+                Typ::String | Typ::ObjectId | Typ::Decimal | Typ::Keyword | Typ::Symbol => {
+                    Hcl::String(contents)
+                }
+            };
+
+            Value(v)
+        }
+
+        fn from_bytes<T>(contents: T, config: &Config) -> Self
+        where
+            T: AsRef<[u8]>,
+        {
+            Value(Hcl::String(base64::encode_config(contents, config.base64)))
+        }
+
+        fn from_list_dir(files: Vec<Self>, _config: &Config) -> Self {
+            Value(Hcl::Array(files.into_iter().map(|v| v.0).collect()))
+        }
+
+        fn from_named_dir(files: HashMap<String, Self>, _config: &Config) -> Self {
+            let mut fvs = files.into_iter().map(|(f, v)| (f, v.0)).collect::<Vec<_>>();
+            fvs.sort_unstable_by(|(k1, _), (k2, _)| k1.cmp(k2));
+            Value(Hcl::Object(fvs.into_iter().collect()))
+        }
+
+        fn from_reader(
+            mut reader: Box<dyn std::io::Read>,
+            config: &Config,
+        ) -> Result<Self, Box<dyn std::error::Error>> {
+            let mut text = String::new();
+            let _len = reader.read_to_string(&mut text)?;
+            let mut body = edit::parser::parse_body(&text)?;
+
+            let mut wide = WideNumbers::default();
+            wide.visit_body_mut(&mut body);
+            if config.strict_conversion && !wide.0.is_empty() {
+                return Err(format!("HCL numbers can't hold {} exactly", wide.0.join(", ")).into());
+            }
+            for n in wide.0 {
+                warn!(
+                    "HCL numbers can't hold {} exactly; keeping it as a string.",
+                    n
+                );
+            }
+
+            Ok(Value(body_value(body.into())))
+        }
+
+        fn to_writer(
+            &self,
+            writer: &mut dyn std::io::Write,
+            _config: &Config,
+        ) -> std::io::Result<()> {
+            let fvs = match &self.0 {
+                Hcl::Object(fvs) => fvs,
+                v => {
+                    return Err(save_error(format!(
+                        "HCL documents are bodies of attributes, so there's no way to save {}",
+                        v
+                    )));
+                }
+            };
+
+            let body = hcl_rs::Body(
+                fvs.iter()
+                    .map(|(k, v)| {
+                        let key = Identifier::new(k.as_str()).unwrap_or_else(|_| {
+                            warn!("'{}' isn't a valid HCL identifier; sanitizing it.", k);
+                            Identifier::sanitized(k.as_str())
+                        });
+                        Structure::Attribute(hcl_rs::Attribute::new(
+                            key,
+                            Expression::from(v.clone()),
+                        ))
+                    })
+                    .collect(),
+            );
+
+            let text = hcl_rs::to_string(&body).map_err(save_error)?;
+            writer.write_all(text.as_bytes())
+        }
+
+        fn replace_at(&mut self, pointer: &str, v: Self) -> Option<Self> {
+            hcl_pointer_mut(&mut self.0, pointer)
+                .map(|subtree| Value(std::mem::replace(subtree, v.0)))
+        }
+
+        fn sort_keys(&mut self) {
+            hcl_sort_keys(&mut self.0)
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+/// RON (Rusty Object Notation) Nodelike implementation
+///
+/// Maps and structs are named directories; sequences and tuples are lists.
+/// `ron::Value` forgets struct names and enum variants, so those are saved as
+/// plain maps, sequences, and values (with a warning when loading).
+pub mod ron {
+    use super::*;
+    use ron_rs::value::{Float, Map, Number};
+    use tracing::warn;
+
+    type Ron = ron_rs::Value;
+
+    #[derive(Clone, Debug)]
+    pub struct Value(Ron);
+
+    impl std::fmt::Display for Value {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+            match ron_rs::to_string(&self.0) {
+                Ok(s) => write!(f, "{}", s),
+                Err(_) => Err(std::fmt::Error),
+            }
+        }
+    }
+
+    impl Default for Value {
+        fn default() -> Self {
+            Value(Ron::Unit)
+        }
+    }
+
+    fn ron_sort_keys(v: &mut Ron) {
+        match v {
+            Ron::Option(Some(v)) => ron_sort_keys(v),
+            Ron::Seq(vs) => vs.iter_mut().for_each(ron_sort_keys),
+            Ron::Map(fvs) => {
+                let mut sorted = std::mem::replace(fvs, Map::new())
+                    .into_iter()
+                    .map(|(k, v)| (ron_key_to_string(&k), k, v))
+                    .collect::<Vec<_>>();
+                sorted.sort_unstable_by(|(k1, ..), (k2, ..)| k1.cmp(k2));
+                for (_, k, mut v) in sorted {
+                    ron_sort_keys(&mut v);
+                    fvs.insert(k, v);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// `Some(v)` is mounted just like `v`, so it doesn't count as a node of
+    /// its own.
+    fn ron_unwrap_option(mut v: &Ron) -> &Ron {
+        while let Ron::Option(Some(inner)) = v {
+            v = inner;
+        }
+        v
+    }
+
+    fn ron_measure(v: &Ron) -> (usize, usize) {
+        measure(ron_unwrap_option(v), |v| match v {
+            Ron::Seq(vs) => Some(vs.iter().map(ron_unwrap_option).collect()),
+            Ron::Map(fvs) => Some(fvs.values().map(ron_unwrap_option).collect()),
+            _ => None,
+        })
+    }
+
+    fn ron_key_to_string(k: &Ron) -> String {
+        match k {
+            Ron::String(s) => s.clone(),
+            k => ron_rs::to_string(k).unwrap_or_else(|_| format!("{:?}", k)),
+        }
+    }
+
+    fn ron_pointer_mut<'a>(mut v: &'a mut Ron, pointer: &str) -> Option<&'a mut Ron> {
+        for token in pointer_tokens(pointer)? {
+            v = match v {
+                Ron::Map(fvs) => fvs
+                    .iter_mut()
+                    .find(|(k, _)| ron_key_to_string(k) == token)
+                    .map(|(_, v)| v)?,
+                Ron::Seq(vs) => vs.get_mut(pointer_index(&token)?)?,
+                _ => return None,
+            };
+        }
+
+        Some(v)
+    }
+
+    fn parse_number(s: &str) -> Option<Number> {
+        if let Ok(n) = i64::from_str(s) {
+            Some(Number::Integer(n))
+        } else {
+            f64::from_str(s).ok().map(|n| Number::Float(Float::new(n)))
+        }
+    }
+
+    /// Finds the struct names and enum variants in RON text, which
+    /// `ron::Value` doesn't keep track of.
+    fn type_names(text: &str) -> Vec<String> {
+        let mut names = Vec::new();
+        let mut chars = text.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                // extensions, e.g., `#![enable(implicit_some)]`
+                '#' => {
+                    for c in chars.by_ref() {
+                        if c == '\n' {
+                            break;
+                        }
+                    }
+                }
+                '/' if chars.peek() == Some(&'/') => {
+                    for c in chars.by_ref() {
+                        if c == '\n' {
+                            break;
+                        }
+                    }
+                }
+                '/' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    let mut last = ' ';
+                    for c in chars.by_ref() {
+                        if last == '*' && c == '/' {
+                            break;
+                        }
+                        last = c;
+                    }
+                }
+                '"' | '\'' => {
+                    let mut escaped = false;
+                    for d in chars.by_ref() {
+                        if d == c && !escaped {
+                            break;
+                        }
+                        escaped = d == '\\' && !escaped;
+                    }
+                }
+                c if c.is_ascii_digit() => {
+                    // numbers, e.g., `0x1F` or `1e5`
+                    while matches!(chars.peek(), Some(c) if c.is_ascii_alphanumeric() || *c == '_' || *c == '.')
+                    {
+                        chars.next();
+                    }
+                }
+                c if c.is_alphabetic() || c == '_' => {
+                    let mut ident = c.to_string();
+                    while let Some(&c) = chars.peek() {
+                        if c.is_alphanumeric() || c == '_' {
+                            ident.push(c);
+                            chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+
+                    // raw strings
+                    if ident == "r" && matches!(chars.peek(), Some('"') | Some('#')) {
+                        let mut hashes = 0;
+                        while chars.peek() == Some(&'#') {
+                            hashes += 1;
+                            chars.next();
+                        }
+                        chars.next();
+                        let close = format!("\"{}", "#".repeat(hashes));
+                        let mut seen = String::new();
+                        for c in chars.by_ref() {
+                            seen.push(c);
+                            if seen.ends_with(&close) {
+                                break;
+                            }
+                        }
+                        continue;
+                    }
+
+                    while matches!(chars.peek(), Some(c) if c.is_whitespace()) {
+                        chars.next();
+                    }
+
+                    // field names are followed by a colon
+                    let is_field = chars.peek() == Some(&':');
+                    let is_literal =
+                        matches!(ident.as_str(), "true" | "false" | "None" | "inf" | "NaN");
+                    if !is_field && !is_literal && !names.contains(&ident) {
+                        names.push(ident);
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        names
+    }
+
+    impl Nodelike for Value {
+        fn kind(&self) -> FileType {
+            match &self.0 {
+                Ron::Map(_) | Ron::Seq(_) => FileType::Directory,
+                Ron::Option(Some(v)) => Value((**v).clone()).kind(),
+                _ => FileType::RegularFile,
+            }
+        }
+
+        fn size(&self) -> usize {
+            ron_measure(&self.0).0
+        }
+
+        fn depth(&self) -> usize {
+            ron_measure(&self.0).1
+        }
+
+        fn node(self, config: &Config) -> Node<Self> {
+            let nl = if config.add_newlines { "\n" } else { "" };
+
+            match self.0 {
+                Ron::Unit | Ron::Option(None) => Node::String(Typ::Null, "".into()), // always empty
+                Ron::Option(Some(v)) => Value(*v).node(config),
+                Ron::Bool(b) => Node::String(Typ::Boolean, format!("{}{}", b, nl)),
+                Ron::Number(Number::Integer(n)) => {
+                    Node::String(Typ::Integer, format!("{}{}", n, nl))
+                }
+                Ron::Number(Number::Float(n)) => {
+                    Node::String(Typ::Float, format!("{}{}", n.get(), nl))
+                }
+                Ron::Char(c) => Node::String(Typ::String, format!("{}{}", c, nl)),
+                Ron::String(s) => {
+                    if config.try_decode_base64 {
+                        if let Ok(bytes) = base64::decode_config(&s, config.base64) {
+                            return Node::Bytes(bytes);
+                        }
+                    }
+
+                    Node::String(Typ::String, if s.ends_with('\n') { s } else { s + nl })
+                }
+                Ron::Seq(vs) => Node::List(vs.into_iter().map(Value).collect()),
+                Ron::Map(fvs) => Node::Map(
+                    fvs.into_iter()
+                        .map(|(k, v)| (ron_key_to_string(&k), Value(v)))
+                        .collect(),
+                ),
+            }
+        }
+
+        fn from_string(typ: Typ, contents: String, _config: &Config) -> Self {
+            let v = match typ {
+                Typ::Auto => {
+                    if contents.is_empty() {
+                        Ron::Option(None)
+                    } else if contents == "true" {
+                        Ron::Bool(true)
+                    } else if contents == "false" {
+                        Ron::Bool(false)
+                    } else if let Some(n) = parse_number(&contents) {
+                        Ron::Number(n)
+                    } else {
+                        Ron::String(contents)
+                    }
+                }
+                Typ::Boolean => {
+                    if contents == "true" {
+                        Ron::Bool(true)
+                    } else if contents == "false" {
+                        Ron::Bool(false)
+                    } else {
+                        debug!("string '{}' tagged as boolean", contents);
+                        Ron::String(contents)
+                    }
+                }
+                Typ::Bytes => panic!("from_string called at typ::bytes"),
+                Typ::Datetime => Ron::String(contents),
+                Typ::Float | Typ::Integer => match parse_number(&contents) {
+                    Some(n) => Ron::Number(n),
+                    None => {
+                        debug!("string '{}' tagged as {}", contents, typ);
+                        Ron::String(contents)
+                    }
+                },
+                Typ::Null => {
+                    if contents.is_empty() {
+                        Ron::Option(None)
+                    } else {
+                        debug!("string '{}' tagged as null", contents);
+                        Ron::String(contents)
+                    }
+                }
+                Typ::String | Typ::ObjectId | Typ::Decimal | Typ::Keyword | Typ::Symbol => {
+                    Ron::String(contents)
+                }
+            };
+
+            Value(v)
+        }
+
+        fn from_bytes<T>(contents: T, config: &Config) -> Self
+        where
+            T: AsRef<[u8]>,
+        {
+            Value(Ron::String(base64::encode_config(contents, config.base64)))
+        }
+
+        fn from_list_dir(files: Vec<Self>, _config: &Config) -> Self {
+            Value(Ron::Seq(files.into_iter().map(|v| v.0).collect()))
+        }
+
+        fn from_named_dir(files: HashMap<String, Self>, _config: &Config) -> Self {
+            let mut fvs = files.into_iter().collect::<Vec<_>>();
+            fvs.sort_unstable_by(|(k1, _), (k2, _)| k1.cmp(k2));
+            Value(Ron::Map(
+                fvs.into_iter()
+                    .map(|(k, v)| (Ron::String(k), v.0))
+                    .collect::<Map>(),
+            ))
+        }
+
+        fn from_reader(
+            mut reader: Box<dyn std::io::Read>,
+            _config: &Config,
+        ) -> Result<Self, Box<dyn std::error::Error>> {
+            let mut text = String::new();
+            let _len = reader.read_to_string(&mut text)?;
+            let v = ron_rs::from_str(&text)?;
+
+            let names = type_names(&text);
+            if !names.is_empty() {
+                warn!(
+                    "RON struct names and enum variants ({}) aren't preserved; they'll be saved as plain maps, sequences, and values.",
+                    names.join(", ")
+                );
+            }
+
+            Ok(Value(v))
+        }
+
+        fn to_writer(
+            &self,
+            writer: &mut dyn std::io::Write,
+            config: &Config,
+        ) -> std::io::Result<()> {
+            let text = if config.pretty {
+                let pretty = ron_rs::ser::PrettyConfig::new().indentor(config.indent_string());
+                ron_rs::ser::to_string_pretty(&self.0, pretty).map_err(save_error)?
+            } else {
+                ron_rs::to_string(&self.0).map_err(save_error)?
+            };
+            writer.write_all(text.as_bytes())
+        }
+
+        fn replace_at(&mut self, pointer: &str, v: Self) -> Option<Self> {
+            ron_pointer_mut(&mut self.0, pointer)
+                .map(|subtree| Value(std::mem::replace(subtree, v.0)))
+        }
+
+        fn sort_keys(&mut self) {
+            ron_sort_keys(&mut self.0)
+        }
+
+        fn non_string_keys(&self) -> Vec<String> {
+            match &self.0 {
+                Ron::Map(fvs) => fvs
+                    .keys()
+                    .filter(|k| !matches!(k, Ron::String(_)))
+                    .map(ron_key_to_string)
+                    .collect(),
+                _ => Vec::new(),
+            }
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+/// BSON Nodelike implementation
+///
+/// Documents are named directories and arrays are lists. `ObjectId`s,
+/// `Decimal128`s, and dates are strings tagged with their own `Typ`, so they
+/// come back as the same BSON type; binary data is bytes.
+pub mod bson {
+    use super::*;
+    use bson_rs::oid::ObjectId;
+    use bson_rs::spec::BinarySubtype;
+    use bson_rs::{Binary, Bson, DateTime, Decimal128, Document};
+    use tracing::warn;
+
+    #[derive(Clone, Debug)]
+    pub struct Value(Bson);
+
+    impl std::fmt::Display for Value {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+            write!(f, "{}", self.0)
+        }
+    }
+
+    impl Default for Value {
+        fn default() -> Self {
+            Value(Bson::Null)
+        }
+    }
+
+    fn bson_sort_keys(v: &mut Bson) {
+        match v {
+            Bson::Array(vs) => vs.iter_mut().for_each(bson_sort_keys),
+            Bson::Document(fvs) => {
+                let mut sorted = std::mem::take(fvs).into_iter().collect::<Vec<_>>();
+                sorted.sort_unstable_by(|(k1, _), (k2, _)| k1.cmp(k2));
+                for (k, mut v) in sorted {
+                    bson_sort_keys(&mut v);
+                    fvs.insert(k, v);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn bson_measure(v: &Bson) -> (usize, usize) {
+        measure(v, |v| match v {
+            Bson::Array(vs) => Some(vs.iter().collect()),
+            Bson::Document(fvs) => Some(fvs.values().collect()),
+            _ => None,
+        })
+    }
+
+    fn bson_pointer_mut<'a>(mut v: &'a mut Bson, pointer: &str) -> Option<&'a mut Bson> {
+        for token in pointer_tokens(pointer)? {
+            v = match v {
+                Bson::Document(fvs) => fvs.get_mut(&token)?,
+                Bson::Array(vs) => vs.get_mut(pointer_index(&token)?)?,
+                _ => return None,
+            };
+        }
+
+        Some(v)
+    }
+
+    fn parse_integer(s: &str) -> Option<Bson> {
+        if let Ok(n) = i32::from_str(s) {
+            Some(Bson::Int32(n))
+        } else {
+            i64::from_str(s).ok().map(Bson::Int64)
+        }
+    }
+
+    impl Nodelike for Value {
+        fn kind(&self) -> FileType {
+            match &self.0 {
+                Bson::Document(_) | Bson::Array(_) => FileType::Directory,
+                _ => FileType::RegularFile,
+            }
+        }
+
+        fn size(&self) -> usize {
+            bson_measure(&self.0).0
+        }
+
+        fn depth(&self) -> usize {
+            bson_measure(&self.0).1
+        }
+
+        fn node(self, config: &Config) -> Node<Self> {
+            let nl = if config.add_newlines { "\n" } else { "" };
+
+            match self.0 {
+                Bson::Null | Bson::Undefined => Node::String(Typ::Null, "".into()), // always empty
+                Bson::Boolean(b) => Node::String(Typ::Boolean, format!("{}{}", b, nl)),
+                Bson::Int32(n) => Node::String(Typ::Integer, format!("{}{}", n, nl)),
+                Bson::Int64(n) => Node::String(Typ::Integer, format!("{}{}", n, nl)),
+                Bson::Double(n) => Node::String(Typ::Float, format!("{}{}", n, nl)),
+                Bson::ObjectId(oid) => {
+                    Node::String(Typ::ObjectId, format!("{}{}", oid.to_hex(), nl))
+                }
+                Bson::Decimal128(d) => Node::String(Typ::Decimal, format!("{}{}", d, nl)),
+                Bson::DateTime(dt) => match dt.try_to_rfc3339_string() {
+                    Ok(s) => Node::String(Typ::Datetime, format!("{}{}", s, nl)),
+                    Err(_) => {
+                        warn!(
+                            "The BSON date {} can't be written in RFC 3339; using milliseconds since the epoch.",
+                            dt.timestamp_millis()
+                        );
+                        Node::String(Typ::Integer, format!("{}{}", dt.timestamp_millis(), nl))
+                    }
+                },
+                Bson::Binary(b) => {
+                    if b.subtype != BinarySubtype::Generic {
+                        warn!(
+                            "BSON binary subtype {:?} isn't preserved; it will be saved as generic binary data.",
+                            b.subtype
+                        );
+                    }
+                    Node::Bytes(b.bytes)
+                }
+                Bson::String(s) => {
+                    if config.try_decode_base64 {
+                        if let Ok(bytes) = base64::decode_config(&s, config.base64) {
+                            return Node::Bytes(bytes);
+                        }
+                    }
+
+                    Node::String(Typ::String, if s.ends_with('\n') { s } else { s + nl })
+                }
+                Bson::Array(vs) => Node::List(vs.into_iter().map(Value).collect()),
+                Bson::Document(fvs) => {
+                    Node::Map(fvs.into_iter().map(|(k, v)| (k, Value(v))).collect())
+                }
+                v => {
+                    warn!(
+                        "BSON value {} has no ffs equivalent; it will be saved as a string.",
+                        v
+                    );
+                    Node::String(Typ::String, format!("{}{}", v, nl))
+                }
+            }
+        }
+
+        fn from_string(typ: Typ, contents: String, _config: &Config) -> Self {
+            let v = match typ {
+                Typ::Auto => {
+                    if contents.is_empty() {
+                        Bson::Null
+                    } else if contents == "true" {
+                        Bson::Boolean(true)
+                    } else if contents == "false" {
+                        Bson::Boolean(false)
+                    } else if let Some(n) = parse_integer(&contents) {
+                        n
+                    } else if let Ok(n) = f64::from_str(&contents) {
+                        Bson::Double(n)
+                    } else {
+                        Bson::String(contents)
+                    }
+                }
+                Typ::Boolean => {
+                    if contents == "true" {
+                        Bson::Boolean(true)
+                    } else if contents == "false" {
+                        Bson::Boolean(false)
+                    } else {
+                        debug!("string '{}' tagged as boolean", contents);
+                        Bson::String(contents)
+                    }
+                }
+                Typ::Bytes => panic!("from_string called at typ::bytes"),
+                Typ::Datetime => match DateTime::parse_rfc3339_str(&contents) {
+                    Ok(dt) => Bson::DateTime(dt),
+                    Err(_) => {
+                        debug!("string '{}' tagged as datetime", contents);
+                        Bson::String(contents)
+                    }
+                },
+                Typ::Decimal => match Decimal128::from_str(&contents) {
+                    Ok(d) => Bson::Decimal128(d),
+                    Err(_) => {
+                        debug!("string '{}' tagged as decimal", contents);
+                        Bson::String(contents)
+                    }
+                },
+                Typ::Float => match f64::from_str(&contents) {
+                    Ok(n) => Bson::Double(n),
+                    Err(_) => {
+                        debug!("string '{}' tagged as float", contents);
+                        Bson::String(contents)
+                    }
+                },
+                Typ::Integer => match parse_integer(&contents) {
+                    Some(n) => n,
+                    None => {
+                        debug!("string '{}' tagged as integer", contents);
+                        Bson::String(contents)
+                    }
+                },
+                Typ::Null => {
+                    if contents.is_empty() {
+                        Bson::Null
+                    } else {
+                        debug!("string '{}' tagged as null", contents);
+                        Bson::String(contents)
+                    }
+                }
+                Typ::ObjectId => match ObjectId::parse_str(&contents) {
+                    Ok(oid) => Bson::ObjectId(oid),
+                    Err(_) => {
+                        debug!("string '{}' tagged as objectid", contents);
+                        Bson::String(contents)
+                    }
+                },
+                Typ::String | Typ::Keyword | Typ::Symbol => Bson::String(contents),
+            };
+
+            Value(v)
+        }
+
+        fn from_bytes<T>(contents: T, _config: &Config) -> Self
+        where
+            T: AsRef<[u8]>,
+        {
+            Value(Bson::Binary(Binary {
+                subtype: BinarySubtype::Generic,
+                bytes: contents.as_ref().to_vec(),
+            }))
+        }
+
+        fn from_list_dir(files: Vec<Self>, _config: &Config) -> Self {
+            Value(Bson::Array(files.into_iter().map(|v| v.0).collect()))
+        }
+
+        fn from_named_dir(files: HashMap<String, Self>, _config: &Config) -> Self {
+            let mut fvs = files.into_iter().collect::<Vec<_>>();
+            fvs.sort_unstable_by(|(k1, _), (k2, _)| k1.cmp(k2));
+            Value(Bson::Document(
+                fvs.into_iter().map(|(k, v)| (k, v.0)).collect::<Document>(),
+            ))
+        }
+
+        fn from_reader(
+            mut reader: Box<dyn std::io::Read>,
+            _config: &Config,
+        ) -> Result<Self, Box<dyn std::error::Error>> {
+            Ok(Value(Bson::Document(Document::from_reader(&mut reader)?)))
+        }
+
+        fn to_writer(
+            &self,
+            writer: &mut dyn std::io::Write,
+            config: &Config,
+        ) -> std::io::Result<()> {
+            // a BSON file is always a single document
+            let doc = match &self.0 {
+                Bson::Document(fvs) => fvs.clone(),
+                Bson::Array(vs) => vs
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| (i.to_string(), v.clone()))
+                    .collect(),
+                v => {
+                    warn!(
+                        "BSON files must hold a document; saving the value in the field '{}'.",
+                        config.primitive_root_name
+                    );
+                    let mut fvs = Document::new();
+                    fvs.insert(config.primitive_root_name.clone(), v.clone());
+                    fvs
+                }
+            };
+            doc.to_writer(writer).map_err(save_error)
+        }
+
+        fn replace_at(&mut self, pointer: &str, v: Self) -> Option<Self> {
+            bson_pointer_mut(&mut self.0, pointer)
+                .map(|subtree| Value(std::mem::replace(subtree, v.0)))
+        }
+
+        fn sort_keys(&mut self) {
+            bson_sort_keys(&mut self.0)
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+/// Environment (dotenv) Nodelike implementation
+///
+/// A dotenv file is a flat list of `KEY=VALUE` lines, so it's mounted as a
+/// single directory of files. Values may be single quoted (taken literally) or
+/// double quoted (with backslash escapes), and quoted values may span lines;
+/// `export` prefixes and `#` comments are ignored. There are no types: every
+/// value is a string.
+///
+/// Nested directories can't be represented, so saving flattens them into dotted
+/// keys.
+pub mod env {
+    use super::*;
+    use tracing::warn;
+
+    #[derive(Clone, Debug)]
+    pub enum Value {
+        String(String),
+        Map(Vec<(String, Value)>),
+    }
+
+    impl Default for Value {
+        fn default() -> Self {
+            Value::String(String::new())
+        }
+    }
+
+    impl std::fmt::Display for Value {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+            match self {
+                Value::String(s) => write!(f, "{}", s),
+                Value::Map(fvs) => {
+                    for (key, v) in fvs.iter() {
+                        write_entries(f, key, v)?;
+                    }
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    /// Writes `KEY=value` lines, flattening nested maps into dotted keys.
+    fn write_entries(
+        f: &mut std::fmt::Formatter<'_>,
+        key: &str,
+        v: &Value,
+    ) -> std::result::Result<(), std::fmt::Error> {
+        match v {
+            Value::String(s) => {
+                if key.is_empty() || key.contains(|c: char| c == '=' || c.is_whitespace()) {
+                    warn!(
+                        "Can't save the key '{}' in a dotenv file; dropping it.",
+                        key
+                    );
+                    Ok(())
+                } else {
+                    writeln!(f, "{}={}", key, quote(s))
+                }
+            }
+            Value::Map(fvs) => {
+                warn!(
+                    "Dotenv files are flat; saving the directory '{}' as dotted keys.",
+                    key
+                );
+                for (field, v) in fvs.iter() {
+                    write_entries(f, &format!("{}.{}", key, field), v)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Double quotes values that wouldn't survive being parsed back unquoted,
+    /// i.e., those with whitespace, quotes, comments, or escapes.
+    fn quote(s: &str) -> String {
+        if !s.contains(|c: char| c.is_whitespace() || "\"'#$\\`".contains(c)) {
+            return s.into();
+        }
+
+        let mut quoted = String::with_capacity(s.len() + 2);
+        quoted.push('"');
+        for c in s.chars() {
+            match c {
+                '\n' => quoted.push_str("\\n"),
+                '\r' => quoted.push_str("\\r"),
+                '\t' => quoted.push_str("\\t"),
+                '"' | '\\' | '$' => {
+                    quoted.push('\\');
+                    quoted.push(c);
+                }
+                c => quoted.push(c),
+            }
+        }
+        quoted.push('"');
+        quoted
+    }
+
+    /// Finds the quote closing a value that opened with `q`, skipping escaped
+    /// characters in double-quoted values.
+    fn closing_quote(s: &str, q: char) -> Option<usize> {
+        let mut escaped = false;
+        for (idx, c) in s.char_indices() {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' && q == '"' {
+                escaped = true;
+            } else if c == q {
+                return Some(idx);
+            }
+        }
+        None
+    }
+
+    fn unescape(s: &str) -> String {
+        let mut unescaped = String::with_capacity(s.len());
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                unescaped.push(c);
+                continue;
+            }
+
+            match chars.next() {
+                Some('n') => unescaped.push('\n'),
+                Some('r') => unescaped.push('\r'),
+                Some('t') => unescaped.push('\t'),
+                Some(c @ ('"' | '\\' | '$' | '\'' | '`')) => unescaped.push(c),
+                Some(c) => {
+                    unescaped.push('\\');
+                    unescaped.push(c);
+                }
+                None => unescaped.push('\\'),
+            }
+        }
+        unescaped
+    }
+
+    fn parse(text: &str) -> Result<Value, String> {
+        let mut fvs: Vec<(String, Value)> = Vec::new();
+        let mut lines = text.lines().enumerate();
+
+        while let Some((lineno, line)) = lines.next() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let line = match line.strip_prefix("export") {
+                Some(rest) if rest.starts_with(char::is_whitespace) => rest.trim_start(),
+                _ => line,
+            };
+
+            let (key, rest) = match line.split_once('=') {
+                Some((key, rest)) if !key.trim().is_empty() => (key.trim(), rest.trim_start()),
+                _ => {
+                    warn!(
+                        "Ignoring line {} of the dotenv file, which isn't of the form KEY=VALUE.",
+                        lineno + 1
+                    );
+                    continue;
+                }
+            };
+
+            let value = match rest.chars().next() {
+                Some(q @ ('"' | '\'')) => {
+                    // quoted values run to the closing quote, maybe on a later line
+                    let mut quoted = rest[1..].to_string();
+                    let end = loop {
+                        if let Some(end) = closing_quote(&quoted, q) {
+                            break end;
+                        }
+                        match lines.next() {
+                            Some((_, line)) => {
+                                quoted.push('\n');
+                                quoted.push_str(line);
+                            }
+                            None => {
+                                return Err(format!(
+                                    "unterminated quoted value for '{}' on line {}",
+                                    key,
+                                    lineno + 1
+                                ))
+                            }
+                        }
+                    };
+
+                    let trailing = quoted[end + 1..].trim();
+                    if !trailing.is_empty() && !trailing.starts_with('#') {
+                        warn!(
+                            "Ignoring '{}' after the quoted value for '{}'.",
+                            trailing, key
+                        );
+                    }
+                    quoted.truncate(end);
+
+                    if q == '"' {
+                        unescape(&quoted)
+                    } else {
+                        quoted
+                    }
+                }
+                _ => {
+                    // unquoted values end at a comment, i.e., a '#' after whitespace
+                    let comment = rest.char_indices().find(|&(idx, c)| {
+                        c == '#' && (idx == 0 || rest[..idx].ends_with(char::is_whitespace))
+                    });
+                    match comment {
+                        Some((idx, _)) => rest[..idx].trim_end().into(),
+                        None => rest.trim_end().into(),
+                    }
+                }
+            };
+
+            match fvs.iter_mut().find(|(k, _)| k == key) {
+                Some(entry) => {
+                    warn!(
+                        "Duplicate dotenv key '{}' on line {}; keeping the last value.",
+                        key,
+                        lineno + 1
+                    );
+                    entry.1 = Value::String(value);
+                }
+                None => fvs.push((key.into(), Value::String(value))),
+            }
+        }
+
+        Ok(Value::Map(fvs))
+    }
+
+    fn env_pointer_mut<'a>(mut v: &'a mut Value, pointer: &str) -> Option<&'a mut Value> {
+        for token in pointer_tokens(pointer)? {
+            v = match v {
+                Value::Map(fvs) => fvs.iter_mut().find(|(k, _)| *k == token).map(|(_, v)| v)?,
+                Value::String(_) => return None,
+            };
+        }
+
+        Some(v)
+    }
+
+    fn env_measure(v: &Value) -> (usize, usize) {
+        measure(v, |v| match v {
+            Value::String(_) => None,
+            Value::Map(fvs) => Some(fvs.iter().map(|(_, v)| v).collect()),
+        })
+    }
+
+    impl Nodelike for Value {
+        fn kind(&self) -> FileType {
+            match self {
+                Value::String(_) => FileType::RegularFile,
+                Value::Map(_) => FileType::Directory,
+            }
+        }
+
+        fn size(&self) -> usize {
+            env_measure(self).0
+        }
+
+        fn depth(&self) -> usize {
+            env_measure(self).1
+        }
+
+        fn node(self, config: &Config) -> Node<Self> {
+            let nl = if config.add_newlines { "\n" } else { "" };
+
+            match self {
+                Value::String(s) => {
+                    if config.try_decode_base64 {
+                        if let Ok(bytes) = base64::decode_config(&s, config.base64) {
+                            return Node::Bytes(bytes);
+                        }
+                    }
+
+                    Node::String(Typ::Auto, s + nl)
+                }
+                Value::Map(fvs) => Node::Map(fvs),
+            }
+        }
+
+        fn from_string(_typ: Typ, contents: String, _config: &Config) -> Self {
+            Value::String(contents)
+        }
+
+        fn from_bytes<T>(contents: T, config: &Config) -> Self
+        where
+            T: AsRef<[u8]>,
+        {
+            Value::String(base64::encode_config(contents, config.base64))
+        }
+
+        fn from_list_dir(files: Vec<Self>, _config: &Config) -> Self {
+            debug!("dotenv has no lists; using indices as keys");
+            Value::Map(
+                files
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, v)| (i.to_string(), v))
+                    .collect(),
+            )
+        }
+
+        fn from_named_dir(files: HashMap<String, Self>, _config: &Config) -> Self {
+            let mut fvs = files.into_iter().collect::<Vec<_>>();
+            fvs.sort_unstable_by(|(k1, _), (k2, _)| k1.cmp(k2));
+            Value::Map(fvs)
+        }
+
+        fn from_reader(
+            mut reader: Box<dyn std::io::Read>,
+            _config: &Config,
+        ) -> Result<Self, Box<dyn std::error::Error>> {
+            let mut text = String::new();
+            let _len = reader.read_to_string(&mut text)?;
+            Ok(parse(&text)?)
+        }
+
+        fn to_writer(
+            &self,
+            writer: &mut dyn std::io::Write,
+            config: &Config,
+        ) -> std::io::Result<()> {
+            let text = match self {
+                Value::Map(_) => self.to_string(),
+                Value::String(s) => {
+                    warn!(
+                        "Dotenv files must hold keys; saving the value in the key '{}'.",
+                        config.primitive_root_name
+                    );
+                    format!("{}={}\n", config.primitive_root_name, quote(s))
+                }
+            };
+            writer.write_all(text.as_bytes())
+        }
+
+        fn replace_at(&mut self, pointer: &str, v: Self) -> Option<Self> {
+            env_pointer_mut(self, pointer).map(|subtree| std::mem::replace(subtree, v))
+        }
+
+        fn sort_keys(&mut self) {
+            if let Value::Map(fvs) = self {
+                fvs.sort_by(|(k1, _), (k2, _)| k1.cmp(k2));
+                for (_, v) in fvs.iter_mut() {
+                    v.sort_keys();
+                }
+            }
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+/// Property list (plist) Nodelike implementation
+///
+/// Dictionaries are named directories and arrays are lists; `<data>` is bytes
+/// and dates are strings tagged `Typ::Datetime`, so they come back as dates.
+/// XML, binary, and ASCII plists are all loaded (the encoding is detected);
+/// saving writes XML, unless `Config::plist_binary` is set. Plists have no
+/// null, so null values are saved as empty strings.
+pub mod plist {
+    use super::*;
+    use plist_rs::{Date, Dictionary, Integer, Value as Plist};
+    use tracing::warn;
+
+    #[derive(Clone, Debug)]
+    pub struct Value(Plist);
+
+    impl std::fmt::Display for Value {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+            let mut xml = Vec::new();
+            match self.0.to_writer_xml(&mut xml) {
+                Ok(()) => write!(f, "{}", String::from_utf8_lossy(&xml)),
+                Err(_) => Err(std::fmt::Error),
+            }
+        }
+    }
+
+    impl Default for Value {
+        fn default() -> Self {
+            Value(Plist::String(String::new()))
+        }
+    }
+
+    fn plist_sort_keys(v: &mut Plist) {
+        match v {
+            Plist::Array(vs) => vs.iter_mut().for_each(plist_sort_keys),
+            Plist::Dictionary(fvs) => {
+                fvs.sort_keys();
+                fvs.values_mut().for_each(plist_sort_keys);
+            }
+            _ => (),
+        }
+    }
+
+    fn plist_measure(v: &Plist) -> (usize, usize) {
+        measure(v, |v| match v {
+            Plist::Array(vs) => Some(vs.iter().collect()),
+            Plist::Dictionary(fvs) => Some(fvs.values().collect()),
+            _ => None,
+        })
+    }
+
+    fn plist_pointer_mut<'a>(mut v: &'a mut Plist, pointer: &str) -> Option<&'a mut Plist> {
+        for token in pointer_tokens(pointer)? {
+            v = match v {
+                Plist::Dictionary(fvs) => fvs.get_mut(&token)?,
+                Plist::Array(vs) => vs.get_mut(pointer_index(&token)?)?,
+                _ => return None,
+            };
+        }
+
+        Some(v)
+    }
+
+    fn parse_integer(s: &str) -> Option<Plist> {
+        if let Ok(n) = i64::from_str(s) {
+            Some(Plist::Integer(Integer::from(n)))
+        } else {
+            u64::from_str(s)
+                .ok()
+                .map(|n| Plist::Integer(Integer::from(n)))
+        }
+    }
+
+    impl Nodelike for Value {
+        fn kind(&self) -> FileType {
+            match &self.0 {
+                Plist::Dictionary(_) | Plist::Array(_) => FileType::Directory,
+                _ => FileType::RegularFile,
+            }
+        }
+
+        fn size(&self) -> usize {
+            plist_measure(&self.0).0
+        }
+
+        fn depth(&self) -> usize {
+            plist_measure(&self.0).1
+        }
+
+        fn node(self, config: &Config) -> Node<Self> {
+            let nl = if config.add_newlines { "\n" } else { "" };
+
+            match self.0 {
+                Plist::Boolean(b) => Node::String(Typ::Boolean, format!("{}{}", b, nl)),
+                Plist::Integer(n) => Node::String(Typ::Integer, format!("{}{}", n, nl)),
+                Plist::Real(n) => Node::String(Typ::Float, format!("{}{}", n, nl)),
+                Plist::Date(d) => {
+                    Node::String(Typ::Datetime, format!("{}{}", d.to_xml_format(), nl))
+                }
+                Plist::Data(bytes) => Node::Bytes(bytes),
+                Plist::String(s) => {
+                    if config.try_decode_base64 {
+                        if let Ok(bytes) = base64::decode_config(&s, config.base64) {
+                            return Node::Bytes(bytes);
+                        }
+                    }
+
+                    Node::String(Typ::String, if s.ends_with('\n') { s } else { s + nl })
+                }
+                Plist::Array(vs) => Node::List(vs.into_iter().map(Value).collect()),
+                Plist::Dictionary(fvs) => {
+                    Node::Map(fvs.into_iter().map(|(k, v)| (k, Value(v))).collect())
+                }
+                Plist::Uid(uid) => {
+                    warn!("The plist UID {} will be saved as an integer.", uid.get());
+                    Node::String(Typ::Integer, format!("{}{}", uid.get(), nl))
+                }
+                v => {
+                    warn!(
+                        "Plist value {:?} has no ffs equivalent; it will be saved as a string.",
+                        v
+                    );
+                    Node::String(Typ::String, format!("{:?}{}", v, nl))
+                }
+            }
+        }
+
+        fn from_string(typ: Typ, contents: String, _config: &Config) -> Self {
+            let v = match typ {
+                Typ::Auto => {
+                    if contents == "true" {
+                        Plist::Boolean(true)
+                    } else if contents == "false" {
+                        Plist::Boolean(false)
+                    } else if let Some(n) = parse_integer(&contents) {
+                        n
+                    } else if let Ok(n) = f64::from_str(&contents) {
+                        Plist::Real(n)
+                    } else {
+                        Plist::String(contents)
+                    }
+                }
+                Typ::Boolean => {
+                    if contents == "true" {
+                        Plist::Boolean(true)
+                    } else if contents == "false" {
+                        Plist::Boolean(false)
+                    } else {
+                        debug!("string '{}' tagged as boolean", contents);
+                        Plist::String(contents)
+                    }
+                }
+                Typ::Bytes => panic!("from_string called at typ::bytes"),
+                Typ::Datetime => match Date::from_xml_format(&contents) {
+                    Ok(d) => Plist::Date(d),
+                    Err(_) => {
+                        debug!("string '{}' tagged as datetime", contents);
+                        Plist::String(contents)
+                    }
+                },
+                Typ::Float => match f64::from_str(&contents) {
+                    Ok(n) => Plist::Real(n),
+                    Err(_) => {
+                        debug!("string '{}' tagged as float", contents);
+                        Plist::String(contents)
+                    }
+                },
+                Typ::Integer => match parse_integer(&contents) {
+                    Some(n) => n,
+                    None => {
+                        debug!("string '{}' tagged as integer", contents);
+                        Plist::String(contents)
+                    }
+                },
+                Typ::Null => {
+                    if !contents.is_empty() {
+                        debug!("string '{}' tagged as null", contents);
+                    }
+                    Plist::String(contents)
+                }
+                Typ::String | Typ::ObjectId | Typ::Decimal | Typ::Keyword | Typ::Symbol => {
+                    Plist::String(contents)
+                }
+            };
+
+            Value(v)
+        }
+
+        fn from_bytes<T>(contents: T, _config: &Config) -> Self
+        where
+            T: AsRef<[u8]>,
+        {
+            Value(Plist::Data(contents.as_ref().to_vec()))
+        }
+
+        fn from_list_dir(files: Vec<Self>, _config: &Config) -> Self {
+            Value(Plist::Array(files.into_iter().map(|v| v.0).collect()))
+        }
+
+        fn from_named_dir(files: HashMap<String, Self>, _config: &Config) -> Self {
+            let mut fvs = files.into_iter().collect::<Vec<_>>();
+            fvs.sort_unstable_by(|(k1, _), (k2, _)| k1.cmp(k2));
+            Value(Plist::Dictionary(
+                fvs.into_iter()
+                    .map(|(k, v)| (k, v.0))
+                    .collect::<Dictionary>(),
+            ))
+        }
+
+        fn from_reader(
+            mut reader: Box<dyn std::io::Read>,
+            _config: &Config,
+        ) -> Result<Self, Box<dyn std::error::Error>> {
+            // detecting the encoding needs to seek
+            let mut bytes = Vec::new();
+            let _len = reader.read_to_end(&mut bytes)?;
+            Ok(Value(Plist::from_reader(std::io::Cursor::new(bytes))?))
+        }
+
+        fn to_writer(
+            &self,
+            writer: &mut dyn std::io::Write,
+            config: &Config,
+        ) -> std::io::Result<()> {
+            if config.plist_binary {
+                self.0.to_writer_binary(writer).map_err(save_error)
+            } else {
+                self.0.to_writer_xml(writer).map_err(save_error)
+            }
+        }
+
+        fn replace_at(&mut self, pointer: &str, v: Self) -> Option<Self> {
+            plist_pointer_mut(&mut self.0, pointer)
+                .map(|subtree| Value(std::mem::replace(subtree, v.0)))
+        }
+
+        fn sort_keys(&mut self) {
+            plist_sort_keys(&mut self.0)
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+/// Protobuf Nodelike implementation
+pub mod protobuf {
+    use super::*;
+    use prost::Message;
+    use prost_reflect::{DynamicMessage, MessageDescriptor, SerializeOptions};
+    use std::path::Path;
+
+    /// A protobuf message, held in its JSON mapping: message fields and maps
+    /// are objects (keyed by field name), repeated fields are arrays, and
+    /// enums are their symbolic names.
+    #[derive(Clone, Debug, Default, PartialEq)]
+    pub struct Value(serde_json::Value);
+
+    impl std::fmt::Display for Value {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+            write!(f, "{}", self.0)
+        }
+    }
+
+    /// Compiles `Config::proto_schema` and finds the message it's read and
+    /// written as: `Config::proto_message`, or else the schema's only
+    /// top-level message.
+    pub fn message_descriptor(
+        config: &Config,
+    ) -> Result<MessageDescriptor, Box<dyn std::error::Error>> {
+        let schema = config
+            .proto_schema
+            .as_ref()
+            .ok_or("protobuf needs a schema; use `--proto-schema FILE.proto`")?;
+        let include = match schema.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let pool = protox::Compiler::new([include])?
+            .include_imports(true)
+            .open_file(schema)?
+            .descriptor_pool();
+
+        if let Some(name) = &config.proto_message {
+            return pool.get_message_by_name(name).ok_or_else(|| {
+                format!("{} doesn't define the message '{}'", schema.display(), name).into()
+            });
+        }
+
+        let file = schema
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| pool.get_file_by_name(name));
+        let mut messages = file.iter().flat_map(|file| file.messages());
+        match (messages.next(), messages.next()) {
+            (Some(message), None) => Ok(message),
+            _ => Err(format!(
+                "{} doesn't define exactly one message; use `--proto-message NAME`",
+                schema.display()
+            )
+            .into()),
+        }
+    }
+
+    impl Nodelike for Value {
+        fn kind(&self) -> FileType {
+            self.0.kind()
+        }
+
+        fn size(&self) -> usize {
+            self.0.size()
+        }
+
+        fn depth(&self) -> usize {
+            self.0.depth()
+        }
+
+        fn node(self, config: &Config) -> Node<Self> {
+            match self.0.node(config) {
+                Node::String(t, s) => Node::String(t, s),
+                Node::Bytes(b) => Node::Bytes(b),
+                Node::Link(target) => Node::Link(target),
+                Node::List(vs) => Node::List(vs.into_iter().map(Value).collect()),
+                Node::Map(fvs) => Node::Map(fvs.into_iter().map(|(f, v)| (f, Value(v))).collect()),
+            }
+        }
+
+        fn from_string(typ: Typ, contents: String, config: &Config) -> Self {
+            Value(serde_json::Value::from_string(typ, contents, config))
+        }
+
+        fn from_bytes<T>(contents: T, config: &Config) -> Self
+        where
+            T: AsRef<[u8]>,
+        {
+            Value(serde_json::Value::from_bytes(contents, config))
+        }
+
+        fn from_list_dir(files: Vec<Self>, _config: &Config) -> Self {
+            Value(serde_json::Value::Array(
+                files.into_iter().map(|v| v.0).collect(),
+            ))
+        }
+
+        fn from_named_dir(files: HashMap<String, Self>, config: &Config) -> Self {
+            Value(serde_json::Value::from_named_dir(
+                files.into_iter().map(|(f, v)| (f, v.0)).collect(),
+                config,
+            ))
+        }
+
+        fn to_writer(
+            &self,
+            writer: &mut dyn std::io::Write,
+            config: &Config,
+        ) -> std::io::Result<()> {
+            // deserializing the value itself (rather than its text) hands
+            // numbers over as numbers, even under `arbitrary_precision`
+            let message = message_descriptor(config)
+                .and_then(|desc| Ok(DynamicMessage::deserialize(desc, self.0.clone())?));
+            let message = message.map_err(save_error)?;
+            writer.write_all(&message.encode_to_vec())
+        }
+
+        fn from_reader(
+            mut reader: Box<dyn std::io::Read>,
+            config: &Config,
+        ) -> Result<Self, Box<dyn std::error::Error>> {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes)?;
+
+            let message = DynamicMessage::decode(message_descriptor(config)?, bytes.as_slice())?;
+            // show every field, even ones holding their default values
+            let options = SerializeOptions::new()
+                .stringify_64_bit_integers(false)
+                .use_proto_field_name(true)
+                .skip_default_fields(false);
+            let v = message.serialize_with_options(serde_json::value::Serializer, &options)?;
+            Ok(Value(v))
+        }
+
+        fn replace_at(&mut self, pointer: &str, v: Self) -> Option<Self> {
+            self.0.replace_at(pointer, v.0).map(Value)
+        }
+
+        fn sort_keys(&mut self) {
+            self.0.sort_keys()
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+/// EDN Nodelike implementation
+///
+/// Maps are named directories; vectors, lists, and sets are lists. Keywords
+/// and symbols are strings tagged `Typ::Keyword` and `Typ::Symbol`, shown as
+/// written (e.g., `:active`), and keyword keys keep their colon, so both come
+/// back as they were. Lists, sets, and tagged literals (e.g., `#inst` and
+/// `#uuid`) are mounted like what they hold, and `Nodelike::tag` lets the
+/// mount remember what they were; `#inst`s are `Typ::Datetime` strings.
+/// Characters are loaded as strings.
+pub mod edn {
+    use super::*;
+
+    /// The tag `Nodelike::tag` gives sets.
+    const SET_TAG: &str = "#{}";
+    /// The tag `Nodelike::tag` gives lists, i.e., `(...)`.
+    const LIST_TAG: &str = "()";
+    /// How deeply collections may nest: deeper input is an error, rather
+    /// than a stack overflow.
+    const MAX_NESTING: usize = 128;
+
+    #[derive(Clone, Debug, Default, PartialEq)]
+    pub enum Value {
+        #[default]
+        Nil,
+        Bool(bool),
+        /// An integer, as written (e.g., `42` or `7N`)
+        Integer(String),
+        /// A float, as written (e.g., `1.5`, `1e3`, `2.50M`, or `##Inf`)
+        Float(String),
+        Char(char),
+        String(String),
+        /// A keyword, without its colon
+        Keyword(String),
+        Symbol(String),
+        List(Vec<Value>),
+        Vector(Vec<Value>),
+        Set(Vec<Value>),
+        Map(Vec<(Value, Value)>),
+        Tagged(String, Box<Value>),
+    }
+
+    impl std::fmt::Display for Value {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+            match self {
+                Value::Nil => write!(f, "nil"),
+                Value::Bool(b) => write!(f, "{}", b),
+                Value::Integer(n) | Value::Float(n) => write!(f, "{}", n),
+                Value::Char(c) => write!(f, "{}", char_literal(*c)),
+                Value::String(s) => write!(f, "{}", quote(s)),
+                Value::Keyword(k) => write!(f, ":{}", k),
+                Value::Symbol(s) => write!(f, "{}", s),
+                Value::List(vs) => write_seq(f, "(", vs, ")"),
+                Value::Vector(vs) => write_seq(f, "[", vs, "]"),
+                Value::Set(vs) => write_seq(f, "#{", vs, "}"),
+                Value::Map(fvs) => {
+                    write!(f, "{{")?;
+                    for (i, (k, v)) in fvs.iter().enumerate() {
+                        if i > 0 {
+                            write!(f, ", ")?;
+                        }
+                        write!(f, "{} {}", k, v)?;
+                    }
+                    write!(f, "}}")
+                }
+                Value::Tagged(tag, v) => write!(f, "#{} {}", tag, v),
+            }
+        }
+    }
+
+    fn write_seq(
+        f: &mut std::fmt::Formatter<'_>,
+        open: &str,
+        vs: &[Value],
+        close: &str,
+    ) -> std::result::Result<(), std::fmt::Error> {
+        write!(f, "{}", open)?;
+        for (i, v) in vs.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", v)?;
+        }
+        write!(f, "{}", close)
+    }
+
+    fn quote(s: &str) -> String {
+        let mut quoted = String::with_capacity(s.len() + 2);
+        quoted.push('"');
+        for c in s.chars() {
+            match c {
+                '"' => quoted.push_str("\\\""),
+                '\\' => quoted.push_str("\\\\"),
+                '\n' => quoted.push_str("\\n"),
+                '\t' => quoted.push_str("\\t"),
+                '\r' => quoted.push_str("\\r"),
+                c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+                c => quoted.push(c),
+            }
+        }
+        quoted.push('"');
+        quoted
+    }
+
+    fn char_literal(c: char) -> String {
+        match c {
+            '\n' => "\\newline".into(),
+            ' ' => "\\space".into(),
+            '\t' => "\\tab".into(),
+            '\r' => "\\return".into(),
+            c if c.is_control() => format!("\\u{:04x}", c as u32),
+            c => format!("\\{}", c),
+        }
+    }
+
+    /// Is `s` a symbol (or, after its colon, a keyword)? Symbols may have a
+    /// namespace, as in `my.app/name`.
+    fn is_symbol(s: &str) -> bool {
+        let mut chars = s.chars();
+        let first = match chars.next() {
+            Some(c) => c,
+            None => return false,
+        };
+        let second = chars.next();
+        let valid = |c: char| c.is_alphanumeric() || ".*+!-_?$%&=<>/:#'".contains(c);
+
+        // no leading digits, even after a sign or a dot, lest it be a number
+        if first.is_ascii_digit() || matches!(first, ':' | '#' | '\'') {
+            return false;
+        }
+        if matches!(first, '+' | '-' | '.') && second.is_some_and(|c| c.is_ascii_digit()) {
+            return false;
+        }
+
+        s.chars().all(valid)
+            && (s == "/" || (s.split('/').count() <= 2 && !s.starts_with('/') && !s.ends_with('/')))
+            && !matches!(s, "nil" | "true" | "false")
+    }
+
+    /// Parses an integer (e.g., `-3` or `7N`) or a float (e.g., `1.5`,
+    /// `1e3`, or `2.50M`), keeping it as written.
+    fn parse_number(token: &str) -> Option<Value> {
+        let unsigned = token.strip_prefix(['+', '-']).unwrap_or(token);
+        let (digits, rest) = unsigned.split_at(
+            unsigned
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(unsigned.len()),
+        );
+        if digits.is_empty() || (digits.len() > 1 && digits.starts_with('0')) {
+            return None;
+        }
+        if rest.is_empty() || rest == "N" {
+            return Some(Value::Integer(token.into()));
+        }
+
+        let rest = rest.strip_suffix('M').unwrap_or(rest);
+        let rest = match rest.strip_prefix('.') {
+            Some(fraction) => fraction.trim_start_matches(|c: char| c.is_ascii_digit()),
+            None => rest,
+        };
+        let valid = match rest.strip_prefix(['e', 'E']) {
+            Some(exponent) => {
+                let exponent = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+                !exponent.is_empty() && exponent.bytes().all(|b| b.is_ascii_digit())
+            }
+            None => rest.is_empty(),
+        };
+
+        if valid {
+            Some(Value::Float(token.into()))
+        } else {
+            None
+        }
+    }
+
+    /// `##Inf`, `##-Inf`, or `##NaN`, for NaN or an infinity as any format
+    /// would write it (see `is_special_float`).
+    fn special_float(s: &str) -> Value {
+        let s = s.trim().trim_start_matches('#').to_lowercase();
+        Value::Float(
+            if s.contains("nan") {
+                "##NaN"
+            } else if s.starts_with('-') {
+                "##-Inf"
+            } else {
+                "##Inf"
+            }
+            .into(),
+        )
+    }
+
+    /// A scalar written without delimiters: `nil`, a boolean, a number, a
+    /// keyword, or a symbol.
+    fn atom(token: &str) -> Option<Value> {
+        match token {
+            "nil" => Some(Value::Nil),
+            "true" => Some(Value::Bool(true)),
+            "false" => Some(Value::Bool(false)),
+            _ => {
+                if let Some(n) = parse_number(token) {
+                    Some(n)
+                } else if let Some(k) = token.strip_prefix(':') {
+                    if is_symbol(k) {
+                        Some(Value::Keyword(k.into()))
+                    } else {
+                        None
+                    }
+                } else if is_symbol(token) {
+                    Some(Value::Symbol(token.into()))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    struct Parser<'a> {
+        text: &'a str,
+        pos: usize,
+    }
+
+    impl<'a> Parser<'a> {
+        fn peek(&self) -> Option<char> {
+            self.text[self.pos..].chars().next()
+        }
+
+        fn bump(&mut self) -> Option<char> {
+            let c = self.peek()?;
+            self.pos += c.len_utf8();
+            Some(c)
+        }
+
+        fn error(&self, msg: &str) -> String {
+            let before = &self.text[..self.pos];
+            let line = before.matches('\n').count() + 1;
+            let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+            format!("{} at line {} column {}", msg, line, column)
+        }
+
+        /// Skips whitespace (commas included), comments, and forms discarded
+        /// with `#_`.
+        fn skip_space(&mut self, depth: usize) -> Result<(), String> {
+            loop {
+                match self.peek() {
+                    Some(c) if c.is_whitespace() || c == ',' => {
+                        self.bump();
+                    }
+                    Some(';') => while !matches!(self.bump(), None | Some('\n')) {},
+                    Some('#') if self.text[self.pos..].starts_with("#_") => {
+                        self.pos += 2;
+                        self.value(depth + 1)?;
+                    }
+                    _ => return Ok(()),
+                }
+            }
+        }
+
+        /// Reads up to the next whitespace or delimiter.
+        fn token(&mut self) -> String {
+            let start = self.pos;
+            while let Some(c) = self.peek() {
+                if c.is_whitespace() || "()[]{}\",;".contains(c) {
+                    break;
+                }
+                self.bump();
+            }
+            self.text[start..self.pos].into()
+        }
+
+        fn document(&mut self) -> Result<Value, String> {
+            let v = self.value(0)?;
+            self.skip_space(0)?;
+            if self.pos < self.text.len() {
+                return Err(self.error("trailing characters"));
+            }
+            Ok(v)
+        }
+
+        fn value(&mut self, depth: usize) -> Result<Value, String> {
+            if depth > MAX_NESTING {
+                return Err(self.error("nesting too deep"));
+            }
+            self.skip_space(depth)?;
+
+            let c = match self.peek() {
+                Some(c) => c,
+                None => return Err(self.error("unexpected end of input")),
+            };
+            match c {
+                '(' => {
+                    self.bump();
+                    Ok(Value::List(self.seq(')', depth)?))
+                }
+                '[' => {
+                    self.bump();
+                    Ok(Value::Vector(self.seq(']', depth)?))
+                }
+                '{' => {
+                    self.bump();
+                    let vs = self.seq('}', depth)?;
+                    if vs.len() % 2 != 0 {
+                        return Err(self.error("map with a key but no value"));
+                    }
+                    let mut fvs = Vec::with_capacity(vs.len() / 2);
+                    let mut vs = vs.into_iter();
+                    while let (Some(k), Some(v)) = (vs.next(), vs.next()) {
+                        fvs.push((k, v));
+                    }
+                    Ok(Value::Map(fvs))
+                }
+                '#' => {
+                    self.bump();
+                    match self.peek() {
+                        Some('{') => {
+                            self.bump();
+                            Ok(Value::Set(self.seq('}', depth)?))
+                        }
+                        Some('#') => {
+                            self.bump();
+                            match self.token().as_str() {
+                                "Inf" => Ok(Value::Float("##Inf".into())),
+                                "-Inf" => Ok(Value::Float("##-Inf".into())),
+                                "NaN" => Ok(Value::Float("##NaN".into())),
+                                other => Err(self.error(&format!("invalid value '##{}'", other))),
+                            }
+                        }
+                        _ => {
+                            let tag = self.token();
+                            if !is_symbol(&tag) {
+                                return Err(self.error(&format!("invalid tag '#{}'", tag)));
+                            }
+                            let v = self.value(depth + 1)?;
+                            Ok(Value::Tagged(tag, Box::new(v)))
+                        }
+                    }
+                }
+                '"' => {
+                    self.bump();
+                    self.string()
+                }
+                '\\' => {
+                    self.bump();
+                    self.character()
+                }
+                ')' | ']' | '}' => Err(self.error(&format!("unexpected '{}'", c))),
+                _ => {
+                    let start = self.pos;
+                    let token = self.token();
+                    match atom(&token) {
+                        Some(v) => Ok(v),
+                        None => {
+                            self.pos = start;
+                            Err(self.error(&format!("invalid token '{}'", token)))
+                        }
+                    }
+                }
+            }
+        }
+
+        /// The elements of a collection, up to and including `close`.
+        fn seq(&mut self, close: char, depth: usize) -> Result<Vec<Value>, String> {
+            let mut vs = Vec::new();
+            loop {
+                self.skip_space(depth + 1)?;
+                match self.peek() {
+                    Some(c) if c == close => {
+                        self.bump();
+                        return Ok(vs);
+                    }
+                    Some(_) => vs.push(self.value(depth + 1)?),
+                    None => return Err(self.error(&format!("expected '{}'", close))),
+                }
+            }
+        }
+
+        /// A string, after its opening quote.
+        fn string(&mut self) -> Result<Value, String> {
+            let mut s = String::new();
+            loop {
+                match self.bump() {
+                    Some('"') => return Ok(Value::String(s)),
+                    Some('\\') => match self.bump() {
+                        Some('n') => s.push('\n'),
+                        Some('t') => s.push('\t'),
+                        Some('r') => s.push('\r'),
+                        Some('b') => s.push('\u{8}'),
+                        Some('f') => s.push('\u{c}'),
+                        Some('\\') => s.push('\\'),
+                        Some('"') => s.push('"'),
+                        Some('u') => {
+                            let hex = (0..4).filter_map(|_| self.bump()).collect::<String>();
+                            match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                                Some(c) => s.push(c),
+                                None => {
+                                    return Err(self.error(&format!("invalid escape '\\u{}'", hex)))
+                                }
+                            }
+                        }
+                        Some(c) => return Err(self.error(&format!("invalid escape '\\{}'", c))),
+                        None => break,
+                    },
+                    Some(c) => s.push(c),
+                    None => break,
+                }
+            }
+            Err(self.error("unterminated string"))
+        }
+
+        /// A character, after its backslash: e.g., `a`, `newline`, or
+        /// `u00e9`.
+        fn character(&mut self) -> Result<Value, String> {
+            // the first character may be a delimiter, as in `\(`
+            let first = match self.bump() {
+                Some(c) => c,
+                None => return Err(self.error("unexpected end of input")),
+            };
+            let name = format!("{}{}", first, self.token());
+
+            let c = match name.as_str() {
+                "newline" => '\n',
+                "space" => ' ',
+                "tab" => '\t',
+                "return" => '\r',
+                "backspace" => '\u{8}',
+                "formfeed" => '\u{c}',
+                _ if name.chars().count() == 1 => first,
+                _ => match name
+                    .strip_prefix('u')
+                    .filter(|hex| hex.len() == 4)
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .and_then(char::from_u32)
+                {
+                    Some(c) => c,
+                    None => return Err(self.error(&format!("invalid character '\\{}'", name))),
+                },
+            };
+            Ok(Value::Char(c))
+        }
+    }
+
+    fn parse(text: &str) -> Result<Value, String> {
+        Parser { text, pos: 0 }.document()
+    }
+
+    /// The name of a map key: strings as they are, keywords with their
+    /// colon, and anything else as EDN.
+    fn key_to_string(k: &Value) -> String {
+        match k {
+            Value::String(s) => s.clone(),
+            k => k.to_string(),
+        }
+    }
+
+    /// Undoes `key_to_string` for string and keyword keys.
+    fn key_from_string(s: String) -> Value {
+        match s.strip_prefix(':') {
+            Some(k) if is_symbol(k) => Value::Keyword(k.into()),
+            _ => Value::String(s),
+        }
+    }
+
+    /// `v` without any tags.
+    fn untagged(mut v: &Value) -> &Value {
+        while let Value::Tagged(_, inner) = v {
+            v = inner;
+        }
+        v
+    }
+
+    fn untagged_mut(v: &mut Value) -> &mut Value {
+        match v {
+            Value::Tagged(_, inner) => untagged_mut(inner),
+            v => v,
+        }
+    }
+
+    fn edn_measure(v: &Value) -> (usize, usize) {
+        measure(untagged(v), |v| match v {
+            Value::List(vs) | Value::Vector(vs) | Value::Set(vs) => {
+                Some(vs.iter().map(untagged).collect())
+            }
+            Value::Map(fvs) => Some(fvs.iter().map(|(_, v)| untagged(v)).collect()),
+            _ => None,
+        })
+    }
+
+    fn edn_pointer_mut<'a>(mut v: &'a mut Value, pointer: &str) -> Option<&'a mut Value> {
+        for token in pointer_tokens(pointer)? {
+            v = match untagged_mut(v) {
+                Value::Map(fvs) => fvs
+                    .iter_mut()
+                    .find(|(k, _)| key_to_string(k) == token)
+                    .map(|(_, v)| v)?,
+                Value::List(vs) | Value::Vector(vs) | Value::Set(vs) => {
+                    vs.get_mut(pointer_index(&token)?)?
+                }
+                _ => return None,
+            };
+        }
+
+        Some(v)
+    }
+
+    impl Nodelike for Value {
+        fn kind(&self) -> FileType {
+            match untagged(self) {
+                Value::List(_) | Value::Vector(_) | Value::Set(_) | Value::Map(_) => {
+                    FileType::Directory
+                }
+                _ => FileType::RegularFile,
+            }
+        }
+
+        fn size(&self) -> usize {
+            edn_measure(self).0
+        }
+
+        fn depth(&self) -> usize {
+            edn_measure(self).1
+        }
+
+        fn node(self, config: &Config) -> Node<Self> {
+            let nl = if config.add_newlines { "\n" } else { "" };
+
+            match self {
+                Value::Nil => Node::String(Typ::Null, "".into()), // always empty
+                Value::Bool(b) => Node::String(Typ::Boolean, format!("{}{}", b, nl)),
+                Value::Integer(n) => Node::String(Typ::Integer, n + nl),
+                Value::Float(n) => Node::String(Typ::Float, n + nl),
+                Value::Char(c) => Node::String(Typ::String, format!("{}{}", c, nl)),
+                Value::String(s) => {
+                    if config.try_decode_base64 {
+                        if let Ok(bytes) = base64::decode_config(&s, config.base64) {
+                            return Node::Bytes(bytes);
+                        }
+                    }
+
+                    Node::String(Typ::String, if s.ends_with('\n') { s } else { s + nl })
+                }
+                Value::Keyword(k) => Node::String(Typ::Keyword, format!(":{}{}", k, nl)),
+                Value::Symbol(s) => Node::String(Typ::Symbol, s + nl),
+                Value::List(vs) | Value::Vector(vs) | Value::Set(vs) => Node::List(vs),
+                Value::Map(fvs) => Node::Map(
+                    fvs.into_iter()
+                        .map(|(k, v)| (key_to_string(&k), v))
+                        .collect(),
+                ),
+                Value::Tagged(tag, v) => match *v {
+                    Value::String(s) if tag == "inst" => {
+                        Node::String(Typ::Datetime, if s.ends_with('\n') { s } else { s + nl })
+                    }
+                    v => v.node(config),
+                },
+            }
+        }
+
+        fn from_string(typ: Typ, contents: String, _config: &Config) -> Self {
+            match typ {
+                Typ::Auto => {
+                    if contents.is_empty() {
+                        return Value::Nil;
+                    }
+                    match atom(&contents) {
+                        Some(
+                            v @ (Value::Bool(_)
+                            | Value::Integer(_)
+                            | Value::Float(_)
+                            | Value::Keyword(_)),
+                        ) => v,
+                        _ => Value::String(contents),
+                    }
+                }
+                Typ::Boolean => {
+                    if contents == "true" {
+                        Value::Bool(true)
+                    } else if contents == "false" {
+                        Value::Bool(false)
+                    } else {
+                        debug!("string '{}' tagged as boolean", contents);
+                        Value::String(contents)
+                    }
+                }
+                Typ::Bytes => panic!("from_string called at typ::bytes"),
+                Typ::Datetime => Value::Tagged("inst".into(), Box::new(Value::String(contents))),
+                Typ::Float | Typ::Integer => match parse_number(&contents) {
+                    Some(n) => n,
+                    None if typ == Typ::Float && is_special_float(&contents) => {
+                        special_float(&contents)
+                    }
+                    None => {
+                        debug!("string '{}' tagged as {}", contents, typ);
+                        Value::String(contents)
+                    }
+                },
+                Typ::Keyword => match contents.strip_prefix(':') {
+                    Some(k) if is_symbol(k) => Value::Keyword(k.into()),
+                    None if is_symbol(&contents) => Value::Keyword(contents),
+                    _ => {
+                        debug!("string '{}' tagged as keyword", contents);
+                        Value::String(contents)
+                    }
+                },
+                Typ::Symbol => {
+                    if is_symbol(&contents) {
+                        Value::Symbol(contents)
+                    } else {
+                        debug!("string '{}' tagged as symbol", contents);
+                        Value::String(contents)
+                    }
+                }
+                Typ::Null => {
+                    if contents.is_empty() {
+                        Value::Nil
+                    } else {
+                        debug!("string '{}' tagged as null", contents);
+                        Value::String(contents)
+                    }
+                }
+                Typ::String | Typ::ObjectId | Typ::Decimal => Value::String(contents),
+            }
+        }
+
+        fn from_bytes<T>(contents: T, config: &Config) -> Self
+        where
+            T: AsRef<[u8]>,
+        {
+            Value::String(base64::encode_config(contents, config.base64))
+        }
+
+        fn from_list_dir(files: Vec<Self>, _config: &Config) -> Self {
+            Value::Vector(files)
+        }
+
+        fn from_named_dir(files: HashMap<String, Self>, _config: &Config) -> Self {
+            let mut fvs = files.into_iter().collect::<Vec<_>>();
+            fvs.sort_unstable_by(|(k1, _), (k2, _)| k1.cmp(k2));
+            Value::Map(
+                fvs.into_iter()
+                    .map(|(k, v)| (key_from_string(k), v))
+                    .collect(),
+            )
+        }
+
+        fn from_reader(
+            mut reader: Box<dyn std::io::Read>,
+            _config: &Config,
+        ) -> Result<Self, Box<dyn std::error::Error>> {
+            let mut text = String::new();
+            let _len = reader.read_to_string(&mut text)?;
+            Ok(parse(&text)?)
+        }
+
+        fn to_writer(
+            &self,
+            writer: &mut dyn std::io::Write,
+            _config: &Config,
+        ) -> std::io::Result<()> {
+            writer.write_all(self.to_string().as_bytes())
+        }
+
+        fn replace_at(&mut self, pointer: &str, v: Self) -> Option<Self> {
+            edn_pointer_mut(self, pointer).map(|subtree| std::mem::replace(subtree, v))
+        }
+
+        fn sort_keys(&mut self) {
+            match self {
+                Value::List(vs) | Value::Vector(vs) | Value::Set(vs) => {
+                    vs.iter_mut().for_each(Value::sort_keys)
+                }
+                Value::Map(fvs) => {
+                    fvs.sort_by_key(|(k, _)| key_to_string(k));
+                    for (_, v) in fvs.iter_mut() {
+                        v.sort_keys();
+                    }
+                }
+                Value::Tagged(_, v) => v.sort_keys(),
+                _ => (),
+            }
+        }
+
+        fn non_string_keys(&self) -> Vec<String> {
+            match untagged(self) {
+                Value::Map(fvs) => fvs
+                    .iter()
+                    .filter(|(k, _)| !matches!(k, Value::String(_) | Value::Keyword(_)))
+                    .map(|(k, _)| key_to_string(k))
+                    .collect(),
+                _ => Vec::new(),
+            }
+        }
+
+        fn tag(&self) -> Option<String> {
+            match self {
+                Value::Tagged(tag, _) => Some(tag.clone()),
+                Value::List(_) => Some(LIST_TAG.into()),
+                Value::Set(_) => Some(SET_TAG.into()),
+                _ => None,
+            }
+        }
+
+        fn with_tag(self, tag: &str) -> Self {
+            match (tag, self) {
+                (SET_TAG, Value::Vector(vs)) => Value::Set(vs),
+                (LIST_TAG, Value::Vector(vs)) => Value::List(vs),
+                (SET_TAG, v) | (LIST_TAG, v) => v,
+                (tag, Value::Tagged(t, v)) if t == tag => Value::Tagged(t, v),
+                (tag, v) => Value::Tagged(tag.into(), Box::new(v)),
+            }
         }
     }
 }
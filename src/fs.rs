@@ -1,16 +1,18 @@
 use std::cell::Cell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ffi::OsStr;
 use std::fmt::{Debug, Display};
 use std::mem;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::time::{Duration, SystemTime};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Instant, SystemTime};
 
 use fuser::{
-    FileAttr, FileType, Filesystem, ReplyAttr, ReplyBmap, ReplyCreate, ReplyData, ReplyDirectory,
-    ReplyDirectoryPlus, ReplyEmpty, ReplyEntry, ReplyIoctl, ReplyLock, ReplyLseek, ReplyOpen,
-    ReplyStatfs, ReplyWrite, ReplyXattr, Request, TimeOrNow,
+    FileAttr, FileType, Filesystem, KernelConfig, ReplyAttr, ReplyBmap, ReplyCreate, ReplyData,
+    ReplyDirectory, ReplyDirectoryPlus, ReplyEmpty, ReplyEntry, ReplyIoctl, ReplyLock, ReplyLseek,
+    ReplyOpen, ReplyStatfs, ReplyWrite, ReplyXattr, Request, TimeOrNow,
 };
 
 #[cfg(target_os = "macos")]
@@ -18,14 +20,30 @@ use fuser::ReplyXTimes;
 
 use tracing::{debug, error, info, instrument, trace, warn};
 
-use super::config::{Config, Munge, Output, ERROR_STATUS_FUSE};
-use super::format::{json, toml, yaml, Format, Node, Nodelike, Typ};
+use notify::{EventKind, RecursiveMode, Watcher};
+
+use super::config::{
+    Commit, Config, Input, ListRename, Munge, NullAs, Output, Sibling, SlashPolicy, WatchConflict,
+    INFO_FILE,
+};
+use super::format::{
+    self, bson, edn, env, hcl, ini, json, json5, ndjson, plist, properties, protobuf, ron, toml,
+    yaml, Format, Node, Nodelike, Typ,
+};
 use crate::time_ns;
 
 /// A filesystem `FS` is just a vector of nullable inodes, where the index is
 /// the inode number.
 ///
 /// NB that inode 0 is always invalid.
+///
+/// Requests are handled one at a time. `fuser` runs a single session loop,
+/// which calls each `Filesystem` method with `&mut self`, and the `Cell`s
+/// below make `FS` `!Sync`, so it can't be shared between threads without
+/// wrapping it in a lock first. Concurrent operations from any number of
+/// processes are safe: the kernel queues them for the loop. The only other
+/// threads ffs starts (the watcher and the signal handler) never touch an
+/// `FS`; the watcher just sets a flag we check on the next request.
 #[derive(Debug)]
 pub struct FS<V>
 where
@@ -33,16 +51,89 @@ where
 {
     /// Vector of nullable inodes; the index is the inode number.
     pub inodes: Vec<Option<Inode<V>>>,
+    /// How many inodes are in use, i.e., allocated and not since removed
+    /// from the tree. It's what `Config::max_inodes` limits: `inodes` also
+    /// has the slots of removed inodes, which are kept in case they're open.
+    live_inodes: usize,
+    /// The bytes of file contents the filesystem holds, counting values that
+    /// haven't been loaded yet; it's what `Config::capacity` limits. Synthetic
+    /// files and removed files (whose inodes stay around, in case they're
+    /// still open) don't count.
+    used_bytes: u64,
+    /// The generation of new inodes (see `Inode::generation`); `FS::reload`
+    /// bumps it when it reuses the old tree's inode numbers.
+    generation: u64,
     /// Configuration, which determines various file attributes.
     pub config: Config,
     /// Dirty bit: set to `true` when there are outstanding writes
     dirty: Cell<bool>,
     /// Synced bit: set to `true` if syncing has _ever_ happened
     synced: Cell<bool>,
+    /// When only a subtree is mounted (see `Config::root_pointer`), the rest
+    /// of the input document, with a placeholder where the subtree belongs.
+    document: Option<V>,
+    /// Directory listings snapshotted by `opendir`, indexed by file handle, so
+    /// that `readdir` offsets stay stable even if the directory changes. An
+    /// offset is an index into the snapshot, so each page of a listing costs
+    /// only its own length, however large the directory.
+    dir_handles: HashMap<u64, Vec<(u64, FileType, String)>>,
+    /// Files opened by `open`, indexed by file handle.
+    file_handles: HashMap<u64, OpenFile>,
+    /// The next file handle `open` or `opendir` will hand out. Never 0.
+    next_fh: u64,
+    /// Set when the input was a primitive, which we've wrapped in a directory
+    /// (see `Config::allow_primitive_root`).
+    wrapped_root: bool,
+    /// The synthetic length files of list directories (see
+    /// `Config::list_length_file`), indexed by the directory's inode.
+    length_files: HashMap<u64, u64>,
+    /// The synthetic info file at the root (see `Config::expose_info`), once
+    /// it's been looked up.
+    info_file: Option<u64>,
+    /// The entries left out of each directory by `Config::exclude`, indexed by
+    /// the directory's inode, as `(key, value)` pairs.
+    excluded: HashMap<u64, Vec<(String, V)>>,
+    /// Under `Config::watch`, the watcher on the input file and the flag it
+    /// sets when the file changes.
+    watcher: Option<(notify::RecommendedWatcher, Arc<AtomicBool>)>,
+    /// The modification time of the input file when we last loaded or saved
+    /// it, so we can ignore our own writes (see `Config::watch`).
+    input_mtime: Option<SystemTime>,
+    /// Under `Config::sync_on_close`, when the save owed for files closed
+    /// since the last one is due (see `FS::check_sync`).
+    sync_due: Option<Instant>,
+    /// Under `Config::write_overlay_only`, the input before the overlay was
+    /// merged in, which is what the saved overlay is relative to.
+    base: Option<V>,
+    /// The text of the input, when saving can keep its comments and
+    /// formatting (see `Format::keeps_formatting`).
+    source: Option<String>,
+    /// Under `Config::stream`, the elements of the root list that are already
+    /// in the output file.
+    streamed: HashSet<u64>,
+    /// Under `Input::Directory`, the files and subdirectories the document
+    /// was read from (and will be saved to).
+    layout: Option<DirLayout>,
+    /// The compiled `Config::schema`, if there is one.
+    schema: Option<jsonschema::JSONSchema>,
 }
 
-/// Default TTL on information passed to the OS, which caches responses.
-const TTL: Duration = Duration::from_secs(300);
+/// The block size we report in `Inode::attr` and `Filesystem::statfs`. It's
+/// also the unit of `FileAttr::blocks`, per `stat(2)`.
+const BLOCK_SIZE: u32 = 512;
+
+/// How deeply files can include each other (see `Config::include_tag`),
+/// counting the input file.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// The target of the symbolic links that stand for nulls under
+/// `NullAs::Symlink`. It isn't a JSON Pointer, so it can't be confused with a
+/// YAML alias (see `Config::yaml_aliases`).
+const NULL_LINK: &str = "null";
+
+/// The files in a typed leaf (see `Config::typed_leaves`).
+const TYPED_LEAF_VALUE: &str = "value";
+const TYPED_LEAF_TYPE: &str = "type";
 
 /// An inode, the core structure in the filesystem.
 #[derive(Debug)]
@@ -53,6 +144,25 @@ pub struct Inode<V> {
     pub parent: u64,
     /// Inode number of this node. Will not be 0.
     pub inum: u64,
+    /// The name of this node's entry in its parent directory, so
+    /// `FS::document_path` needn't search the parent for it. It's empty for
+    /// the root and for synthetic files, which aren't in any directory.
+    pub name: String,
+    /// Generation number of this node's slot in `FS::inodes`. It's 0 for a
+    /// fresh slot; if a freed slot is ever reused for a new node, the new
+    /// node must have a higher generation than the old one, so the kernel
+    /// (and NFS-style file handles) can tell the two apart.
+    pub generation: u64,
+    /// Was this file mounted from a base64 string (see `Config::blob_keys`)?
+    /// If so, its contents are encoded as a base64 string again when saving.
+    pub blob: bool,
+    /// The file this subtree was read from, if it was spliced in by
+    /// `Config::include_tag`. It's saved back to that file, and as a reference
+    /// to it in the enclosing document.
+    pub include: Option<Include>,
+    /// The tag on the value this inode was mounted from (see
+    /// `Nodelike::tag`), e.g., an EDN set's. It's put back when saving.
+    pub tag: Option<String>,
     /// User ID of the owner
     pub uid: u32,
     /// Group ID of the owner,
@@ -86,6 +196,10 @@ pub enum Entry<V> {
     // strip newlines during writeback
     File(Typ, Vec<u8>),
     Directory(DirType, HashMap<String, DirEntry>),
+    /// A symbolic link to the value named by an RFC 6901 JSON Pointer (see
+    /// `Config::yaml_aliases`). Links are resolved against the current
+    /// filesystem, so a link sees edits to its target.
+    Link(String),
     Lazy(V),
 }
 
@@ -99,296 +213,3276 @@ pub struct DirEntry {
     ///
     /// If the file is renamed, we'll drop the original name.
     pub original_name: Option<String>,
+    /// Under `Config::flatten_single`, the single-child directories that were
+    /// collapsed into this entry, outermost first: each directory's type and
+    /// the key of its only child. `original_name` holds the key of the
+    /// outermost one. Like `original_name`, this is dropped on rename.
+    pub nesting: Vec<(DirType, String)>,
+    /// Under `SlashPolicy::Split`, marks a directory made up from the first
+    /// part of keys like `a/b`; its entries are saved back as keys of the
+    /// parent. Like `original_name`, this is dropped on rename.
+    pub split: bool,
     pub inum: u64,
 }
 
+/// A file spliced into the document by `Config::include_tag`.
+#[derive(Clone, Debug)]
+pub struct Include {
+    /// The path as written after the tag, which is what we save back.
+    pub reference: String,
+    /// The (canonicalized) file it refers to.
+    pub file: PathBuf,
+    pub format: Format,
+}
+
+/// An open file, as recorded by `open` (and forgotten by `release`).
 #[derive(Debug)]
+pub struct OpenFile {
+    /// The flags given to `open(2)`, e.g., the access mode or `O_APPEND`.
+    pub flags: i32,
+    /// Whether anything has been written through this handle yet.
+    pub written: bool,
+}
+
+impl OpenFile {
+    pub fn writable(&self) -> bool {
+        self.flags & libc::O_ACCMODE != libc::O_RDONLY
+    }
+}
+
+/// The files and subdirectories of a directory read by `Input::Directory`,
+/// by the key each is mounted as.
+#[derive(Debug, Default)]
+struct DirLayout {
+    /// Each file's path and format.
+    files: HashMap<String, (PathBuf, Format)>,
+    /// Each subdirectory's path and layout.
+    dirs: HashMap<String, (PathBuf, DirLayout)>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum DirType {
     Named,
     List,
 }
 
-#[derive(Debug)]
-pub enum FSError {
-    NoSuchInode(u64),
-    InvalidInode(u64),
+/// An entry produced by `FS::flatten_entries`: its key, its (dotted) name,
+/// its value, and its `DirEntry::nesting`.
+type FlatEntry<V> = (String, String, V, Vec<(DirType, String)>);
+
+/// The entries of a directory being saved, with collapsed directories (see
+/// `DirEntry::nesting`) restored.
+enum Nested<U> {
+    Value(U),
+    Dir(DirType, Vec<(String, Nested<U>)>),
 }
 
-impl<V> FS<V>
+impl<U> Nested<U>
 where
-    V: Nodelike + Clone + Debug + Display + Default,
+    U: Nodelike,
 {
-    fn fresh_inode(&mut self, parent: u64, entry: Entry<V>, uid: u32, gid: u32, mode: u32) -> u64 {
-        self.dirty.set(true);
-
-        let inum = self.inodes.len() as u64;
-        let mode = (mode & 0o777) as u16;
+    /// Adds `v` at `key`, inside of the directories in `nesting`, reusing
+    /// directories already restored for other entries.
+    fn insert(
+        entries: &mut Vec<(String, Nested<U>)>,
+        key: String,
+        nesting: &[(DirType, String)],
+        v: U,
+    ) {
+        let ((typ, inner), nesting) = match nesting.split_first() {
+            Some(split) => split,
+            None => {
+                entries.push((key, Nested::Value(v)));
+                return;
+            }
+        };
 
-        self.inodes
-            .push(Some(Inode::with_mode(parent, inum, entry, uid, gid, mode)));
+        let idx = match entries
+            .iter()
+            .position(|(k, n)| k == &key && matches!(n, Nested::Dir(t, _) if t == typ))
+        {
+            Some(idx) => idx,
+            None => {
+                entries.push((key, Nested::Dir(*typ, Vec::new())));
+                entries.len() - 1
+            }
+        };
 
-        inum
+        if let Nested::Dir(_, children) = &mut entries[idx].1 {
+            Nested::insert(children, inner.clone(), nesting, v);
+        }
     }
 
-    #[instrument(level = "debug", skip(self))]
-    fn resolve_node(&mut self, inum: u64) -> Result<Option<Vec<u64>>, FSError>
-    where
-        V: Nodelike + std::fmt::Display + Default,
-    {
-        debug!("called");
-
-        let idx = inum as usize;
+    /// Adds the entries of the map `v` as `key/k` (see `DirEntry::split`);
+    /// anything else is added at `key`.
+    fn splice(entries: &mut Vec<(String, Nested<U>)>, key: String, v: U, config: &Config) {
+        match v.clone().node(config) {
+            Node::Map(fvs) => entries.extend(
+                fvs.into_iter()
+                    .map(|(k, v)| (format!("{}/{}", key, k), Nested::Value(v))),
+            ),
+            _ => entries.push((key, Nested::Value(v))),
+        }
+    }
 
-        if idx >= self.inodes.len() || idx == 0 {
-            return Err(FSError::NoSuchInode(inum));
+    fn build(self, config: &Config) -> U {
+        match self {
+            Nested::Value(v) => v,
+            Nested::Dir(DirType::List, entries) => U::from_list_dir(
+                entries.into_iter().map(|(_, n)| n.build(config)).collect(),
+                config,
+            ),
+            Nested::Dir(DirType::Named, entries) => U::from_named_dir(
+                entries
+                    .into_iter()
+                    .map(|(k, n)| (k, n.build(config)))
+                    .collect(),
+                config,
+            ),
         }
+    }
+}
 
-        let inode = match &mut self.inodes[idx] {
-            Some(inode) => inode,
-            _ => return Err(FSError::InvalidInode(inum)),
-        };
+/// Errors from operations on an `FS`. Each corresponds to the `errno` the
+/// mount reports for it; see `FSError::errno`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FSError {
+    /// There's no inode with this number.
+    NoSuchInode(u64),
+    /// The inode with this number has been removed.
+    InvalidInode(u64),
+    /// There's no file with this name in the directory.
+    NotFound(String),
+    /// The inode isn't a directory, but needs to be.
+    NotADirectory(u64),
+    /// The inode is a directory, but mustn't be.
+    IsADirectory(u64),
+    /// The inode is neither a regular file nor a directory (i.e., it's a
+    /// symbolic link).
+    NotAFile(u64),
+    /// The directory still has entries.
+    NotEmpty(u64),
+    /// There's already a file with this name in the directory.
+    Exists(String),
+    /// Names must be valid UTF-8, and can't be `.` or `..` or contain `/`.
+    InvalidName(String),
+    /// The file is synthetic (see `FS::is_synthetic`), or the user isn't
+    /// allowed to change it.
+    AccessDenied,
+    /// The filesystem is mounted read-only.
+    ReadOnly,
+    /// The file would grow beyond `Config::max_file_size`.
+    FileTooBig,
+    /// There are no inodes left (see `Config::max_inodes`), or no room for
+    /// more data (see `Config::capacity`).
+    NoSpace,
+    /// The name is longer than `Config::name_max`.
+    NameTooLong(String),
+    /// The document violates `Config::schema` in this many places, and
+    /// `Config::schema_enforce` is set, so it can't be saved.
+    SchemaViolation(usize),
+    /// The document couldn't be saved, for this reason.
+    SaveFailed(String),
+    /// The user may not make this change (e.g., giving away a file they
+    /// don't own).
+    NotPermitted,
+    /// The file has no extended attribute by this name.
+    NoAttribute(String),
+    /// The operation doesn't make sense with these arguments (e.g., an
+    /// unknown type in `user.ffs.type`, or moving a directory into itself).
+    InvalidArgument,
+    /// The operation isn't supported (e.g., an extended attribute other than
+    /// the type, or an unknown `fallocate(2)` mode).
+    Unsupported,
+}
 
-        let v = match &mut inode.entry {
-            Entry::Directory(..) | Entry::File(..) => return Ok(Option::None),
-            Entry::Lazy(v) => mem::take(v),
-        };
-        let uid = inode.uid;
-        let gid = inode.gid;
+impl FSError {
+    /// The `errno` to report to the kernel.
+    pub fn errno(&self) -> libc::c_int {
+        match self {
+            FSError::NoSuchInode(_) | FSError::InvalidInode(_) | FSError::NotFound(_) => {
+                libc::ENOENT
+            }
+            FSError::NotADirectory(_) => libc::ENOTDIR,
+            FSError::IsADirectory(_) => libc::EISDIR,
+            FSError::NotAFile(_) | FSError::InvalidName(_) => libc::EINVAL,
+            FSError::NotEmpty(_) => libc::ENOTEMPTY,
+            FSError::Exists(_) => libc::EEXIST,
+            FSError::AccessDenied => libc::EACCES,
+            FSError::ReadOnly => libc::EROFS,
+            FSError::FileTooBig => libc::EFBIG,
+            FSError::NoSpace => libc::ENOSPC,
+            FSError::NameTooLong(_) => libc::ENAMETOOLONG,
+            FSError::SchemaViolation(_) | FSError::SaveFailed(_) => libc::EIO,
+            FSError::NotPermitted => libc::EPERM,
+            FSError::NoAttribute(_) => ENOATTR,
+            FSError::InvalidArgument => libc::EINVAL,
+            FSError::Unsupported => libc::ENOTSUP,
+        }
+    }
+}
 
-        let (entry, new_nodes) = match v.node(&self.config) {
-            Node::Bytes(b) => (Entry::File(Typ::Bytes, b), Option::None),
-            Node::String(t, s) => (Entry::File(t, s.into_bytes()), Option::None),
-            Node::List(vs) => {
-                let mut children = HashMap::new();
-                children.reserve(vs.len());
-                let num_elts = vs.len() as f64;
-                let width = num_elts.log10().ceil() as usize;
+impl std::fmt::Display for FSError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            FSError::NoSuchInode(inum) => write!(f, "no such inode {}", inum),
+            FSError::InvalidInode(inum) => write!(f, "inode {} was removed", inum),
+            FSError::NotFound(name) => write!(f, "no such file '{}'", name),
+            FSError::NotADirectory(inum) => write!(f, "inode {} isn't a directory", inum),
+            FSError::IsADirectory(inum) => write!(f, "inode {} is a directory", inum),
+            FSError::NotAFile(inum) => write!(f, "inode {} is a symbolic link", inum),
+            FSError::NotEmpty(inum) => write!(f, "directory {} isn't empty", inum),
+            FSError::Exists(name) => write!(f, "'{}' already exists", name),
+            FSError::InvalidName(name) => write!(f, "invalid name '{}'", name),
+            FSError::AccessDenied => write!(f, "access denied"),
+            FSError::ReadOnly => write!(f, "read-only filesystem"),
+            FSError::FileTooBig => write!(f, "file too big"),
+            FSError::NoSpace => write!(f, "no space left"),
+            FSError::NameTooLong(name) => write!(f, "name too long '{}'", name),
+            FSError::SchemaViolation(n) => {
+                write!(f, "document violates the schema ({} violations)", n)
+            }
+            FSError::SaveFailed(e) => write!(f, "couldn't save: {}", e),
+            FSError::NotPermitted => write!(f, "operation not permitted"),
+            FSError::NoAttribute(name) => write!(f, "no extended attribute '{}'", name),
+            FSError::InvalidArgument => write!(f, "invalid argument"),
+            FSError::Unsupported => write!(f, "operation not supported"),
+        }
+    }
+}
 
-                let mut new_nodes = Vec::with_capacity(vs.len());
-                for (i, child) in vs.into_iter().enumerate() {
-                    // TODO 2021-06-08 ability to add prefixes
-                    let name = if self.config.pad_element_names {
-                        format!("{:0width$}", i, width = width)
-                    } else {
-                        format!("{}", i)
-                    };
+impl std::error::Error for FSError {}
 
-                    let kind = child.kind();
-                    let child_id = self.fresh_inode(
-                        inum,
-                        Entry::Lazy(child),
-                        uid,
-                        gid,
-                        self.config.mode(kind) as u32,
-                    );
+impl<V> FS<V>
+where
+    V: Nodelike + Clone + Debug + Display + Default,
+{
+    fn fresh_fh(&mut self) -> u64 {
+        let fh = self.next_fh;
+        self.next_fh += 1;
+        fh
+    }
 
-                    children.insert(
-                        name,
-                        DirEntry {
-                            kind,
-                            original_name: None,
-                            inum: child_id,
-                        },
-                    );
-                    new_nodes.push(child_id)
-                }
+    /// Checks `Config::max_inodes`, i.e., whether we can't allocate any more
+    /// inodes.
+    fn inodes_exhausted(&self) -> bool {
+        matches!(self.config.max_inodes, Some(max) if self.live_inodes >= max)
+    }
 
-                (
-                    Entry::Directory(DirType::List, children),
-                    Option::Some(new_nodes),
-                )
-            }
-            Node::Map(fvs) => {
-                let mut children = HashMap::new();
-                children.reserve(fvs.len());
+    /// Stops counting the inode `inum` against `Config::max_inodes`, and its
+    /// contents against `Config::capacity`, once it's been removed from the
+    /// tree. Its slot stays, since it may still be open, but a removed list's
+    /// length file is freed.
+    fn remove_inode(&mut self, inum: u64) {
+        debug!("removing inode {}", inum);
+        self.live_inodes -= 1;
+        let bytes = self.file_bytes(inum);
+        self.used_bytes = self.used_bytes.saturating_sub(bytes);
+
+        if let Some(length_file) = self.length_files.remove(&inum) {
+            self.inodes[length_file as usize] = None;
+            self.live_inodes -= 1;
+        }
+    }
 
-                let mut new_nodes = Vec::with_capacity(fvs.len());
-                for (field, child) in fvs.into_iter() {
-                    let original = field.clone();
+    /// The bytes of file contents `inum` holds, counting its value if it
+    /// hasn't been loaded yet (see `FS::used_bytes`).
+    fn file_bytes(&self, inum: u64) -> u64 {
+        match self.inodes.get(inum as usize) {
+            Some(Some(inode)) => match &inode.entry {
+                Entry::File(_t, contents) => contents.len() as u64,
+                Entry::Lazy(v) => format::leaf_bytes(v.clone(), &self.config),
+                Entry::Directory(..) | Entry::Link(..) => 0,
+            },
+            _ => 0,
+        }
+    }
 
-                    let nfield = if !self.config.valid_name(&original) {
-                        match self.config.munge {
-                            Munge::Rename => {
-                                let mut nfield = self.config.normalize_name(field);
+    /// Checks `Config::capacity`, i.e., whether the filesystem can't hold
+    /// `growth` more bytes.
+    fn out_of_space(&self, growth: u64) -> bool {
+        matches!(self.config.capacity, Some(max) if growth > 0 && self.used_bytes + growth > max)
+    }
 
-                                // TODO 2021-07-08 could be better to check fvs, but it's a vec now... :/
-                                while children.contains_key(&nfield) {
-                                    nfield.push('_');
-                                }
+    /// How many bytes the file `ino` would grow by if it were `size` bytes
+    /// long.
+    fn growth(&mut self, ino: u64, size: u64) -> u64 {
+        match self.get(ino) {
+            Ok(Inode {
+                entry: Entry::File(_t, contents),
+                ..
+            }) => size.saturating_sub(contents.len() as u64),
+            _ => 0,
+        }
+    }
 
-                                nfield
-                            }
-                            Munge::Filter => {
-                                warn!("skipping '{}'", field);
-                                continue;
-                            }
-                        }
-                    } else {
-                        field
-                    };
+    /// Checks `Config::max_file_size`, i.e., whether a file can't grow to
+    /// `size` bytes.
+    fn too_big(&self, size: u64) -> bool {
+        matches!(self.config.max_file_size, Some(max) if size > max)
+    }
 
-                    let kind = child.kind();
-                    let child_id = self.fresh_inode(
-                        inum,
-                        Entry::Lazy(child),
-                        uid,
-                        gid,
-                        self.config.mode(kind) as u32,
-                    );
-                    let original_name = if original != nfield {
-                        info!(
-                            "renamed {} to {} (inode {} with parent {})",
-                            original, nfield, child_id, inum
-                        );
-                        Some(original)
-                    } else {
-                        assert!(self.config.valid_name(&original));
-                        None
-                    };
+    /// Finds the length file of the list directory `dir` (see
+    /// `Config::list_length_file`), creating it if need be, and brings its
+    /// contents up to date. The length file isn't one of the directory's
+    /// files, so it's never renumbered or saved. It counts against
+    /// `Config::max_inodes`, though, so there's none once they've run out.
+    fn length_file(&mut self, dir: u64) -> Option<u64> {
+        self.config.list_length_file.as_ref()?;
+
+        let dir_inode = self.get(dir).ok()?;
+        let len = match &dir_inode.entry {
+            Entry::Directory(DirType::List, files) => files.len(),
+            _ => return None,
+        };
+        let (uid, gid) = (dir_inode.uid, dir_inode.gid);
 
-                    children.insert(
-                        nfield,
-                        DirEntry {
-                            kind,
-                            original_name,
-                            inum: child_id,
-                        },
-                    );
+        let nl = if self.config.add_newlines { "\n" } else { "" };
+        let entry = Entry::File(Typ::Integer, format!("{}{}", len, nl).into_bytes());
 
-                    new_nodes.push(child_id);
+        match self.length_files.get(&dir) {
+            Some(&inum) => {
+                if let Some(inode) = &mut self.inodes[inum as usize] {
+                    inode.entry = entry;
                 }
-
-                (
-                    Entry::Directory(DirType::Named, children),
-                    Option::Some(new_nodes),
-                )
+                Some(inum)
             }
-        };
-
-        let inode = match &mut self.inodes[idx] {
-            Some(inode) => inode,
-            _ => return Err(FSError::InvalidInode(inum)),
-        };
-        inode.entry = entry;
+            None => {
+                if self.inodes_exhausted() {
+                    return None;
+                }
+                let inum = self.fresh_inode(dir, "", entry, uid, gid, 0o444);
+                self.length_files.insert(dir, inum);
+                Some(inum)
+            }
+        }
+    }
 
-        if let Some(nodes) = &new_nodes {
-            debug!("new_nodes = {:?}", nodes);
+    fn is_length_file(&self, ino: u64) -> bool {
+        match self.inodes.get(ino as usize) {
+            Some(Some(inode)) => self.length_files.get(&inode.parent) == Some(&ino),
+            _ => false,
         }
+    }
 
-        Ok(new_nodes)
+    fn refresh_length_file(&mut self, ino: u64) {
+        if self.is_length_file(ino) {
+            let dir = self.inodes[ino as usize].as_ref().unwrap().parent;
+            self.length_file(dir);
+        }
     }
 
-    fn resolve_nodes_transitively(&mut self, inum: u64) -> Result<(), FSError> {
-        let mut worklist = match self.resolve_node(inum)? {
-            Some(nodes) => nodes,
-            None => return Ok(()),
-        };
+    /// Finds the info file (see `Config::expose_info`) if `dir` is the root,
+    /// creating it if need be, and brings its contents up to date. Like the
+    /// length files, it's never renumbered or saved.
+    fn info_file(&mut self, dir: u64) -> Option<u64> {
+        if !self.config.expose_info || dir != fuser::FUSE_ROOT_ID {
+            return None;
+        }
 
-        while !worklist.is_empty() {
-            let node = worklist.pop().unwrap();
-            if let Some(nodes) = self.resolve_node(node)? {
-                worklist.extend(nodes);
+        let inodes = self
+            .inodes
+            .iter()
+            .enumerate()
+            .filter(|(inum, inode)| inode.is_some() && !self.is_synthetic(*inum as u64))
+            .count();
+        let info = format!(
+            "source: {}\ninodes: {}\ndirty: {}\n\n{:#?}\n",
+            self.config.input,
+            inodes,
+            self.dirty.get(),
+            self.config
+        );
+        let entry = Entry::File(Typ::String, info.into_bytes());
+
+        match self.info_file {
+            Some(inum) => {
+                if let Some(inode) = &mut self.inodes[inum as usize] {
+                    inode.entry = entry;
+                }
+                Some(inum)
+            }
+            None => {
+                let (uid, gid) = (self.config.uid, self.config.gid);
+                let inum = self.fresh_inode(dir, "", entry, uid, gid, 0o444);
+                self.info_file = Some(inum);
+                Some(inum)
             }
         }
+    }
 
-        Ok(())
+    /// Is `ino` a length file or the info file, i.e., a read-only file that
+    /// isn't part of the document?
+    fn is_synthetic(&self, ino: u64) -> bool {
+        self.info_file == Some(ino) || self.is_length_file(ino)
     }
 
-    fn check_access(&self, req: &Request) -> bool {
-        req.uid() == 0 || req.uid() == self.config.uid
+    fn refresh_synthetic(&mut self, ino: u64) {
+        if self.info_file == Some(ino) {
+            self.info_file(fuser::FUSE_ROOT_ID);
+        } else {
+            self.refresh_length_file(ino);
+        }
     }
 
-    pub fn get(&mut self, inum: u64) -> Result<&Inode<V>, FSError> {
-        let _new_nodes = self.resolve_node(inum)?;
+    /// Is `name` reserved for the info file in `dir`?
+    fn is_info_file_name(&self, dir: u64, name: &str) -> bool {
+        self.config.expose_info && dir == fuser::FUSE_ROOT_ID && name == INFO_FILE
+    }
 
-        let idx = inum as usize;
+    /// Is `name` reserved for a synthetic file in `dir`?
+    fn is_synthetic_name(&mut self, dir: u64, name: &str) -> bool {
+        self.is_info_file_name(dir, name) || self.is_length_file_name(dir, name)
+    }
 
-        if idx >= self.inodes.len() || idx == 0 {
-            return Err(FSError::NoSuchInode(inum));
-        }
+    /// Is `name` reserved for the length file in `dir`?
+    fn is_length_file_name(&mut self, dir: u64, name: &str) -> bool {
+        self.config.list_length_file.as_deref() == Some(name)
+            && matches!(
+                self.get(dir),
+                Ok(Inode {
+                    entry: Entry::Directory(DirType::List, _),
+                    ..
+                })
+            )
+    }
 
-        match &self.inodes[idx] {
-            None => Err(FSError::InvalidInode(inum)),
-            Some(inode) => Ok(inode),
+    /// The `FileType` that `v` will have once it's resolved: under
+    /// `Config::typed_leaves`, every value is a directory.
+    fn kind_of(&self, v: &V) -> FileType {
+        match v.kind() {
+            FileType::RegularFile
+                if self.config.null_as == NullAs::Symlink && is_null(v, &self.config) =>
+            {
+                FileType::Symlink
+            }
+            FileType::RegularFile if self.config.typed_leaves => FileType::Directory,
+            kind => kind,
         }
     }
 
-    fn get_mut(&mut self, inum: u64) -> Result<&mut Inode<V>, FSError> {
-        let _new_nodes = self.resolve_node(inum)?;
+    /// Builds the typed leaf for a primitive at `inum`: a named directory
+    /// holding the primitive's `contents` in a `value` file and its `typ` in a
+    /// `type` file.
+    fn typed_leaf_dir(&mut self, inum: u64, typ: Typ, contents: Vec<u8>) -> Entry<V> {
+        let inode = self.inodes[inum as usize].as_ref().unwrap();
+        let (uid, gid) = (inode.uid, inode.gid);
+        let mode = self.config.mode(FileType::RegularFile) as u32;
+        let nl = if self.config.add_newlines { "\n" } else { "" };
+
+        // the value was already counted, but its type wasn't
+        let type_contents = format!("{}{}", typ, nl).into_bytes();
+        self.used_bytes += type_contents.len() as u64;
+
+        let mut files = HashMap::with_capacity(2);
+        for (name, entry) in [
+            (TYPED_LEAF_VALUE, Entry::File(typ, contents)),
+            (TYPED_LEAF_TYPE, Entry::File(Typ::String, type_contents)),
+        ] {
+            let child_id = self.fresh_inode(inum, name, entry, uid, gid, mode);
+            files.insert(
+                name.into(),
+                DirEntry {
+                    kind: FileType::RegularFile,
+                    original_name: None,
+                    nesting: Vec::new(),
+                    split: false,
+                    inum: child_id,
+                },
+            );
+        }
 
-        let idx = inum as usize;
+        Entry::Directory(DirType::Named, files)
+    }
 
-        if idx >= self.inodes.len() {
-            return Err(FSError::NoSuchInode(inum));
+    /// Under `Config::typed_leaves`, a named directory holding just a `value`
+    /// file and a `type` file is a primitive; its type comes from the `type`
+    /// file, so editing it changes how `value` is saved. Returns the type and
+    /// the `value` file's inode.
+    fn typed_leaf(&self, typ: DirType, files: &HashMap<String, DirEntry>) -> Option<(Typ, u64)> {
+        if !self.config.typed_leaves || typ != DirType::Named || files.len() != 2 {
+            return None;
         }
 
-        match self.inodes.get_mut(idx) {
-            Some(Some(inode)) => Ok(inode),
-            _ => Err(FSError::InvalidInode(inum)),
-        }
+        let file = |name| match files.get(name) {
+            Some(DirEntry {
+                kind: FileType::RegularFile,
+                inum,
+                ..
+            }) => Some(*inum),
+            _ => None,
+        };
+        let value = file(TYPED_LEAF_VALUE)?;
+        let contents = match &self.inodes[file(TYPED_LEAF_TYPE)? as usize].as_ref()?.entry {
+            Entry::File(_, contents) => String::from_utf8_lossy(contents).into_owned(),
+            _ => return None,
+        };
+
+        let typ = Typ::from_str(&contents).unwrap_or_else(|()| {
+            warn!(
+                "Unknown type '{}' for the typed leaf at inode {}; using auto.",
+                contents.trim(),
+                value
+            );
+            Typ::Auto
+        });
+        Some((typ, value))
     }
 
-    pub fn new(config: Config) -> Self {
+    /// Saves the contents of a file, per its `typ`.
+    fn file_value<U>(&self, typ: Typ, contents: &[u8]) -> U
+    where
+        U: Nodelike,
+    {
+        // TODO 2021-07-01 use _t to try to force the type
+        match String::from_utf8(contents.to_vec()) {
+            Ok(mut contents) if typ != Typ::Bytes => {
+                if self.config.add_newlines && contents.ends_with('\n') {
+                    contents.truncate(contents.len() - 1);
+                }
+                if self.config.trim {
+                    contents = contents.trim().into();
+                }
+                U::from_string(typ, contents, &self.config)
+            }
+            Ok(_) | Err(_) => U::from_bytes(contents, &self.config),
+        }
+    }
+
+    /// Does the list directory `dir` hold its compound elements as files (see
+    /// `Config::elements_as_files`)?
+    fn holds_element_files(&self, dir: u64) -> bool {
+        match self.config.elements_as_files {
+            // while it's being resolved, the list is still `Entry::Lazy`
+            Some(depth) => {
+                matches!(
+                    self.inodes.get(dir as usize),
+                    Some(Some(Inode {
+                        entry: Entry::Directory(DirType::List, _) | Entry::Lazy(_),
+                        ..
+                    }))
+                ) && self.ancestry(dir).len() > depth
+            }
+            None => false,
+        }
+    }
+
+    /// A file holding the list element `v`, serialized in the input format
+    /// (see `Config::elements_as_files`).
+    fn element_file(&self, v: V) -> Entry<V> {
+        let mut contents = Vec::new();
+        if let Err(e) = v.to_writer(&mut contents, &self.config) {
+            warn!("Couldn't write out a list element: {}.", e);
+        }
+        if self.config.add_newlines && !contents.ends_with(b"\n") {
+            contents.push(b'\n');
+        }
+        Entry::File(Typ::String, contents)
+    }
+
+    /// Parses the file `inum` back into a list element, if it's in a list
+    /// that holds its elements as files and it holds a list or map.
+    fn element_value(&self, inum: u64, contents: &[u8]) -> Option<V> {
+        let parent = self.inodes[inum as usize].as_ref()?.parent;
+        if !self.holds_element_files(parent) {
+            return None;
+        }
+
+        let reader = Box::new(std::io::Cursor::new(contents.to_vec()));
+        match V::from_reader(reader, &self.config) {
+            Ok(v) if v.kind() == FileType::Directory => Some(v),
+            Ok(_) => None,
+            Err(e) => {
+                warn!(
+                    "Couldn't parse list element (inode {}): {}; saving it as a string.",
+                    inum, e
+                );
+                None
+            }
+        }
+    }
+
+    /// Saves a typed leaf (see `FS::typed_leaf`).
+    fn typed_leaf_value<U>(&self, typ: Typ, value: u64) -> U
+    where
+        U: Nodelike,
+    {
+        match &self.inodes[value as usize].as_ref().unwrap().entry {
+            Entry::File(_, contents) => self.file_value(typ, contents),
+            _ => unreachable!("typed leaf values are files"),
+        }
+    }
+
+    /// Allocates an inode. Doesn't set the dirty bit, since resolving lazy
+    /// values allocates inodes without changing anything.
+    fn fresh_inode(
+        &mut self,
+        parent: u64,
+        name: &str,
+        entry: Entry<V>,
+        uid: u32,
+        gid: u32,
+        mode: u32,
+    ) -> u64 {
+        let inum = self.inodes.len() as u64;
+        let mode = (mode & 0o777) as u16;
+
+        let mut inode = Inode::with_mode(parent, inum, entry, uid, gid, mode);
+        inode.name = name.into();
+        inode.generation = self.generation;
+        self.inodes.push(Some(inode));
+        self.live_inodes += 1;
+
+        inum
+    }
+
+    /// Under `Config::case_insensitive`, the name in the directory `parent`
+    /// matching `filename` but for case, if none matches exactly; otherwise,
+    /// just `filename`.
+    fn canonical_name(&self, parent: u64, filename: &str) -> String {
+        if !self.config.case_insensitive {
+            return filename.into();
+        }
+
+        if let Some(Some(Inode {
+            entry: Entry::Directory(_, files),
+            ..
+        })) = self.inodes.get(parent as usize)
+        {
+            if !files.contains_key(filename) {
+                let lower = filename.to_lowercase();
+                if let Some(name) = files.keys().find(|name| name.to_lowercase() == lower) {
+                    return name.clone();
+                }
+            }
+        }
+        filename.into()
+    }
+
+    /// Finds `filename` in the directory `parent`, resolving it.
+    fn lookup_name(&mut self, parent: u64, filename: &str) -> Result<u64, FSError> {
+        if self.is_info_file_name(parent, filename) {
+            return Ok(self.info_file(parent).unwrap());
+        }
+        if self.is_length_file_name(parent, filename) {
+            return self
+                .length_file(parent)
+                .ok_or_else(|| FSError::NotFound(filename.into()));
+        }
+
+        self.get(parent)?;
+        let filename = self.canonical_name(parent, filename);
+        let inum = match &self.get(parent)?.entry {
+            Entry::Directory(_kind, files) => match files.get(&filename) {
+                None => return Err(FSError::NotFound(filename)),
+                Some(DirEntry { inum, .. }) => *inum,
+            },
+            _ => return Err(FSError::NotADirectory(parent)),
+        };
+
+        self.get(inum)?;
+        Ok(inum)
+    }
+
+    /// New names can't be longer than `Config::name_max`, in bytes.
+    fn check_name_length(&self, name: &str) -> Result<(), FSError> {
+        if name.len() > self.config.name_max as usize {
+            return Err(FSError::NameTooLong(name.into()));
+        }
+        Ok(())
+    }
+
+    /// Moves the element `src` of the list `inum` to the position of the
+    /// element `tgt`, per `Config::list_rename`. The list keeps the same
+    /// names; they're given to the elements in their new order. Returns
+    /// `false` (changing nothing) unless `inum` is a list and `src` and `tgt`
+    /// both name elements of it.
+    ///
+    /// The kernel only hears about `src` and `tgt`, so under
+    /// `ListRename::Shift` it may keep the other names it has cached pointing
+    /// to their old elements until `Config::cache_ttl` runs out.
+    fn reorder_list(&mut self, inum: u64, src: &str, tgt: &str) -> bool {
+        let policy = self.config.list_rename;
+        let files = match self.get_mut(inum) {
+            Ok(Inode {
+                entry: Entry::Directory(DirType::List, files),
+                ..
+            }) => files,
+            _ => return false,
+        };
+
+        let is_element = |name: &str| !name.is_empty() && name.bytes().all(|b| b.is_ascii_digit());
+        if !is_element(src) || !is_element(tgt) {
+            return false;
+        }
+        if !files.contains_key(src) || !files.contains_key(tgt) {
+            return false;
+        }
+
+        let renamed = match policy {
+            ListRename::Swap => {
+                let moved = files.remove(src).unwrap();
+                let displaced = files.insert(tgt.into(), moved).unwrap();
+                files.insert(src.into(), displaced);
+                vec![src.to_string(), tgt.to_string()]
+            }
+            ListRename::Shift => {
+                // the same order `FS::as_value` saves them in
+                let mut names = files
+                    .keys()
+                    .filter(|name| is_element(name))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                names.sort_unstable_by(|name1, name2| {
+                    format::element_key(name1).cmp(&format::element_key(name2))
+                });
+                let from = names.iter().position(|name| name == src).unwrap();
+                let to = names.iter().position(|name| name == tgt).unwrap();
+
+                let mut elements = names
+                    .iter()
+                    .map(|name| files.remove(name).unwrap())
+                    .collect::<Vec<_>>();
+                let moved = elements.remove(from);
+                elements.insert(to, moved);
+                files.extend(names.iter().cloned().zip(elements));
+                names
+            }
+        };
+        let renamed = renamed
+            .into_iter()
+            .map(|name| (files[&name].inum, name))
+            .collect::<Vec<_>>();
+        for (child, name) in renamed {
+            if let Ok(inode) = self.get_mut(child) {
+                inode.name = name;
+            }
+        }
+        info!("moved element '{}' to '{}' in list {}", src, tgt, inum);
+        true
+    }
+
+    /// Makes a new, empty regular file or named directory (per `kind`) called
+    /// `filename` in `parent`.
+    fn make_node(
+        &mut self,
+        parent: u64,
+        filename: &str,
+        kind: FileType,
+        uid: u32,
+        gid: u32,
+        mode: u32,
+    ) -> Result<u64, FSError> {
+        self.check_name_length(filename)?;
+        if self.is_synthetic_name(parent, filename) {
+            return Err(FSError::Exists(filename.into()));
+        }
+
+        // make sure the parent exists, is a directory, and doesn't have that file
+        self.get(parent)?;
+        let existing = self.canonical_name(parent, filename);
+        match &self.get(parent)?.entry {
+            Entry::File(..) | Entry::Link(..) => return Err(FSError::NotADirectory(parent)),
+            Entry::Directory(_dirtype, files) => {
+                if files.contains_key(&existing) {
+                    return Err(FSError::Exists(filename.into()));
+                }
+            }
+            Entry::Lazy(..) => unreachable!("unresolved lazy value in mknod"),
+        };
+
+        if self.inodes_exhausted() {
+            return Err(FSError::NoSpace);
+        }
+
+        // a full filesystem has no room for new files, even empty ones
+        if kind == FileType::RegularFile && self.out_of_space(1) {
+            return Err(FSError::NoSpace);
+        }
+
+        // create the inode entry
+        let entry = match kind {
+            FileType::RegularFile => Entry::File(Typ::Auto, Vec::new()),
+            FileType::Directory => Entry::Directory(self.config.new_dir_type, HashMap::new()),
+            _ => unreachable!("make_node only makes regular files and directories"),
+        };
+
+        // allocate the inode
+        let inum = self.fresh_inode(parent, filename, entry, uid, gid, mode);
+        self.dirty.set(true);
+
+        // update the parent
+        // NB we can't get_mut the parent earlier due to borrowing restrictions
+        match self.get_mut(parent) {
+            Err(_e) => unreachable!("error finding parent again"),
+            Ok(inode) => match &mut inode.entry {
+                Entry::File(..) | Entry::Link(..) => {
+                    unreachable!("parent changed to a regular file")
+                }
+                Entry::Directory(_dirtype, files) => {
+                    files.insert(
+                        filename.into(),
+                        DirEntry {
+                            kind,
+                            original_name: None,
+                            nesting: Vec::new(),
+                            split: false,
+                            inum,
+                        },
+                    );
+                }
+                Entry::Lazy(..) => unreachable!("unresolved lazy value in mknod"),
+            },
+        };
+        self.touch_dir(parent);
+
+        Ok(inum)
+    }
+
+    /// Records that the entries of the directory `dir` just changed, bumping
+    /// its mtime and ctime: tools like `make` and `rsync` look at directory
+    /// mtimes to notice added and removed files.
+    fn touch_dir(&mut self, dir: u64) {
+        self.touch(dir);
+    }
+
+    /// Records that the contents of `inum` just changed, bumping its mtime
+    /// and ctime.
+    fn touch(&mut self, inum: u64) {
+        if let Ok(inode) = self.get_mut(inum) {
+            let now = SystemTime::now();
+            inode.mtime = now;
+            inode.ctime = now;
+        }
+    }
+
+    /// Records that the metadata of `inum` (its mode, owner, times, or place
+    /// in the tree) just changed, bumping its ctime but not its mtime.
+    fn touch_metadata(&mut self, inum: u64) {
+        if let Ok(inode) = self.get_mut(inum) {
+            inode.ctime = SystemTime::now();
+        }
+    }
+
+    /// Records that `inum` was just read, bumping its atime---unless
+    /// `Config::noatime` is set.
+    fn touch_atime(&mut self, inum: u64) {
+        if self.config.noatime {
+            return;
+        }
+        if let Ok(inode) = self.get_mut(inum) {
+            inode.atime = SystemTime::now();
+        }
+    }
+
+    /// Writes `data` into the file `ino` at `offset` (or at the end, when
+    /// `append`ing), growing it as needed.
+    ///
+    /// Like other filesystems, writing past the end of the file fills the gap
+    /// with zeroes. Those NULs will be saved as part of the value, so we warn
+    /// unless the file holds bytes.
+    fn write_at(
+        &mut self,
+        ino: u64,
+        offset: usize,
+        data: &[u8],
+        append: bool,
+    ) -> Result<(), FSError> {
+        let end = if append {
+            self.get(ino)?.entry.size()
+        } else {
+            offset as u64
+        };
+        let growth = self.growth(ino, end + data.len() as u64);
+        if self.out_of_space(growth) {
+            return Err(FSError::NoSpace);
+        }
+
+        let max_file_size = self.config.max_file_size;
+        let (typ, contents) = match &mut self.get_mut(ino)?.entry {
+            Entry::File(t, contents) => (*t, contents),
+            Entry::Directory(_, _) => return Err(FSError::IsADirectory(ino)),
+            Entry::Link(..) => return Err(FSError::NotAFile(ino)),
+            Entry::Lazy(..) => unreachable!("unresolved lazy value in write"),
+        };
+
+        let offset = if append { contents.len() } else { offset };
+        let before = contents.len();
+
+        // make space
+        if matches!(max_file_size, Some(max) if (offset + data.len()) as u64 > max) {
+            return Err(FSError::FileTooBig);
+        }
+        if offset > contents.len() && typ != Typ::Bytes {
+            warn!(
+                "Writing at offset {} of inode {}, past its end ({} bytes); the gap will be saved as NUL bytes in its {} value.",
+                offset,
+                ino,
+                contents.len(),
+                typ
+            );
+        }
+        if offset + data.len() > contents.len() {
+            contents.resize(offset + data.len(), 0);
+        }
+
+        // actually write
+        contents[offset..offset + data.len()].copy_from_slice(data);
+        self.used_bytes += (contents.len() - before) as u64;
+        self.dirty.set(true);
+        self.touch(ino);
+
+        Ok(())
+    }
+
+    /// Changes the permission bits of `ino` to `mode`.
+    fn chmod(&mut self, ino: u64, mode: u32) -> Result<(), FSError> {
+        if mode != mode & 0o777 {
+            info!("truncating mode {:o} to {:o}", mode, mode & 0o777);
+        }
+
+        let inode = self.get_mut(ino)?;
+        inode.mode = (mode as u16) & 0o777;
+        inode.ctime = SystemTime::now();
+        Ok(())
+    }
+
+    /// Changes the owner and/or group of `ino` on behalf of the user
+    /// `caller`.
+    fn chown(
+        &mut self,
+        caller: u32,
+        ino: u64,
+        uid: Option<u32>,
+        gid: Option<u32>,
+    ) -> Result<(), FSError> {
+        // cribbing from https://github.com/cberner/fuser/blob/13557921548930afd6b70e109521044fea98c23b/examples/simple.rs#L594-L639
+
+        // gotta be a member of the target group!
+        if let Some(gid) = gid {
+            if caller != 0 && !groups_for(caller).contains(&gid) {
+                return Err(FSError::NotPermitted);
+            }
+        }
+
+        let inode = self.get_mut(ino)?;
+
+        // non-root owner can only do noop uid changes
+        if let Some(uid) = uid {
+            if caller != 0 && !(uid == inode.uid && caller == inode.uid) {
+                return Err(FSError::NotPermitted);
+            }
+        }
+
+        // only owner may change the group
+        if gid.is_some() && caller != 0 && caller != inode.uid {
+            return Err(FSError::NotPermitted);
+        }
+
+        // NB if we allowed SETUID/SETGID bits, we might need to clear them here
+        if let Some(uid) = uid {
+            inode.uid = uid;
+        }
+
+        if let Some(gid) = gid {
+            inode.gid = gid;
+        }
+
+        inode.ctime = SystemTime::now();
+        Ok(())
+    }
+
+    /// Truncates the file `ino` to `size` bytes, or extends it with zeroes.
+    fn truncate(&mut self, ino: u64, size: u64) -> Result<(), FSError> {
+        if self.too_big(size) {
+            return Err(FSError::FileTooBig);
+        }
+
+        let growth = self.growth(ino, size);
+        if self.out_of_space(growth) {
+            return Err(FSError::NoSpace);
+        }
+
+        let before = match &mut self.get_mut(ino)?.entry {
+            Entry::File(_t, contents) => {
+                let before = contents.len() as u64;
+                contents.resize(size as usize, 0);
+                before
+            }
+            Entry::Directory(..) => return Err(FSError::IsADirectory(ino)),
+            Entry::Link(..) => return Err(FSError::NotAFile(ino)),
+            Entry::Lazy(..) => unreachable!("unresolved lazy value in truncate"),
+        };
+        self.used_bytes = self.used_bytes.saturating_sub(before) + size;
+
+        self.dirty.set(true);
+        self.touch(ino);
+        Ok(())
+    }
+
+    /// Sets the access and/or modification times of `ino`.
+    fn set_times(
+        &mut self,
+        ino: u64,
+        atime: Option<TimeOrNow>,
+        mtime: Option<TimeOrNow>,
+    ) -> Result<(), FSError> {
+        let now = SystemTime::now();
+        let time = |t| match t {
+            TimeOrNow::Now => now,
+            TimeOrNow::SpecificTime(t) => t,
+        };
+
+        let inode = self.get_mut(ino)?;
+        if let Some(atime) = atime {
+            inode.atime = time(atime);
+        }
+        if let Some(mtime) = mtime {
+            inode.mtime = time(mtime);
+        }
+        inode.ctime = now;
+        Ok(())
+    }
+
+    /// Grows the file `ino` to hold `length` bytes at `offset`, and/or zeroes
+    /// that range, as `fallocate(2)`'s `mode` says.
+    fn allocate(&mut self, ino: u64, offset: i64, length: i64, mode: i32) -> Result<(), FSError> {
+        if offset < 0 || length <= 0 {
+            return Err(FSError::InvalidArgument);
+        }
+
+        if mode & !(FALLOC_FL_KEEP_SIZE | FALLOC_FL_PUNCH_HOLE | FALLOC_FL_ZERO_RANGE) != 0 {
+            return Err(FSError::Unsupported);
+        }
+
+        // punching a hole never changes the size, and can't be combined with
+        // zeroing a range
+        if mode & FALLOC_FL_PUNCH_HOLE != 0
+            && (mode & FALLOC_FL_KEEP_SIZE == 0 || mode & FALLOC_FL_ZERO_RANGE != 0)
+        {
+            return Err(FSError::InvalidArgument);
+        }
+        let keep_size = mode & FALLOC_FL_KEEP_SIZE != 0;
+        let zero = mode & (FALLOC_FL_PUNCH_HOLE | FALLOC_FL_ZERO_RANGE) != 0;
+
+        if !keep_size && self.too_big((offset + length) as u64) {
+            return Err(FSError::FileTooBig);
+        }
+
+        if !keep_size {
+            let growth = self.growth(ino, (offset + length) as u64);
+            if self.out_of_space(growth) {
+                return Err(FSError::NoSpace);
+            }
+        }
+
+        // load the contents
+        let contents = match &mut self.get_mut(ino)?.entry {
+            Entry::File(_t, contents) => contents,
+            Entry::Directory(..) => return Err(FSError::IsADirectory(ino)),
+            Entry::Link(..) => return Err(FSError::NotAFile(ino)),
+            Entry::Lazy(..) => unreachable!("unresolved lazy value in fallocate"),
+        };
+
+        // we don't have holes, so punching one just zeroes the range
+        let end = (offset + length) as usize;
+        if zero {
+            let start = std::cmp::min(offset as usize, contents.len());
+            let stop = std::cmp::min(end, contents.len());
+            contents[start..stop].fill(0);
+        }
+
+        // extend the vector
+        if !keep_size && end > contents.len() {
+            let before = contents.len();
+            contents.resize(end, 0);
+            self.used_bytes += (end - before) as u64;
+        }
+
+        self.dirty.set(true);
+        self.touch(ino);
+        Ok(())
+    }
+
+    /// The value of the extended attribute `name` on `ino`. Only the type
+    /// attributes (see `TYPE_XATTRS`) exist.
+    fn get_xattr(&mut self, ino: u64, name: &OsStr) -> Result<Vec<u8>, FSError> {
+        let file = self.get(ino)?;
+
+        if !is_type_xattr(name) {
+            return Err(FSError::NoAttribute(name.to_string_lossy().into()));
+        }
+        Ok(file.entry.typ().into_bytes())
+    }
+
+    /// Sets the extended attribute `name` on `ino`, i.e., changes its type.
+    fn set_xattr(&mut self, ino: u64, name: &OsStr, value: &[u8]) -> Result<(), FSError> {
+        let file = self.get_mut(ino)?;
+
+        if !is_type_xattr(name) {
+            return Err(FSError::Unsupported);
+        }
+
+        let typ = std::str::from_utf8(value).map_err(|_| FSError::InvalidArgument)?;
+        if !file.entry.try_set_typ(typ) {
+            return Err(FSError::InvalidArgument);
+        }
+
+        self.dirty.set(true);
+        Ok(())
+    }
+
+    /// Removes the extended attribute `name` from `ino`.
+    fn remove_xattr(&mut self, ino: u64, name: &OsStr) -> Result<(), FSError> {
+        let file = self.get_mut(ino)?;
+
+        if !is_type_xattr(name) {
+            return Err(FSError::NoAttribute(name.to_string_lossy().into()));
+        }
+
+        // removing a file's type makes it a string again; directories and
+        // links always have their type
+        match &mut file.entry {
+            Entry::File(typ, _) => *typ = Typ::String,
+            _ => return Err(FSError::AccessDenied),
+        }
+
+        self.dirty.set(true);
+        Ok(())
+    }
+
+    /// Removes `filename` from the directory `parent`: a regular file or
+    /// link, or, when `dir` is set, an empty directory.
+    fn remove_entry(&mut self, parent: u64, filename: &str, dir: bool) -> Result<(), FSError> {
+        let filename = &self.canonical_name(parent, filename);
+
+        // find the parent
+        let (kind, inum) = match &self.get(parent)?.entry {
+            Entry::Directory(_dirtype, files) => match files.get(filename) {
+                Some(DirEntry { kind, inum, .. }) => (*kind, *inum),
+                None => return Err(FSError::NotFound(filename.into())),
+            },
+            Entry::File(..) | Entry::Link(..) => return Err(FSError::NotADirectory(parent)),
+            Entry::Lazy(..) => unreachable!("unresolved lazy value in remove_entry"),
+        };
+
+        // make sure it's the right kind of thing---and empty, if it's a
+        // directory
+        match (kind, dir) {
+            (FileType::Directory, false) => return Err(FSError::IsADirectory(inum)),
+            (FileType::Directory, true) => match &self.get(inum)?.entry {
+                Entry::Directory(_, files) if !files.is_empty() => {
+                    return Err(FSError::NotEmpty(inum))
+                }
+                Entry::Directory(..) => (),
+                _ => unreachable!("mismatched metadata on inode {} in parent {}", inum, parent),
+            },
+            (_, true) => return Err(FSError::NotADirectory(inum)),
+            (_, false) => (),
+        }
+
+        // remove it
+        match &mut self.get_mut(parent)?.entry {
+            Entry::Directory(_dirtype, files) => files.remove(filename),
+            _ => unreachable!("parent changed to a regular file"),
+        };
+        self.remove_inode(inum);
+        self.dirty.set(true);
+        self.touch_dir(parent);
+        Ok(())
+    }
+
+    /// Moves `src` in the directory `parent` to `tgt` in `newparent`,
+    /// replacing whatever is there.
+    fn rename_entry(
+        &mut self,
+        parent: u64,
+        src: &str,
+        newparent: u64,
+        tgt: &str,
+    ) -> Result<(), FSError> {
+        if src == "." || src == ".." {
+            return Err(FSError::InvalidName(src.into()));
+        }
+
+        self.check_name_length(tgt)?;
+
+        if self.is_synthetic_name(newparent, tgt) {
+            return Err(FSError::AccessDenied);
+        }
+
+        let src = &self.canonical_name(parent, src);
+        // changing only the case of a name is a real rename
+        let existing = self.canonical_name(newparent, tgt);
+        let tgt = if parent == newparent && &existing == src {
+            tgt
+        } else {
+            &existing
+        };
+
+        // make sure src exists
+        let (src_kind, src_original, src_nesting, src_split, src_inum) =
+            match &self.get(parent)?.entry {
+                Entry::Directory(_kind, files) => match files.get(src) {
+                    Some(DirEntry {
+                        kind,
+                        original_name,
+                        nesting,
+                        split,
+                        inum,
+                    }) => (*kind, original_name.clone(), nesting.clone(), *split, *inum),
+                    None => return Err(FSError::NotFound(src.into())),
+                },
+                _ => return Err(FSError::NotADirectory(parent)),
+            };
+
+        // renaming something to itself is a no-op
+        if parent == newparent && src == tgt {
+            return Ok(());
+        }
+
+        // renaming one list element onto another reorders the list
+        if parent == newparent && self.reorder_list(parent, src, tgt) {
+            self.dirty.set(true);
+            self.touch_dir(parent);
+            self.touch_metadata(src_inum);
+            return Ok(());
+        }
+
+        // a directory can't be moved into itself or one of its descendants
+        if src_kind == FileType::Directory && self.ancestry(newparent).contains(&src_inum) {
+            return Err(FSError::InvalidArgument);
+        }
+
+        // determine whether tgt exists
+        let tgt_info = match &self.get(newparent)?.entry {
+            Entry::Directory(_kind, files) => match files.get(tgt) {
+                Some(DirEntry { kind, inum, .. }) => {
+                    if *kind == FileType::Directory && src_kind != FileType::Directory {
+                        return Err(FSError::IsADirectory(*inum));
+                    }
+                    if src_kind != *kind {
+                        return Err(FSError::NotADirectory(*inum));
+                    }
+                    Some((*kind, *inum))
+                }
+                None => None,
+            },
+            _ => return Err(FSError::NotADirectory(newparent)),
+        };
+
+        // if tgt exists and is a directory, make sure it's empty
+        if let Some((FileType::Directory, tgt_inum)) = tgt_info {
+            match &self.get(tgt_inum)?.entry {
+                Entry::Directory(_type, files) => {
+                    if !files.is_empty() {
+                        return Err(FSError::NotEmpty(tgt_inum));
+                    }
+                }
+                _ => unreachable!("bad metadata on inode {} in {}", tgt_inum, newparent),
+            }
+        }
+
+        // remove src from parent
+        match self.get_mut(parent) {
+            Ok(Inode {
+                entry: Entry::Directory(_kind, files),
+                ..
+            }) => files.remove(src),
+            _ => unreachable!("parent changed"),
+        };
+
+        // add src as tgt to newparent, replacing tgt
+        let replaced = match self.get_mut(newparent) {
+            Ok(Inode {
+                entry: Entry::Directory(_kind, files),
+                ..
+            }) => files.insert(
+                tgt.into(),
+                DirEntry {
+                    kind: src_kind,
+                    // if the filename is the same, we'll keep the source
+                    // original filename (if it exists; otherwise we overwrite
+                    // it)
+                    original_name: if src == tgt { src_original } else { None },
+                    nesting: if src == tgt { src_nesting } else { Vec::new() },
+                    split: src == tgt && src_split,
+                    inum: src_inum,
+                },
+            ),
+            _ => unreachable!("parent changed"),
+        };
+        if let Some(replaced) = replaced {
+            self.remove_inode(replaced.inum);
+        }
+
+        // set src's parent inode and name
+        match self.get_mut(src_inum) {
+            Ok(inode) => {
+                inode.parent = newparent;
+                inode.name = tgt.into();
+            }
+            Err(_) => unreachable!(
+                "missing inode {} moved from {} to {}",
+                src_inum, parent, newparent
+            ),
+        }
+
+        self.dirty.set(true);
+        self.touch_dir(parent);
+        self.touch_dir(newparent);
+        self.touch_metadata(src_inum);
+        Ok(())
+    }
+
+    /// The contents of the file `ino`.
+    fn file_contents(&mut self, ino: u64) -> Result<&[u8], FSError> {
+        self.refresh_synthetic(ino);
+        match &self.get(ino)?.entry {
+            Entry::File(_t, s) => Ok(s),
+            Entry::Directory(..) => Err(FSError::IsADirectory(ino)),
+            Entry::Link(..) => Err(FSError::NotAFile(ino)),
+            Entry::Lazy(..) => unreachable!("unresolved lazy value in file_contents"),
+        }
+    }
+
+    /// Finds the inode at `path`, relative to the root (e.g., `"db/host"`),
+    /// resolving it.
+    fn resolve_path(&mut self, path: &str) -> Result<u64, FSError> {
+        self.check_watch();
+
+        let mut inum = fuser::FUSE_ROOT_ID;
+        for name in path.split('/').filter(|name| !name.is_empty()) {
+            inum = self.lookup_name(inum, name)?;
+        }
+        Ok(inum)
+    }
+
+    /// Looks up the inode at `path`, relative to the root (e.g., `"db/host"`,
+    /// or `""` for the root itself), loading lazy values along the way. A
+    /// missing component is `FSError::NotFound`; a path that runs through a
+    /// file is `FSError::NotADirectory`.
+    ///
+    /// ```
+    /// use ffs::config::Config;
+    /// use ffs::format::{json, Nodelike};
+    /// use ffs::fs::{FSError, FS};
+    ///
+    /// let config = Config::default();
+    /// let input = r#"{"db": {"host": "localhost"}}"#;
+    /// let v = json::Value::from_reader(Box::new(input.as_bytes()), &config).unwrap();
+    /// let mut fs = FS::from_value(config, v);
+    ///
+    /// let db = fs.lookup_path("db").unwrap().inum;
+    /// assert_eq!(fs.lookup_path("db/host").unwrap().parent, db);
+    /// assert_eq!(
+    ///     fs.lookup_path("db/port").unwrap_err(),
+    ///     FSError::NotFound("port".into())
+    /// );
+    /// assert!(matches!(
+    ///     fs.lookup_path("db/host/name"),
+    ///     Err(FSError::NotADirectory(_))
+    /// ));
+    /// ```
+    pub fn lookup_path(&mut self, path: &str) -> Result<&Inode<V>, FSError> {
+        let inum = self.resolve_path(path)?;
+        self.get(inum)
+    }
+
+    /// Lists the directory at `path`, relative to the root, as `(inum, name)`
+    /// pairs, just as `readdir` on the mount would: `.` and `..` come first,
+    /// and the root is its own parent.
+    ///
+    /// ```
+    /// use ffs::config::Config;
+    /// use ffs::format::{json, Nodelike};
+    /// use ffs::fs::FS;
+    ///
+    /// let config = Config::default();
+    /// let input = r#"{"db": {"host": "localhost"}}"#;
+    /// let v = json::Value::from_reader(Box::new(input.as_bytes()), &config).unwrap();
+    /// let mut fs = FS::from_value(config, v);
+    ///
+    /// let root = fs.list_path("").unwrap();
+    /// assert_eq!(root[..2], [(1, ".".into()), (1, "..".into())]);
+    ///
+    /// let db = fs.lookup_path("db").unwrap().inum;
+    /// let listing = fs.list_path("db").unwrap();
+    /// assert_eq!(listing[..2], [(db, ".".into()), (1, "..".into())]);
+    /// assert!(listing.iter().any(|(_inum, name)| name == "host"));
+    /// ```
+    pub fn list_path(&mut self, path: &str) -> Result<Vec<(u64, String)>, FSError> {
+        let inum = self.resolve_path(path)?;
+        let entries = self.dir_listing(inum)?;
+        Ok(entries
+            .into_iter()
+            .map(|(inum, _kind, name)| (inum, name))
+            .collect())
+    }
+
+    /// Reads the file at `path`, relative to the root, just as reading it
+    /// through the mount would. Returns `None` if `path` doesn't name a
+    /// regular file.
+    pub fn read_path(&mut self, path: &str) -> Option<&[u8]> {
+        let inum = self.resolve_path(path).ok()?;
+        self.file_contents(inum).ok()
+    }
+
+    /// Sets the contents of the file at `path`, relative to the root, creating
+    /// the file if need be (but not its directory). Changes are saved by
+    /// `FS::sync`. `FSError::errno` gives the error the mount would report.
+    pub fn write_path(&mut self, path: &str, data: &[u8]) -> Result<(), FSError> {
+        if self.config.read_only {
+            return Err(FSError::ReadOnly);
+        }
+
+        let (dir, filename) = match path.trim_end_matches('/').rsplit_once('/') {
+            Some((dir, filename)) => (self.resolve_path(dir)?, filename),
+            None => (fuser::FUSE_ROOT_ID, path),
+        };
+        if !self.config.valid_name(filename) {
+            return Err(FSError::InvalidName(filename.into()));
+        }
+
+        let inum = match self.lookup_name(dir, filename) {
+            Ok(inum) => inum,
+            Err(FSError::NotFound(_)) => {
+                let (uid, gid) = (self.config.uid, self.config.gid);
+                let mode = self.config.filemode as u32;
+                self.make_node(dir, filename, FileType::RegularFile, uid, gid, mode)?
+            }
+            Err(e) => return Err(e),
+        };
+        if self.is_synthetic(inum) {
+            return Err(FSError::AccessDenied);
+        }
+
+        // like opening with `O_TRUNC`
+        if let Entry::File(_t, contents) = &mut self.get_mut(inum)?.entry {
+            let before = contents.len() as u64;
+            contents.clear();
+            self.used_bytes = self.used_bytes.saturating_sub(before);
+        }
+        self.write_at(inum, 0, data, false)
+    }
+
+    /// Walks the filesystem in pre-order, yielding each inode with its path
+    /// relative to the root (e.g., `"db/host"`, or `""` for the root itself),
+    /// as `FS::read_path` takes it. Entries are visited in name order. Subtrees
+    /// that haven't been loaded yet show up as a single `Entry::Lazy` inode;
+    /// set `Config::eager` to load everything up front.
+    ///
+    /// ```
+    /// use ffs::config::Config;
+    /// use ffs::format::{json, Nodelike};
+    /// use ffs::fs::FS;
+    ///
+    /// let mut config = Config::default();
+    /// config.eager = true;
+    /// let input = r#"{"db": {"host": "localhost"}, "tags": ["a"]}"#;
+    /// let v = json::Value::from_reader(Box::new(input.as_bytes()), &config).unwrap();
+    /// let fs = FS::from_value(config, v);
+    ///
+    /// let paths = fs.walk().map(|(path, _inode)| path).collect::<Vec<_>>();
+    /// assert_eq!(paths, ["", "db", "db/host", "tags", "tags/0"]);
+    /// ```
+    pub fn walk(&self) -> impl Iterator<Item = (String, &Inode<V>)> + '_ {
+        // an explicit stack, so deep documents can't overflow the real one
+        let mut stack = vec![(String::new(), fuser::FUSE_ROOT_ID)];
+        std::iter::from_fn(move || loop {
+            let (path, inum) = stack.pop()?;
+            let inode = match self.inodes.get(inum as usize) {
+                Some(Some(inode)) => inode,
+                _ => continue,
+            };
+
+            if let Entry::Directory(typ, files) = &inode.entry {
+                let mut files = files.iter().collect::<Vec<_>>();
+                match typ {
+                    DirType::Named => files.sort_unstable_by_key(|(name, _)| *name),
+                    DirType::List => {
+                        files.sort_unstable_by_key(|(name, _)| format::element_key(name))
+                    }
+                }
+                // pushed in reverse, so the first entry is visited first
+                for (name, de) in files.into_iter().rev() {
+                    let path = if path.is_empty() {
+                        name.clone()
+                    } else {
+                        format!("{}/{}", path, name)
+                    };
+                    stack.push((path, de.inum));
+                }
+            }
+            return Some((path, inode));
+        })
+    }
+
+    /// Lists a directory, including `.` and `..`, as `(inum, kind, name)`
+    /// triples.
+    fn dir_listing(&mut self, ino: u64) -> Result<Vec<(u64, FileType, String)>, FSError> {
+        let inode = self.get(ino)?;
+
+        let mut entries = match &inode.entry {
+            Entry::File(..) | Entry::Link(..) => return Err(FSError::NotADirectory(ino)),
+            Entry::Directory(_kind, files) => {
+                // as at the root of any mount, the root's `..` is the root
+                let parent = if ino == fuser::FUSE_ROOT_ID {
+                    debug_assert_eq!(inode.parent, ino, "the root must be its own parent");
+                    ino
+                } else {
+                    inode.parent
+                };
+
+                let mut entries = Vec::with_capacity(files.len() + 3);
+                entries.push((ino, FileType::Directory, ".".into()));
+                entries.push((parent, FileType::Directory, "..".into()));
+                entries.extend(files.iter().map(|(filename, DirEntry { inum, kind, .. })| {
+                    (*inum, *kind, filename.clone())
+                }));
+                entries
+            }
+            Entry::Lazy(..) => unreachable!("unresolved lazy value in dir_listing"),
+        };
+
+        if let Some(inum) = self.length_file(ino) {
+            let name = self.config.list_length_file.clone().unwrap();
+            entries.push((inum, FileType::RegularFile, name));
+        }
+        if let Some(inum) = self.info_file(ino) {
+            entries.push((inum, FileType::RegularFile, INFO_FILE.into()));
+        }
+
+        Ok(entries)
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    fn resolve_node(&mut self, inum: u64) -> Result<Option<Vec<u64>>, FSError>
+    where
+        V: Nodelike + std::fmt::Display + Default,
+    {
+        debug!("called");
+
+        let idx = inum as usize;
+
+        if idx >= self.inodes.len() || idx == 0 {
+            return Err(FSError::NoSuchInode(inum));
+        }
+
+        let inode = match &mut self.inodes[idx] {
+            Some(inode) => inode,
+            _ => return Err(FSError::InvalidInode(inum)),
+        };
+
+        let v = match &mut inode.entry {
+            Entry::Directory(..) | Entry::File(..) | Entry::Link(..) => return Ok(Option::None),
+            Entry::Lazy(v) => mem::take(v),
+        };
+        let uid = inode.uid;
+        let gid = inode.gid;
+
+        let (v, include) = self.splice_include(inum, v);
+        let tag = v.tag();
+        let (blob, node) = match self.decode_blob(inum, v.node(&self.config)) {
+            Ok(bytes) => (true, Node::Bytes(bytes)),
+            Err(node) => (false, node),
+        };
+        let (entry, new_nodes) = match node {
+            Node::String(Typ::Null, _) if self.config.null_as == NullAs::Symlink => {
+                (Entry::Link(NULL_LINK.into()), Option::None)
+            }
+            Node::Bytes(b) if self.config.typed_leaves && !blob => {
+                (self.typed_leaf_dir(inum, Typ::Bytes, b), Option::None)
+            }
+            Node::String(t, s) if self.config.typed_leaves => {
+                (self.typed_leaf_dir(inum, t, s.into_bytes()), Option::None)
+            }
+            Node::Bytes(b) => (Entry::File(Typ::Bytes, b), Option::None),
+            Node::String(t, s) => (Entry::File(t, s.into_bytes()), Option::None),
+            Node::Link(pointer) => (Entry::Link(pointer), Option::None),
+            Node::List(vs) => {
+                let elements_as_files = self.holds_element_files(inum);
+                let num_elts = vs.len();
+                let elts = vs
+                    .into_iter()
+                    .enumerate()
+                    // TODO 2021-06-08 ability to add prefixes
+                    .map(|(i, child)| (self.config.element_name(i, num_elts), child))
+                    .collect();
+                let elts = self.exclude_entries(inum, elts);
+                let elts = self.flatten_entries(inum, elts, &HashSet::new());
+
+                let mut children = HashMap::new();
+                children.reserve(elts.len());
+
+                let mut new_nodes = Vec::with_capacity(elts.len());
+                for (key, name, child, nesting) in elts {
+                    let (kind, entry) = if elements_as_files && child.kind() == FileType::Directory
+                    {
+                        // it holds the element's text, not just its leaves
+                        let leaves = format::leaf_bytes(child.clone(), &self.config);
+                        let entry = self.element_file(child);
+                        self.used_bytes = self.used_bytes.saturating_sub(leaves) + entry.size();
+                        (FileType::RegularFile, entry)
+                    } else {
+                        (self.kind_of(&child), Entry::Lazy(child))
+                    };
+                    let child_id = self.fresh_inode(
+                        inum,
+                        &name,
+                        entry,
+                        uid,
+                        gid,
+                        self.config.mode(kind) as u32,
+                    );
+
+                    let original_name = if nesting.is_empty() { None } else { Some(key) };
+
+                    children.insert(
+                        name,
+                        DirEntry {
+                            kind,
+                            original_name,
+                            nesting,
+                            split: false,
+                            inum: child_id,
+                        },
+                    );
+                    new_nodes.push(child_id)
+                }
+
+                (
+                    Entry::Directory(DirType::List, children),
+                    Option::Some(new_nodes),
+                )
+            }
+            Node::Map(fvs) => {
+                let (fvs, split) = self.split_entries(fvs);
+                let fvs = self.exclude_entries(inum, fvs);
+                let fvs = self.flatten_entries(inum, fvs, &split);
+
+                let mut children = HashMap::new();
+                children.reserve(fvs.len());
+
+                // munged names mustn't clobber fields that already have them
+                let mut taken = fvs
+                    .iter()
+                    .filter(|(_, field, ..)| self.config.valid_name(field))
+                    .map(|(_, field, ..)| field.clone())
+                    .collect::<HashSet<_>>();
+                let mut mapped = self.mapped_names(inum, &fvs, &taken);
+                taken.extend(mapped.values().cloned());
+                // under `Config::case_insensitive`, the lowercased names in use
+                let case_insensitive = self.config.case_insensitive;
+                let mut lowered = HashSet::new();
+                let taken_lowered = if case_insensitive {
+                    taken.iter().map(|name| name.to_lowercase()).collect()
+                } else {
+                    HashSet::new()
+                };
+
+                let mut new_nodes = Vec::with_capacity(fvs.len());
+                for (key, field, child, nesting) in fvs.into_iter() {
+                    let original = field.clone();
+
+                    let mapped_name = if nesting.is_empty() {
+                        mapped.remove(&key)
+                    } else {
+                        None
+                    };
+                    let nfield = if let Some(name) = mapped_name {
+                        name
+                    } else if !self.config.valid_name(&original) {
+                        match self.config.munge {
+                            Munge::Rename => {
+                                let mut nfield = self.config.normalize_name(field);
+
+                                while children.contains_key(&nfield) || taken.contains(&nfield) {
+                                    nfield.push('_');
+                                }
+
+                                nfield
+                            }
+                            Munge::Filter => {
+                                warn!("skipping '{}'", field);
+                                continue;
+                            }
+                        }
+                    } else {
+                        field
+                    };
+
+                    let nfield = if case_insensitive && lowered.contains(&nfield.to_lowercase()) {
+                        let mut renamed = nfield.clone();
+                        renamed.push('_');
+                        while children.contains_key(&renamed)
+                            || lowered.contains(&renamed.to_lowercase())
+                            || taken_lowered.contains(&renamed.to_lowercase())
+                        {
+                            renamed.push('_');
+                        }
+                        warn!(
+                            "'{}' differs only in case from another name in inode {}; calling it '{}'.",
+                            nfield, inum, renamed
+                        );
+                        renamed
+                    } else {
+                        nfield
+                    };
+                    // nothing may clobber a name that's already in use
+                    let nfield = if children.contains_key(&nfield) {
+                        let mut renamed = nfield.clone();
+                        renamed.push('_');
+                        while children.contains_key(&renamed) || taken.contains(&renamed) {
+                            renamed.push('_');
+                        }
+                        warn!(
+                            "'{}' is already in use in inode {}; calling it '{}'.",
+                            nfield, inum, renamed
+                        );
+                        renamed
+                    } else {
+                        nfield
+                    };
+                    if case_insensitive {
+                        lowered.insert(nfield.to_lowercase());
+                    }
+
+                    let kind = self.kind_of(&child);
+                    let child_id = self.fresh_inode(
+                        inum,
+                        &nfield,
+                        Entry::Lazy(child),
+                        uid,
+                        gid,
+                        self.config.mode(kind) as u32,
+                    );
+                    if original != nfield {
+                        info!(
+                            "renamed {} to {} (inode {} with parent {})",
+                            original, nfield, child_id, inum
+                        );
+                    }
+                    let split = nesting.is_empty() && split.contains(&key);
+                    let original_name = if key != nfield {
+                        Some(key)
+                    } else {
+                        assert!(self.config.valid_name(&key));
+                        None
+                    };
+
+                    children.insert(
+                        nfield,
+                        DirEntry {
+                            kind,
+                            original_name,
+                            nesting,
+                            split,
+                            inum: child_id,
+                        },
+                    );
+
+                    new_nodes.push(child_id);
+                }
+
+                (
+                    Entry::Directory(DirType::Named, children),
+                    Option::Some(new_nodes),
+                )
+            }
+        };
+
+        let inode = match &mut self.inodes[idx] {
+            Some(inode) => inode,
+            _ => return Err(FSError::InvalidInode(inum)),
+        };
+        inode.entry = entry;
+        inode.blob = blob;
+        inode.include = include;
+        inode.tag = tag;
+
+        if let Some(nodes) = &new_nodes {
+            debug!("new_nodes = {:?}", nodes);
+        }
+
+        Ok(new_nodes)
+    }
+
+    /// Decodes `node`, the value at `inum`, if it's a string whose path
+    /// matches `Config::blob_keys`; otherwise (or if it isn't valid base64),
+    /// gives `node` back.
+    fn decode_blob(&self, inum: u64, node: Node<V>) -> Result<Vec<u8>, Node<V>> {
+        if self.config.blob_keys.is_empty() {
+            return Err(node);
+        }
+
+        match node {
+            Node::String(Typ::String, s) if self.config.blob(&self.document_path(inum)) => {
+                match base64::decode_config(s.trim_end(), self.config.base64) {
+                    Ok(bytes) => Ok(bytes),
+                    Err(e) => {
+                        warn!(
+                            "Couldn't decode the blob at inode {} as base64 ({}); mounting it as a string.",
+                            inum, e
+                        );
+                        Err(Node::String(Typ::String, s))
+                    }
+                }
+            }
+            node => Err(node),
+        }
+    }
+
+    /// If `v`, the value at `inum`, is a string holding `Config::include_tag`
+    /// and a path, reads that file and returns its contents in place of `v`.
+    /// If the file can't be read (or would include itself), `v` is left as is.
+    fn splice_include(&self, inum: u64, v: V) -> (V, Option<Include>) {
+        let tag = match &self.config.include_tag {
+            Some(tag) if v.kind() == FileType::RegularFile => tag,
+            _ => return (v, None),
+        };
+        let reference = match v.clone().node(&self.config) {
+            Node::String(Typ::String, s) => match s.trim_end().strip_prefix(tag.as_str()) {
+                Some(path) if path.starts_with(char::is_whitespace) => path.trim().to_string(),
+                _ => return (v, None),
+            },
+            _ => return (v, None),
+        };
+
+        match self.read_include(inum, &reference) {
+            Ok((file, format, included)) => (
+                included,
+                Some(Include {
+                    reference,
+                    file,
+                    format,
+                }),
+            ),
+            Err(e) => {
+                warn!(
+                    "Couldn't include '{}' at inode {}: {}; leaving it as a string.",
+                    reference, inum, e
+                );
+                (v, None)
+            }
+        }
+    }
+
+    /// Reads the file included at `inum` by `reference`, which is relative to
+    /// the file `inum` comes from. Its format comes from its extension,
+    /// defaulting to the input format.
+    fn read_include(&self, inum: u64, reference: &str) -> Result<(PathBuf, Format, V), String> {
+        let ancestry = self.ancestry(inum);
+
+        // the files `inum` is (transitively) included from, outermost first
+        let mut chain = Vec::new();
+        match &self.config.input {
+            Input::File(file) => chain.extend(file.canonicalize().ok()),
+            Input::Siblings(siblings) => {
+                if let Some(name) = ancestry.get(1).and_then(|dir| self.name_of(*dir)) {
+                    chain.extend(
+                        siblings
+                            .iter()
+                            .find(|sibling| sibling.name == name)
+                            .and_then(|sibling| sibling.file.canonicalize().ok()),
+                    );
+                }
+            }
+            Input::Stdin | Input::Empty | Input::Directory(_) => (),
+        }
+        chain.extend(ancestry.iter().filter_map(|ancestor| {
+            let include = self.inodes[*ancestor as usize].as_ref()?.include.as_ref()?;
+            Some(include.file.clone())
+        }));
+
+        let dir = chain
+            .last()
+            .and_then(|file| file.parent())
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        let file = dir
+            .join(reference)
+            .canonicalize()
+            .map_err(|e| e.to_string())?;
+        if chain.contains(&file) {
+            return Err(format!("{} includes itself", file.display()));
+        }
+        if chain.len() >= MAX_INCLUDE_DEPTH {
+            return Err(format!(
+                "includes can only nest {} files deep",
+                MAX_INCLUDE_DEPTH
+            ));
+        }
+
+        let format = file
+            .extension()
+            .and_then(|s| s.to_str())
+            .and_then(|s| s.parse::<Format>().ok())
+            .unwrap_or(self.config.input_format);
+        let reader = std::fs::File::open(&file).map_err(|e| e.to_string())?;
+        let v =
+            format::read_as(format, Box::new(reader), &self.config).map_err(|e| e.to_string())?;
+        Ok((file, format, v))
+    }
+
+    /// The reference saved in place of the subtree `inum`, if it was spliced
+    /// in by `Config::include_tag`.
+    fn include_reference(&self, inum: u64) -> Option<String> {
+        let include = self.inodes[inum as usize].as_ref()?.include.as_ref()?;
+        let tag = self.config.include_tag.as_ref()?;
+        Some(format!("{} {}", tag, include.reference))
+    }
+
+    /// Encodes the contents of the file `inum` as a base64 string, if it was
+    /// mounted as a blob (see `FS::decode_blob`).
+    fn blob_value<U>(&self, inum: u64, contents: &[u8]) -> Option<U>
+    where
+        U: Nodelike,
+    {
+        match self.inodes[inum as usize].as_ref() {
+            Some(Inode { blob: true, .. }) => Some(U::from_string(
+                Typ::String,
+                base64::encode_config(contents, self.config.base64),
+                &self.config,
+            )),
+            _ => None,
+        }
+    }
+
+    /// The keys leading from the root to `inum`: original names, including
+    /// any directories collapsed by `Config::flatten_single`.
+    fn document_path(&self, inum: u64) -> Vec<String> {
+        let mut path = Vec::new();
+        for pair in self.ancestry(inum).windows(2) {
+            if let Some(Inode {
+                entry: Entry::Directory(_, files),
+                ..
+            }) = &self.inodes[pair[0] as usize]
+            {
+                let name = match &self.inodes[pair[1] as usize] {
+                    Some(child) => &child.name,
+                    None => continue,
+                };
+                // the name should always be up to date, but searching is a safe fallback
+                let found = match files.get(name) {
+                    Some(de) if de.inum == pair[1] => Some((name, de)),
+                    _ => files.iter().find(|(_, de)| de.inum == pair[1]),
+                };
+                if let Some((name, de)) = found {
+                    path.push(de.original_name.as_ref().unwrap_or(name).clone());
+                    path.extend(de.nesting.iter().map(|(_, k)| k.clone()));
+                }
+            }
+        }
+        path
+    }
+
+    /// The names `Config::name_map` gives the fields of the directory `inum`,
+    /// by key. A name that's `taken` by another field (or given twice) isn't
+    /// given; `FS::try_from_value` resolves every renamed directory up front,
+    /// so that's an error at mount time.
+    fn mapped_names(
+        &self,
+        inum: u64,
+        fields: &[FlatEntry<V>],
+        taken: &HashSet<String>,
+    ) -> HashMap<String, String> {
+        let mut mapped = HashMap::new();
+        if self.config.name_map.is_empty() {
+            return mapped;
+        }
+
+        let mut path = self.document_path(inum);
+        let mut names = HashSet::new();
+        for (key, field, _, nesting) in fields {
+            if !nesting.is_empty() {
+                continue;
+            }
+
+            path.push(key.clone());
+            let name = self.config.mapped_name(&path);
+            path.pop();
+            let name = match name {
+                Some(name) => name,
+                None => continue,
+            };
+
+            if (name != field && taken.contains(name)) || !names.insert(name) {
+                warn!(
+                    "`--name-map` can't rename '{}' to '{}' in inode {}: that name is already in use.",
+                    key, name, inum
+                );
+                continue;
+            }
+            info!("renaming '{}' to '{}' in inode {}", key, name, inum);
+            mapped.insert(key.clone(), name.to_string());
+        }
+        mapped
+    }
+
+    /// Sets aside the entries of the directory `inum` that match
+    /// `Config::exclude` (and, under `NullAs::Missing`, its nulls), so they
+    /// never become inodes; `FS::restore_excluded` puts them back when saving.
+    fn exclude_entries(&mut self, inum: u64, entries: Vec<(String, V)>) -> Vec<(String, V)> {
+        let omit_nulls = self.config.null_as == NullAs::Missing;
+        if self.config.exclude.is_empty() && self.config.ignore.is_none() && !omit_nulls {
+            return entries;
+        }
+
+        let mut path = self.document_path(inum);
+        let mut kept = Vec::with_capacity(entries.len());
+        for (key, v) in entries {
+            path.push(key);
+            let excluded = self.config.excluded(&path, v.kind() == FileType::Directory);
+            let key = path.pop().unwrap();
+
+            if excluded {
+                debug!("excluding '{}' from inode {}", key, inum);
+                self.excluded.entry(inum).or_default().push((key, v));
+            } else if omit_nulls && is_null(&v, &self.config) {
+                info!("leaving out '{}' in inode {}, which is null", key, inum);
+                self.excluded.entry(inum).or_default().push((key, v));
+            } else {
+                kept.push((key, v));
+            }
+        }
+        kept
+    }
+
+    /// Puts the values excluded from the directory `inum` back among its
+    /// (sorted) `entries`; files made since with the same name win.
+    fn restore_excluded<U>(
+        &self,
+        inum: u64,
+        entries: &mut Vec<(String, Nested<U>)>,
+        convert: fn(V, &Config) -> U,
+    ) where
+        U: Nodelike,
+    {
+        for (key, v) in self.excluded.get(&inum).into_iter().flatten() {
+            if entries.iter().any(|(k, _)| k == key) {
+                warn!(
+                    "'{}' (inode {}) replaces an excluded value of the same name.",
+                    key, inum
+                );
+                continue;
+            }
+
+            let idx = entries
+                .iter()
+                .position(|(k, _)| k > key)
+                .unwrap_or(entries.len());
+            entries.insert(
+                idx,
+                (key.clone(), Nested::Value(convert(v.clone(), &self.config))),
+            );
+        }
+    }
+
+    /// Under `Config::flatten_single`, collapses directories with a single
+    /// child into the directory `inum`, prefixing the child's name: `{"a":
+    /// {"b": 1}}` becomes a single file `a.b`. Entries are `(key, name, value,
+    /// nesting)`: the entry's key in `inum`, its (dotted) filename, its value,
+    /// and the collapsed directories (see `DirEntry::nesting`).
+    ///
+    /// The root is also hoisted out of single-entry wrappers, like
+    /// `{"config": {...}}`, but it's never collapsed into a single file.
+    /// Directories made by `FS::split_entries` are left alone.
+    fn flatten_entries(
+        &self,
+        inum: u64,
+        entries: Vec<(String, V)>,
+        split: &HashSet<String>,
+    ) -> Vec<FlatEntry<V>> {
+        let mut entries = entries
+            .into_iter()
+            .map(|(key, v)| (key.clone(), key, v, Vec::new()))
+            .collect::<Vec<_>>();
+
+        if !self.config.flatten_single {
+            return entries;
+        }
+
+        // excluded values mustn't be collapsed into a visible name
+        let path = self.document_path(inum);
+        let excluded = |key: &str, nesting: &[(DirType, String)], k: &str, v: &V| {
+            let mut path = path.iter().map(String::as_str).collect::<Vec<_>>();
+            path.push(key);
+            path.extend(nesting.iter().map(|(_, k)| k.as_str()));
+            path.push(k);
+            self.config.excluded(&path, v.kind() == FileType::Directory)
+        };
+
+        while inum == fuser::FUSE_ROOT_ID
+            && entries.len() == 1
+            && entries[0].2.kind() == FileType::Directory
+            && !(entries[0].3.is_empty() && split.contains(&entries[0].0))
+        {
+            let (key, name, v, nesting) = entries.pop().unwrap();
+            let (typ, children) = self.dir_entries(v);
+            if children.is_empty()
+                || children
+                    .iter()
+                    .any(|(k, child)| excluded(&key, &nesting, k, child))
+            {
+                entries.push((key, name, self.dir_from_entries(typ, children), nesting));
+                break;
+            }
+
+            entries = children
+                .into_iter()
+                .map(|(k, child)| {
+                    let mut nesting = nesting.clone();
+                    nesting.push((typ, k.clone()));
+                    (key.clone(), format!("{}.{}", name, k), child, nesting)
+                })
+                .collect();
+        }
+
+        // each entry remembers its name and nesting before collapsing, so a
+        // clash can be undone below
+        let mut entries = entries
+            .into_iter()
+            .map(|(key, mut name, mut v, mut nesting)| {
+                let uncollapsed = (name.clone(), nesting.len());
+                if nesting.is_empty() && split.contains(&key) {
+                    return (key, name, v, nesting, uncollapsed);
+                }
+
+                while v.kind() == FileType::Directory {
+                    let (typ, mut children) = self.dir_entries(v);
+                    if children.len() != 1
+                        || excluded(&key, &nesting, &children[0].0, &children[0].1)
+                    {
+                        v = self.dir_from_entries(typ, children);
+                        break;
+                    }
+
+                    let (k, child) = children.pop().unwrap();
+                    name = format!("{}.{}", name, k);
+                    nesting.push((typ, k));
+                    v = child;
+                }
+
+                (key, name, v, nesting, uncollapsed)
+            })
+            .collect::<Vec<_>>();
+
+        // a collapsed name mustn't clobber another entry, e.g., `a.b` in
+        // `{"a": {"b": 1}, "a.b": 2}`, so clashing entries stay uncollapsed;
+        // the names from before collapsing are all distinct, so this ends
+        loop {
+            let mut counts: HashMap<&str, usize> = HashMap::with_capacity(entries.len());
+            for (_, name, ..) in entries.iter() {
+                *counts.entry(name.as_str()).or_insert(0) += 1;
+            }
+            let clash = entries
+                .iter()
+                .position(|(_, name, _, nesting, (_, depth))| {
+                    nesting.len() > *depth && counts[name.as_str()] > 1
+                });
+            let i = match clash {
+                Some(i) => i,
+                None => break,
+            };
+
+            let (key, name, mut v, mut nesting, (uncollapsed, depth)) = entries.remove(i);
+            warn!(
+                "Not collapsing '{}' into '{}', since there's already an entry by that name.",
+                uncollapsed, name
+            );
+            while nesting.len() > depth {
+                let (typ, k) = nesting.pop().unwrap();
+                v = self.dir_from_entries(typ, vec![(k, v)]);
+            }
+            entries.insert(
+                i,
+                (key, uncollapsed.clone(), v, nesting, (uncollapsed, depth)),
+            );
+        }
+
+        entries
+            .into_iter()
+            .map(|(key, name, v, nesting, _)| (key, name, v, nesting))
+            .collect()
+    }
+
+    /// Under `SlashPolicy::Split`, gathers the keys with a `/` in them into
+    /// directories named for their first part, e.g., `a/b` and `a/c` become
+    /// `b` and `c` in a new directory `a`. Returns the new entries and the
+    /// names of the directories made (see `DirEntry::split`).
+    ///
+    /// Keys whose first part is already a key, or that start or end with a
+    /// `/`, are left alone, to be munged like any other invalid name.
+    fn split_entries(&self, entries: Vec<(String, V)>) -> (Vec<(String, V)>, HashSet<String>) {
+        let mut split = HashSet::new();
+        if self.config.slash_policy != SlashPolicy::Split
+            || !entries.iter().any(|(key, _)| key.contains('/'))
+        {
+            return (entries, split);
+        }
+
+        let keys = entries
+            .iter()
+            .map(|(key, _)| key.clone())
+            .collect::<HashSet<_>>();
+        let mut kept = Vec::with_capacity(entries.len());
+        let mut dirs: Vec<(String, HashMap<String, V>)> = Vec::new();
+        for (key, v) in entries {
+            let (first, rest) = match key.split_once('/') {
+                Some((first, rest)) if !first.is_empty() && !rest.is_empty() => (first, rest),
+                _ => {
+                    kept.push((key, v));
+                    continue;
+                }
+            };
+
+            if keys.contains(first) {
+                warn!(
+                    "Can't split '{}', since there's already a field named '{}'.",
+                    key, first
+                );
+                kept.push((key, v));
+                continue;
+            }
+
+            match dirs.iter_mut().find(|(name, _)| name == first) {
+                Some((_, files)) => {
+                    files.insert(rest.into(), v);
+                }
+                None => {
+                    let mut files = HashMap::new();
+                    files.insert(rest.into(), v);
+                    dirs.push((first.into(), files));
+                }
+            }
+        }
+
+        for (name, files) in dirs {
+            debug!("splitting {} keys into '{}'", files.len(), name);
+            kept.push((name.clone(), V::from_named_dir(files, &self.config)));
+            split.insert(name);
+        }
+        (kept, split)
+    }
+
+    /// Splits a directory value into its entries; list elements are keyed by
+    /// index, padded like their filenames would be.
+    fn dir_entries(&self, v: V) -> (DirType, Vec<(String, V)>) {
+        match v.node(&self.config) {
+            Node::List(vs) => {
+                let len = vs.len();
+                (
+                    DirType::List,
+                    vs.into_iter()
+                        .enumerate()
+                        .map(|(i, v)| (self.config.element_name(i, len), v))
+                        .collect(),
+                )
+            }
+            Node::Map(fvs) => (DirType::Named, fvs),
+            _ => unreachable!("dir_entries called on a non-directory"),
+        }
+    }
+
+    /// Undoes `FS::dir_entries`.
+    fn dir_from_entries(&self, typ: DirType, entries: Vec<(String, V)>) -> V {
+        match typ {
+            DirType::List => {
+                V::from_list_dir(entries.into_iter().map(|(_, v)| v).collect(), &self.config)
+            }
+            DirType::Named => V::from_named_dir(entries.into_iter().collect(), &self.config),
+        }
+    }
+
+    fn resolve_nodes_transitively(&mut self, inum: u64) -> Result<(), FSError> {
+        let mut worklist = match self.resolve_node(inum)? {
+            Some(nodes) => nodes,
+            None => return Ok(()),
+        };
+
+        while !worklist.is_empty() {
+            let node = worklist.pop().unwrap();
+            if let Some(nodes) = self.resolve_node(node)? {
+                worklist.extend(nodes);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Looks up one JSON Pointer reference token in the directory `inum`.
+    /// Names are the original ones, i.e., from before munging.
+    fn pointer_step(&self, inum: u64, token: &str) -> Option<u64> {
+        match &self.inodes.get(inum as usize)?.as_ref()?.entry {
+            Entry::Directory(DirType::Named, files) => files
+                .iter()
+                .find(|(name, de)| de.original_name.as_ref().unwrap_or(name) == token)
+                .map(|(_, de)| de.inum),
+            Entry::Directory(DirType::List, files) => {
+                let mut files = files.iter().collect::<Vec<_>>();
+                files.sort_unstable_by_key(|(name, _)| format::element_key(name));
+                files
+                    .get(format::pointer_index(token)?)
+                    .map(|(_, de)| de.inum)
+            }
+            _ => None,
+        }
+    }
+
+    /// Finds the inode named by a JSON Pointer, resolving lazy values along
+    /// the way.
+    fn resolve_pointer(&mut self, pointer: &str) -> Option<u64> {
+        let mut inum = fuser::FUSE_ROOT_ID;
+        for token in format::pointer_tokens(pointer)? {
+            self.get(inum).ok()?;
+            inum = self.pointer_step(inum, &token)?;
+        }
+        Some(inum)
+    }
+
+    /// Finds the inode named by a JSON Pointer, without resolving anything.
+    fn find_pointer(&self, pointer: &str) -> Option<u64> {
+        let mut inum = fuser::FUSE_ROOT_ID;
+        for token in format::pointer_tokens(pointer)? {
+            inum = self.pointer_step(inum, &token)?;
+        }
+        Some(inum)
+    }
+
+    /// Resolves the targets of every link, so that `FS::as_value` can find
+    /// them.
+    fn resolve_links(&mut self) {
+        // resolving can add inodes (and links), which we'll see as we go
+        let mut inum = fuser::FUSE_ROOT_ID;
+        while (inum as usize) < self.inodes.len() {
+            if let Some(Inode {
+                entry: Entry::Link(pointer),
+                ..
+            }) = &self.inodes[inum as usize]
+            {
+                let pointer = pointer.clone();
+                if pointer != NULL_LINK && self.resolve_pointer(&pointer).is_none() {
+                    warn!("The link to '{}' (inode {}) is dangling.", pointer, inum);
+                }
+            }
+            inum += 1;
+        }
+    }
+
+    /// The inodes from the root to `inum`, inclusive.
+    fn ancestry(&self, mut inum: u64) -> Vec<u64> {
+        let mut inums = vec![inum];
+        while inum != fuser::FUSE_ROOT_ID {
+            inum = match &self.inodes[inum as usize] {
+                Some(inode) => inode.parent,
+                None => break,
+            };
+            inums.push(inum);
+        }
+        inums.reverse();
+        inums
+    }
+
+    /// The name of `inum` in its parent directory.
+    fn name_of(&self, inum: u64) -> Option<&str> {
+        let parent = self.inodes.get(inum as usize)?.as_ref()?.parent;
+        match &self.inodes.get(parent as usize)?.as_ref()?.entry {
+            Entry::Directory(_, files) => files
+                .iter()
+                .find(|(_, de)| de.inum == inum)
+                .map(|(name, _)| name.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Computes the relative path from the directory holding the link `inum`
+    /// to its target.
+    fn link_path(&mut self, inum: u64, pointer: &str) -> Option<String> {
+        let target = self.resolve_pointer(pointer)?;
+        let from = self.ancestry(self.inodes[inum as usize].as_ref()?.parent);
+        let to = self.ancestry(target);
+
+        let common = from
+            .iter()
+            .zip(to.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let mut path = vec![".."; from.len() - common];
+        for inum in &to[common..] {
+            path.push(self.name_of(*inum)?);
+        }
+
+        if path.is_empty() {
+            Some(".".into())
+        } else {
+            Some(path.join("/"))
+        }
+    }
+
+    /// Under `Config::private`, only the owner, root, and the
+    /// `Config::allowed_uids` may read; unlike `FS::check_access`, a bare
+    /// `Config::allow_other` doesn't let everyone in.
+    fn check_read_access(&self, req: &Request) -> bool {
+        !self.config.private
+            || req.uid() == 0
+            || req.uid() == self.config.uid
+            || self.config.allowed_uids.contains(&req.uid())
+    }
+
+    fn check_access(&self, req: &Request) -> bool {
+        req.uid() == 0
+            || req.uid() == self.config.uid
+            || (self.config.allow_other
+                && (self.config.allowed_uids.is_empty()
+                    || self.config.allowed_uids.contains(&req.uid())))
+    }
+
+    pub fn get(&mut self, inum: u64) -> Result<&Inode<V>, FSError> {
+        let _new_nodes = self.resolve_node(inum)?;
+
+        let idx = inum as usize;
+
+        if idx >= self.inodes.len() || idx == 0 {
+            return Err(FSError::NoSuchInode(inum));
+        }
+
+        match &self.inodes[idx] {
+            None => Err(FSError::InvalidInode(inum)),
+            Some(inode) => Ok(inode),
+        }
+    }
+
+    fn get_mut(&mut self, inum: u64) -> Result<&mut Inode<V>, FSError> {
+        let _new_nodes = self.resolve_node(inum)?;
+
+        let idx = inum as usize;
+
+        if idx >= self.inodes.len() {
+            return Err(FSError::NoSuchInode(inum));
+        }
+
+        match self.inodes.get_mut(idx) {
+            Some(Some(inode)) => Ok(inode),
+            _ => Err(FSError::InvalidInode(inum)),
+        }
+    }
+
+    /// Loads `config.input` into a new filesystem. Errors (e.g., input that
+    /// doesn't parse, or that breaks one of the limits in `Config`) are
+    /// returned, ready to be shown to the user; nothing is mounted.
+    pub fn try_new(mut config: Config) -> Result<Self, Box<dyn std::error::Error>> {
         info!("loading");
         let mut inodes: Vec<Option<Inode<V>>> = Vec::with_capacity(1024);
         // allocate space for dummy inode 0, root node
         inodes.resize_with(2, || None);
 
-        let reader = match config.input_reader() {
-            Some(reader) => reader,
-            None => {
-                // create an empty directory
-                let contents = HashMap::with_capacity(16);
-                inodes[1] = Some(Inode::new(
-                    fuser::FUSE_ROOT_ID,
-                    fuser::FUSE_ROOT_ID,
-                    Entry::Directory(DirType::Named, contents),
-                    &config,
-                ));
-                return FS {
-                    inodes,
-                    config,
-                    dirty: Cell::new(false),
-                    synced: Cell::new(false),
-                };
+        if let Input::Siblings(siblings) = &config.input {
+            let v = time_ns!(
+                "reading",
+                FS::read_siblings(siblings, &config)?,
+                config.timing
+            );
+            return FS::try_from_value(config, v);
+        }
+
+        if let Input::Directory(dir) = &config.input {
+            let (v, layout) = time_ns!("reading", FS::read_directory(dir, &config)?, config.timing);
+            let mut fs = FS::try_from_value(config, v)?;
+            fs.layout = Some(layout);
+            return Ok(fs);
+        }
+
+        let reader = match config.input_reader() {
+            Some(reader) => reader,
+            None => {
+                // create an empty directory
+                let contents = HashMap::with_capacity(16);
+                inodes[1] = Some(Inode::new(
+                    fuser::FUSE_ROOT_ID,
+                    fuser::FUSE_ROOT_ID,
+                    Entry::Directory(DirType::Named, contents),
+                    &config,
+                ));
+                return Ok(FS {
+                    inodes,
+                    live_inodes: 1,
+                    used_bytes: 0,
+                    generation: 0,
+                    config,
+                    dirty: Cell::new(false),
+                    synced: Cell::new(false),
+                    document: None,
+                    dir_handles: HashMap::new(),
+                    file_handles: HashMap::new(),
+                    next_fh: 1,
+                    wrapped_root: false,
+                    length_files: HashMap::new(),
+                    info_file: None,
+                    excluded: HashMap::new(),
+                    watcher: None,
+                    input_mtime: None,
+                    sync_due: None,
+                    base: None,
+                    source: None,
+                    streamed: HashSet::new(),
+                    layout: None,
+                    schema: None,
+                });
+            }
+        };
+
+        let (reader, source) = FS::<V>::keep_source(reader, &config)
+            .map_err(|e| format!("Unable to read {}: {}", config.input, e))?;
+        let v = time_ns!(
+            "reading",
+            format::read_input(reader, &config),
+            config.timing
+        )
+        .map_err(|e| {
+            format!(
+                "Couldn't parse {} as {}: {}.",
+                config.input, config.input_format, e
+            )
+        })?;
+        let (v, base) = FS::merge_overlay(v, &config).map_err(|e| {
+            format!(
+                "Couldn't parse the overlay {} as {}: {}.",
+                config.overlay.as_ref().unwrap().display(),
+                config.input_format,
+                e
+            )
+        })?;
+        let mut fs = FS::try_from_value(config, v)?;
+        fs.base = base;
+        fs.source = source;
+        if fs.config.watch {
+            fs.start_watching();
+        }
+        Ok(fs)
+    }
+
+    /// Reads all of `reader` when saving can keep the input's formatting (see
+    /// `Format::keeps_formatting`), returning a reader over the same contents
+    /// along with the text.
+    fn keep_source(
+        mut reader: Box<dyn std::io::Read>,
+        config: &Config,
+    ) -> std::io::Result<(Box<dyn std::io::Read>, Option<String>)> {
+        if !config.input_format.keeps_formatting() || config.input_format != config.output_format {
+            return Ok((reader, None));
+        }
+
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        let source = String::from_utf8(bytes.clone()).ok();
+        Ok((Box::new(std::io::Cursor::new(bytes)), source))
+    }
+
+    /// Merges `Config::overlay`, if any, into `v`. Under
+    /// `Config::write_overlay_only`, also returns `v` as it was, for
+    /// `FS::base`.
+    fn merge_overlay(v: V, config: &Config) -> Result<(V, Option<V>), Box<dyn std::error::Error>> {
+        let overlay = match &config.overlay {
+            Some(overlay) => overlay,
+            None => return Ok((v, None)),
+        };
+
+        let reader = Box::new(std::io::BufReader::new(std::fs::File::open(overlay)?));
+        let overlay = time_ns!(
+            "reading overlay",
+            V::from_reader(reader, config)?,
+            config.timing
+        );
+        let base = if config.write_overlay_only {
+            Some(v.clone())
+        } else {
+            None
+        };
+        Ok((format::merge(v, overlay, config), base))
+    }
+
+    /// Reads each of the `Input::Siblings`, in its own format, into one named
+    /// directory.
+    fn read_siblings(
+        siblings: &[Sibling],
+        config: &Config,
+    ) -> Result<V, Box<dyn std::error::Error>> {
+        let mut files = HashMap::with_capacity(siblings.len());
+        for sibling in siblings {
+            let reader = std::fs::File::open(&sibling.file)
+                .map_err(|e| format!("Unable to open {}: {}", sibling.file.display(), e))?;
+            let v = format::read_as(sibling.format, Box::new(reader), config).map_err(|e| {
+                format!(
+                    "Couldn't parse {} as {}: {}.",
+                    sibling.file.display(),
+                    sibling.format,
+                    e
+                )
+            })?;
+            files.insert(sibling.name.clone(), v);
+        }
+        Ok(V::from_named_dir(files, config))
+    }
+
+    /// Reads each file in `dir` whose extension names a format, in that
+    /// format, into one named directory, keyed by the file's stem;
+    /// subdirectories are read the same way (see `Input::Directory`). Hidden
+    /// files and files in other formats are skipped.
+    fn read_directory(
+        dir: &Path,
+        config: &Config,
+    ) -> Result<(V, DirLayout), Box<dyn std::error::Error>> {
+        let mut paths = std::fs::read_dir(dir)
+            .map_err(|e| format!("Unable to read {}: {}", dir.display(), e))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        let mut files = HashMap::with_capacity(paths.len());
+        let mut layout = DirLayout::default();
+        for path in paths {
+            match path.file_name().and_then(OsStr::to_str) {
+                Some(name) if !name.starts_with('.') => (),
+                _ => continue,
+            }
+
+            let format = if path.is_dir() {
+                None
+            } else {
+                match path
+                    .extension()
+                    .and_then(OsStr::to_str)
+                    .and_then(|s| s.parse::<Format>().ok())
+                {
+                    Some(format) => Some(format),
+                    None => {
+                        debug!("skipping {}, which isn't in a known format", path.display());
+                        continue;
+                    }
+                }
+            };
+
+            let key = match format {
+                Some(_) => path.file_stem(),
+                None => path.file_name(),
+            };
+            let key = match key.and_then(OsStr::to_str) {
+                Some(key) => key.to_string(),
+                None => continue,
+            };
+            if files.contains_key(&key) {
+                return Err(format!(
+                    "Two entries in {} would both be mounted as '{}'.",
+                    dir.display(),
+                    key
+                )
+                .into());
+            }
+
+            let v = match format {
+                None => {
+                    let (v, sublayout) = FS::read_directory(&path, config)?;
+                    layout.dirs.insert(key.clone(), (path, sublayout));
+                    v
+                }
+                Some(format) => {
+                    let reader = std::fs::File::open(&path)
+                        .map_err(|e| format!("Unable to open {}: {}", path.display(), e))?;
+                    let v = format::read_as(format, Box::new(reader), config).map_err(|e| {
+                        format!("Couldn't parse {} as {}: {}.", path.display(), format, e)
+                    })?;
+                    layout.files.insert(key.clone(), (path, format));
+                    v
+                }
+            };
+            files.insert(key, v);
+        }
+        Ok((V::from_named_dir(files, config), layout))
+    }
+
+    /// Builds a filesystem holding `v`, as though it had been read from
+    /// `config.input`. Along with `FS::read_path`, `FS::write_path`,
+    /// `FS::to_value`, and `FS::sync`, this lets you use a filesystem without
+    /// mounting it.
+    ///
+    /// Panics if `v` can't be mounted; `FS::try_from_value` returns the
+    /// error instead.
+    pub fn from_value(config: Config, v: V) -> Self {
+        FS::try_from_value(config, v).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Builds a filesystem holding `v`, like `FS::from_value`, returning an
+    /// error if `v` breaks one of the limits in `Config` (or otherwise can't
+    /// be mounted).
+    ///
+    /// ```
+    /// use ffs::config::Config;
+    /// use ffs::format::{json, Nodelike};
+    /// use ffs::fs::FS;
+    ///
+    /// let config = Config {
+    ///     max_depth: 1,
+    ///     ..Config::default()
+    /// };
+    /// let input = r#"{"db": {"host": "localhost"}}"#;
+    /// let v = json::Value::from_reader(Box::new(input.as_bytes()), &config).unwrap();
+    /// let e = FS::try_from_value(config, v).unwrap_err();
+    /// assert!(e.to_string().contains("`--max-depth` is 1"));
+    /// ```
+    pub fn try_from_value(config: Config, mut v: V) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut inodes: Vec<Option<Inode<V>>> = Vec::with_capacity(1024);
+        // allocate space for dummy inode 0, root node
+        inodes.resize_with(2, || None);
+
+        if config.input_format != config.output_format
+            && matches!(config.output, Output::File(_) | Output::Stdout)
+        {
+            FS::check_conversion(&v, &config)?;
+        }
+
+        let document = match &config.root_pointer {
+            Some(pointer) => match v.replace_at(pointer, V::default()) {
+                Some(subtree) => Some(mem::replace(&mut v, subtree)),
+                None => {
+                    return Err(format!(
+                        "The pointer '{}' doesn't refer to anything in {}.",
+                        pointer, config.input
+                    )
+                    .into());
+                }
+            },
+            None => None,
+        };
+        let depth = v.depth();
+        if depth > config.max_depth {
+            return Err(format!(
+                "{} nests {} levels deep, but `--max-depth` is {}.",
+                config.input, depth, config.max_depth
+            )
+            .into());
+        }
+        if let Some(max) = config.max_inodes {
+            let size = v.size();
+            if size > max {
+                return Err(format!(
+                    "{} would need {} inodes, but `--max-inodes` is {}.",
+                    config.input, size, max
+                )
+                .into());
+            }
+        }
+        let used_bytes = format::leaf_bytes(v.clone(), &config);
+        if let Some(max) = config.max_total_bytes {
+            if used_bytes > max {
+                return Err(format!(
+                    "{} holds {} bytes of data, but `--max-total-bytes` is {}.",
+                    config.input, used_bytes, max
+                )
+                .into());
+            }
+        }
+        if let Some(capacity) = config.capacity {
+            if used_bytes > capacity {
+                return Err(format!(
+                    "{} holds {} bytes of data, more than `--size` allows ({}).",
+                    config.input, used_bytes, capacity
+                )
+                .into());
+            }
+        }
+
+        if config.slash_policy == SlashPolicy::Reject {
+            if let Some(key) = format::slashed_key(v.clone(), &config) {
+                return Err(format!(
+                    "{} has a key with a '/' in it ('{}'), but `--slash-policy` is reject.",
+                    config.input, key
+                )
+                .into());
+            }
+        }
+
+        let wrapped_root = v.kind() != FileType::Directory;
+        if wrapped_root {
+            if !config.allow_primitive_root {
+                return Err(format!("The root of the filesystem must be a directory, but '{}' only generates a single file; use `--allow-primitive-root` to mount it anyway.", v).into());
+            }
+
+            let mut files = HashMap::with_capacity(1);
+            files.insert(config.primitive_root_name.clone(), v);
+            v = V::from_named_dir(files, &config);
+        }
+
+        // `Config::from_args` has already reported any problem with it
+        let schema = json::schema(&config).unwrap_or(None);
+        let mut fs = FS {
+            inodes,
+            // the root, which we're about to add
+            live_inodes: 1,
+            used_bytes,
+            generation: 0,
+            config,
+            dirty: Cell::new(false),
+            synced: Cell::new(false),
+            document,
+            dir_handles: HashMap::new(),
+            file_handles: HashMap::new(),
+            next_fh: 1,
+            wrapped_root,
+            length_files: HashMap::new(),
+            info_file: None,
+            excluded: HashMap::new(),
+            watcher: None,
+            input_mtime: None,
+            sync_due: None,
+            base: None,
+            source: None,
+            streamed: HashSet::new(),
+            layout: None,
+            schema,
+        };
+
+        time_ns!(
+            "loading",
+            {
+                fs.inodes[fuser::FUSE_ROOT_ID as usize] = Option::Some(Inode::new(
+                    fuser::FUSE_ROOT_ID,
+                    fuser::FUSE_ROOT_ID,
+                    Entry::Lazy(v),
+                    &fs.config,
+                ));
+
+                if fs.config.eager {
+                    fs.resolve_nodes_transitively(fuser::FUSE_ROOT_ID)
+                        .expect("resolve_nodes_transitively");
+                } else {
+                    // kick start the root directory
+                    fs.resolve_node(fuser::FUSE_ROOT_ID).expect("resolve_node");
+                }
+            },
+            fs.config.timing
+        );
+
+        // renames apply as directories are resolved; resolving the renamed
+        // keys now reports any collisions before we mount
+        let renamed = fs.config.name_map.clone();
+        for (pointer, name) in renamed {
+            match fs.resolve_pointer(&pointer) {
+                None => warn!(
+                    "`--name-map` renames '{}', but there's nothing there.",
+                    pointer
+                ),
+                Some(inum) if fs.name_of(inum) != Some(name.as_str()) => {
+                    return Err(format!(
+                        "`--name-map` can't rename '{}' to '{}': that name is already in use.",
+                        pointer, name
+                    )
+                    .into());
+                }
+                Some(_) => (),
+            }
+        }
+
+        // converting checks the schema when it saves, right away
+        if fs.schema.is_some() && !fs.config.convert {
+            let violations = fs.schema_violations();
+            if !violations.is_empty() {
+                warn!(
+                    "{} violates the schema in {} place(s).",
+                    fs.config.input,
+                    violations.len()
+                );
+            }
+        }
+
+        if fs.config.stream {
+            fs.start_streaming();
+        }
+
+        Ok(fs)
+    }
+
+    /// Checks the whole document against `Config::schema`, logging each
+    /// violation along with the JSON Pointer to the offending value. Returns
+    /// the violations as `(pointer, problem)` pairs.
+    fn schema_violations(&mut self) -> Vec<(String, String)> {
+        let schema = match self.schema.take() {
+            Some(schema) => schema,
+            None => return Vec::new(),
+        };
+        let v: json::Value = format::convert(self.to_value(), &self.config);
+        let violations = json::schema_violations(&schema, &v);
+        self.schema = Some(schema);
+
+        for (pointer, problem) in violations.iter() {
+            if pointer.is_empty() {
+                warn!("Schema violation at the root: {}.", problem);
+            } else {
+                warn!("Schema violation at '{}': {}.", pointer, problem);
+            }
+        }
+        violations
+    }
+
+    /// Gets the output file ready for `FS::stream` (see `Config::stream`): it
+    /// gets the whole document, unless it's the input file (which already
+    /// has it), and every element of the root list counts as streamed.
+    fn start_streaming(&mut self) {
+        let elements = match &self.get(fuser::FUSE_ROOT_ID).expect("root inode").entry {
+            Entry::Directory(DirType::List, files) => {
+                files.values().map(|de| de.inum).collect::<HashSet<_>>()
+            }
+            _ => {
+                warn!("`--stream` needs a list at the root; saving at unmount instead.");
+                self.config.stream = false;
+                return;
+            }
+        };
+
+        match (&self.config.input, &self.config.output) {
+            (Input::File(input), Output::File(output)) if input == output => (),
+            // `save` has already logged why it couldn't
+            _ => {
+                let _ = self.save();
+            }
+        }
+        self.streamed = elements;
+    }
+
+    /// Under `Config::stream`, appends the new elements of the root list, up
+    /// to and including the one holding `inum`, to the output file.
+    ///
+    /// Elements only ever get appended in order, so if a new element sorts
+    /// before one that's already streamed, it waits for the save at unmount
+    /// (which rewrites the whole file, picking up any other changes, too).
+    fn stream(&mut self, inum: u64) {
+        let element = match self.ancestry(inum).get(1) {
+            Some(element) => *element,
+            None => return,
+        };
+        if self.streamed.contains(&element) {
+            return;
+        }
+
+        let root = self.inodes[fuser::FUSE_ROOT_ID as usize].as_ref().unwrap();
+        let files = match &root.entry {
+            Entry::Directory(DirType::List, files) => files,
+            _ => return,
+        };
+        let mut files = files
+            .iter()
+            .filter(|(name, _)| !self.config.ignored_file(name))
+            .collect::<Vec<_>>();
+        files.sort_unstable_by_key(|(name, _)| format::element_key(name));
+        let inums = files.iter().map(|(_, de)| de.inum).collect::<Vec<_>>();
+
+        let start = match inums.iter().rposition(|inum| self.streamed.contains(inum)) {
+            Some(last) => last + 1,
+            None => 0,
+        };
+        let end = match inums.iter().position(|inum| *inum == element) {
+            Some(end) if end >= start => end + 1,
+            _ => {
+                debug!(
+                    "element {} is out of order; it'll be saved at unmount",
+                    element
+                );
+                return;
+            }
+        };
+
+        let vs = inums[start..end]
+            .iter()
+            .map(|inum| self.as_value(*inum, &mut HashSet::new()))
+            .collect();
+
+        let output = match &self.config.output {
+            Output::File(output) => output.clone(),
+            _ => return,
+        };
+        let appended = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&output)
+            .and_then(|mut file| {
+                V::from_list_dir(vs, &self.config).to_writer(&mut file, &self.config)
+            });
+        if let Err(e) = appended {
+            warn!("Unable to append to {}: {}.", output.display(), e);
+            return;
+        }
+        self.streamed.extend(&inums[start..end]);
+
+        // don't reload what we just appended
+        if self.watcher.is_some() {
+            if let Input::File(input) = &self.config.input {
+                if *input == output {
+                    self.input_mtime = modified(input);
+                }
+            }
+        }
+    }
+
+    /// Looks for values in `v` that the output format can't represent,
+    /// refusing to go on under `Config::strict_conversion` and warning
+    /// otherwise.
+    fn check_conversion(v: &V, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+        let found = format::unrepresentable(v.clone(), config.output_format, config);
+        if found.is_empty() {
+            return Ok(());
+        }
+
+        if config.strict_conversion {
+            let found = found
+                .iter()
+                .map(|(pointer, construct)| format!("'{}' ({})", pointer, construct))
+                .collect::<Vec<_>>();
+            return Err(format!(
+                "{} can't be saved as {} without changing some values: {}.",
+                config.input,
+                config.output_format,
+                found.join(", ")
+            )
+            .into());
+        }
+
+        for (pointer, construct) in found {
+            warn!(
+                "{} can't represent {} at '{}'; it will be converted when saving.",
+                config.output_format, construct, pointer
+            );
+        }
+        Ok(())
+    }
+
+    /// Starts watching the input file for changes (see `Config::watch`).
+    fn start_watching(&mut self) {
+        let file = match &self.config.input {
+            Input::File(file) => file.clone(),
+            Input::Stdin | Input::Empty | Input::Siblings(_) | Input::Directory(_) => return,
+        };
+        self.input_mtime = modified(&file);
+
+        let changed = Arc::new(AtomicBool::new(false));
+        let flag = changed.clone();
+        let name = file.file_name().map(OsStr::to_os_string);
+        let handler = move |event: notify::Result<notify::Event>| match event {
+            Ok(event) => {
+                if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+                    && event
+                        .paths
+                        .iter()
+                        .any(|path| path.file_name() == name.as_deref())
+                {
+                    flag.store(true, Ordering::SeqCst);
+                }
             }
+            Err(e) => warn!("Error watching the input file: {}.", e),
         };
 
-        let v = time_ns!("reading", V::from_reader(reader), config.timing);
-        if v.kind() != FileType::Directory {
-            error!("The root of the filesystem must be a directory, but '{}' only generates a single file.", v);
-            std::process::exit(ERROR_STATUS_FUSE);
+        // editors often save by renaming over the file, so we watch its directory
+        let dir = match file.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let watcher = notify::recommended_watcher(handler).and_then(|mut watcher| {
+            watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+            Ok(watcher)
+        });
+        match watcher {
+            Ok(watcher) => self.watcher = Some((watcher, changed)),
+            Err(e) => warn!("Couldn't watch {} for changes: {}.", file.display(), e),
         }
+    }
 
-        let mut fs = FS {
-            inodes,
-            config,
-            dirty: Cell::new(false),
-            synced: Cell::new(false),
+    /// Under `Config::sync_on_close`, arranges for a save once
+    /// `Config::sync_delay` has passed, so a burst of closes saves only
+    /// once. We only check for a due save on requests (see `FS::check_sync`),
+    /// so a thread pokes the mount once it's due; like the watcher, it never
+    /// touches the `FS` itself.
+    fn schedule_sync(&mut self) {
+        if self.sync_due.is_some() {
+            return;
+        }
+
+        let delay = self.config.sync_delay;
+        self.sync_due = Some(Instant::now() + delay);
+        if let Some(mount) = self.config.mount.clone() {
+            std::thread::spawn(move || {
+                std::thread::sleep(delay);
+                // listing the root is a request the kernel can't answer itself
+                let _ = std::fs::read_dir(mount);
+            });
+        }
+    }
+
+    /// Saves, if a save scheduled by `FS::schedule_sync` is due. Errors are
+    /// logged by `FS::sync`; the changes stay dirty, and are saved again by a
+    /// later close or at unmount.
+    fn check_sync(&mut self) {
+        match self.sync_due {
+            Some(due) if due <= Instant::now() => self.sync_due = None,
+            _ => return,
+        }
+
+        let _ = self.sync(false);
+    }
+
+    /// Reloads the input file if it has changed on disk since we last loaded
+    /// or saved it (see `Config::watch`). If there are unsaved edits,
+    /// `Config::watch_conflict` decides which side wins.
+    fn check_watch(&mut self) {
+        match &self.watcher {
+            Some((_watcher, changed)) if changed.swap(false, Ordering::SeqCst) => (),
+            _ => return,
+        }
+
+        let mtime = match &self.config.input {
+            Input::File(file) => modified(file),
+            Input::Stdin | Input::Empty | Input::Siblings(_) | Input::Directory(_) => return,
         };
+        if mtime == self.input_mtime {
+            debug!("input unchanged since we last loaded or saved it");
+            return;
+        }
+        self.input_mtime = mtime;
+
+        if self.dirty.get() {
+            match self.config.watch_conflict {
+                WatchConflict::Mount => {
+                    warn!(
+                        "{} changed on disk, but the mount has unsaved edits; keeping the edits.",
+                        self.config.input
+                    );
+                    return;
+                }
+                WatchConflict::File => warn!(
+                    "{} changed on disk, but the mount has unsaved edits; discarding the edits.",
+                    self.config.input
+                ),
+            }
+        }
 
-        time_ns!(
-            "loading",
-            {
-                fs.inodes[fuser::FUSE_ROOT_ID as usize] = Option::Some(Inode::new(
-                    fuser::FUSE_ROOT_ID,
-                    fuser::FUSE_ROOT_ID,
-                    Entry::Lazy(v),
-                    &fs.config,
-                ));
+        self.reload();
+    }
 
-                if fs.config.eager {
-                    fs.resolve_nodes_transitively(fuser::FUSE_ROOT_ID)
-                        .expect("resolve_nodes_transitively");
-                } else {
-                    // kick start the root directory
-                    fs.resolve_node(fuser::FUSE_ROOT_ID).expect("resolve_node");
+    /// Replaces the contents of the filesystem with the input's current
+    /// contents, keeping the old ones if the input no longer loads.
+    ///
+    /// The old tree's inode numbers (other than the root's) are reused for
+    /// the new one, with a higher generation, so the kernel can tell them
+    /// apart. While files or directories are open, though, their inode
+    /// numbers must keep referring to what they did, so the slots are left
+    /// empty instead.
+    fn reload(&mut self) {
+        info!("reloading {}", self.config.input);
+
+        let reader = match self.config.input_reader() {
+            Some(reader) => reader,
+            None => return,
+        };
+        let (reader, source) = match FS::<V>::keep_source(reader, &self.config) {
+            Ok(kept) => kept,
+            Err(e) => {
+                warn!(
+                    "Unable to read {}: {}; keeping the old contents.",
+                    self.config.input, e
+                );
+                return;
+            }
+        };
+        let v = match format::read_input::<V>(reader, &self.config) {
+            Ok(v) => v,
+            Err(e) => {
+                warn!(
+                    "Couldn't parse {} as {}: {}; keeping the old contents.",
+                    self.config.input, self.config.input_format, e
+                );
+                return;
+            }
+        };
+        let (mut v, base) = match FS::merge_overlay(v, &self.config) {
+            Ok(merged) => merged,
+            Err(e) => {
+                warn!(
+                    "Couldn't parse the overlay {} as {}: {}; keeping the old contents.",
+                    self.config.overlay.as_ref().unwrap().display(),
+                    self.config.input_format,
+                    e
+                );
+                return;
+            }
+        };
+
+        let document = match &self.config.root_pointer {
+            Some(pointer) => match v.replace_at(pointer, V::default()) {
+                Some(subtree) => Some(mem::replace(&mut v, subtree)),
+                None => {
+                    warn!(
+                        "The pointer '{}' doesn't refer to anything in {}; keeping the old contents.",
+                        pointer, self.config.input
+                    );
+                    return;
                 }
             },
-            fs.config.timing
-        );
+            None => None,
+        };
+
+        let wrapped_root = v.kind() != FileType::Directory;
+        if wrapped_root {
+            if !self.config.allow_primitive_root {
+                warn!(
+                    "{} only generates a single file; keeping the old contents.",
+                    self.config.input
+                );
+                return;
+            }
+
+            let mut files = HashMap::with_capacity(1);
+            files.insert(self.config.primitive_root_name.clone(), v);
+            v = V::from_named_dir(files, &self.config);
+        }
+
+        if self.file_handles.is_empty() && self.dir_handles.is_empty() {
+            self.inodes.truncate(fuser::FUSE_ROOT_ID as usize + 1);
+            self.generation += 1;
+        } else {
+            for inode in self
+                .inodes
+                .iter_mut()
+                .skip(fuser::FUSE_ROOT_ID as usize + 1)
+            {
+                *inode = None;
+            }
+        }
+        self.live_inodes = 1;
+        self.used_bytes = format::leaf_bytes(v.clone(), &self.config);
+        self.document = document;
+        self.base = base;
+        self.source = source;
+        self.wrapped_root = wrapped_root;
+        self.length_files.clear();
+        self.info_file = None;
+        self.excluded.clear();
+        self.streamed.clear();
+
+        let root = self.get_mut(fuser::FUSE_ROOT_ID).expect("root inode");
+        root.entry = Entry::Lazy(v);
+        root.mtime = SystemTime::now();
+        root.ctime = root.mtime;
+        if self.config.eager {
+            self.resolve_nodes_transitively(fuser::FUSE_ROOT_ID)
+                .expect("resolve_nodes_transitively");
+        } else {
+            self.resolve_node(fuser::FUSE_ROOT_ID)
+                .expect("resolve_node");
+        }
+
+        // the filesystem matches the input again
+        self.dirty.set(false);
 
-        fs
+        if self.config.stream {
+            self.start_streaming();
+        }
     }
 
     /// Tries to synchronize the in-memory `FS` with its on-disk representation.
@@ -401,27 +3495,58 @@ where
     ///
     ///   - if `self.config.output == Output::Stdout` and `last_sync == false`,
     ///     nothing will happen (to prevent redundant writes to STDOUT)
+    ///
+    /// The document is checked against `Config::schema` first. Under
+    /// `Config::schema_enforce`, a document that violates it isn't saved at
+    /// all, and stays dirty.
     #[instrument(level = "debug", skip(self), fields(synced = self.synced.get(), dirty = self.dirty.get()))]
-    pub fn sync(&mut self, last_sync: bool) {
+    pub fn sync(&mut self, last_sync: bool) -> Result<(), FSError> {
         info!("called");
         trace!("{:?}", self.inodes);
 
         if self.synced.get() && !self.dirty.get() {
             info!("skipping sync; already synced and not dirty");
-            return;
+            return Ok(());
         }
 
         match self.config.output {
             Output::Stdout if !last_sync => {
                 info!("skipping sync; not last sync, using stdout");
-                return;
+                return Ok(());
             }
             _ => (),
         };
 
-        self.save();
+        // check before saving, since saving truncates the output
+        if self.schema.is_some() {
+            let violations = self.schema_violations();
+            if self.config.schema_enforce && !violations.is_empty() {
+                error!(
+                    "Not saving, since the document violates the schema in {} place(s).",
+                    violations.len()
+                );
+                return Err(FSError::SchemaViolation(violations.len()));
+            }
+        }
+
+        // `save` has already logged why it couldn't
+        if let Err(e) = self.save() {
+            return Err(FSError::SaveFailed(e.to_string()));
+        }
         self.dirty.set(false);
         self.synced.set(true);
+
+        // don't reload what we just saved
+        if self.watcher.is_some() {
+            if let (Input::File(input), Output::File(output)) =
+                (&self.config.input, &self.config.output)
+            {
+                if input == output {
+                    self.input_mtime = modified(input);
+                }
+            }
+        }
+        Ok(())
     }
 
     /// Actually output results, using `self.config.output`.
@@ -429,108 +3554,525 @@ where
     /// When `self.config.input == self.config.output`, then resolved lazy nodes
     /// can be directly returned. If the input and output formats are different,
     /// we eager resolve everything and then save.
-    fn save(&mut self) {
+    ///
+    /// Each file is saved even if another couldn't be; the first failure is
+    /// returned (and all of them are logged).
+    fn save(&mut self) -> std::io::Result<()> {
+        if self.config.write_overlay_only {
+            let saved = self.save_overlay();
+            return saved.and(self.save_includes());
+        }
+
+        if self.config.output == Output::Siblings {
+            let saved = self.save_siblings();
+            return saved.and(self.save_includes());
+        }
+
+        if self.config.output == Output::Directory {
+            let saved = self.save_directory();
+            return saved.and(self.save_includes());
+        }
+
         let writer = match self.config.output_writer() {
             Some(writer) => writer,
-            None => return,
+            None => return Ok(()),
+        };
+        let name = match &self.config.output {
+            Output::File(output) => output.display().to_string(),
+            _ => "STDOUT".into(),
+        };
+
+        if self.config.input_format == self.config.output_format {
+            let mut v = time_ns!("saving", self.to_value(), self.config.timing);
+            if self.config.sort_keys {
+                v.sort_keys();
+            }
+
+            let saved = time_ns!(
+                "writing",
+                write_out(writer, &name, |writer| match &self.source {
+                    Some(source) if !self.config.sort_keys => {
+                        v.to_writer_preserving(writer, source, &self.config)
+                    }
+                    _ => v.to_writer(writer, &self.config),
+                }),
+                self.config.timing
+            );
+            saved.and(self.save_includes())
+        } else {
+            self.resolve_links();
+            let saved = match self.config.output_format {
+                Format::Json => self.save_as::<json::Value>(writer, &name),
+                Format::Json5 => self.save_as::<json5::Value>(writer, &name),
+                Format::Ndjson => self.save_as::<ndjson::Value>(writer, &name),
+                Format::Toml => self.save_as::<toml::Value>(writer, &name),
+                Format::Yaml => self.save_as::<yaml::Value>(writer, &name),
+                Format::Ini => self.save_as::<ini::Value>(writer, &name),
+                Format::Properties => self.save_as::<properties::Value>(writer, &name),
+                Format::Hcl => self.save_as::<hcl::Value>(writer, &name),
+                Format::Ron => self.save_as::<ron::Value>(writer, &name),
+                Format::Bson => self.save_as::<bson::Value>(writer, &name),
+                Format::Env => self.save_as::<env::Value>(writer, &name),
+                Format::Plist => self.save_as::<plist::Value>(writer, &name),
+                Format::Protobuf => self.save_as::<protobuf::Value>(writer, &name),
+                Format::Edn => self.save_as::<edn::Value>(writer, &name),
+            };
+            saved.and(self.save_includes())
+        }
+    }
+
+    /// Saves the differences from `FS::base` to `Config::overlay`, in the
+    /// input format.
+    fn save_overlay(&mut self) -> std::io::Result<()> {
+        let overlay = match &self.config.overlay {
+            Some(overlay) => overlay.clone(),
+            None => unreachable!("saving the overlay without an overlay"),
+        };
+        let base = match &self.base {
+            Some(base) => base.clone(),
+            None => V::from_named_dir(HashMap::new(), &self.config),
+        };
+
+        let v = time_ns!("saving", self.to_value(), self.config.timing);
+        let mut v = format::diff(base, v, &self.config)
+            .unwrap_or_else(|| V::from_named_dir(HashMap::new(), &self.config));
+        if self.config.sort_keys {
+            v.sort_keys();
+        }
+
+        time_ns!(
+            "writing",
+            write_out(
+                self.config.file_writer(&overlay),
+                &overlay.display().to_string(),
+                |writer| v.to_writer(writer, &self.config)
+            ),
+            self.config.timing
+        )
+    }
+
+    /// Saves each of the `Input::Siblings` back to its own file, in its own
+    /// format.
+    fn save_siblings(&mut self) -> std::io::Result<()> {
+        let siblings = match &self.config.input {
+            Input::Siblings(siblings) => siblings.clone(),
+            _ => unreachable!("saving siblings without sibling inputs"),
+        };
+
+        if let Entry::Directory(_, files) = &self.get(fuser::FUSE_ROOT_ID).unwrap().entry {
+            for name in files.keys() {
+                if !siblings.iter().any(|sibling| &sibling.name == name) {
+                    warn!(
+                        "'{}' isn't one of the input files, so it won't be saved.",
+                        name
+                    );
+                }
+            }
+        }
+
+        self.resolve_links();
+        let mut saved = Ok(());
+        for sibling in siblings.iter() {
+            let inum = match self.lookup_name(fuser::FUSE_ROOT_ID, &sibling.name) {
+                Ok(inum) => inum,
+                Err(_) => {
+                    warn!(
+                        "'{}' is no longer in the mount, so {} won't be saved.",
+                        sibling.name,
+                        sibling.file.display()
+                    );
+                    continue;
+                }
+            };
+
+            saved = saved.and(self.save_subtree(inum, sibling.format, &sibling.file));
+        }
+        saved
+    }
+
+    /// Saves the tree back out to the files of the `Input::Directory`, each in
+    /// its own format. Keys added to a directory are saved as new files in
+    /// the output format; the files and directories of removed keys are
+    /// deleted.
+    fn save_directory(&mut self) -> std::io::Result<()> {
+        let dir = match &self.config.input {
+            Input::Directory(dir) => dir.clone(),
+            _ => unreachable!("saving a directory without a directory input"),
+        };
+        let layout = self.layout.take().unwrap_or_default();
+
+        self.resolve_links();
+        let mut saved = Ok(());
+        let layout = self.save_directory_at(fuser::FUSE_ROOT_ID, &dir, layout, &mut saved);
+        self.layout = Some(layout);
+        saved
+    }
+
+    /// Saves the directory `inum` to `dir`, which held the files in `layout`;
+    /// returns what it holds now. If a file couldn't be saved, `result` gets
+    /// the error (unless it already has one).
+    fn save_directory_at(
+        &mut self,
+        inum: u64,
+        dir: &Path,
+        mut layout: DirLayout,
+        result: &mut std::io::Result<()>,
+    ) -> DirLayout {
+        self.resolve_node(inum).expect("resolve_node");
+        let entries = match &self.inodes[inum as usize].as_ref().unwrap().entry {
+            Entry::Directory(DirType::Named, files) => files
+                .iter()
+                .filter(|(name, _)| !self.config.ignored_file(name))
+                .map(|(name, de)| {
+                    let key = de.original_name.as_ref().unwrap_or(name).clone();
+                    (key, de.inum, de.kind)
+                })
+                .collect::<Vec<_>>(),
+            _ => {
+                warn!(
+                    "The mount of {} is no longer a named directory, so it won't be saved.",
+                    dir.display()
+                );
+                return layout;
+            }
+        };
+
+        let mut saved = DirLayout::default();
+        for (key, child, kind) in entries {
+            if let Some((subdir, sublayout)) = layout.dirs.remove(&key) {
+                if kind == FileType::Directory {
+                    let sublayout = self.save_directory_at(child, &subdir, sublayout, result);
+                    saved.dirs.insert(key, (subdir, sublayout));
+                    continue;
+                }
+                // no longer a directory, so it's saved as a file
+                self.remove_layout(&subdir, sublayout);
+            }
+
+            let (file, format) = layout.files.remove(&key).unwrap_or_else(|| {
+                let format = self.config.output_format;
+                (dir.join(format!("{}.{}", key, format)), format)
+            });
+            if let Err(e) = self.save_subtree(child, format, &file) {
+                if result.is_ok() {
+                    *result = Err(e);
+                }
+            }
+            saved.files.insert(key, (file, format));
+        }
+
+        // whatever's left was removed from the mount
+        for (file, _format) in layout.files.into_values() {
+            self.remove_file(&file);
+        }
+        for (subdir, sublayout) in layout.dirs.into_values() {
+            self.remove_layout(&subdir, sublayout);
+        }
+        saved
+    }
+
+    /// Deletes the directory `dir`, which holds the files in `layout`.
+    /// Anything else in it (e.g., hidden files) is left alone, along with the
+    /// directory itself.
+    fn remove_layout(&self, dir: &Path, layout: DirLayout) {
+        for (file, _format) in layout.files.into_values() {
+            self.remove_file(&file);
+        }
+        for (subdir, sublayout) in layout.dirs.into_values() {
+            self.remove_layout(&subdir, sublayout);
+        }
+
+        if self.config.dry_run {
+            return;
+        }
+        if let Err(e) = std::fs::remove_dir(dir) {
+            warn!("Unable to remove {}: {}.", dir.display(), e);
+        }
+    }
+
+    /// Deletes `file`, whose key was removed from the mount.
+    fn remove_file(&self, file: &Path) {
+        if self.config.dry_run {
+            eprintln!("would remove {}", file.display());
+            return;
+        }
+
+        info!("removing {}", file.display());
+        if let Err(e) = std::fs::remove_file(file) {
+            warn!("Unable to remove {}: {}.", file.display(), e);
+        }
+    }
+
+    /// Saves each subtree spliced in by `Config::include_tag` (and still in
+    /// the mount) back to its own file, in its own format.
+    fn save_includes(&mut self) -> std::io::Result<()> {
+        let includes = self
+            .inodes
+            .iter()
+            .flatten()
+            .filter_map(|inode| Some((inode.inum, inode.include.clone()?)))
+            .collect::<Vec<_>>();
+
+        let mut saved = Ok(());
+        for (inum, include) in includes {
+            let ancestry = self.ancestry(inum);
+            if !ancestry[1..]
+                .iter()
+                .all(|inum| self.name_of(*inum).is_some())
+            {
+                debug!("not saving {}, which was removed", include.file.display());
+                continue;
+            }
+
+            // without its `include`, the subtree is saved rather than referenced
+            let inode = self.inodes[inum as usize].as_mut().unwrap();
+            let include = inode.include.take().unwrap();
+            saved = saved.and(self.save_subtree(inum, include.format, &include.file));
+            self.inodes[inum as usize].as_mut().unwrap().include = Some(include);
+        }
+        saved
+    }
+
+    /// Saves the subtree `inum` to `file` in `format`.
+    fn save_subtree(&mut self, inum: u64, format: Format, file: &Path) -> std::io::Result<()> {
+        let writer = self.config.file_writer(file);
+        match format {
+            Format::Json => self.save_subtree_as::<json::Value>(inum, writer, file),
+            Format::Json5 => self.save_subtree_as::<json5::Value>(inum, writer, file),
+            Format::Ndjson => self.save_subtree_as::<ndjson::Value>(inum, writer, file),
+            Format::Toml => self.save_subtree_as::<toml::Value>(inum, writer, file),
+            Format::Yaml => self.save_subtree_as::<yaml::Value>(inum, writer, file),
+            Format::Ini => self.save_subtree_as::<ini::Value>(inum, writer, file),
+            Format::Properties => self.save_subtree_as::<properties::Value>(inum, writer, file),
+            Format::Hcl => self.save_subtree_as::<hcl::Value>(inum, writer, file),
+            Format::Ron => self.save_subtree_as::<ron::Value>(inum, writer, file),
+            Format::Bson => self.save_subtree_as::<bson::Value>(inum, writer, file),
+            Format::Env => self.save_subtree_as::<env::Value>(inum, writer, file),
+            Format::Plist => self.save_subtree_as::<plist::Value>(inum, writer, file),
+            Format::Protobuf => self.save_subtree_as::<protobuf::Value>(inum, writer, file),
+            Format::Edn => self.save_subtree_as::<edn::Value>(inum, writer, file),
+        }
+    }
+
+    /// Saves the subtree `inum` as a `U`; see `FS::save_subtree`.
+    fn save_subtree_as<U>(
+        &mut self,
+        inum: u64,
+        writer: Box<dyn Commit>,
+        file: &Path,
+    ) -> std::io::Result<()>
+    where
+        U: Nodelike,
+    {
+        let mut v: U = self.as_other_value(inum, &mut HashSet::new());
+        if self.config.sort_keys {
+            v.sort_keys();
+        }
+        write_out(writer, &file.display().to_string(), |writer| {
+            v.to_writer(writer, &self.config)
+        })
+    }
+
+    /// Rebuilds the document from the filesystem, reflecting every change made
+    /// so far; it's what `FS::sync` would save (before `Config::sort_keys`),
+    /// in the input format. The inverse of `FS::from_value`.
+    ///
+    /// ```
+    /// use ffs::config::Config;
+    /// use ffs::format::{json, Nodelike};
+    /// use ffs::fs::FS;
+    ///
+    /// let config = Config::default();
+    /// let input = r#"{"db": {"host": "localhost", "port": 5432, "replica": null},
+    ///                 "tags": ["a", ["b", null]], "empty": {}}"#;
+    /// let v = json::Value::from_reader(Box::new(input.as_bytes()), &config).unwrap();
+    /// let mut fs = FS::from_value(config, v.clone());
+    /// assert_eq!(fs.to_value(), v);
+    ///
+    /// // loading part of the tree doesn't change what comes back
+    /// fs.lookup_path("tags/1/1").unwrap();
+    /// fs.lookup_path("db/replica").unwrap();
+    /// assert_eq!(fs.to_value(), v);
+    /// ```
+    pub fn to_value(&mut self) -> V {
+        self.resolve_links();
+        if self.config.prune_empty {
+            // pruning needs to see inside unchanged (lazy) subtrees, too
+            for inum in fuser::FUSE_ROOT_ID..self.inodes.len() as u64 {
+                if let Some(Some(Inode {
+                    entry: Entry::Lazy(_),
+                    ..
+                })) = self.inodes.get(inum as usize)
+                {
+                    self.resolve_nodes_transitively(inum)
+                        .expect("resolve_nodes_transitively");
+                }
+            }
+        }
+        let root = self.as_value(fuser::FUSE_ROOT_ID, &mut HashSet::new());
+        self.splice_root(root, |v, _config| v)
+    }
+
+    /// Saves in a format other than the input format, which requires eagerly
+    /// resolving everything.
+    fn save_as<U>(&mut self, writer: Box<dyn Commit>, name: &str) -> std::io::Result<()>
+    where
+        U: Nodelike,
+    {
+        let mut v: U = time_ns!(
+            "saving",
+            {
+                let root = self.as_other_value(fuser::FUSE_ROOT_ID, &mut HashSet::new());
+                self.splice_root(root, format::convert)
+            },
+            self.config.timing
+        );
+        if self.config.sort_keys {
+            v.sort_keys();
+        }
+
+        time_ns!(
+            "writing",
+            write_out(writer, name, |writer| v.to_writer(writer, &self.config)),
+            self.config.timing
+        )
+    }
+
+    /// When only a subtree is mounted (see `Config::root_pointer`), puts the
+    /// saved `root` back into the rest of the document; `convert` brings the
+    /// document into the output format.
+    fn splice_root<U>(&self, root: U, convert: fn(V, &Config) -> U) -> U
+    where
+        U: Nodelike,
+    {
+        let root = if self.wrapped_root {
+            self.unwrap_root(root)
+        } else {
+            root
         };
 
-        if self.config.input_format == self.config.output_format {
-            let v = time_ns!(
-                "saving",
-                self.as_value(fuser::FUSE_ROOT_ID),
-                self.config.timing
+        match (&self.document, &self.config.root_pointer) {
+            (Some(document), Some(pointer)) => {
+                let mut document = convert(document.clone(), &self.config);
+                if document.replace_at(pointer, root).is_none() {
+                    unreachable!("pointer '{}' no longer resolves", pointer);
+                }
+                document
+            }
+            _ => root,
+        }
+    }
+
+    /// Undoes the wrapping of a primitive root (see `Config::allow_primitive_root`).
+    /// If the directory holds anything else, we save it as a directory.
+    fn unwrap_root<U>(&self, root: U) -> U
+    where
+        U: Nodelike,
+    {
+        let name = &self.config.primitive_root_name;
+
+        match root.node(&self.config) {
+            Node::Map(mut fvs) if fvs.len() == 1 && &fvs[0].0 == name => fvs.pop().unwrap().1,
+            Node::Map(fvs) => {
+                warn!(
+                    "The filesystem no longer holds just '{}', so it will be saved as a directory.",
+                    name
+                );
+                U::from_named_dir(fvs.into_iter().collect(), &self.config)
+            }
+            _ => unreachable!("the root is always a directory"),
+        }
+    }
+
+    /// Finds the target of the link `inum`, for saving. `following` holds
+    /// the links we're already saving, so we don't go around in circles.
+    fn link_target(&self, inum: u64, pointer: &str, following: &mut HashSet<u64>) -> Option<u64> {
+        if !following.insert(inum) {
+            warn!(
+                "The link to '{}' (inode {}) is circular; saving null.",
+                pointer, inum
             );
+            return None;
+        }
 
-            time_ns!(
-                "writing",
-                v.to_writer(writer, self.config.pretty),
-                self.config.timing
+        let target = self.find_pointer(pointer);
+        if target.is_none() {
+            warn!(
+                "The link to '{}' (inode {}) is dangling; saving null.",
+                pointer, inum
             );
-        } else {
-            match self.config.output_format {
-                Format::Json => {
-                    let v: json::Value = time_ns!(
-                        "saving",
-                        self.as_other_value(fuser::FUSE_ROOT_ID),
-                        self.config.timing
-                    );
-
-                    time_ns!(
-                        "writing",
-                        v.to_writer(writer, self.config.pretty),
-                        self.config.timing
-                    );
-                }
-                Format::Toml => {
-                    let v: toml::Value = time_ns!(
-                        "saving",
-                        self.as_other_value(fuser::FUSE_ROOT_ID),
-                        self.config.timing
-                    );
-
-                    time_ns!(
-                        "writing",
-                        v.to_writer(writer, self.config.pretty),
-                        self.config.timing
-                    );
-                }
-                Format::Yaml => {
-                    let v: yaml::Value = time_ns!(
-                        "saving",
-                        self.as_other_value(fuser::FUSE_ROOT_ID),
-                        self.config.timing
-                    );
+        }
+        target
+    }
 
-                    time_ns!(
-                        "writing",
-                        v.to_writer(writer, self.config.pretty),
-                        self.config.timing
-                    );
-                }
-            }
+    /// Should the value `v`, named `name` in a directory of type `typ`, be
+    /// left out when saving? See `Config::prune_empty`.
+    fn pruned<U>(&self, typ: DirType, name: &str, v: &U) -> bool
+    where
+        U: Nodelike,
+    {
+        if self.config.prune_empty && typ == DirType::Named && is_empty_dir(v) {
+            debug!("pruning empty directory '{}'", name);
+            true
+        } else {
+            false
         }
     }
 
     // save as a value of the same type as the input
     // we need this special case to avoid type-level shenanigans
-    fn as_value(&self, inum: u64) -> V {
+    fn as_value(&self, inum: u64, following: &mut HashSet<u64>) -> V {
+        let v = self.as_untagged_value(inum, following);
+        match &self.inodes[inum as usize].as_ref().unwrap().tag {
+            Some(tag) => v.with_tag(tag),
+            None => v,
+        }
+    }
+
+    /// `FS::as_value`, without the inode's tag (see `Inode::tag`).
+    fn as_untagged_value(&self, inum: u64, following: &mut HashSet<u64>) -> V {
+        if let Some(reference) = self.include_reference(inum) {
+            return V::from_string(Typ::String, reference, &self.config);
+        }
+
         match &self.inodes[inum as usize].as_ref().unwrap().entry {
             Entry::Lazy(v) => v.clone(),
-            Entry::File(typ, contents) => {
-                // TODO 2021-07-01 use _t to try to force the type
-                match String::from_utf8(contents.clone()) {
-                    Ok(mut contents) if typ != &Typ::Bytes => {
-                        if self.config.add_newlines && contents.ends_with('\n') {
-                            contents.truncate(contents.len() - 1);
-                        }
-                        // TODO 2021-06-24 trim?
-                        V::from_string(*typ, contents, &self.config)
-                    }
-                    Ok(_) | Err(_) => V::from_bytes(contents, &self.config),
-                }
+            Entry::Link(pointer) if pointer == NULL_LINK => {
+                V::from_string(Typ::Null, String::new(), &self.config)
             }
-            Entry::Directory(DirType::List, files) => {
+            Entry::Link(pointer) => {
+                let v = match self.link_target(inum, pointer, following) {
+                    Some(target) => self.as_value(target, following),
+                    None => V::from_string(Typ::Null, String::new(), &self.config),
+                };
+                following.remove(&inum);
+                v
+            }
+            Entry::File(typ, contents) => self
+                .element_value(inum, contents)
+                .or_else(|| self.blob_value(inum, contents))
+                .unwrap_or_else(|| self.file_value(*typ, contents)),
+            Entry::Directory(typ, files) => {
+                if let Some((typ, value)) = self.typed_leaf(*typ, files) {
+                    return self.typed_leaf_value(typ, value);
+                }
+
                 let mut entries = Vec::with_capacity(files.len());
+                // sorting puts list elements (even collapsed ones) in order
                 let mut files = files.iter().collect::<Vec<_>>();
-                files.sort_unstable_by(|(name1, _), (name2, _)| name1.cmp(name2));
-                for (name, DirEntry { inum, .. }) in files.iter() {
-                    if self.config.ignored_file(name) {
-                        warn!("skipping ignored file '{}'", name);
-                        continue;
+                match typ {
+                    DirType::Named => files.sort_unstable_by_key(|(name, _)| *name),
+                    DirType::List => {
+                        files.sort_unstable_by_key(|(name, _)| format::element_key(name))
                     }
-                    let v = self.as_value(*inum);
-                    entries.push(v);
                 }
-                V::from_list_dir(entries, &self.config)
-            }
-            Entry::Directory(DirType::Named, files) => {
-                let mut entries = HashMap::with_capacity(files.len());
                 for (
                     name,
                     DirEntry {
                         inum,
                         original_name,
+                        nesting,
+                        split,
                         ..
                     },
                 ) in files.iter()
@@ -539,72 +4081,101 @@ where
                         warn!("skipping ignored file '{}'", name);
                         continue;
                     }
-                    let v = self.as_value(*inum);
+                    let v = self.as_value(*inum, following);
+                    if self.pruned(*typ, name, &v) {
+                        continue;
+                    }
                     let name = original_name.as_ref().unwrap_or(name).into();
-                    entries.insert(name, v);
+                    if *split {
+                        Nested::splice(&mut entries, name, v, &self.config);
+                    } else {
+                        Nested::insert(&mut entries, name, nesting, v);
+                    }
                 }
-                V::from_named_dir(entries, &self.config)
+                self.restore_excluded(inum, &mut entries, |v, _| v);
+                Nested::Dir(*typ, entries).build(&self.config)
             }
         }
     }
 
     #[instrument(level = "trace", skip(self))]
-    fn as_other_value<U>(&mut self, inum: u64) -> U
+    fn as_other_value<U>(&mut self, inum: u64, following: &mut HashSet<u64>) -> U
     where
         U: Nodelike,
     {
+        if let Some(reference) = self.include_reference(inum) {
+            return U::from_string(Typ::String, reference, &self.config);
+        }
+
         match &self.inodes[inum as usize].as_ref().unwrap().entry {
             Entry::Lazy(_) => {
                 self.resolve_nodes_transitively(inum).unwrap();
-                self.as_other_value(inum)
-            }
-            Entry::File(typ, contents) => {
-                // TODO 2021-07-01 use _t to try to force the type
-                match String::from_utf8(contents.clone()) {
-                    Ok(mut contents) if typ != &Typ::Bytes => {
-                        if self.config.add_newlines && contents.ends_with('\n') {
-                            contents.truncate(contents.len() - 1);
-                        }
-                        // TODO 2021-06-24 trim?
-                        U::from_string(*typ, contents, &self.config)
-                    }
-                    Ok(_) | Err(_) => U::from_bytes(contents, &self.config),
-                }
+                self.as_other_value(inum, following)
             }
-            Entry::Directory(DirType::List, files) => {
+            Entry::Link(pointer) if pointer == NULL_LINK => {
+                U::from_string(Typ::Null, String::new(), &self.config)
+            }
+            Entry::Link(pointer) => {
+                let pointer = pointer.clone();
+                let v = match self.link_target(inum, &pointer, following) {
+                    Some(target) => self.as_other_value(target, following),
+                    None => U::from_string(Typ::Null, String::new(), &self.config),
+                };
+                following.remove(&inum);
+                v
+            }
+            Entry::File(typ, contents) => match self.element_value(inum, contents) {
+                Some(v) => format::convert(v, &self.config),
+                None => self
+                    .blob_value(inum, contents)
+                    .unwrap_or_else(|| self.file_value(*typ, contents)),
+            },
+            Entry::Directory(typ, files) => {
+                if let Some((typ, value)) = self.typed_leaf(*typ, files) {
+                    return self.typed_leaf_value(typ, value);
+                }
+
+                let typ = *typ;
                 let mut entries = Vec::with_capacity(files.len());
+
                 let mut files = files
                     .iter()
-                    .map(|(name, entry)| (name.clone(), entry.inum))
+                    .map(|(name, entry)| {
+                        (
+                            name.clone(),
+                            entry.inum,
+                            entry.original_name.clone(),
+                            entry.nesting.clone(),
+                            entry.split,
+                        )
+                    })
                     .collect::<Vec<_>>();
-                files.sort_unstable_by(|(name1, _), (name2, _)| name1.cmp(name2));
-                for (name, inum) in files {
+                match typ {
+                    DirType::Named => {
+                        files.sort_unstable_by(|(name1, ..), (name2, ..)| name1.cmp(name2))
+                    }
+                    DirType::List => files.sort_unstable_by(|(name1, ..), (name2, ..)| {
+                        format::element_key(name1).cmp(&format::element_key(name2))
+                    }),
+                }
+                for (name, inum, original_name, nesting, split) in files {
                     if self.config.ignored_file(&name) {
                         warn!("skipping ignored file '{}'", name);
                         continue;
                     }
-                    let v = self.as_other_value(inum);
-                    entries.push(v);
-                }
-                U::from_list_dir(entries, &self.config)
-            }
-            Entry::Directory(DirType::Named, files) => {
-                let mut entries = HashMap::with_capacity(files.len());
-
-                let files = files
-                    .iter()
-                    .map(|(name, entry)| (name.clone(), entry.inum, entry.original_name.clone()))
-                    .collect::<Vec<_>>();
-                for (name, inum, original_name) in files.iter() {
-                    if self.config.ignored_file(name) {
-                        warn!("skipping ignored file '{}'", name);
+                    let v = self.as_other_value(inum, following);
+                    if self.pruned(typ, &name, &v) {
                         continue;
                     }
-                    let v = self.as_other_value(*inum);
-                    let name = original_name.as_ref().unwrap_or(name).into();
-                    entries.insert(name, v);
+                    let name = original_name.unwrap_or(name);
+                    if split {
+                        Nested::splice(&mut entries, name, v, &self.config);
+                    } else {
+                        Nested::insert(&mut entries, name, &nesting, v);
+                    }
                 }
-                U::from_named_dir(entries, &self.config)
+                self.restore_excluded(inum, &mut entries, format::convert);
+                Nested::Dir(typ, entries).build(&self.config)
             }
         }
     }
@@ -634,6 +4205,11 @@ where
         Inode {
             parent,
             inum,
+            name: String::new(),
+            generation: 0,
+            blob: false,
+            include: None,
+            tag: None,
             uid,
             gid,
             mode,
@@ -658,7 +4234,7 @@ where
                     .filter(|(_, de)| de.kind == FileType::Directory)
                     .count() as u32
             }
-            Entry::File(..) => 1,
+            Entry::File(..) | Entry::Link(..) => 1,
             Entry::Lazy(..) => unreachable!("unresolved lazy value in Inode::attr"),
         };
 
@@ -670,8 +4246,8 @@ where
             mtime: self.mtime,
             nlink,
             size,
-            blksize: 1,
-            blocks: size,
+            blksize: BLOCK_SIZE,
+            blocks: size.div_ceil(BLOCK_SIZE as u64),
             kind,
             uid: self.uid,
             gid: self.gid,
@@ -703,6 +4279,7 @@ where
                 files.iter().map(|(name, _inum)| name.len() as u64).sum()
             }
             Entry::Directory(DirType::List, files) => files.len() as u64,
+            Entry::Link(pointer) => pointer.len() as u64,
             Entry::Lazy(v) => v.size() as u64, // give an answer because we can... but should
         }
     }
@@ -712,6 +4289,7 @@ where
         match self {
             Entry::File(..) => FileType::RegularFile,
             Entry::Directory(..) => FileType::Directory,
+            Entry::Link(..) => FileType::Symlink,
             Entry::Lazy(v) => v.kind(),
         }
     }
@@ -720,6 +4298,7 @@ where
         match self {
             Entry::File(t, _) => t.to_string(),
             Entry::Directory(t, _) => t.to_string(),
+            Entry::Link(_) => "link".into(),
             Entry::Lazy(_) => unreachable!("unresolved lazy value in Entry::typ"),
         }
     }
@@ -742,6 +4321,7 @@ where
                 }
                 Err(..) => false,
             },
+            Entry::Link(_) => false,
             Entry::Lazy(_) => todo!("Entry::try_set_typ"),
         }
     }
@@ -788,21 +4368,125 @@ const ENOATTR: i32 = libc::ENODATA;
 #[cfg(target_os = "macos")]
 const ENOATTR: i32 = libc::ENOATTR;
 
+// fallocate(2) modes; libc only defines these on Linux
+const FALLOC_FL_KEEP_SIZE: i32 = 0x01;
+const FALLOC_FL_PUNCH_HOLE: i32 = 0x02;
+const FALLOC_FL_ZERO_RANGE: i32 = 0x10;
+
+/// The extended attributes holding a file's type: `user.ffs.type`, and the
+/// older `user.type`.
+const TYPE_XATTRS: &[&str] = &["user.ffs.type", "user.type"];
+
+fn is_type_xattr(name: &OsStr) -> bool {
+    TYPE_XATTRS.iter().any(|attr| name == *attr)
+}
+
+/// Is `v` an empty list or map? (A directory is one node, plus the nodes of
+/// its contents.)
+fn is_empty_dir<U>(v: &U) -> bool
+where
+    U: Nodelike,
+{
+    v.kind() == FileType::Directory && v.size() == 1
+}
+
+/// Is `v` a null? See `Config::null_as`.
+fn is_null<U>(v: &U, config: &Config) -> bool
+where
+    U: Nodelike,
+{
+    v.kind() == FileType::RegularFile
+        && matches!(v.clone().node(config), Node::String(Typ::Null, _))
+}
+
+/// Writes an output with `write`, then commits it (see `Commit`), logging
+/// why if either fails. `name` says what's being saved.
+fn write_out<F>(mut writer: Box<dyn Commit>, name: &str, write: F) -> std::io::Result<()>
+where
+    F: FnOnce(&mut dyn std::io::Write) -> std::io::Result<()>,
+{
+    let saved = write(&mut writer).and_then(|()| writer.commit());
+    if let Err(e) = &saved {
+        error!("Couldn't save {}: {}.", name, e);
+    }
+    saved
+}
+
+/// The modification time of `file`, if it has one.
+fn modified(file: &Path) -> Option<SystemTime> {
+    std::fs::metadata(file)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+/// Decodes a filename given to us by the kernel.
+///
+/// All of our formats are UTF-8 text, so a name that isn't valid UTF-8 isn't
+/// missing, it's unrepresentable: we warn and return `EINVAL`.
+fn utf8_name(name: &OsStr) -> Result<&str, FSError> {
+    name.to_str().ok_or_else(|| {
+        warn!(
+            "Invalid filename '{}': names must be valid UTF-8",
+            name.to_string_lossy()
+        );
+        FSError::InvalidName(name.to_string_lossy().into())
+    })
+}
+
 impl<V> Filesystem for FS<V>
 where
     V: Nodelike,
 {
+    /// Asks the kernel to list directories with `readdirplus`, so `ls -l`
+    /// doesn't need a `lookup` for every entry.
+    #[instrument(level = "debug", skip(self, _req, config))]
+    fn init(&mut self, _req: &Request<'_>, config: &mut KernelConfig) -> Result<(), libc::c_int> {
+        info!("called");
+
+        if let Err(unsupported) = config.add_capabilities(fuser::consts::FUSE_DO_READDIRPLUS) {
+            debug!(
+                "kernel doesn't support readdirplus (capabilities {:#x})",
+                unsupported
+            );
+        }
+        Ok(())
+    }
+
     /// Synchronizes the `FS`, calling `FS::sync` with `last_sync == true`.
     #[instrument(level = "debug", skip(self), fields(dirty = self.dirty.get()))]
     fn destroy(&mut self) {
         info!("called");
-        self.sync(true);
+        // `sync` has already logged why it couldn't save
+        let _ = self.sync(true);
     }
 
     #[instrument(level = "debug", skip(self, _req, reply))]
     fn statfs(&mut self, _req: &Request<'_>, _ino: u64, reply: ReplyStatfs) {
         info!("called");
-        reply.statfs(0, 0, 0, 0, 0, 1, 255, 0);
+
+        let namelen = self.config.name_max;
+        // only count what's been loaded, i.e., what `du` could have seen
+        let mut files = 0;
+        let mut blocks = 0;
+        for inode in self.inodes.iter().flatten() {
+            if self.info_file == Some(inode.inum) {
+                continue;
+            }
+            files += 1;
+            if let Entry::File(..) = inode.entry {
+                blocks += inode.attr().blocks;
+            }
+        }
+
+        // with a capacity, report it as the size, and what's left as free
+        if let Some(capacity) = self.config.capacity {
+            let total = capacity.div_ceil(BLOCK_SIZE as u64);
+            let free = total.saturating_sub(self.used_bytes.div_ceil(BLOCK_SIZE as u64));
+            reply.statfs(total, free, free, files, 0, BLOCK_SIZE, namelen, BLOCK_SIZE);
+            return;
+        }
+
+        reply.statfs(blocks, 0, 0, files, 0, BLOCK_SIZE, namelen, BLOCK_SIZE);
     }
 
     #[instrument(level = "debug", skip(self, req, reply))]
@@ -813,6 +4497,12 @@ where
             return;
         }
 
+        // nothing is writable on a read-only mount, whatever its mode says
+        if self.config.read_only && mask & libc::W_OK != 0 {
+            reply.error(libc::EACCES);
+            return;
+        }
+
         match self.get(inode) {
             Ok(inode) => {
                 // cribbed from https://github.com/cberner/fuser/blob/4639a490f4aa7dfe8a342069a761d4cf2bd8f821/examples/simple.rs#L1703-L1736
@@ -825,9 +4515,11 @@ where
                     mask -= mask & (mode >> 6);
                     mask -= mask & (mode >> 3);
                     mask -= mask & mode;
-                } else if req.uid() == self.config.uid {
+                } else if req.uid() == attr.uid {
+                    // the file's owner, which under `Config::allow_other`
+                    // may not be the mount's
                     mask -= mask & (mode >> 6);
-                } else if req.gid() == self.config.gid {
+                } else if req.gid() == attr.gid {
                     mask -= mask & (mode >> 3);
                 } else {
                     mask -= mask & mode;
@@ -839,66 +4531,61 @@ where
                     reply.error(libc::EACCES);
                 }
             }
-            Err(_) => reply.error(libc::ENOENT),
+            Err(e) => reply.error(e.errno()),
         }
     }
 
-    #[instrument(level = "debug", skip(self, _req, reply))]
-    fn lookup(&mut self, _req: &Request, parent: u64, name: &OsStr, reply: ReplyEntry) {
+    #[instrument(level = "debug", skip(self, req, reply))]
+    fn lookup(&mut self, req: &Request, parent: u64, name: &OsStr, reply: ReplyEntry) {
         info!("called");
-        let dir = match self.get(parent) {
-            Err(_e) => {
-                reply.error(libc::ENOENT);
-                return;
-            }
-            Ok(inode) => inode,
-        };
 
-        let filename = match name.to_str() {
-            None => {
-                reply.error(libc::ENOENT);
-                return;
-            }
-            Some(name) => name,
-        };
+        if !self.check_read_access(req) {
+            reply.error(libc::EACCES);
+            return;
+        }
 
-        let inum = match &dir.entry {
-            Entry::Directory(_kind, files) => match files.get(filename) {
-                None => {
-                    reply.error(libc::ENOENT);
-                    return;
-                }
-                Some(DirEntry { inum, .. }) => *inum,
-            },
-            _ => {
-                reply.error(libc::ENOTDIR);
+        let ttl = self.config.cache_ttl;
+        self.check_watch();
+        self.check_sync();
+        let filename = match utf8_name(name) {
+            Err(e) => {
+                reply.error(e.errno());
                 return;
             }
+            Ok(name) => name,
         };
 
-        let file = match self.get(inum) {
-            Err(_e) => {
-                reply.error(libc::ENOENT);
-                return;
+        match self.lookup_name(parent, filename) {
+            Ok(inum) => {
+                let file = self.inodes[inum as usize].as_ref().unwrap();
+                reply.entry(&ttl, &file.attr(), file.generation);
             }
-            Ok(inode) => inode,
-        };
-
-        reply.entry(&TTL, &file.attr(), 0);
+            Err(e) => reply.error(e.errno()),
+        }
     }
 
-    #[instrument(level = "debug", skip(self, _req, reply))]
-    fn getattr(&mut self, _req: &Request, ino: u64, reply: ReplyAttr) {
+    #[instrument(level = "debug", skip(self, req, reply))]
+    fn getattr(&mut self, req: &Request, ino: u64, reply: ReplyAttr) {
         info!("called");
+
+        if !self.check_read_access(req) {
+            reply.error(libc::EACCES);
+            return;
+        }
+
+        let ttl = self.config.cache_ttl;
+        self.check_watch();
+        self.check_sync();
+        self.refresh_synthetic(ino);
         let file = match self.get(ino) {
-            Err(_e) => {
-                reply.error(libc::ENOENT);
+            Err(e) => {
+                reply.error(e.errno());
                 return;
             }
             Ok(inode) => inode,
         };
 
-        reply.attr(&TTL, &file.attr());
+        reply.attr(&ttl, &file.attr());
     }
 
     #[instrument(
@@ -927,165 +4614,45 @@ where
         reply: ReplyAttr,
     ) {
         info!("called");
+        let ttl = self.config.cache_ttl;
 
         if !self.check_access(req) {
             reply.error(libc::EPERM);
             return;
         }
 
-        if let Some(mode) = mode {
-            info!("chmod to {:o}", mode);
-
-            if mode != mode & 0o777 {
-                info!("truncating mode {:o} to {:o}", mode, mode & 0o777);
-            }
-            let mode = (mode as u16) & 0o777;
-
-            match self.get_mut(ino) {
-                Ok(inode) => {
-                    inode.mode = mode;
-                    reply.attr(&TTL, &inode.attr());
-                    return;
-                }
-                Err(_) => {
-                    reply.error(libc::ENOENT);
-                    return;
-                }
-            };
-        }
-
-        // cribbing from https://github.com/cberner/fuser/blob/13557921548930afd6b70e109521044fea98c23b/examples/simple.rs#L594-L639
-        if uid.is_some() || gid.is_some() {
-            info!("chown called with uid {:?} guid {:?}", uid, gid);
-
-            // gotta be a member of the target group!
-            if let Some(gid) = gid {
-                let groups = groups_for(req.uid());
-                if req.uid() != 0 && !groups.contains(&gid) {
-                    reply.error(libc::EPERM);
-                    return;
-                }
-            }
-
-            let inode = match self.get_mut(ino) {
-                Ok(inode) => inode,
-                Err(_) => {
-                    reply.error(libc::ENOENT);
-                    return;
-                }
-            };
-
-            // non-root owner can only do noop uid changes
-            if let Some(uid) = uid {
-                if req.uid() != 0 && !(uid == inode.uid && req.uid() == inode.uid) {
-                    reply.error(libc::EPERM);
-                    return;
-                }
-            }
-
-            // only owner may change the group
-            if gid.is_some() && req.uid() != 0 && req.uid() != inode.uid {
-                reply.error(libc::EPERM);
-                return;
-            }
-
-            // NB if we allowed SETUID/SETGID bits, we might need to clear them here
-            if let Some(uid) = uid {
-                inode.uid = uid;
-            }
-
-            if let Some(gid) = gid {
-                inode.gid = gid;
-            }
-
-            inode.ctime = SystemTime::now();
-            reply.attr(&TTL, &inode.attr());
+        if self.is_synthetic(ino) {
+            reply.error(libc::EPERM);
             return;
         }
 
-        if let Some(size) = size {
+        let result = if let Some(mode) = mode {
+            info!("chmod to {:o}", mode);
+            self.chmod(ino, mode)
+        } else if uid.is_some() || gid.is_some() {
+            info!("chown called with uid {:?} guid {:?}", uid, gid);
+            self.chown(req.uid(), ino, uid, gid)
+        } else if let Some(size) = size {
             info!("truncate() to {}", size);
-
-            match self.get_mut(ino) {
-                Ok(inode) => match &mut inode.entry {
-                    Entry::File(_t, contents) => {
-                        contents.resize(size as usize, 0);
-                        reply.attr(&TTL, &inode.attr());
-                    }
-                    Entry::Directory(..) => {
-                        reply.error(libc::EISDIR);
-                        return;
-                    }
-                    Entry::Lazy(..) => unreachable!("unresolved lazy value found in setattr"),
-                },
-                Err(_) => {
-                    reply.error(libc::ENOENT);
-                    return;
-                }
-            };
-
-            self.dirty.set(true);
-            return;
-        }
-
-        let now = SystemTime::now();
-        let mut set_time = false;
-        if let Some(atime) = atime {
-            info!("setting atime");
-            if !self.check_access(req) {
-                reply.error(libc::EPERM);
-                return;
-            }
-            match self.get_mut(ino) {
-                Ok(inode) => {
-                    inode.atime = match atime {
-                        TimeOrNow::Now => now,
-                        TimeOrNow::SpecificTime(time) => time,
-                    }
-                }
-                Err(_) => {
-                    reply.error(libc::ENOENT);
-                    return;
-                }
-            }
-
-            set_time = true;
-        }
-
-        if let Some(mtime) = mtime {
-            info!("setting mtime");
-
-            if !self.check_access(req) {
-                reply.error(libc::EPERM);
-                return;
-            }
-            match self.get_mut(ino) {
-                Ok(inode) => {
-                    inode.mtime = match mtime {
-                        TimeOrNow::Now => now,
-                        TimeOrNow::SpecificTime(time) => time,
-                    }
-                }
-                Err(_) => {
-                    reply.error(libc::ENOENT);
-                    return;
-                }
-            }
-
-            set_time = true;
-        }
-
-        if set_time {
-            reply.attr(&TTL, &self.get(ino).unwrap().attr());
+            self.truncate(ino, size)
+        } else if atime.is_some() || mtime.is_some() {
+            info!("setting atime {:?} and mtime {:?}", atime, mtime);
+            self.set_times(ino, atime, mtime)
         } else {
             reply.error(libc::ENOSYS);
+            return;
+        };
+
+        match result.and_then(|()| self.get(ino).map(Inode::attr)) {
+            Ok(attr) => reply.attr(&ttl, &attr),
+            Err(e) => reply.error(e.errno()),
         }
     }
 
-    #[instrument(level = "debug", skip(self, _req, reply))]
+    #[instrument(level = "debug", skip(self, req, reply))]
     fn getxattr(
         &mut self,
-        _req: &Request<'_>,
+        req: &Request<'_>,
         ino: u64,
         name: &OsStr,
         size: u32,
@@ -1093,37 +4660,33 @@ where
     ) {
         info!("called");
 
+        if !self.check_read_access(req) {
+            reply.error(libc::EACCES);
+            return;
+        }
+
         if !self.config.allow_xattr {
             info!("disabled");
             reply.error(libc::ENOSYS);
             return;
-        }
-
-        let file = match self.get(ino) {
-            Err(_e) => {
-                reply.error(libc::EFAULT);
-                return;
-            }
-            Ok(inode) => inode,
-        };
-
-        if name == "user.type" {
-            let user_type = file.entry.typ().into_bytes();
-            let actual_size = user_type.len() as u32;
-
-            if size == 0 {
-                reply.size(actual_size);
-                return;
-            } else if size < actual_size {
-                reply.error(libc::ERANGE);
-                return;
-            } else {
-                reply.data(&user_type);
+        }
+
+        let value = match self.get_xattr(ino, name) {
+            Ok(value) => value,
+            Err(e) => {
+                reply.error(e.errno());
                 return;
             }
-        }
+        };
+        let actual_size = value.len() as u32;
 
-        reply.error(ENOATTR);
+        if size == 0 {
+            reply.size(actual_size);
+        } else if size < actual_size {
+            reply.error(libc::ERANGE);
+        } else {
+            reply.data(&value);
+        }
     }
 
     #[instrument(level = "debug", skip(self, req, reply, value, _flags, _position))]
@@ -1149,51 +4712,38 @@ where
             return;
         }
 
-        let file = match self.get_mut(ino) {
-            Err(_e) => {
-                reply.error(libc::EFAULT);
-                return;
-            }
-            Ok(inode) => inode,
-        };
-
-        if name == "user.type" {
-            match std::str::from_utf8(value) {
-                Err(_) => {
-                    reply.error(libc::EINVAL);
-                }
-                Ok(s) => {
-                    if file.entry.try_set_typ(s) {
-                        reply.ok()
-                    } else {
-                        reply.error(libc::EINVAL)
-                    }
-                }
-            }
-        } else {
-            reply.error(libc::EINVAL);
+        match self.set_xattr(ino, name, value) {
+            Ok(()) => reply.ok(),
+            Err(e) => reply.error(e.errno()),
         }
     }
 
-    #[instrument(level = "debug", skip(self, _req, reply))]
-    fn listxattr(&mut self, _req: &Request<'_>, ino: u64, size: u32, reply: ReplyXattr) {
+    #[instrument(level = "debug", skip(self, req, reply))]
+    fn listxattr(&mut self, req: &Request<'_>, ino: u64, size: u32, reply: ReplyXattr) {
         info!("called");
 
+        if !self.check_read_access(req) {
+            reply.error(libc::EACCES);
+            return;
+        }
+
         if !self.config.allow_xattr {
             reply.error(libc::ENOSYS);
             return;
         }
 
-        if self.get(ino).is_err() {
-            reply.error(libc::EFAULT);
+        if let Err(e) = self.get(ino) {
+            reply.error(e.errno());
             return;
         }
 
         // TODO 2021-07-02
         // - we could add user.original_name here when present
-        // - we could use a clearer name (e.g., `user.ffs.type`)
-        let mut attrs: Vec<u8> = "user.type".into();
-        attrs.push(0);
+        let mut attrs: Vec<u8> = Vec::new();
+        for attr in TYPE_XATTRS {
+            attrs.extend_from_slice(attr.as_bytes());
+            attrs.push(0);
+        }
         let actual_size = attrs.len() as u32;
 
         if size == 0 {
@@ -1205,102 +4755,100 @@ where
         }
     }
 
-    #[instrument(level = "debug", skip(self, _req, reply))]
-    fn removexattr(&mut self, _req: &Request<'_>, ino: u64, name: &OsStr, reply: ReplyEmpty) {
+    #[instrument(level = "debug", skip(self, req, reply))]
+    fn removexattr(&mut self, req: &Request<'_>, ino: u64, name: &OsStr, reply: ReplyEmpty) {
         info!("called");
 
-        // 50 ways to leave your lover: this call never succeeds
-
         if !self.config.allow_xattr {
             reply.error(libc::ENOSYS);
             return;
         }
 
-        if self.get(ino).is_err() {
-            reply.error(libc::EFAULT);
+        if !self.check_access(req) {
+            reply.error(libc::EPERM);
             return;
         }
 
-        if name == "user.type" {
-            reply.error(libc::EACCES);
-        } else {
-            reply.error(ENOATTR);
+        match self.remove_xattr(ino, name) {
+            Ok(()) => reply.ok(),
+            Err(e) => reply.error(e.errno()),
         }
     }
 
-    #[instrument(level = "debug", skip(self, _req, reply))]
+    #[instrument(level = "debug", skip(self, req, reply))]
     fn read(
         &mut self,
-        _req: &Request,
+        req: &Request,
         ino: u64,
         _fh: u64,
         offset: i64,
-        _size: u32,
+        size: u32,
         _flags: i32,
         _lock: Option<u64>,
         reply: ReplyData,
     ) {
         info!("called");
-        let file = match self.get(ino) {
-            Err(_e) => {
-                reply.error(libc::ENOENT);
+
+        if !self.check_read_access(req) {
+            reply.error(libc::EACCES);
+            return;
+        }
+
+        match self.file_contents(ino) {
+            Ok(s) => {
+                // reads past the end (e.g., of a file that's been truncated) are empty
+                let start = (offset as usize).min(s.len());
+                let end = start.saturating_add(size as usize).min(s.len());
+                reply.data(&s[start..end])
+            }
+            Err(e) => {
+                reply.error(e.errno());
                 return;
             }
-            Ok(inode) => inode,
-        };
-
-        match &file.entry {
-            Entry::File(_t, s) => reply.data(&s[offset as usize..]),
-            _ => reply.error(libc::ENOENT),
         }
+        self.touch_atime(ino);
     }
 
-    #[instrument(level = "debug", skip(self, _req, reply))]
+    #[instrument(level = "debug", skip(self, req, reply))]
     fn readdir(
         &mut self,
-        _req: &Request,
+        req: &Request,
         ino: u64,
-        _fh: u64,
+        fh: u64,
         offset: i64,
         mut reply: ReplyDirectory,
     ) {
         info!("called");
 
-        let inode = match self.get(ino) {
-            Err(_e) => {
-                reply.error(libc::ENOENT);
-                return;
-            }
-            Ok(inode) => inode,
-        };
-
-        match &inode.entry {
-            Entry::File(..) => reply.error(libc::ENOTDIR),
-            Entry::Directory(_kind, files) => {
-                let dot_entries = vec![
-                    (ino, FileType::Directory, "."),
-                    (inode.parent, FileType::Directory, ".."),
-                ];
-
-                let entries = files.iter().map(|(filename, DirEntry { inum, kind, .. })| {
-                    (*inum, *kind, filename.as_str())
-                });
+        if !self.check_read_access(req) {
+            reply.error(libc::EACCES);
+            return;
+        }
 
-                for (i, entry) in dot_entries
-                    .into_iter()
-                    .chain(entries)
-                    .into_iter()
-                    .enumerate()
-                    .skip(offset as usize)
-                {
-                    if reply.add(entry.0, (i + 1) as i64, entry.1, entry.2) {
-                        break;
-                    }
+        // page through the snapshot from `opendir`; without one, list afresh
+        let fresh;
+        let entries = match self.dir_handles.get(&fh) {
+            Some(entries) => entries,
+            None => match self.dir_listing(ino) {
+                Ok(entries) => {
+                    fresh = entries;
+                    &fresh
                 }
-                reply.ok()
+                Err(e) => {
+                    reply.error(e.errno());
+                    return;
+                }
+            },
+        };
+
+        // each entry's offset is that of the next one
+        let start = (offset as usize).min(entries.len());
+        for (i, (inum, kind, name)) in entries[start..].iter().enumerate() {
+            if reply.add(*inum, (start + i + 1) as i64, *kind, name) {
+                break;
             }
-            Entry::Lazy(..) => unreachable!("unresolved lazy value in readdir"),
         }
+        reply.ok()
     }
 
     #[instrument(level = "debug", skip(self, _req, reply))]
@@ -1332,6 +4880,7 @@ where
         reply: ReplyEntry,
     ) {
         info!("called");
+        let ttl = self.config.cache_ttl;
 
         // access control
         if !self.check_access(req) {
@@ -1351,71 +4900,27 @@ where
         }
 
         // get the filename
-        let filename = match name.to_str() {
-            None => {
-                reply.error(libc::ENOENT);
-                return;
-            }
-            Some(name) => name,
-        };
-
-        // make sure the parent exists, is a directory, and doesn't have that file
-        match self.get(parent) {
-            Err(_e) => {
-                reply.error(libc::ENOENT);
+        let filename = match utf8_name(name) {
+            Err(e) => {
+                reply.error(e.errno());
                 return;
             }
-            Ok(inode) => match &inode.entry {
-                Entry::File(..) => {
-                    reply.error(libc::ENOTDIR);
-                    return;
-                }
-                Entry::Directory(_dirtype, files) => {
-                    if files.contains_key(filename) {
-                        reply.error(libc::EEXIST);
-                        return;
-                    }
-                }
-                Entry::Lazy(..) => unreachable!("unresolved lazy value in mknod"),
-            },
+            Ok(name) => name,
         };
 
-        // create the inode entry
-        let (entry, kind) = if file_type == libc::S_IFREG as u32 {
-            (Entry::File(Typ::Auto, Vec::new()), FileType::RegularFile)
+        let kind = if file_type == libc::S_IFDIR as u32 {
+            FileType::Directory
         } else {
-            assert_eq!(file_type, libc::S_IFDIR as u32);
-            (
-                Entry::Directory(DirType::Named, HashMap::new()),
-                FileType::Directory,
-            )
+            FileType::RegularFile
         };
-
-        // allocate the inode (sets dirty bit)
-        let inum = self.fresh_inode(parent, entry, req.uid(), req.gid(), mode);
-
-        // update the parent
-        // NB we can't get_mut the parent earlier due to borrowing restrictions
-        match self.get_mut(parent) {
-            Err(_e) => unreachable!("error finding parent again"),
-            Ok(inode) => match &mut inode.entry {
-                Entry::File(..) => unreachable!("parent changed to a regular file"),
-                Entry::Directory(_dirtype, files) => {
-                    files.insert(
-                        filename.into(),
-                        DirEntry {
-                            kind,
-                            original_name: None,
-                            inum,
-                        },
-                    );
-                }
-                Entry::Lazy(..) => unreachable!("unresolved lazy value in mknod"),
-            },
-        };
-
-        reply.entry(&TTL, &self.get(inum).unwrap().attr(), 0);
-        assert!(self.dirty.get());
+        match self.make_node(parent, filename, kind, req.uid(), req.gid(), mode) {
+            Ok(inum) => {
+                let file = self.get(inum).unwrap();
+                reply.entry(&ttl, &file.attr(), file.generation);
+                assert!(self.dirty.get());
+            }
+            Err(e) => reply.error(e.errno()),
+        }
     }
 
     #[instrument(level = "debug", skip(self, req, reply))]
@@ -1429,6 +4934,7 @@ where
         reply: ReplyEntry,
     ) {
         info!("called");
+        let ttl = self.config.cache_ttl;
 
         if !self.check_access(req) {
             reply.error(libc::EACCES);
@@ -1436,64 +4942,23 @@ where
         }
 
         // get the new directory name
-        let filename = match name.to_str() {
-            None => {
-                reply.error(libc::ENOENT);
-                return;
-            }
-            Some(name) => name,
-        };
-
-        // make sure the parent exists, is a directory, and doesn't have anything with that name
-        match self.get(parent) {
-            Err(_e) => {
-                reply.error(libc::ENOENT);
+        let filename = match utf8_name(name) {
+            Err(e) => {
+                reply.error(e.errno());
                 return;
             }
-            Ok(inode) => match &inode.entry {
-                Entry::File(..) => {
-                    reply.error(libc::ENOTDIR);
-                    return;
-                }
-                Entry::Directory(_dirtype, files) => {
-                    if files.contains_key(filename) {
-                        reply.error(libc::EEXIST);
-                        return;
-                    }
-                }
-                Entry::Lazy(..) => unreachable!("unresolved lazy value in mkdir"),
-            },
+            Ok(name) => name,
         };
 
-        // create the inode entry
-        let entry = Entry::Directory(DirType::Named, HashMap::new());
         let kind = FileType::Directory;
-
-        // allocate the inode (sets dirty bit)
-        let inum = self.fresh_inode(parent, entry, req.uid(), req.gid(), mode);
-
-        // update the parent
-        // NB we can't get_mut the parent earlier due to borrowing restrictions
-        match self.get_mut(parent) {
-            Err(_e) => unreachable!("error finding parent again"),
-            Ok(inode) => match &mut inode.entry {
-                Entry::File(..) => unreachable!("parent changed to a regular file"),
-                Entry::Directory(_dirtype, files) => {
-                    files.insert(
-                        filename.into(),
-                        DirEntry {
-                            kind,
-                            original_name: None,
-                            inum,
-                        },
-                    );
-                }
-                Entry::Lazy(..) => unreachable!("unresolved lazy value in mkdir"),
-            },
-        };
-
-        reply.entry(&TTL, &self.get(inum).unwrap().attr(), 0);
-        assert!(self.dirty.get());
+        match self.make_node(parent, filename, kind, req.uid(), req.gid(), mode) {
+            Ok(inum) => {
+                let dir = self.get(inum).unwrap();
+                reply.entry(&ttl, &dir.attr(), dir.generation);
+                assert!(self.dirty.get());
+            }
+            Err(e) => reply.error(e.errno()),
+        }
     }
 
     #[instrument(level = "debug", skip(self, req, reply))]
@@ -1501,11 +4966,11 @@ where
         &mut self,
         req: &Request,
         ino: u64,
-        _fh: u64,
+        fh: u64,
         offset: i64,
         data: &[u8],
         _write_flags: u32,
-        _flags: i32,
+        flags: i32,
         _lock_owner: Option<u64>,
         reply: ReplyWrite,
     ) {
@@ -1519,189 +4984,60 @@ where
             return;
         }
 
-        // find inode
-        let file = match self.get_mut(ino) {
-            Err(_e) => {
-                reply.error(libc::ENOENT);
-                return;
-            }
-            Ok(inode) => inode,
-        };
-
-        // load contents
-        let contents = match &mut file.entry {
-            Entry::File(_t, contents) => contents,
-            Entry::Directory(_, _) => {
-                reply.error(libc::EISDIR);
+        // no writing to files opened read-only
+        if let Some(open_file) = self.file_handles.get(&fh) {
+            if !open_file.writable() {
+                reply.error(libc::EBADF);
                 return;
             }
-            Entry::Lazy(..) => unreachable!("unresolved lazy value in write"),
-        };
-
-        // make space
-        let extra_bytes = (offset + data.len() as i64) - contents.len() as i64;
-        if extra_bytes > 0 {
-            contents.resize(contents.len() + extra_bytes as usize, 0);
-        }
-
-        // actually write
-        let offset = offset as usize;
-        contents[offset..offset + data.len()].copy_from_slice(data);
-        self.dirty.set(true);
-
-        reply.written(data.len() as u32);
-    }
-
-    #[instrument(level = "debug", skip(self, req, reply))]
-    fn unlink(&mut self, req: &Request, parent: u64, name: &OsStr, reply: ReplyEmpty) {
-        info!("called");
-
-        // access control
-        if !self.check_access(req) {
-            reply.error(libc::EACCES);
-            return;
         }
 
-        // get the filename
-        let filename = match name.to_str() {
-            None => {
-                reply.error(libc::ENOENT);
-                return;
-            }
-            Some(name) => name,
-        };
-
-        // find the parent
-        let files = match self.get_mut(parent) {
-            Err(_e) => {
-                reply.error(libc::ENOENT);
-                return;
-            }
-            Ok(Inode {
-                entry: Entry::Directory(_dirtype, files),
-                ..
-            }) => files,
-            Ok(Inode {
-                entry: Entry::File(..),
-                ..
-            }) => {
-                reply.error(libc::ENOTDIR);
-                return;
-            }
-            Ok(Inode {
-                entry: Entry::Lazy(..),
-                ..
-            }) => unreachable!("unresolved lazy value in unlink"),
-        };
-
-        // ensure it's a regular file
-        match files.get(filename) {
-            Some(DirEntry {
-                kind: FileType::RegularFile,
-                ..
-            }) => (),
-            _ => {
-                reply.error(libc::EPERM);
-                return;
+        // files opened with O_APPEND always write at the end
+        let append = flags & libc::O_APPEND != 0;
+        match self.write_at(ino, offset as usize, data, append) {
+            Ok(()) => {
+                if let Some(open_file) = self.file_handles.get_mut(&fh) {
+                    open_file.written = true;
+                }
+                reply.written(data.len() as u32)
             }
+            Err(e) => reply.error(e.errno()),
         }
-
-        // try to remove it
-        let res = files.remove(filename);
-        assert!(res.is_some());
-        self.dirty.set(true);
-        reply.ok();
     }
 
     #[instrument(level = "debug", skip(self, req, reply))]
-    fn rmdir(&mut self, req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEmpty) {
+    fn unlink(&mut self, req: &Request, parent: u64, name: &OsStr, reply: ReplyEmpty) {
         info!("called");
 
-        // access control
-        if !self.check_access(req) {
-            reply.error(libc::EACCES);
-            return;
-        }
-
-        // get the filename
-        let filename = match name.to_str() {
-            None => {
-                reply.error(libc::ENOENT);
-                return;
-            }
-            Some(name) => name,
-        };
-
-        // find the parent
-        let files = match self.get(parent) {
-            Err(_e) => {
-                reply.error(libc::ENOENT);
-                return;
-            }
-            Ok(Inode {
-                entry: Entry::Directory(_dirtype, files),
-                ..
-            }) => files,
-            Ok(Inode {
-                entry: Entry::File(..),
-                ..
-            }) => {
-                reply.error(libc::ENOTDIR);
-                return;
-            }
-            Ok(Inode {
-                entry: Entry::Lazy(..),
-                ..
-            }) => unreachable!("unresolved lazy value in rmdir"),
-        };
-
-        // find the actual directory being deleted
-        let inum = match files.get(filename) {
-            Some(DirEntry {
-                kind: FileType::Directory,
-                inum,
-                ..
-            }) => *inum,
-            Some(_) => {
-                reply.error(libc::ENOTDIR);
-                return;
-            }
-            None => {
-                reply.error(libc::ENOENT);
-                return;
-            }
-        };
+        // access control
+        if !self.check_access(req) {
+            reply.error(libc::EACCES);
+            return;
+        }
 
-        // make sure it's empty
-        match self.get(inum) {
-            Ok(Inode {
-                entry: Entry::Directory(_, dir_files),
-                ..
-            }) => {
-                if !dir_files.is_empty() {
-                    reply.error(libc::ENOTEMPTY);
-                    return;
-                }
-            }
-            Ok(_) => unreachable!("mismatched metadata on inode {} in parent {}", inum, parent),
-            _ => unreachable!("couldn't find inode {} in parent {}", inum, parent),
-        };
+        let result =
+            utf8_name(name).and_then(|filename| self.remove_entry(parent, filename, false));
+        match result {
+            Ok(()) => reply.ok(),
+            Err(e) => reply.error(e.errno()),
+        }
+    }
 
-        // find the parent again, mutably
-        let files = match self.get_mut(parent) {
-            Ok(Inode {
-                entry: Entry::Directory(_dirtype, files),
-                ..
-            }) => files,
-            Ok(_) => unreachable!("parent changed to a regular file"),
-            Err(_) => unreachable!("error finding parent again"),
-        };
+    #[instrument(level = "debug", skip(self, req, reply))]
+    fn rmdir(&mut self, req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEmpty) {
+        info!("called");
 
-        // try to remove it
-        let res = files.remove(filename);
-        assert!(res.is_some());
-        self.dirty.set(true);
-        reply.ok();
+        // access control
+        if !self.check_access(req) {
+            reply.error(libc::EACCES);
+            return;
+        }
+
+        let result = utf8_name(name).and_then(|filename| self.remove_entry(parent, filename, true));
+        match result {
+            Ok(()) => reply.ok(),
+            Err(e) => reply.error(e.errno()),
+        }
     }
 
     #[instrument(level = "debug", skip(self, req, reply))]
@@ -1723,124 +5059,14 @@ where
             return;
         }
 
-        let src = match name.to_str() {
-            None => {
-                reply.error(libc::ENOENT);
-                return;
-            }
-            Some(name) => name,
-        };
-
-        if src == "." || src == ".." {
-            reply.error(libc::EINVAL);
-            return;
-        }
-
-        let tgt = match newname.to_str() {
-            None => {
-                reply.error(libc::ENOENT);
-                return;
-            }
-            Some(name) => name,
-        };
-
-        // make sure src exists
-        let (src_kind, src_original, src_inum) = match self.get(parent) {
-            Ok(Inode {
-                entry: Entry::Directory(_kind, files),
-                ..
-            }) => match files.get(src) {
-                Some(DirEntry {
-                    kind,
-                    original_name,
-                    inum,
-                    ..
-                }) => (*kind, original_name.clone(), *inum),
-                None => {
-                    reply.error(libc::ENOENT);
-                    return;
-                }
-            },
-            _ => {
-                reply.error(libc::ENOENT);
-                return;
-            }
-        };
-        // determine whether tgt exists
-        let tgt_info = match self.get(newparent) {
-            Ok(Inode {
-                entry: Entry::Directory(_kind, files),
-                ..
-            }) => match files.get(tgt) {
-                Some(DirEntry { kind, inum, .. }) => {
-                    if src_kind != *kind {
-                        reply.error(libc::ENOTDIR);
-                        return;
-                    }
-                    Some((*kind, *inum))
-                }
-                None => None,
-            },
-            _ => {
-                reply.error(libc::ENOENT);
-                return;
-            }
-        };
-
-        // if tgt exists and is a directory, make sure it's empty
-        if let Some((FileType::Directory, tgt_inum)) = tgt_info {
-            match self.get(tgt_inum) {
-                Ok(Inode {
-                    entry: Entry::Directory(_type, files),
-                    ..
-                }) => {
-                    if !files.is_empty() {
-                        reply.error(libc::ENOTEMPTY);
-                        return;
-                    }
-                }
-                _ => unreachable!("bad metadata on inode {} in {}", tgt_inum, newparent),
-            }
-        }
-        // remove src from parent
-        match self.get_mut(parent) {
-            Ok(Inode {
-                entry: Entry::Directory(_kind, files),
-                ..
-            }) => files.remove(src),
-            _ => unreachable!("parent changed"),
-        };
-
-        // add src as tgt to newparent
-        match self.get_mut(newparent) {
-            Ok(Inode {
-                entry: Entry::Directory(_kind, files),
-                ..
-            }) => files.insert(
-                tgt.into(),
-                DirEntry {
-                    kind: src_kind,
-                    // if the filename is the same, we'll keep the source
-                    // original filename (if it exists; otherwise we overwrite
-                    // it)
-                    original_name: if src == tgt { src_original } else { None },
-                    inum: src_inum,
-                },
-            ),
-            _ => unreachable!("parent changed"),
-        };
-
-        // set src's parent inode
-        match self.get_mut(src_inum) {
-            Ok(inode) => inode.parent = newparent,
-            Err(_) => unreachable!(
-                "missing inode {} moved from {} to {}",
-                src_inum, parent, newparent
-            ),
+        let result = utf8_name(name).and_then(|src| {
+            let tgt = utf8_name(newname)?;
+            self.rename_entry(parent, src, newparent, tgt)
+        });
+        match result {
+            Ok(()) => reply.ok(),
+            Err(e) => reply.error(e.errno()),
         }
-
-        self.dirty.set(true);
-        reply.ok();
     }
 
     #[instrument(level = "debug", skip(self, req, reply))]
@@ -1856,67 +5082,36 @@ where
     ) {
         info!("called");
 
-        if offset < 0 || length <= 0 {
-            reply.error(libc::EINVAL);
-            return;
-        }
-
-        if mode != 0 {
-            reply.error(libc::EOPNOTSUPP);
-            return;
-        }
-
         // access control
         if !self.check_access(req) {
             reply.error(libc::EACCES);
             return;
         }
 
-        // load the contents
-        let contents = match self.get_mut(ino) {
-            Ok(Inode {
-                entry: Entry::File(_t, contents),
-                ..
-            }) => contents,
-            Ok(Inode {
-                entry: Entry::Directory(..),
-                ..
-            }) => {
-                reply.error(libc::EBADF);
-                return;
-            }
-            Ok(Inode {
-                entry: Entry::Lazy(..),
-                ..
-            }) => unreachable!("unresolved lazy value in fallocate"),
-
-            Err(_e) => {
-                reply.error(libc::ENODEV);
-                return;
-            }
-        };
-
-        // extend the vector
-        let extra_bytes = (offset + length as i64) - contents.len() as i64;
-        if extra_bytes > 0 {
-            contents.resize(contents.len() + extra_bytes as usize, 0);
+        match self.allocate(ino, offset, length, mode) {
+            Ok(()) => reply.ok(),
+            Err(e) => reply.error(e.errno()),
         }
-
-        self.dirty.set(true);
-        reply.ok()
     }
 
     #[instrument(level = "debug", skip(self, _req, reply))]
     fn fsync(
         &mut self,
         _req: &Request<'_>,
-        _ino: u64,
+        ino: u64,
         _fh: u64,
         _datasync: bool,
         reply: ReplyEmpty,
     ) {
         info!("called");
-        reply.error(libc::ENOSYS);
+
+        if !self.config.stream {
+            reply.error(libc::ENOSYS);
+            return;
+        }
+
+        self.stream(ino);
+        reply.ok();
     }
 
     // TODO
@@ -1961,11 +5156,42 @@ where
     #[instrument(level = "debug", skip(self, _req))]
     fn forget(&mut self, _req: &Request<'_>, _ino: u64, _nlookup: u64) {}
 
-    #[instrument(level = "debug", skip(self, _req, reply))]
-    fn readlink(&mut self, _req: &Request<'_>, _ino: u64, reply: ReplyData) {
+    #[instrument(level = "debug", skip(self, req, reply))]
+    fn readlink(&mut self, req: &Request<'_>, ino: u64, reply: ReplyData) {
         info!("called");
 
-        reply.error(libc::ENOSYS);
+        if !self.check_read_access(req) {
+            reply.error(libc::EACCES);
+            return;
+        }
+
+        let pointer = match self.get(ino) {
+            Ok(Inode {
+                entry: Entry::Link(pointer),
+                ..
+            }) => pointer.clone(),
+            Ok(_) => {
+                reply.error(libc::EINVAL);
+                return;
+            }
+            Err(e) => {
+                reply.error(e.errno());
+                return;
+            }
+        };
+
+        if pointer == NULL_LINK {
+            reply.data(NULL_LINK.as_bytes());
+            return;
+        }
+
+        match self.link_path(ino, &pointer) {
+            Some(path) => reply.data(path.as_bytes()),
+            None => {
+                warn!("The link to '{}' (inode {}) is dangling.", pointer, ino);
+                reply.error(libc::ENOENT);
+            }
+        }
     }
 
     #[instrument(level = "debug", skip(self, _req, reply))]
@@ -1996,34 +5222,89 @@ where
         reply.error(libc::ENOSYS);
     }
 
-    #[instrument(level = "debug", skip(self, _req, reply))]
-    fn open(&mut self, _req: &Request<'_>, _ino: u64, _flags: i32, reply: ReplyOpen) {
+    #[instrument(level = "debug", skip(self, req, reply))]
+    fn open(&mut self, req: &Request<'_>, ino: u64, flags: i32, reply: ReplyOpen) {
         info!("called");
+        self.check_watch();
+        self.check_sync();
 
         // TODO 2021-06-16 access check?
-        reply.opened(0, 0);
+        if let Err(e) = self.get(ino) {
+            reply.error(e.errno());
+            return;
+        }
+
+        if self.is_synthetic(ino) && flags & libc::O_ACCMODE != libc::O_RDONLY {
+            reply.error(libc::EACCES);
+            return;
+        }
+
+        if flags & libc::O_TRUNC != 0 && flags & libc::O_ACCMODE != libc::O_RDONLY {
+            if self.config.read_only {
+                reply.error(libc::EROFS);
+                return;
+            }
+
+            if !self.check_access(req) {
+                reply.error(libc::EACCES);
+                return;
+            }
+
+            if let Err(e) = self.truncate(ino, 0) {
+                reply.error(e.errno());
+                return;
+            }
+        }
+
+        let fh = self.fresh_fh();
+        self.file_handles.insert(
+            fh,
+            OpenFile {
+                flags,
+                written: false,
+            },
+        );
+        reply.opened(fh, 0);
     }
 
     #[instrument(level = "debug", skip(self, _req, reply))]
     fn flush(
         &mut self,
         _req: &Request<'_>,
-        _ino: u64,
-        _fh: u64,
+        ino: u64,
+        fh: u64,
         _lock_owner: u64,
         reply: ReplyEmpty,
     ) {
         info!("called");
 
-        reply.error(libc::ENOSYS);
+        if !self.config.stream && !self.config.sync_on_close {
+            reply.error(libc::ENOSYS);
+            return;
+        }
+
+        // `close(2)` waits for a flush, but not for the release; shells flush
+        // once before writing anything, when they `dup2(2)` the new file onto
+        // stdout, so we only stream elements that have been written to
+        if let Some(open_file) = self.file_handles.get(&fh).filter(|f| f.writable()) {
+            if self.config.stream {
+                if open_file.written {
+                    self.stream(ino);
+                }
+            } else {
+                self.schedule_sync();
+            }
+        }
+        self.check_sync();
+        reply.ok();
     }
 
     #[instrument(level = "debug", skip(self, _req, reply))]
     fn release(
         &mut self,
         _req: &Request<'_>,
-        _ino: u64,
-        _fh: u64,
+        ino: u64,
+        fh: u64,
         _flags: i32,
         _lock_owner: Option<u64>,
         _flush: bool,
@@ -2031,27 +5312,92 @@ where
     ) {
         info!("called");
 
+        if let Some(open_file) = self.file_handles.remove(&fh) {
+            if self.config.stream && open_file.writable() {
+                self.stream(ino);
+            }
+        }
         reply.ok();
     }
-    #[instrument(level = "debug", skip(self, _req, reply))]
-    fn opendir(&mut self, _req: &Request<'_>, _ino: u64, _flags: i32, reply: ReplyOpen) {
+    #[instrument(level = "debug", skip(self, req, reply))]
+    fn opendir(&mut self, req: &Request<'_>, ino: u64, _flags: i32, reply: ReplyOpen) {
         info!("called");
 
-        reply.opened(0, 0);
+        if !self.check_read_access(req) {
+            reply.error(libc::EACCES);
+            return;
+        }
+
+        self.check_watch();
+        self.check_sync();
+
+        match self.dir_listing(ino) {
+            Ok(entries) => {
+                let fh = self.fresh_fh();
+                self.dir_handles.insert(fh, entries);
+                reply.opened(fh, 0);
+            }
+            Err(e) => reply.error(e.errno()),
+        }
     }
 
-    #[instrument(level = "debug", skip(self, _req, reply))]
+    /// Like `readdir`, but with each entry's attributes (as `lookup` would
+    /// give them).
+    #[instrument(level = "debug", skip(self, req, reply))]
     fn readdirplus(
         &mut self,
-        _req: &Request<'_>,
-        _ino: u64,
-        _fh: u64,
-        _offset: i64,
-        reply: ReplyDirectoryPlus,
+        req: &Request<'_>,
+        ino: u64,
+        fh: u64,
+        offset: i64,
+        mut reply: ReplyDirectoryPlus,
     ) {
         info!("called");
 
-        reply.error(libc::ENOSYS);
+        if !self.check_read_access(req) {
+            reply.error(libc::EACCES);
+            return;
+        }
+
+        let ttl = self.config.cache_ttl;
+        // page through the snapshot from `opendir`, taking it out (rather
+        // than copying it) while we need `self`; without one, list afresh
+        let (entries, snapshot) = match self.dir_handles.remove(&fh) {
+            Some(entries) => (entries, true),
+            None => match self.dir_listing(ino) {
+                Ok(entries) => (entries, false),
+                Err(e) => {
+                    reply.error(e.errno());
+                    return;
+                }
+            },
+        };
+
+        let start = (offset as usize).min(entries.len());
+        for (i, (inum, _kind, name)) in entries[start..].iter().enumerate() {
+            self.refresh_synthetic(*inum);
+            // entries removed since `opendir` are skipped
+            let file = match self.get(*inum) {
+                Ok(inode) => inode,
+                Err(_e) => continue,
+            };
+
+            if reply.add(
+                *inum,
+                (start + i + 1) as i64,
+                name,
+                &ttl,
+                &file.attr(),
+                file.generation,
+            ) {
+                break;
+            }
+        }
+
+        if snapshot {
+            self.dir_handles.insert(fh, entries);
+        }
+        reply.ok()
     }
 
     #[instrument(level = "debug", skip(self, _req, reply))]
@@ -2059,12 +5405,13 @@ where
         &mut self,
         _req: &Request<'_>,
         _ino: u64,
-        _fh: u64,
+        fh: u64,
         _flags: i32,
         reply: ReplyEmpty,
     ) {
         info!("called");
 
+        self.dir_handles.remove(&fh);
         reply.ok();
     }
 
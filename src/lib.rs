@@ -0,0 +1,260 @@
+//! ffs, the file filesystem: mounts semi-structured data (JSON, YAML, TOML,
+//! etc.) as a filesystem. See `fs::FS`.
+
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+use fuser::{BackgroundSession, MountOption, Session};
+use signal_hook::consts::{SIGINT, SIGTERM};
+use signal_hook::iterator::Signals;
+use tracing::{info, warn};
+
+use config::{Config, Input, ERROR_STATUS_FUSE};
+use format::{Format, Nodelike};
+use fs::FS;
+
+pub mod cli;
+pub mod config;
+pub mod format;
+pub mod fs;
+
+/// Loads the input described by `config` and mounts it on `config.mount` in a
+/// background thread, for embedding ffs in another program.
+///
+/// The filesystem is served from that one thread, so operations on it are
+/// handled one at a time; other threads may freely use the mount. Dropping
+/// the returned session unmounts the filesystem, after which the background
+/// thread saves any changes (see `FS::sync`) and exits. Call
+/// `BackgroundSession::join` rather than dropping it to wait until the output
+/// has been written.
+///
+/// Input that can't be loaded (see `FS::try_new`) is an error of kind
+/// `io::ErrorKind::InvalidData`, and nothing is mounted.
+pub fn mount(config: Config) -> io::Result<BackgroundSession> {
+    match config.input_format {
+        Format::Json => spawn::<format::json::Value>(config),
+        Format::Json5 => spawn::<format::json5::Value>(config),
+        Format::Ndjson => spawn::<format::ndjson::Value>(config),
+        Format::Toml => spawn::<format::toml::Value>(config),
+        Format::Yaml => spawn::<format::yaml::Value>(config),
+        Format::Ini => spawn::<format::ini::Value>(config),
+        Format::Properties => spawn::<format::properties::Value>(config),
+        Format::Hcl => spawn::<format::hcl::Value>(config),
+        Format::Ron => spawn::<format::ron::Value>(config),
+        Format::Bson => spawn::<format::bson::Value>(config),
+        Format::Env => spawn::<format::env::Value>(config),
+        Format::Plist => spawn::<format::plist::Value>(config),
+        Format::Protobuf => spawn::<format::protobuf::Value>(config),
+        Format::Edn => spawn::<format::edn::Value>(config),
+    }
+}
+
+fn spawn<V>(config: Config) -> io::Result<BackgroundSession>
+where
+    V: Nodelike + Send + 'static,
+{
+    let mount = config
+        .mount
+        .clone()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no mount point specified"))?;
+    let options = config.mount_options();
+    mount_session::<V>(&config, &mount, &options)?.spawn()
+}
+
+/// Loads the input described by `config` and mounts it on `mount`, ready to
+/// be run.
+///
+/// When the mountpoint is busy (`EBUSY`), or holds a mount whose ffs died
+/// without unmounting it (`ENOTCONN`), the mount is retried
+/// `Config::mount_retries` more times, `Config::mount_retry_delay` apart.
+/// Under `Config::force_unmount`, such a stale FUSE mount is unmounted before
+/// retrying; nothing else mounted there is touched. A failed mount takes the
+/// filesystem with it, so each retry loads the input again---except from
+/// standard input, which can't be read twice, and so isn't retried.
+///
+/// Input that can't be loaded (see `FS::try_new`) is an error of kind
+/// `io::ErrorKind::InvalidData`.
+pub fn mount_session<V>(
+    config: &Config,
+    mount: &Path,
+    options: &[MountOption],
+) -> io::Result<Session<FS<V>>>
+where
+    V: Nodelike,
+{
+    let mut retries = 0;
+    loop {
+        let fs: FS<V> = FS::try_new(config.clone())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+
+        info!("mounting on {:?} with options {:?}", mount, options);
+        let e = match Session::new(fs, mount, options) {
+            Ok(session) => return Ok(session),
+            Err(e) if mount_point_busy(&e) => e,
+            Err(e) => return Err(e),
+        };
+
+        if retries == config.mount_retries || matches!(config.input, Input::Stdin) {
+            let hint = if config.force_unmount {
+                ""
+            } else {
+                " (`--force-unmount` unmounts a stale one first)"
+            };
+            return Err(io::Error::other(format!(
+                "{} is still busy after {} retries ({}); is something (e.g., a dead ffs) mounted there?{}",
+                mount.display(),
+                retries,
+                e,
+                hint
+            )));
+        }
+        retries += 1;
+
+        if config.force_unmount && stale_fuse_mount(mount) {
+            warn!(
+                "{} holds a stale FUSE mount; unmounting it.",
+                mount.display()
+            );
+            unmount(mount);
+        }
+
+        info!(
+            "{} is busy ({}); retrying in {:?}",
+            mount.display(),
+            e,
+            config.mount_retry_delay
+        );
+        std::thread::sleep(config.mount_retry_delay);
+    }
+}
+
+/// Checks whether a mount failed because the mountpoint is busy or holds a
+/// dead mount. Mounting with `mount(2)` gives us the error itself, but
+/// `fusermount` only says so in its message.
+fn mount_point_busy(e: &io::Error) -> bool {
+    if matches!(e.raw_os_error(), Some(libc::EBUSY) | Some(libc::ENOTCONN))
+        || matches!(
+            e.kind(),
+            io::ErrorKind::ResourceBusy | io::ErrorKind::NotConnected
+        )
+    {
+        return true;
+    }
+
+    let msg = e.to_string();
+    msg.contains("Device or resource busy") || msg.contains("Transport endpoint is not connected")
+}
+
+/// Checks whether `mount` holds a FUSE mount (per `/proc/self/mountinfo`)
+/// that no longer answers (`ENOTCONN`), i.e., one whose process died.
+fn stale_fuse_mount(mount: &Path) -> bool {
+    match std::fs::metadata(mount) {
+        Err(e) if e.raw_os_error() == Some(libc::ENOTCONN) => (),
+        _ => return false,
+    }
+
+    // the mountpoint itself can't be reached, so we resolve its parent
+    let mount = match (mount.parent(), mount.file_name()) {
+        (Some(parent), Some(name)) => {
+            let parent = if parent.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                parent
+            };
+            match parent.canonicalize() {
+                Ok(parent) => parent.join(name),
+                Err(_) => return false,
+            }
+        }
+        _ => return false,
+    };
+
+    let mountinfo = match std::fs::read_to_string("/proc/self/mountinfo") {
+        Ok(mountinfo) => mountinfo,
+        Err(e) => {
+            info!("couldn't read /proc/self/mountinfo: {}", e);
+            return false;
+        }
+    };
+    // fields are space separated, with the filesystem type after a lone `-`;
+    // the last mount on a mountpoint is the one we see
+    let fstype = mountinfo
+        .lines()
+        .filter_map(|line| {
+            let (fields, rest) = line.split_once(" - ")?;
+            let point = fields.split(' ').nth(4)?;
+            Some((unescape_mountinfo(point), rest.split(' ').next()?))
+        })
+        .filter(|(point, _)| Path::new(point) == mount)
+        .map(|(_, fstype)| fstype)
+        .next_back();
+    matches!(fstype, Some(fstype) if fstype == "fuse" || fstype.starts_with("fuse."))
+}
+
+/// Undoes the octal escapes (e.g., `\040` for a space) in a path from
+/// `/proc/self/mountinfo`.
+fn unescape_mountinfo(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let octal = bytes.get(i + 1..i + 4).and_then(|digits| {
+            std::str::from_utf8(digits)
+                .ok()
+                .and_then(|digits| u8::from_str_radix(digits, 8).ok())
+        });
+        match (bytes[i], octal) {
+            (b'\\', Some(byte)) => {
+                out.push(byte);
+                i += 4;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Unmounts `mount` when the process gets `SIGINT` or `SIGTERM`, so the
+/// session loop ends and the filesystem is saved (see
+/// `Config::save_on_signal`). The signals are handled on their own thread,
+/// which never touches the filesystem itself: unmounting makes the kernel
+/// send the session its `destroy`, as `umount` would. A second signal exits
+/// without saving.
+pub fn unmount_on_signal(mount: &Path) -> io::Result<()> {
+    let mut signals = Signals::new([SIGINT, SIGTERM])?;
+    let mount = mount.to_path_buf();
+    std::thread::spawn(move || {
+        let mut signals = signals.forever();
+        if let Some(signal) = signals.next() {
+            info!("got signal {}; unmounting {}", signal, mount.display());
+            unmount(&mount);
+        }
+        if signals.next().is_some() {
+            warn!("Exiting without saving.");
+            std::process::exit(ERROR_STATUS_FUSE);
+        }
+    });
+    Ok(())
+}
+
+/// Unmounts `mount` with the first of `fusermount3 -u`, `fusermount -u`, and
+/// `umount` that works.
+fn unmount(mount: &Path) {
+    let commands: &[&[&str]] = &[&["fusermount3", "-u"], &["fusermount", "-u"], &["umount"]];
+    for command in commands {
+        match Command::new(command[0])
+            .args(&command[1..])
+            .arg(mount)
+            .status()
+        {
+            Ok(status) if status.success() => return,
+            Ok(status) => info!("`{}` failed: {}", command.join(" "), status),
+            Err(e) => info!("couldn't run `{}`: {}", command.join(" "), e),
+        }
+    }
+    warn!("Couldn't unmount {}.", mount.display());
+}
@@ -1,23 +1,133 @@
-use tracing::{error, info, warn};
+use std::collections::HashMap;
+use std::path::Path;
 
-mod cli;
-mod config;
-mod format;
-mod fs;
+use tracing::{error, info, warn};
 
-use config::{Config, ERROR_STATUS_CLI, ERROR_STATUS_FUSE};
-use format::Format;
-use fs::FS;
+use ffs::config::{Config, ERROR_STATUS_CLI, ERROR_STATUS_FUSE};
+use ffs::format::{self, Format, Nodelike};
+use ffs::fs::FS;
 
 use fuser::MountOption;
 
+/// Loads the input as `V`s and mounts it, returning an exit status once the
+/// filesystem is unmounted.
+fn run<V>(config: Config, mount: &Path, options: &[MountOption]) -> i32
+where
+    V: Nodelike,
+{
+    let mut session = match ffs::mount_session::<V>(&config, mount, options) {
+        Ok(session) => session,
+        Err(e) => {
+            error!("{}", e);
+            return ERROR_STATUS_FUSE;
+        }
+    };
+
+    match session.run() {
+        Ok(()) => {
+            info!("unmounted");
+            0
+        }
+        Err(e) => {
+            error!("I/O error: {}", e);
+            ERROR_STATUS_FUSE
+        }
+    }
+}
+
+/// Loads the input as `V`s and saves it in the output format, without
+/// mounting it, returning an exit status.
+fn convert<V>(config: Config) -> i32
+where
+    V: Nodelike,
+{
+    let mut fs: FS<V> = match FS::try_new(config) {
+        Ok(fs) => fs,
+        Err(e) => {
+            error!("{}", e);
+            return ERROR_STATUS_FUSE;
+        }
+    };
+    match fs.sync(true) {
+        Ok(()) => 0,
+        Err(_) => ERROR_STATUS_FUSE,
+    }
+}
+
+/// Loads the input as `V`s and prints statistics about its structure,
+/// returning an exit status.
+fn analyze<V>(mut config: Config) -> i32
+where
+    V: Nodelike,
+{
+    let v = match config.input_reader() {
+        Some(reader) => match format::read_input(reader, &config) {
+            Ok(v) => v,
+            Err(e) => {
+                error!(
+                    "Couldn't parse {} as {}: {}.",
+                    config.input, config.input_format, e
+                );
+                return ERROR_STATUS_FUSE;
+            }
+        },
+        None => V::from_named_dir(HashMap::new(), &config),
+    };
+
+    let stats = format::Stats::of(v, &config);
+    if config.analyze_json {
+        println!("{}", stats.to_json());
+    } else {
+        println!("{}", stats);
+    }
+    0
+}
+
 fn main() {
     let config = Config::from_args();
-    let mut options = vec![MountOption::FSName(format!("{}", config.input))];
-    if config.read_only {
-        options.push(MountOption::RO);
+
+    if config.analyze {
+        let status = match config.input_format {
+            Format::Json => analyze::<format::json::Value>(config),
+            Format::Json5 => analyze::<format::json5::Value>(config),
+            Format::Ndjson => analyze::<format::ndjson::Value>(config),
+            Format::Toml => analyze::<format::toml::Value>(config),
+            Format::Yaml => analyze::<format::yaml::Value>(config),
+            Format::Ini => analyze::<format::ini::Value>(config),
+            Format::Properties => analyze::<format::properties::Value>(config),
+            Format::Hcl => analyze::<format::hcl::Value>(config),
+            Format::Ron => analyze::<format::ron::Value>(config),
+            Format::Bson => analyze::<format::bson::Value>(config),
+            Format::Env => analyze::<format::env::Value>(config),
+            Format::Plist => analyze::<format::plist::Value>(config),
+            Format::Protobuf => analyze::<format::protobuf::Value>(config),
+            Format::Edn => analyze::<format::edn::Value>(config),
+        };
+        std::process::exit(status);
     }
 
+    if config.convert {
+        let status = match config.input_format {
+            Format::Json => convert::<format::json::Value>(config),
+            Format::Json5 => convert::<format::json5::Value>(config),
+            Format::Ndjson => convert::<format::ndjson::Value>(config),
+            Format::Toml => convert::<format::toml::Value>(config),
+            Format::Yaml => convert::<format::yaml::Value>(config),
+            Format::Ini => convert::<format::ini::Value>(config),
+            Format::Properties => convert::<format::properties::Value>(config),
+            Format::Hcl => convert::<format::hcl::Value>(config),
+            Format::Ron => convert::<format::ron::Value>(config),
+            Format::Bson => convert::<format::bson::Value>(config),
+            Format::Env => convert::<format::env::Value>(config),
+            Format::Plist => convert::<format::plist::Value>(config),
+            Format::Protobuf => convert::<format::protobuf::Value>(config),
+            Format::Edn => convert::<format::edn::Value>(config),
+        };
+        std::process::exit(status);
+    }
+
+    let options = config.mount_options();
+
     assert!(config.mount.is_some());
     let mount = match &config.mount {
         Some(mount) => mount.clone(),
@@ -28,55 +138,32 @@ fn main() {
             std::process::exit(ERROR_STATUS_CLI);
         }
     };
+    if config.save_on_signal {
+        if let Err(e) = ffs::unmount_on_signal(&mount) {
+            warn!(
+                "Couldn't handle signals, so they'll lose unsaved changes: {}",
+                e
+            );
+        }
+    }
     let cleanup_mount = config.cleanup_mount;
     let input_format = config.input_format;
 
     let status = match input_format {
-        Format::Json => {
-            let fs: FS<format::json::Value> = FS::new(config);
-
-            info!("mounting on {:?} with options {:?}", mount, options);
-            match fuser::mount2(fs, &mount, &options) {
-                Ok(()) => {
-                    info!("unmounted");
-                    0
-                }
-                Err(e) => {
-                    error!("I/O error: {}", e);
-                    ERROR_STATUS_FUSE
-                }
-            }
-        }
-        Format::Toml => {
-            let fs: FS<format::toml::Value> = FS::new(config);
-
-            info!("mounting on {:?} with options {:?}", mount, options);
-            match fuser::mount2(fs, &mount, &options) {
-                Ok(()) => {
-                    info!("unmounted");
-                    0
-                }
-                Err(e) => {
-                    error!("I/O error: {}", e);
-                    ERROR_STATUS_FUSE
-                }
-            }
-        }
-        Format::Yaml => {
-            let fs: FS<format::yaml::Value> = FS::new(config);
-
-            info!("mounting on {:?} with options {:?}", mount, options);
-            match fuser::mount2(fs, &mount, &options) {
-                Ok(()) => {
-                    info!("unmounted");
-                    0
-                }
-                Err(e) => {
-                    error!("I/O error: {}", e);
-                    ERROR_STATUS_FUSE
-                }
-            }
-        }
+        Format::Json => run::<format::json::Value>(config, &mount, &options),
+        Format::Json5 => run::<format::json5::Value>(config, &mount, &options),
+        Format::Ndjson => run::<format::ndjson::Value>(config, &mount, &options),
+        Format::Toml => run::<format::toml::Value>(config, &mount, &options),
+        Format::Yaml => run::<format::yaml::Value>(config, &mount, &options),
+        Format::Ini => run::<format::ini::Value>(config, &mount, &options),
+        Format::Properties => run::<format::properties::Value>(config, &mount, &options),
+        Format::Hcl => run::<format::hcl::Value>(config, &mount, &options),
+        Format::Ron => run::<format::ron::Value>(config, &mount, &options),
+        Format::Bson => run::<format::bson::Value>(config, &mount, &options),
+        Format::Env => run::<format::env::Value>(config, &mount, &options),
+        Format::Plist => run::<format::plist::Value>(config, &mount, &options),
+        Format::Protobuf => run::<format::protobuf::Value>(config, &mount, &options),
+        Format::Edn => run::<format::edn::Value>(config, &mount, &options),
     };
 
     if cleanup_mount {
@@ -0,0 +1,29 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+
+ffs --readonly -m "$MNT" ../json/object.json &
+PID=$!
+sleep 2
+cd "$MNT"
+[ -r name ] || fail read
+[ -w name ] && fail write
+[ -w . ] && fail write_dir
+cd - >/dev/null 2>&1
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+rmdir "$MNT" || fail mount
@@ -0,0 +1,43 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+
+ffs --allow-uid nobody -m "$MNT" ../json/object.json
+[ $? -eq 2 ] || fail bad_uid
+
+# the rest needs another user and permission to use allow_other
+if ! sudo -n -u nobody true >/dev/null 2>&1 ||
+   { [ "$(id -u)" -ne 0 ] && ! grep -q '^user_allow_other$' /etc/fuse.conf 2>/dev/null; }
+then
+    rmdir "$MNT"
+    exit 0
+fi
+
+# the mountpoint's parent needs to be visible to nobody
+chmod o+rx "$MNT"
+
+ffs --allow-uid $(id -u nobody) -m "$MNT" --no-output ../json/object.json &
+PID=$!
+sleep 2
+[ "$(sudo -n -u nobody cat "$MNT"/name)" = "Michael Greenberg" ] || fail read
+sudo -n -u nobody sh -c "echo 3 >'$MNT'/eyes" || fail write
+sudo -n -u nobody sh -c "echo hi >'$MNT'/greeting" || fail create
+ls -l "$MNT"/greeting | grep nobody >/dev/null 2>&1 || fail owner
+sudo -n -u nobody test -w "$MNT"/greeting || fail owner_access
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+rmdir "$MNT" || fail mount
@@ -0,0 +1,15 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    exit 1
+}
+
+OUT=$(ffs --analyze ../json/object.json) || fail status
+[ -d object ] && fail mounted
+echo "$OUT" | grep -e '^files: 4$' >/dev/null 2>&1 || fail files
+echo "$OUT" | grep -e '^directories: 1$' >/dev/null 2>&1 || fail directories
+echo "$OUT" | grep -e '^max depth: 1$' >/dev/null 2>&1 || fail depth
+
+OUT=$(echo '[[1,2,3],{"a":[]}]' | ffs --analyze-json) || fail json_status
+[ "$OUT" = '{"inodes":7,"files":3,"directories":4,"max_depth":3,"largest_list":3,"largest_map":1}' ] || fail json
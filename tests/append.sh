@@ -0,0 +1,28 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+
+ffs -m "$MNT" --no-output ../json/object.json &
+PID=$!
+sleep 2
+printf 'first' >"$MNT"/log
+# open in append mode once, writing twice
+{ printf ' second'; printf ' third'; } >>"$MNT"/log
+printf ' fourth' >>"$MNT"/log
+[ "$(cat "$MNT"/log)" = "first second third fourth" ] || fail append
+umount "$MNT" || fail unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+rmdir "$MNT" || fail mount
@@ -18,7 +18,7 @@ echo '{}' >"$FILE"
 
 EXP=$(mktemp)
 
-printf '{"favorite_number":47,"likes":{"cats":false,"dogs":true},"mistakes":null,"name":"Michael Greenberg","website":"https://mgree.github.io"}' >"$EXP"
+printf '{"favorite_number":47,"likes":{"cats":false,"dogs":true},"mistakes":null,"name":"Michael Greenberg","website":"https://mgree.github.io"}\n' >"$EXP"
 
 ffs -m "$MNT" -i "$FILE" &
 PID=$!
@@ -0,0 +1,43 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$DOC"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+DOC=$(mktemp)
+
+# detect() FORMAT DOCUMENT: STDIN and an extensionless file should both be read as FORMAT
+detect() {
+    printf "$2" | ffs -m "$MNT" --no-output &
+    PID=$!
+    sleep 2
+    [ "$(cat "$MNT"/server/port)" -eq 8080 ] || fail "$1 stdin"
+    umount "$MNT" || fail "$1 stdin unmount"
+    sleep 1
+    kill -0 $PID >/dev/null 2>&1 && fail "$1 stdin process"
+
+    printf "$2" >"$DOC"
+    ffs -m "$MNT" --no-output --source auto "$DOC" &
+    PID=$!
+    sleep 2
+    [ "$(cat "$MNT"/server/port)" -eq 8080 ] || fail "$1 file"
+    umount "$MNT" || fail "$1 file unmount"
+    sleep 1
+    kill -0 $PID >/dev/null 2>&1 && fail "$1 file process"
+}
+
+detect json '{"server": {"port": 8080}}\n'
+detect yaml '# config\nserver:\n  port: 8080\n'
+detect toml '[server]\nport = 8080\n'
+
+rmdir "$MNT" || fail mount
+rm "$DOC"
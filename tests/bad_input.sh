@@ -0,0 +1,27 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$MSG"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+MSG=$(mktemp)
+
+echo '{invalid' | ffs -m "$MNT" --source json 2>"$MSG"
+[ $? -ne 0 ] || fail status
+grep -i -e "line 1 column 2" "$MSG" >/dev/null 2>&1 || fail location
+grep -i -e "panicked" "$MSG" >/dev/null 2>&1 && fail panic
+
+echo 'a = [' | ffs -m "$MNT" --source toml 2>"$MSG"
+[ $? -ne 0 ] || fail toml_status
+grep -i -e "panicked" "$MSG" >/dev/null 2>&1 && fail toml_panic
+
+rmdir "$MNT" || fail mount
+rm "$MSG"
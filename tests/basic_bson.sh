@@ -0,0 +1,52 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+ffs -m "$MNT" -t bson -o "$OUT" ../bson/eg.bson &
+PID=$!
+sleep 2
+case $(ls "$MNT") in
+    (_id*blob*created*downloads*name*owner*price*ratio*stars*tags) ;;
+    (*) fail ls;;
+esac
+[ "$(cat $MNT/_id)" = "5f8d0d55b54764421b7156c9" ] || fail objectid
+[ "$(cat $MNT/created)" = "2021-06-21T12:00:00Z" ] || fail date
+[ "$(cat $MNT/price)" = "19.99" ] || fail decimal
+[ "$(cat $MNT/tags/1)" = "json" ] || fail list
+[ "$(cat $MNT/owner/login)" = "mgree" ] || fail named
+echo fuse >"$MNT"/name
+
+umount "$MNT" || fail unmount1
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process1
+
+# the saved document should have the same ObjectId and date
+ffs --readonly -s bson -m "$MNT" "$OUT" &
+PID=$!
+sleep 2
+[ "$(cat $MNT/name)" = "fuse" ] || fail edit
+[ "$(cat $MNT/_id)" = "5f8d0d55b54764421b7156c9" ] || fail objectid_saved
+[ "$(cat $MNT/created)" = "2021-06-21T12:00:00Z" ] || fail date_saved
+[ "$(cat $MNT/price)" = "19.99" ] || fail decimal_saved
+
+umount "$MNT" || fail unmount2
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process2
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
@@ -0,0 +1,47 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+ffs -m "$MNT" -t edn -o "$OUT" ../edn/eg.edn &
+PID=$!
+sleep 2
+case $(ls "$MNT") in
+    (:args*:id*:name*:released*:status*:tags*:version*plain\ key) ;;
+    (*) fail ls;;
+esac
+[ "$(cat "$MNT"/:name)" = "ffs" ] || fail string
+[ "$(cat "$MNT"/:status)" = ":active" ] || fail keyword
+[ "$(cat "$MNT"/:released)" = "2021-06-26T12:00:00Z" ] || fail inst
+[ "$(cat "$MNT"/:args/1)" = "/tmp/x" ] || fail list
+[ "$(ls "$MNT"/:tags | wc -l)" -eq 2 ] || fail set
+echo 3 >"$MNT"/:version
+echo :paused >"$MNT"/:status
+
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+grep -e ':status :paused' "$OUT" >/dev/null 2>&1 || fail keyword_saved
+grep -e ':version 3' "$OUT" >/dev/null 2>&1 || fail integer_saved
+grep -e ':tags #{' "$OUT" >/dev/null 2>&1 || fail set_saved
+grep -e ':args ("--mount" "/tmp/x")' "$OUT" >/dev/null 2>&1 || fail list_saved
+grep -e ':released #inst "2021-06-26T12:00:00Z"' "$OUT" >/dev/null 2>&1 || fail inst_saved
+grep -e ':id #uuid "f81d4fae-7dec-11d0-a765-00a0c91e6bf6"' "$OUT" >/dev/null 2>&1 || fail uuid_saved
+grep -e '"plain key" nil' "$OUT" >/dev/null 2>&1 || fail string_key_saved
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
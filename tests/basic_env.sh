@@ -0,0 +1,44 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+ffs -m "$MNT" -o "$OUT" ../env/eg.env &
+PID=$!
+sleep 2
+case $(ls "$MNT") in
+    (APP_NAME*DATABASE_URL*GREETING*PASSWORD*PRIVATE_KEY) ;;
+    (*) fail ls;;
+esac
+[ "$(cat $MNT/APP_NAME)" = "ffs" ] || fail export
+[ "$(cat $MNT/DATABASE_URL)" = "postgres://localhost:5432/app" ] || fail comment
+[ "$(cat $MNT/GREETING)" = "hello, world" ] || fail double
+[ "$(cat $MNT/PASSWORD)" = 'pa$$word' ] || fail single
+[ "$(head -n 2 $MNT/PRIVATE_KEY | tail -n 1)" = "abc123" ] || fail multiline
+echo "new value" >"$MNT"/GREETING
+echo 8080 >"$MNT"/PORT
+
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+grep -e '^APP_NAME=ffs$' "$OUT" >/dev/null 2>&1 || fail unquoted
+grep -e '^GREETING="new value"$' "$OUT" >/dev/null 2>&1 || fail quoted
+grep -e '^PASSWORD="pa\\\$\\\$word"$' "$OUT" >/dev/null 2>&1 || fail escaped
+grep -e '^PORT=8080$' "$OUT" >/dev/null 2>&1 || fail new
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
@@ -0,0 +1,40 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+ffs -m "$MNT" -o "$OUT" --target json ../hcl/eg.tf &
+PID=$!
+sleep 2
+case $(ls "$MNT") in
+    (region*replicas*resource*tags) ;;
+    (*) fail ls;;
+esac
+[ "$(cat $MNT/region)" = "us-east-1" ] || fail region
+[ "$(cat $MNT/tags/Team)" = "infra" ] || fail object
+[ "$(cat $MNT/resource/aws_instance/db/instance_type)" = "t2.large" ] || fail labels
+[ "$(cat $MNT/resource/aws_instance/web/ingress/1/port)" = "443" ] || fail repeated
+echo 5 >"$MNT"/replicas
+
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+grep -e '"replicas":5' "$OUT" >/dev/null 2>&1 || fail edit
+grep -e '"ingress":\[{"port":80},{"port":443}\]' "$OUT" >/dev/null 2>&1 || fail list
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
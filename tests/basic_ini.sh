@@ -0,0 +1,40 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+ffs -m "$MNT" -o "$OUT" --target ini --ini-split-dots ../ini/eg.ini &
+PID=$!
+sleep 2
+case $(ls "$MNT") in
+    (database*owner*title) ;;
+    (*) fail ls;;
+esac
+[ "$(cat $MNT/title)" = "INI Example" ] || fail title
+[ "$(cat $MNT/owner/name)" = "Tom Preston-Werner" ] || fail name
+[ "$(cat $MNT/database/log/level)" = "debug" ] || fail split
+echo info >"$MNT"/database/log/level
+
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+grep -e '^\[database\]$' "$OUT" >/dev/null 2>&1 || fail section
+grep -e '^log.level = info$' "$OUT" >/dev/null 2>&1 || fail dotted
+grep -e '^title = INI Example$' "$OUT" >/dev/null 2>&1 || fail global
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
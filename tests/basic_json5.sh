@@ -0,0 +1,53 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+ffs -m "$MNT" -o "$OUT" --target json ../json5/config.json5 &
+PID=$!
+sleep 2
+case $(ls "$MNT") in
+    (log\ level*name*ports*retry) ;;
+    (*) fail ls;;
+esac
+[ "$(cat "$MNT"/name)" = "build-01" ] || fail string
+[ "$(cat "$MNT"/ports/1)" -eq 8443 ] || fail list
+[ "$(ls "$MNT"/ports | wc -l | tr -d ' ')" -eq 2 ] || fail trailing_comma
+[ "$(cat "$MNT/log level")" = "debug" ] || fail quoted_key
+[ "$(cat "$MNT"/retry/attempts)" -eq 3 ] || fail nested
+[ "$(cat "$MNT"/retry/backoff)" = "0.5" ] || fail number
+echo 5 >"$MNT"/retry/attempts
+
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+# saved as plain JSON
+grep -e '"attempts":5' "$OUT" >/dev/null 2>&1 || fail save
+grep -e '"name":"build-01"' "$OUT" >/dev/null 2>&1 || fail save_string
+
+# or as JSON5
+ffs -m "$MNT" -o "$OUT" --target json5 --json5-output ../json5/config.json5 &
+PID=$!
+sleep 2
+umount "$MNT" || fail unmount2
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process2
+grep -e 'name:"build-01"' "$OUT" >/dev/null 2>&1 || fail json5_key
+grep -e '"log level":"debug"' "$OUT" >/dev/null 2>&1 || fail json5_quoted_key
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
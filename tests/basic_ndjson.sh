@@ -0,0 +1,43 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+ffs -m "$MNT" -o "$OUT" --target ndjson ../ndjson/log.ndjson &
+PID=$!
+sleep 2
+case $(ls "$MNT") in
+    (0*1*2) ;;
+    (*) fail ls;;
+esac
+[ "$(cat $MNT/0/level)" = "info" ] || fail string
+[ "$(cat $MNT/0/pid)" -eq 4242 ] || fail number
+[ "$(cat $MNT/2/retry)" = "true" ] || fail boolean
+mkdir "$MNT"/3
+echo debug >"$MNT"/3/level
+echo 7 >"$MNT"/3/pid
+
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+[ "$(wc -l <"$OUT")" -eq 4 ] || fail lines
+[ "$(head -n 1 "$OUT")" = '{"level":"info","msg":"starting up","pid":4242}' ] || fail first
+[ "$(sed -n 3p "$OUT")" = '{"level":"error","msg":"out of disk","retry":true}' ] || fail third
+tail -n 1 "$OUT" | grep -e '"pid":7' >/dev/null 2>&1 || fail new
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
@@ -0,0 +1,43 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+ffs -m "$MNT" -t plist -o "$OUT" ../plist/eg.plist &
+PID=$!
+sleep 2
+case $(ls "$MNT") in
+    (Architectures*CFBundleIdentifier*CFBundleVersion*Icon*LSRequiresNativeExecution*Released*Scale) ;;
+    (*) fail ls;;
+esac
+[ "$(cat $MNT/CFBundleIdentifier)" = "org.example.ffs" ] || fail string
+[ "$(cat $MNT/CFBundleVersion)" -eq 42 ] || fail integer
+[ "$(cat $MNT/Released)" = "2021-06-26T12:00:00Z" ] || fail date
+[ "$(cat $MNT/Architectures/1)" = "x86_64" ] || fail list
+[ "$(head -c 4 $MNT/Icon | tail -c 3)" = "PNG" ] || fail data
+echo 2.0 >"$MNT"/Scale
+
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+grep -e '<date>2021-06-26T12:00:00Z</date>' "$OUT" >/dev/null 2>&1 || fail date_saved
+grep -e '<real>2</real>' "$OUT" >/dev/null 2>&1 || fail real_saved
+grep -e 'iVBORw0KGgo=' "$OUT" >/dev/null 2>&1 || fail data_saved
+grep -e '<true/>' "$OUT" >/dev/null 2>&1 || fail boolean_saved
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
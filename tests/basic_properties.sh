@@ -0,0 +1,56 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$PROPS" "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+PROPS=$(mktemp)
+
+cat >"$PROPS" <<'PROPERTIES'
+# a comment
+! another comment
+db.host = localhost
+db.port:5432
+greeting   Hello, \
+           world
+key\=with\:separators = v
+snowman = ☃
+PROPERTIES
+
+ffs -m "$MNT" -o "$OUT" --source properties --target properties --ini-split-dots "$PROPS" &
+PID=$!
+sleep 2
+case $(ls "$MNT") in
+    (db*greeting*key=with:separators*snowman) ;;
+    (*) fail ls;;
+esac
+[ "$(cat "$MNT"/db/host)" = "localhost" ] || fail host
+[ "$(cat "$MNT"/db/port)" = "5432" ] || fail port
+[ "$(cat "$MNT"/greeting)" = "Hello, world" ] || fail continuation
+[ "$(cat "$MNT"/key=with:separators)" = "v" ] || fail escaped_key
+[ "$(cat "$MNT"/snowman)" = "☃" ] || fail unicode
+echo 5433 >"$MNT"/db/port
+
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+grep -e '^db.port = 5433$' "$OUT" >/dev/null 2>&1 || fail dotted
+grep -e '^db.host = localhost$' "$OUT" >/dev/null 2>&1 || fail dotted_host
+grep -e '^key\\=with\\:separators = v$' "$OUT" >/dev/null 2>&1 || fail escaped_key_saved
+grep -e '^greeting = Hello, world$' "$OUT" >/dev/null 2>&1 || fail greeting_saved
+grep -e '\[' "$OUT" >/dev/null 2>&1 && fail section
+
+rmdir "$MNT" || fail mount
+rm "$PROPS" "$OUT"
@@ -0,0 +1,40 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+ffs -m "$MNT" -o "$OUT" ../ron/eg.ron &
+PID=$!
+sleep 2
+case $(ls "$MNT") in
+    (difficulty_options*fullscreen*high_score*key_bindings*mouse_sensitivity*window_size*window_title) ;;
+    (*) fail ls;;
+esac
+[ "$(cat $MNT/window_title)" = "PAC-MAN" ] || fail string
+[ "$(cat $MNT/window_size/1)" -eq 600 ] || fail tuple
+[ "$(cat $MNT/difficulty_options/adaptive)" = "false" ] || fail struct
+[ "$(cat $MNT/high_score)" -eq 10000 ] || fail option
+echo 1.5 >"$MNT"/mouse_sensitivity
+
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+grep -e '"mouse_sensitivity":1.5' "$OUT" >/dev/null 2>&1 || fail edit
+grep -e '"window_size":\[800,600\]' "$OUT" >/dev/null 2>&1 || fail list
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
@@ -0,0 +1,37 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$SRC" "$PNG" "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+SRC=$(mktemp)
+PNG=$(mktemp)
+OUT=$(mktemp)
+
+# a 1x1 PNG
+B64=iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg==
+printf '%s' "$B64" | base64 -d >"$PNG"
+printf '{"name":"dot","thumb":{"image":"%s"}}' "$B64" >"$SRC"
+
+ffs --source json --blob '**/image' -m "$MNT" -o "$OUT" "$SRC" &
+PID=$!
+sleep 2
+cmp "$MNT"/thumb/image "$PNG" || fail decode
+[ "$(cat "$MNT"/name)" = "dot" ] || fail name
+umount "$MNT" || fail unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+cmp "$SRC" "$OUT" || fail roundtrip
+
+rmdir "$MNT" || fail mount
+rm "$SRC" "$PNG" "$OUT"
@@ -0,0 +1,46 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+    fi
+    exit 1
+}
+
+if [ "$RUNNER_OS" = "Linux" ] || [ "$(uname)" = "Linux" ]; then
+    num_blocks() {
+        stat --format %b "$@"
+    }
+elif [ "$RUNNER_OS" = "macOS" ] || [ "$(uname)" = "Darwin" ]; then
+    num_blocks() {
+        stat -f %b "$@"
+    }
+else
+    fail os
+fi
+
+MNT=$(mktemp -d)
+
+ffs -m "$MNT" --no-output ../json/object.json &
+PID=$!
+sleep 2
+cd "$MNT"
+head -c 1000 /dev/zero | tr '\0' a >big
+[ $(wc -c <big) -eq 1000 ] || fail size
+[ $(num_blocks big) -eq 2 ] || fail blocks
+printf a >small
+[ $(num_blocks small) -eq 1 ] || fail small
+: >empty
+[ $(num_blocks empty) -eq 0 ] || fail empty
+[ $(du -k big | cut -f1) -eq 1 ] || fail du
+cd - >/dev/null 2>&1
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+rmdir "$MNT" || fail mount
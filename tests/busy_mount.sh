@@ -0,0 +1,58 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$MSG"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+MSG=$(mktemp)
+
+# the root of some other filesystem isn't busy (needs root to mount a tmpfs)
+if mount -t tmpfs none "$MNT" 2>/dev/null
+then
+    ffs -m "$MNT" --no-output --mount-retries 0 ../json/object.json &
+    PID=$!
+    sleep 2
+    [ "$(cat "$MNT"/name)" = "Michael Greenberg" ] || fail tmpfs
+    umount "$MNT" || fail unmount_tmpfs_ffs
+    sleep 1
+    kill -0 $PID >/dev/null 2>&1 && fail process_tmpfs
+    # fuser's own cleanup may have unmounted the tmpfs already
+    umount "$MNT" 2>/dev/null
+fi
+
+# an ffs that dies without unmounting leaves a stale mount behind
+ffs -m "$MNT" --no-output ../json/object.json &
+PID1=$!
+sleep 2
+[ "$(cat "$MNT"/name)" = "Michael Greenberg" ] || fail mount1
+kill -9 $PID1
+sleep 1
+ls "$MNT" >/dev/null 2>&1 && fail stale
+
+# so a second mount gives up after retrying
+ffs -m "$MNT" --no-output --mount-retries 2 --mount-retry-delay 0.1 ../json/list.json 2>"$MSG"
+[ $? -ne 0 ] || fail status
+grep -e "still busy after 2 retries" "$MSG" >/dev/null 2>&1 || fail error
+
+# unless it's allowed to unmount the stale one
+ffs -m "$MNT" --no-output --force-unmount ../json/list.json &
+PID2=$!
+sleep 2
+[ -e "$MNT"/name ] && fail still_mounted
+[ -e "$MNT"/0 ] || fail mount2
+umount "$MNT" || fail unmount
+sleep 1
+kill -0 $PID2 >/dev/null 2>&1 && fail process2
+
+rmdir "$MNT" || fail mount
+rm "$MSG"
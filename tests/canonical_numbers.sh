@@ -0,0 +1,45 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+# every spelling of a number converts to the same one
+JSON=$(echo '{"a":1e3,"b":1000.0,"c":1.50,"d":-0,"e":1E+3}' | ffs --convert --source json --target json -o - --canonical-numbers --sort-keys) || fail convert
+[ "$JSON" = '{"a":1000,"b":1000,"c":1.5,"d":0,"e":1000}' ] || fail canonical
+
+# the canonical form is stable
+AGAIN=$(echo "$JSON" | ffs --convert --source json --target json -o - --canonical-numbers --sort-keys) || fail reconvert
+[ "$AGAIN" = "$JSON" ] || fail stable
+
+# floats stay floats with --number-suffix
+JSON=$(echo '{"a":1e3}' | ffs --convert --source json --target json -o - --canonical-numbers --number-suffix) || fail suffix_convert
+[ "$JSON" = '{"a":1000.0}' ] || fail suffix
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+# untouched numbers save just as edited ones do
+echo '{"a": 1e3, "b": 2.50}' | ffs -m "$MNT" --source json --target json -o "$OUT" --canonical-numbers --sort-keys &
+PID=$!
+sleep 2
+[ "$(cat "$MNT"/a)" = "1000" ] || fail read
+echo 1e3 >"$MNT"/b || fail write
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+grep -E '"a": *1000([^.0-9]|$)' "$OUT" >/dev/null 2>&1 || fail "a saved"
+grep -E '"b": *1000([^.0-9]|$)' "$OUT" >/dev/null 2>&1 || fail "b saved"
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
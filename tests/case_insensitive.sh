@@ -0,0 +1,43 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+echo '{"Name":"first","name":"second","Other":1}' | ffs -m "$MNT" --source json --target json -o "$OUT" --case-insensitive &
+PID=$!
+sleep 2
+# lookups ignore case
+[ "$(cat "$MNT"/OTHER)" -eq 1 ] || fail other
+[ "$(cat "$MNT"/other)" -eq 1 ] || fail lowercase
+# names differing only in case are disambiguated
+case $(ls "$MNT") in
+    (Name*Other*name_) ;;
+    (*) fail ls;;
+esac
+[ "$(cat "$MNT"/Name)" = "first" ] || fail first
+[ "$(cat "$MNT"/name_)" = "second" ] || fail second
+echo 2 >"$MNT"/OTHER
+echo third >"$MNT"/name_
+umount "$MNT" || fail unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+# original casing is saved
+grep -e '"Other":2' "$OUT" >/dev/null 2>&1 || fail write_other
+grep -e '"name":"third"' "$OUT" >/dev/null 2>&1 || fail write_second
+grep -e '"Name":"first"' "$OUT" >/dev/null 2>&1 || fail write_first
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
@@ -24,6 +24,16 @@ chown :$(groups | cut -d' ' -f 1) "$MNT"/name 2>$ERR >&2 || fail "chgrp2: $(cat
 [ -s "$ERR" ] && fail "chgrp2 error: $(cat $ERR)"
 chown $(whoami) "$MNT"/name 2>$ERR >&2 || fail chown
 [ -s "$ERR" ] && fail "chown error: $(cat $ERR)"
+# only root can give a file away, and the new owner sticks
+if [ "$(id -u)" -eq 0 ]
+then
+    chown nobody "$MNT"/name 2>$ERR >&2 || fail "chown root: $(cat $ERR)"
+    [ "$(ls -ln "$MNT"/name | awk '{print $3}')" -eq "$(id -u nobody)" ] || fail owner
+    [ "$(ls -ln "$MNT"/eyes | awk '{print $3}')" -eq 0 ] || fail other_owner
+else
+    chown nobody "$MNT"/name 2>$ERR >&2 && fail "chown nobody: $(cat $ERR)"
+    [ "$(ls -ln "$MNT"/name | awk '{print $3}')" -eq "$(id -u)" ] || fail owner
+fi
 umount "$MNT" || fail unmount1    
 sleep 1
 kill -0 $PID >/dev/null 2>&1 && fail process1
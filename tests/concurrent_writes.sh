@@ -0,0 +1,57 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+echo '{}' | ffs -m "$MNT" --source json --target json -o "$OUT" &
+PID=$!
+sleep 2
+
+# many writers at once, each to its own file
+WRITERS=""
+for w in 0 1 2 3 4 5 6 7
+do
+    (
+        for i in $(seq 1 50)
+        do
+            echo "writer $w round $i" >"$MNT"/w$w || exit 1
+            cat "$MNT"/w$w >/dev/null || exit 1
+        done
+    ) &
+    WRITERS="$WRITERS $!"
+done
+for pid in $WRITERS
+do
+    wait $pid || fail writer
+done
+
+for w in 0 1 2 3 4 5 6 7
+do
+    [ "$(cat "$MNT"/w$w)" = "writer $w round 50" ] || fail "w$w corrupted"
+done
+[ "$(ls "$MNT" | wc -l)" -eq 8 ] || fail count
+
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+for w in 0 1 2 3 4 5 6 7
+do
+    grep -e "\"w$w\": *\"writer $w round 50\"" "$OUT" >/dev/null 2>&1 || fail "w$w saved"
+done
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
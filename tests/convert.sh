@@ -0,0 +1,25 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    rm -f "$OUT"
+    exit 1
+}
+
+OUT=$(mktemp)
+
+# YAML to JSON on stdout, never mounting
+JSON=$(printf 'a: 1\nb: [x, y]\n' | ffs --convert --source yaml -o - --target json) || fail status
+[ "$JSON" = '{"a":1,"b":["x","y"]}' ] || fail stdout
+
+# a file in, a file out, with the format from its extension
+ffs --convert -o "$OUT".toml ../json/single.json || fail file_status
+[ -d single ] && fail mounted
+grep -e '^onlyone = "highlander"$' "$OUT".toml >/dev/null 2>&1 || fail file
+rm "$OUT".toml
+
+# munging applies to the whole document
+JSON=$(echo '{"ok":{"also/bad":1,"fine":2},"bad/key":3}' | ffs --convert --munge filter 2>/dev/null) || fail munge_status
+[ "$JSON" = '{"ok":{"fine":2}}' ] || fail munge
+
+rm "$OUT"
@@ -0,0 +1,75 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+    fi
+    exit 1
+}
+
+if [ "$RUNNER_OS" = "Linux" ] || [ "$(uname)" = "Linux" ]; then
+    atime() {
+        stat --format %X "$@"
+    }
+    mtime() {
+        stat --format %Y "$@"
+    }
+    ctime() {
+        stat --format %Z "$@"
+    }
+elif [ "$RUNNER_OS" = "macOS" ] || [ "$(uname)" = "Darwin" ]; then
+    atime() {
+        stat -f %a "$@"
+    }
+    mtime() {
+        stat -f %m "$@"
+    }
+    ctime() {
+        stat -f %c "$@"
+    }
+else
+    fail os
+fi
+
+MNT=$(mktemp -d)
+
+ffs --ttl 0 -m "$MNT" --no-output ../json/object.json &
+PID=$!
+sleep 2
+MTIME=$(mtime "$MNT"/name)
+CTIME=$(ctime "$MNT"/name)
+sleep 2
+chmod 600 "$MNT"/name || fail chmod
+[ "$(mtime "$MNT"/name)" -eq "$MTIME" ] || fail "chmod mtime"
+[ "$(ctime "$MNT"/name)" -gt "$CTIME" ] || fail "chmod ctime"
+CTIME=$(ctime "$MNT"/name)
+sleep 2
+echo hi >"$MNT"/name || fail write
+[ "$(mtime "$MNT"/name)" -gt "$MTIME" ] || fail "write mtime"
+[ "$(ctime "$MNT"/name)" -gt "$CTIME" ] || fail "write ctime"
+ATIME=$(atime "$MNT"/name)
+sleep 2
+cat "$MNT"/name >/dev/null || fail read
+[ "$(atime "$MNT"/name)" -gt "$ATIME" ] || fail "read atime"
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+ffs --ttl 0 --noatime -m "$MNT" --no-output ../json/object.json &
+PID=$!
+sleep 2
+ATIME=$(atime "$MNT"/name)
+sleep 2
+cat "$MNT"/name >/dev/null || fail "noatime read"
+[ "$(atime "$MNT"/name)" -eq "$ATIME" ] || fail "noatime atime"
+umount "$MNT" || fail "noatime unmount"
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail "noatime process"
+
+rmdir "$MNT" || fail mount
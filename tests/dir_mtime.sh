@@ -0,0 +1,45 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+    fi
+    exit 1
+}
+
+if [ "$RUNNER_OS" = "Linux" ] || [ "$(uname)" = "Linux" ]; then
+    mtime() {
+        stat --format %Y "$@"
+    }
+elif [ "$RUNNER_OS" = "macOS" ] || [ "$(uname)" = "Darwin" ]; then
+    mtime() {
+        stat -f %m "$@"
+    }
+else
+    fail os
+fi
+
+MNT=$(mktemp -d)
+
+ffs --ttl 0 -m "$MNT" --no-output ../json/object.json &
+PID=$!
+sleep 2
+mkdir "$MNT"/dir || fail mkdir
+BEFORE=$(mtime "$MNT"/dir)
+sleep 2
+touch "$MNT"/dir/file || fail touch
+CREATED=$(mtime "$MNT"/dir)
+[ "$CREATED" -gt "$BEFORE" ] || fail create
+sleep 2
+rm "$MNT"/dir/file || fail rm
+[ "$(mtime "$MNT"/dir)" -gt "$CREATED" ] || fail remove
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+rmdir "$MNT" || fail mount
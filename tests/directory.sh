@@ -0,0 +1,59 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm -r "$D"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+D=$(mktemp -d)
+
+cp ../json/object.json "$D"/object.json
+printf 'name: ffs\nlist:\n  - 1\n  - 2\n' >"$D"/config.yaml
+echo "not mounted" >"$D"/notes.txt
+
+ffs -m "$MNT" "$D" &
+PID=$!
+sleep 2
+case $(ls "$MNT") in
+    (config*object) ;;
+    (*) fail ls;;
+esac
+[ "$(cat "$MNT"/object/name)" = "Michael Greenberg" ] || fail json
+[ "$(cat "$MNT"/config/name)" = "ffs" ] || fail yaml
+echo "Mike" >"$MNT"/object/name
+echo 3 >"$MNT"/config/list/2
+umount "$MNT" || fail unmount1
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process1
+
+grep -e '"name":"Mike"' "$D"/object.json >/dev/null 2>&1 || fail object_saved
+grep -e '"eyes":2' "$D"/object.json >/dev/null 2>&1 || fail object_kept
+grep -e '- 3' "$D"/config.yaml >/dev/null 2>&1 || fail yaml_saved
+grep -e '^name: ffs' "$D"/config.yaml >/dev/null 2>&1 || fail yaml_format
+[ "$(cat "$D"/notes.txt)" = "not mounted" ] || fail skipped
+
+# new keys become files; removed keys' files are deleted
+ffs -m "$MNT" "$D" &
+PID=$!
+sleep 2
+mkdir "$MNT"/extra
+echo 1 >"$MNT"/extra/n
+rm -r "$MNT"/config
+umount "$MNT" || fail unmount2
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process2
+
+[ "$(tr -d ' \n' <"$D"/extra.json)" = '{"n":1}' ] || fail created
+[ -e "$D"/config.yaml ] && fail removed
+[ -e "$D"/object.json ] || fail object_removed
+
+rmdir "$MNT" || fail mount
+rm -r "$D"
@@ -0,0 +1,46 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+# '.' and '..' are renamed, without clobbering a key that has the new name
+echo '{".": 1, "..": 2, "_.": 3}' | ffs -m "$MNT" --source json -o "$OUT" &
+PID=$!
+sleep 2
+# only the real '.' and '..' are listed as such
+[ "$(ls -a "$MNT" | grep -c -e '^\.\.\?$')" -eq 2 ] || fail dot_entries
+[ "$(ls "$MNT" | wc -l)" -eq 3 ] || fail count
+[ "$(cat "$MNT"/_._)" -eq 1 ] || fail dot
+[ "$(cat "$MNT"/_..)" -eq 2 ] || fail dotdot
+[ "$(cat "$MNT"/_.)" -eq 3 ] || fail underscore_dot
+umount "$MNT" || fail unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+grep -e '"\.":1' "$OUT" >/dev/null 2>&1 || fail save_dot
+grep -e '"\.\.":2' "$OUT" >/dev/null 2>&1 || fail save_dotdot
+grep -e '"_\.":3' "$OUT" >/dev/null 2>&1 || fail save_underscore_dot
+
+# under filter, they're left out
+echo '{".": 1, "..": 2, "_.": 3}' | ffs -m "$MNT" --source json --munge filter --no-output &
+PID=$!
+sleep 2
+[ "$(ls "$MNT")" = "_." ] || fail filter
+umount "$MNT" || fail unmount_filter
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process_filter
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
@@ -0,0 +1,34 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$FILE" "$ERR"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+FILE=$(mktemp)
+ERR=$(mktemp)
+
+cp ../json/object.json "$FILE"
+
+ffs -m "$MNT" -i --target json --dry-run "$FILE" 2>"$ERR" &
+PID=$!
+sleep 2
+echo Mikey Indiana >"$MNT"/name
+umount "$MNT" || fail unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+cmp "$FILE" ../json/object.json || fail unchanged
+grep -e '^-.*"name": *"Michael Greenberg"' "$ERR" >/dev/null 2>&1 || fail removed
+grep -e '^+.*"name": *"Mikey Indiana"' "$ERR" >/dev/null 2>&1 || fail added
+
+rmdir "$MNT" || fail mount
+rm "$FILE" "$ERR"
@@ -0,0 +1,36 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$MSG"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+MSG=$(mktemp)
+
+echo '{"a":1,"a":2}' | ffs -m "$MNT" --source json --no-output 2>"$MSG" &
+PID=$!
+sleep 2
+[ "$(ls $MNT)" = "a" ] || fail ls
+[ "$(cat $MNT/a)" -eq 2 ] || fail last
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+grep -i -e "duplicate key at '/a'" "$MSG" >/dev/null 2>&1 || fail warning
+grep -e "earlier value 1" "$MSG" >/dev/null 2>&1 || fail dropped
+
+echo '{"a":1,"a":2}' | ffs -m "$MNT" --source json --strict 2>"$MSG"
+[ $? -ne 0 ] || fail strict
+grep -i -e "duplicate key at '/a'" "$MSG" >/dev/null 2>&1 || fail strict_message
+
+rmdir "$MNT" || fail mount
+rm "$MSG"
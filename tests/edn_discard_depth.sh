@@ -0,0 +1,22 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    exit 1
+}
+
+# a single discard is fine
+EDN=$(echo '{:a 1 #_ (1 2) :b 2}' | ffs --convert --source edn -o - --target edn) || fail discard_status
+[ "$EDN" = '{:a 1, :b 2}' ] || fail discard
+
+# a long chain of discards nests like any other form, so it's an error, not
+# a stack overflow
+DEEP=$(mktemp)
+for i in $(seq 10000)
+do
+    printf '#_ ' >>"$DEEP"
+done
+echo 1 >>"$DEEP"
+ffs --convert --source edn -o - --target json "$DEEP" >/dev/null 2>&1
+[ $? -eq 1 ] || fail deep_status
+rm "$DEEP"
@@ -0,0 +1,39 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT" "$IN"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+IN=$(mktemp)
+mv "$IN" "$IN".json
+IN="$IN".json
+
+printf '{"records":[{"id":1,"tags":["a"]},{"id":2,"tags":[]}],"count":2}' >"$IN"
+
+ffs -m "$MNT" -o "$OUT" --elements-as-files 1 "$IN" &
+PID=$!
+sleep 2
+[ -d "$MNT"/records ] || fail records
+[ -f "$MNT"/records/0 ] || fail file
+[ "$(cat "$MNT"/records/0)" = '{"id":1,"tags":["a"]}' ] || fail contents
+printf '{"id":2,"tags":["b","c"]}' >"$MNT"/records/1
+printf '{"id":3}' >"$MNT"/records/2
+echo 3 >"$MNT"/count
+umount "$MNT" || fail unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+[ "$(tr -d ' \n' <"$OUT")" = '{"count":3,"records":[{"id":1,"tags":["a"]},{"id":2,"tags":["b","c"]},{"id":3}]}' ] || fail output
+
+rmdir "$MNT" || fail mount
+rm "$OUT" "$IN"
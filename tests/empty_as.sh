@@ -0,0 +1,41 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm -r "$TMP"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+TMP=$(mktemp -d)
+: >"$TMP"/empty.json
+
+# by default, an empty JSON file is an error (but not a panic)
+ffs -m "$MNT" "$TMP"/empty.json 2>"$TMP"/msg
+[ $? -ne 0 ] || fail status
+grep -e "--empty-as" "$TMP"/msg >/dev/null 2>&1 || fail message
+grep -i -e "panicked" "$TMP"/msg >/dev/null 2>&1 && fail panic
+
+# mount_empty MODE OUTPUT: mounts empty.json under `--empty-as MODE`, saving an empty OUTPUT
+mount_empty() {
+    ffs --empty-as "$1" -m "$MNT" -o "$TMP"/out.json "$TMP"/empty.json &
+    PID=$!
+    sleep 2
+    [ "$(ls "$MNT")" ] && fail "$1 ls"
+    umount "$MNT" || fail "$1 unmount"
+    sleep 1
+    kill -0 $PID >/dev/null 2>&1 && fail "$1 process"
+    [ "$(cat "$TMP"/out.json)" = "$2" ] || fail "$1 output"
+}
+
+mount_empty empty_object '{}'
+mount_empty empty_array '[]'
+
+rmdir "$MNT" || fail mount
+rm -r "$TMP"
@@ -0,0 +1,43 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+ffs --exclude '**/password' -m "$MNT" -o "$OUT" ../json/secrets.json &
+PID=$!
+sleep 2
+case $(ls "$MNT") in
+    (db*name) ;;
+    (*) fail ls;;
+esac
+case $(ls "$MNT"/db) in
+    (host*user) ;;
+    (*) fail ls_db;;
+esac
+[ -e "$MNT"/password ] && fail password
+[ -e "$MNT"/db/password ] && fail db_password
+echo root >"$MNT"/db/user
+
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+grep -e '"password": *"hunter2"' "$OUT" >/dev/null 2>&1 || fail restore
+grep -e '"password": *"swordfish"' "$OUT" >/dev/null 2>&1 || fail restore_db
+grep -e '"user": *"root"' "$OUT" >/dev/null 2>&1 || fail edit
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
@@ -0,0 +1,47 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+# off by default
+ffs -m "$MNT" -o "$OUT" ../json/object.json &
+PID=$!
+sleep 2
+ls -a "$MNT" | grep -q '^\.ffs-info$' && fail default_readdir
+[ -e "$MNT"/.ffs-info ] && fail default_lookup
+umount "$MNT" || fail unmount1
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process1
+
+ffs -m "$MNT" -o "$OUT" --expose-info ../json/object.json &
+PID=$!
+sleep 2
+ls -a "$MNT" | grep -q '^\.ffs-info$' || fail readdir
+grep -q '^source: .*json/object.json$' "$MNT"/.ffs-info || fail source
+grep -q '^inodes: ' "$MNT"/.ffs-info || fail inodes
+grep -q 'expose_info: true' "$MNT"/.ffs-info || fail config
+echo hi >"$MNT"/.ffs-info 2>/dev/null && fail write
+rm "$MNT"/.ffs-info 2>/dev/null && fail remove
+mkdir "$MNT"/.ffs-info 2>/dev/null && fail mkdir
+echo 3 >"$MNT"/eyes
+umount "$MNT" || fail unmount2
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process2
+
+grep -q 'ffs-info' "$OUT" && fail saved
+[ "$(tr -d ' \n' <"$OUT" | grep -o '"eyes":[0-9]*')" = '"eyes":3' ] || fail output
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
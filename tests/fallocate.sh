@@ -0,0 +1,46 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+# fallocate(1) and fallocate(2) are Linux-specific
+which fallocate >/dev/null 2>&1 || { echo "SKIPPED: no fallocate"; exit 0; }
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+ffs -m "$MNT" -o "$OUT" --target json ../json/object.json &
+PID=$!
+sleep 2
+
+# punching a hole zeroes the range, keeping the size
+printf 'abcdefgh' >"$MNT"/name
+fallocate --punch-hole --offset 2 --length 3 "$MNT"/name || fail punch_hole
+[ "$(wc -c <"$MNT"/name | tr -d ' ')" -eq 8 ] || fail punch_hole_size
+[ "$(od -An -c "$MNT"/name | tr -d ' ')" = 'ab\0\0\0fgh' ] || fail punch_hole_contents
+
+# zeroing a range extends the file if needed (not every kernel passes it on)
+printf 'abcdefgh' >"$MNT"/eyes
+if fallocate --zero-range --offset 6 --length 4 "$MNT"/eyes 2>/dev/null
+then
+    [ "$(wc -c <"$MNT"/eyes | tr -d ' ')" -eq 10 ] || fail zero_range_size
+    [ "$(od -An -c "$MNT"/eyes | tr -d ' ')" = 'abcdef\0\0\0\0' ] || fail zero_range_contents
+fi
+
+umount "$MNT" || fail unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+grep -e '"name":"ab\\u0000\\u0000\\u0000fgh"' "$OUT" >/dev/null 2>&1 || fail save
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
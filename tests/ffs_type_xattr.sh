@@ -0,0 +1,70 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+if [ "$RUNNER_OS" = "Linux" ] || [ "$(uname)" = "Linux" ]; then
+    which getfattr || fail getfattr
+    which setfattr || fail setfattr
+    getattr() {
+        getfattr -n "$1" --only-values "$2"
+    }
+    setattr() {
+        setfattr -n "$1" -v "$2" "$3"
+    }
+    rmattr() {
+        setfattr -x "$1" "$2"
+    }
+elif [ "$RUNNER_OS" = "macOS" ] || [ "$(uname)" = "Darwin" ]; then
+    getattr() {
+        xattr -p "$1" "$2"
+    }
+    setattr() {
+        xattr -w "$1" "$2" "$3"
+    }
+    rmattr() {
+        xattr -d "$1" "$2"
+    }
+else
+    fail os
+fi
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+ffs -m "$MNT" --target json -o "$OUT" ../json/object.json &
+PID=$!
+sleep 2
+
+[ "$(getattr user.ffs.type "$MNT"/eyes)" = "float" ] || fail get
+setattr user.ffs.type string "$MNT"/eyes || fail set
+[ "$(getattr user.ffs.type "$MNT"/eyes)" = "string" ] || fail get_after_set
+[ "$(getattr user.type "$MNT"/eyes)" = "string" ] || fail old_name
+echo 12 >"$MNT"/name
+setattr user.ffs.type number "$MNT"/name || fail set_number
+setattr user.ffs.type frobnitz "$MNT"/name && fail invalid_type
+setattr user.ffs.fake number "$MNT"/name && fail unknown_name
+rmattr user.ffs.type "$MNT"/fingernails || fail remove
+[ "$(getattr user.ffs.type "$MNT"/fingernails)" = "string" ] || fail get_after_remove
+rmattr user.ffs.type "$MNT" && fail remove_dir
+
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+grep -e '"eyes":"2"' "$OUT" >/dev/null 2>&1 || fail eyes_saved
+grep -e '"name":12' "$OUT" >/dev/null 2>&1 || fail name_saved
+grep -e '"fingernails":"10"' "$OUT" >/dev/null 2>&1 || fail fingernails_saved
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
@@ -0,0 +1,55 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm -r "$TMP"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+TMP=$(mktemp -d)
+
+# ends_in_newline FILE: succeeds iff FILE's last byte is a newline
+ends_in_newline() {
+    [ "$(tail -c 1 "$1" | od -An -c | tr -d ' ')" = '\n' ]
+}
+
+# edit FILE [FLAGS...]: changes a value in FILE in place
+edit() {
+    FILE="$1"
+    shift
+    ffs "$@" -m "$MNT" -i "$FILE" &
+    PID=$!
+    sleep 2
+    echo edited >"$MNT"/a
+    umount "$MNT" || fail "unmount $FILE"
+    sleep 1
+    kill -0 $PID >/dev/null 2>&1 && fail "process $FILE"
+    grep -e '"a": *"edited"' "$FILE" >/dev/null 2>&1 || fail "edit $FILE"
+}
+
+printf '{"a":"original"}\n' >"$TMP"/newline.json
+edit "$TMP"/newline.json
+ends_in_newline "$TMP"/newline.json || fail newline_kept
+[ "$(tail -c 2 "$TMP"/newline.json | od -An -c | tr -d ' ')" = '}\n' ] || fail newline_single
+
+printf '{"a":"original"}' >"$TMP"/none.json
+edit "$TMP"/none.json
+ends_in_newline "$TMP"/none.json && fail none_kept
+
+printf '{"a":"original"}' >"$TMP"/always.json
+edit "$TMP"/always.json --final-newline always
+ends_in_newline "$TMP"/always.json || fail always
+
+printf '{"a":"original"}\n' >"$TMP"/never.json
+edit "$TMP"/never.json --final-newline never
+ends_in_newline "$TMP"/never.json && fail never
+
+rmdir "$MNT" || fail mount
+rm -r "$TMP"
@@ -0,0 +1,44 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+echo '{"a":{"b":1}}' | ffs -m "$MNT" -o "$OUT" --source json --flatten &
+PID=$!
+sleep 2
+[ "$(ls "$MNT")" = "a.b" ] || fail ls
+[ "$(cat "$MNT"/a.b)" -eq 1 ] || fail read
+echo 2 >"$MNT"/a.b
+umount "$MNT" || fail unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+[ "$(cat "$OUT")" = '{"a":{"b":2}}' ] || fail output
+
+# a collapsed name can't clobber a key that already has it
+echo '{"a":{"b":1},"a.b":2}' | ffs -m "$MNT" -o "$OUT" --source json --flatten &
+PID=$!
+sleep 2
+[ "$(ls "$MNT" | tr '\n' ' ')" = "a a.b " ] || fail clash_ls
+[ "$(cat "$MNT"/a/b)" -eq 1 ] || fail clash_nested
+[ "$(cat "$MNT"/a.b)" -eq 2 ] || fail clash_dotted
+umount "$MNT" || fail clash_unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail clash_process
+
+[ "$(cat "$OUT")" = '{"a":{"b":1},"a.b":2}' ] || fail clash_output
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
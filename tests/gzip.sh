@@ -0,0 +1,55 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        umount "$PLAIN"
+        rmdir "$MNT" "$PLAIN"
+        rm -r "$TMP"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+PLAIN=$(mktemp -d)
+TMP=$(mktemp -d)
+
+gzip -c ../json/json_eg1.json >"$TMP"/eg.json.gz
+
+# the gzipped file mounts just like the plain one
+ffs -m "$PLAIN" --no-output ../json/json_eg1.json &
+PLAIN_PID=$!
+ffs -m "$MNT" --no-output "$TMP"/eg.json.gz &
+PID=$!
+sleep 2
+diff -r "$PLAIN" "$MNT" || fail tree
+umount "$PLAIN" || fail unmount_plain
+umount "$MNT" || fail unmount
+sleep 1
+kill -0 $PLAIN_PID >/dev/null 2>&1 && fail process_plain
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+# gzipped STDIN is detected by its magic number
+ffs -m "$MNT" --no-output <"$TMP"/eg.json.gz &
+PID=$!
+sleep 2
+[ -d "$MNT"/glossary ] || fail stdin
+umount "$MNT" || fail unmount_stdin
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process_stdin
+
+# saving in place keeps the file gzipped
+ffs -m "$MNT" -i "$TMP"/eg.json.gz &
+PID=$!
+sleep 2
+echo hi >"$MNT"/greeting
+umount "$MNT" || fail unmount_inplace
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process_inplace
+gzip -dc "$TMP"/eg.json.gz | grep -q '"greeting": *"hi' || fail inplace
+
+rmdir "$MNT" "$PLAIN" || fail mount
+rm -r "$TMP"
@@ -0,0 +1,37 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$FILE"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+FILE=$(mktemp).hcl
+
+printf 'name = "web"\nport = 80\n' >"$FILE"
+
+ffs -m "$MNT" -i "$FILE" &
+PID=$!
+sleep 2
+[ "$(cat $MNT/name)" = "web" ] || fail name
+echo 8080 >"$MNT"/port
+echo true >"$MNT"/public
+
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+grep -e '^name = "web"$' "$FILE" >/dev/null 2>&1 || fail string
+grep -e '^port = 8080$' "$FILE" >/dev/null 2>&1 || fail number
+grep -e '^public = true$' "$FILE" >/dev/null 2>&1 || fail bool
+
+rmdir "$MNT" || fail mount
+rm "$FILE"
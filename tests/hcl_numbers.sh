@@ -0,0 +1,53 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT" "$ERR"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+ERR=$(mktemp)
+
+# HCL turns whole floats into 64-bit integers, so bigger ones stay as written
+ffs -m "$MNT" -o "$OUT" --target hcl ../hcl/numbers.tf 2>"$ERR" &
+PID=$!
+sleep 2
+[ "$(cat $MNT/small)" = "1000" ] || fail small
+[ "$(cat $MNT/fraction)" = "0.0025" ] || fail fraction
+[ "$(cat $MNT/big)" = "1e20" ] || fail big
+[ "$(cat $MNT/negative)" = "-1e20" ] || fail negative
+[ "$(cat $MNT/wide)" = "12345678901234567890" ] || fail wide
+[ "$(cat $MNT/limits/x)" = "1e20" ] || fail nested_big
+[ "$(cat $MNT/limits/y)" = "12345678901234567890" ] || fail nested_wide
+echo 3e25 >"$MNT"/small
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+grep -e "can't hold 1e20 exactly" "$ERR" >/dev/null 2>&1 || fail warning
+grep -e "9223372036854775807" "$OUT" >/dev/null 2>&1 && fail clamped
+grep -e '^small *= *"3e25"' "$OUT" >/dev/null 2>&1 || fail written
+grep -e '^wide *= *12345678901234567890' "$OUT" >/dev/null 2>&1 || fail saved_wide
+
+# ...unless they'd rather not mount it at all
+ffs -m "$MNT" --no-output --strict-types ../hcl/numbers.tf 2>"$ERR" && fail strict
+grep -e "can't hold 1e20" "$ERR" >/dev/null 2>&1 || fail strict_error
+
+# the same goes for converting to HCL
+HCL=$(echo '{"x": 1e20, "y": 12345678901234567890}' | ffs --convert --source json --target hcl -o - 2>"$ERR") || fail convert
+echo "$HCL" | grep -e '^x *= *"1e20"' >/dev/null 2>&1 || fail convert_big
+echo "$HCL" | grep -e '^y *= *12345678901234567890' >/dev/null 2>&1 || fail convert_wide
+echo '{"x": 1e20}' | ffs --convert --source json --target hcl --strict-types -o - >/dev/null 2>"$ERR" && fail convert_strict
+grep -e "too big for 64 bits" "$ERR" >/dev/null 2>&1 || fail convert_strict_error
+
+rmdir "$MNT" || fail mount
+rm "$OUT" "$ERR"
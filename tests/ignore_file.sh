@@ -0,0 +1,51 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm -r "$DIR"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+DIR=$(mktemp -d)
+
+echo '{"name":"app","secrets":{"api_key":"hunter2","db":{"password":"swordfish"}},"public":{"key":"abc"}}' >"$DIR"/config.json
+printf '# keep these out of sight\nsecrets/**\n' >"$DIR"/.ffsignore
+
+# .ffsignore next to the input is read by default
+ffs -m "$MNT" -o "$DIR"/out.json "$DIR"/config.json &
+PID=$!
+sleep 2
+[ -d "$MNT"/secrets ] || fail secrets_dir
+[ -e "$MNT"/secrets/api_key ] && fail api_key
+[ -e "$MNT"/secrets/db ] && fail db
+[ "$(ls "$MNT"/secrets | wc -l | tr -d ' ')" -eq 0 ] || fail ls
+[ "$(cat "$MNT"/public/key)" = "abc" ] || fail public
+echo app2 >"$MNT"/name
+umount "$MNT" || fail unmount1
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process1
+
+# hidden, but preserved
+grep -e '"api_key":"hunter2"' "$DIR"/out.json >/dev/null 2>&1 || fail save_api_key
+grep -e '"password":"swordfish"' "$DIR"/out.json >/dev/null 2>&1 || fail save_password
+grep -e '"name":"app2"' "$DIR"/out.json >/dev/null 2>&1 || fail save_name
+
+# or given explicitly
+mv "$DIR"/.ffsignore "$DIR"/rules
+ffs --ignore-file "$DIR"/rules --no-output -m "$MNT" "$DIR"/config.json &
+PID=$!
+sleep 2
+[ -e "$MNT"/secrets/api_key ] && fail explicit
+umount "$MNT" || fail unmount2
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process2
+
+rmdir "$MNT" || fail mount
+rm -r "$DIR"
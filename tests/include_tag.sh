@@ -0,0 +1,37 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm -r "$DIR"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+DIR=$(mktemp -d)
+
+printf '{"db":"!include db.yaml","loop":"!include main.json","name":"app"}' >"$DIR"/main.json
+printf 'host: localhost\n' >"$DIR"/db.yaml
+
+ffs --include-tag '!include' -i -m "$MNT" "$DIR"/main.json &
+PID=$!
+sleep 2
+[ -d "$MNT"/db ] || fail include
+[ "$(cat "$MNT"/db/host)" = "localhost" ] || fail read
+[ -f "$MNT"/loop ] || fail cycle
+echo example.com >"$MNT"/db/host
+umount "$MNT" || fail unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+grep -e '"db":"!include db.yaml"' "$DIR"/main.json >/dev/null 2>&1 || fail reference
+grep -e '"loop":"!include main.json"' "$DIR"/main.json >/dev/null 2>&1 || fail loop
+grep -e 'host: example.com' "$DIR"/db.yaml >/dev/null 2>&1 || fail writeback
+
+rmdir "$MNT" || fail mount
+rm -r "$DIR"
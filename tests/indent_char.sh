@@ -0,0 +1,61 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT" "$TABS" "$EXP"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+EXP=$(mktemp)
+TABS=$(mktemp)
+mv "$TABS" "$TABS".json
+TABS="$TABS".json
+
+TAB=$(printf '\t')
+
+ffs -m "$MNT" -o "$TABS" --indent-char tab --indent 2 ../json/json_eg2.json &
+PID=$!
+sleep 2
+umount "$MNT" || fail unmount1
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process1
+
+grep "^$TAB$TAB$TAB$TAB\"id\": \"file\",\$" "$TABS" >/dev/null 2>&1 || fail tabs
+grep '^ ' "$TABS" >/dev/null 2>&1 && fail spaces
+
+# the tab-indented output loads back to the same document...
+ffs -m "$MNT" -o "$OUT" --compact "$TABS" &
+PID=$!
+sleep 2
+umount "$MNT" || fail unmount2
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process2
+
+ffs -m "$MNT" -o "$EXP" --compact ../json/json_eg2.json &
+PID=$!
+sleep 2
+umount "$MNT" || fail unmount3
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process3
+
+diff "$OUT" "$EXP" || fail roundtrip
+
+# ...and its indentation is inferred
+ffs -m "$MNT" -o "$OUT" "$TABS" &
+PID=$!
+sleep 2
+umount "$MNT" || fail unmount4
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process4
+
+diff "$OUT" "$TABS" || fail inferred
+
+rmdir "$MNT" || fail mount
+rm "$OUT" "$TABS" "$EXP"
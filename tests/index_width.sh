@@ -0,0 +1,41 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+echo '[0,1,2]' | ffs -m "$MNT" --source json --target json -o "$OUT" --index-width 3 &
+PID=$!
+sleep 2
+cd "$MNT"
+[ "$(ls | tr '\n' ' ')" = "000 001 002 " ] || fail ls
+for i in 003 004 005 006 007 008 009 010 011
+do
+    expr $i + 0 >$i || fail "create $i"
+done
+case $(ls) in
+    (000*001*002*003*004*005*006*007*008*009*010*011) ;;
+    (*) fail grown;;
+esac
+[ "$(ls | grep -v -c -e '^[0-9][0-9][0-9]$')" -eq 0 ] || fail widths
+cd - >/dev/null 2>&1
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+[ "$(tr -d ' \n' <"$OUT")" = "[0,1,2,3,4,5,6,7,8,9,10,11]" ] || fail output
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
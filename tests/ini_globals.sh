@@ -0,0 +1,49 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$INI" "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+INI=$(mktemp)
+
+printf 'log.level = debug\nlog.path = /var/log\nowner = me\n\n[owner]\nname = Tom\n\n[owner]\nemail = tom@example.com\n' >"$INI"
+
+ffs -m "$MNT" -o "$OUT" --source ini --target ini --ini-split-dots "$INI" &
+PID=$!
+sleep 2
+case $(ls "$MNT") in
+    (log*owner*owner_) ;;
+    (*) fail ls;;
+esac
+# keys outside of any section are split on dots, too
+[ "$(cat "$MNT"/log/level)" = "debug" ] || fail split
+# a section named like a key gets renamed
+[ "$(cat "$MNT"/owner)" = "me" ] || fail global
+[ "$(cat "$MNT"/owner_/name)" = "Tom" ] || fail section
+[ "$(cat "$MNT"/owner_/email)" = "tom@example.com" ] || fail repeated_section
+echo info >"$MNT"/log/level
+
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+grep -e '^log.level = info$' "$OUT" >/dev/null 2>&1 || fail dotted
+grep -e '^\[log\]$' "$OUT" >/dev/null 2>&1 && fail log_section
+grep -e '^owner = me$' "$OUT" >/dev/null 2>&1 || fail global_saved
+grep -e '^\[owner_\]$' "$OUT" >/dev/null 2>&1 || fail section_saved
+# the globals come before any section
+[ "$(head -n 1 "$OUT")" = "log.level = info" ] || fail order
+
+rmdir "$MNT" || fail mount
+rm "$INI" "$OUT"
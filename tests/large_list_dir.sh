@@ -0,0 +1,34 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$IN"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+IN=$(mktemp)
+
+# a list too big to list in one readdir call
+seq 0 99999 | awk 'BEGIN { printf "[" } NR > 1 { printf "," } { printf "%d", $1 } END { print "]" }' >"$IN"
+
+ffs -m "$MNT" --source json --no-output "$IN" &
+PID=$!
+sleep 5
+[ "$(ls "$MNT" | wc -l)" -eq 100000 ] || fail count
+[ "$(ls "$MNT" | sort -u | wc -l)" -eq 100000 ] || fail duplicates
+[ "$(ls -l "$MNT" | grep -c '^-')" -eq 100000 ] || fail long_count
+[ "$(cat "$MNT"/99999)" -eq 99999 ] || fail last
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+rmdir "$MNT" || fail mount
+rm "$IN"
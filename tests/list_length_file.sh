@@ -0,0 +1,53 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+ffs -m "$MNT" -o "$OUT" --list-length-file .length ../json/list.json &
+PID=$!
+sleep 2
+ls -a "$MNT" | grep -q '^\.length$' || fail readdir
+[ "$(cat "$MNT"/.length)" -eq 4 ] || fail read
+echo 5 >"$MNT"/4
+[ "$(cat "$MNT"/.length)" -eq 5 ] || fail add
+rm "$MNT"/0
+[ "$(cat "$MNT"/.length)" -eq 4 ] || fail remove
+echo 10 >"$MNT"/.length 2>/dev/null && fail write
+[ "$(cat "$MNT"/.length)" -eq 4 ] || fail unchanged
+umount "$MNT" || fail unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+[ "$(tr -d ' \n' <"$OUT")" = '[2,"3",false,5]' ] || fail output
+
+# length files count against --max-inodes, and go with their lists
+echo '{"l":[1],"m":[]}' | ffs -m "$MNT" --source json --no-output --list-length-file .length --max-inodes 5 &
+PID=$!
+sleep 2
+[ "$(cat "$MNT"/l/.length)" -eq 1 ] || fail limit_first
+[ -e "$MNT"/m/.length ] && fail limit_second
+# removing l frees l, its element, and its length file
+rm "$MNT"/l/0 || fail rm
+rmdir "$MNT"/l || fail rmdir
+touch "$MNT"/n || fail freed1
+mkdir "$MNT"/o || fail freed2
+touch "$MNT"/p || fail freed3
+touch "$MNT"/q 2>/dev/null && fail limit_again
+umount "$MNT" || fail unmount_limit
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process_limit
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
@@ -0,0 +1,60 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+# reorder MODE EXPECTED: moves element 2 to 0 under `--list-rename MODE`
+reorder() {
+    echo '{"l":["a","b","c","d"]}' | ffs --list-rename "$1" -m "$MNT" --source json -o "$OUT" &
+    PID=$!
+    sleep 2
+    mv "$MNT"/l/2 "$MNT"/l/0 || fail "$1 mv"
+    [ "$(cat "$MNT"/l/0)" = "c" ] || fail "$1 moved"
+    [ "$(ls "$MNT"/l | wc -l)" -eq 4 ] || fail "$1 count"
+    umount "$MNT" || fail "$1 unmount"
+    sleep 1
+    kill -0 $PID >/dev/null 2>&1 && fail "$1 process"
+    grep -e "\"l\":$2" "$OUT" >/dev/null 2>&1 || fail "$1 output"
+}
+
+reorder shift '\["c","a","b","d"\]'
+reorder swap '\["c","b","a","d"\]'
+
+# the neighbours shift, too; the kernel can't be told, so read them with
+# caching off (see --ttl)
+echo '{"l":["a","b","c","d"]}' | ffs --ttl 0 -m "$MNT" --source json --no-output &
+PID=$!
+sleep 2
+[ "$(cat "$MNT"/l/1)" = "b" ] || fail neighbour_before
+mv "$MNT"/l/2 "$MNT"/l/0 || fail neighbour_mv
+[ "$(cat "$MNT"/l/1)" = "a" ] || fail neighbour_1
+[ "$(cat "$MNT"/l/2)" = "b" ] || fail neighbour_2
+umount "$MNT" || fail neighbour_unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail neighbour_process
+
+# unpadded names shift in numeric order: 10 comes after 9, not 1
+echo '[0,1,2,3,4,5,6,7,8,9,10,11]' | ffs --unpadded -m "$MNT" --source json -o "$OUT" &
+PID=$!
+sleep 2
+mv "$MNT"/10 "$MNT"/2 || fail unpadded_mv
+[ "$(cat "$MNT"/2)" -eq 10 ] || fail unpadded_moved
+umount "$MNT" || fail unpadded_unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail unpadded_process
+[ "$(cat "$OUT")" = "[0,1,10,2,3,4,5,6,7,8,9,11]" ] || fail unpadded_output
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
@@ -0,0 +1,42 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$MSG" "$DEEP"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+MSG=$(mktemp)
+DEEP=$(mktemp)
+
+# 100,000 nested lists: should be refused cleanly, not overflow the stack
+awk 'BEGIN { for (i = 0; i < 100000; i++) printf "["; for (i = 0; i < 100000; i++) printf "]"; print "" }' >"$DEEP"
+ffs -m "$MNT" --source json "$DEEP" 2>"$MSG"
+STATUS=$?
+[ $STATUS -ne 0 ] || fail status
+[ $STATUS -lt 128 ] || fail signal
+grep -i -e "panicked" -e "overflow" "$MSG" >/dev/null 2>&1 && fail panic
+
+# depth 4: a map holding a list holding a list holding a map
+echo '{"a":[[{"b":1}]]}' | ffs -m "$MNT" --source json --max-depth 3 2>"$MSG"
+[ $? -ne 0 ] || fail limit
+grep -e "nests 4 levels deep" "$MSG" >/dev/null 2>&1 || fail error
+grep -i -e "panicked" "$MSG" >/dev/null 2>&1 && fail panic
+
+echo '{"a":[[{"b":1}]]}' | ffs -m "$MNT" --source json --no-output --max-depth 4 &
+PID=$!
+sleep 2
+[ "$(cat "$MNT"/a/0/0/b)" -eq 1 ] || fail read
+umount "$MNT" || fail unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+rmdir "$MNT" || fail mount
+rm "$MSG" "$DEEP"
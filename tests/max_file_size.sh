@@ -0,0 +1,34 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT" "$MSG"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+MSG=$(mktemp)
+
+echo '{"a":"small"}' | ffs -m "$MNT" --source json -o "$OUT" --max-file-size 1024 &
+PID=$!
+sleep 2
+head -c 512 /dev/zero | tr '\0' x >"$MNT"/b || fail small
+head -c 2048 /dev/zero | tr '\0' x >"$MNT"/c 2>"$MSG" && fail big
+grep -i -e "too large" "$MSG" >/dev/null 2>&1 || fail efbig
+truncate -s 2048 "$MNT"/a 2>/dev/null && fail truncate
+[ "$(cat $MNT/a)" = "small" ] || fail unchanged
+umount "$MNT" || fail unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+grep -e '"b":"x\{512\}"' "$OUT" >/dev/null 2>&1 || fail output
+
+rmdir "$MNT" || fail mount
+rm "$OUT" "$MSG"
@@ -0,0 +1,46 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$MSG"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+MSG=$(mktemp)
+
+# 20 nodes: a list and its 19 elements
+echo '[1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19]' | ffs -m "$MNT" --source json --max-inodes 10 2>"$MSG"
+[ $? -ne 0 ] || fail status
+grep -e "would need 20 inodes" "$MSG" >/dev/null 2>&1 || fail error
+grep -i -e "panicked" "$MSG" >/dev/null 2>&1 && fail panic
+
+# 5 nodes, room for one more
+echo '{"a":1,"b":2,"c":3,"d":4}' | ffs -m "$MNT" --source json --no-output --max-inodes 6 &
+PID=$!
+sleep 2
+touch "$MNT"/e || fail touch
+touch "$MNT"/f 2>/dev/null && fail limit
+# removed files don't count, however many come and go
+for i in 1 2 3 4 5 6 7 8 9 10
+do
+    rm "$MNT"/e || fail rm$i
+    touch "$MNT"/e || fail retouch$i
+done
+mkdir "$MNT"/g 2>/dev/null && fail limit_dir
+rm "$MNT"/e || fail rm
+mkdir "$MNT"/g || fail mkdir
+rmdir "$MNT"/g || fail rmdir
+echo 5 >"$MNT"/e || fail refill
+umount "$MNT" || fail unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+rmdir "$MNT" || fail mount
+rm "$MSG"
@@ -0,0 +1,41 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$BIG" "$MSG"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+BIG=$(mktemp)
+MSG=$(mktemp)
+
+# one 10 MB string: only 2 inodes, but far too many bytes (plus the
+# newline its file ends in)
+{
+    printf '{"blob":"'
+    head -c 10000000 /dev/zero | tr '\0' a
+    printf '"}'
+} >"$BIG"
+ffs -m "$MNT" --source json --max-inodes 10 --max-total-bytes 1000000 "$BIG" 2>"$MSG"
+[ $? -ne 0 ] || fail status
+grep -e "holds 10000001 bytes" "$MSG" >/dev/null 2>&1 || fail error
+grep -i -e "panicked" "$MSG" >/dev/null 2>&1 && fail panic
+
+# small documents mount as usual, right up to the limit
+echo '{"a":"hello","b":"world"}' | ffs -m "$MNT" --source json --no-output --max-total-bytes 12 &
+PID=$!
+sleep 2
+[ "$(cat "$MNT"/a)" = "hello" ] || fail read
+umount "$MNT" || fail unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+rmdir "$MNT" || fail mount
+rm "$BIG" "$MSG"
@@ -0,0 +1,66 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$BASE" "$OVERLAY" "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+BASE=$(mktemp)
+OVERLAY=$(mktemp)
+OUT=$(mktemp)
+
+printf '{"db":{"host":"localhost","port":5432},"tags":["a","b"],"name":"base"}' >"$BASE"
+printf '{"db":{"port":6543,"user":"admin"},"tags":["c"]}' >"$OVERLAY"
+
+# nested objects merge; lists are replaced by default
+ffs -m "$MNT" --source json --target json -o "$OUT" --merge "$OVERLAY" "$BASE" &
+PID=$!
+sleep 2
+[ "$(cat "$MNT"/db/host)" = "localhost" ] || fail host
+[ "$(cat "$MNT"/db/port)" -eq 6543 ] || fail port
+[ "$(cat "$MNT"/db/user)" = "admin" ] || fail user
+[ "$(cat "$MNT"/name)" = "base" ] || fail name
+[ "$(ls "$MNT"/tags | wc -l)" -eq 1 ] || fail replace_length
+[ "$(cat "$MNT"/tags/0)" = "c" ] || fail replace
+umount "$MNT" || fail unmount1
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process1
+
+grep -e '"port":6543' "$OUT" >/dev/null 2>&1 || fail output_port
+grep -e '"host":"localhost"' "$OUT" >/dev/null 2>&1 || fail output_host
+
+# lists concatenated
+ffs -m "$MNT" --source json --no-output --merge "$OVERLAY" --merge-arrays concat "$BASE" &
+PID=$!
+sleep 2
+[ "$(ls "$MNT"/tags | wc -l)" -eq 3 ] || fail concat_length
+[ "$(cat "$MNT"/tags/0)" = "a" ] || fail concat_first
+[ "$(cat "$MNT"/tags/2)" = "c" ] || fail concat_last
+umount "$MNT" || fail unmount2
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process2
+
+# only the differences go back to the overlay
+ffs -m "$MNT" --source json --merge "$OVERLAY" --write-overlay-only "$BASE" &
+PID=$!
+sleep 2
+echo prod >"$MNT"/name
+umount "$MNT" || fail unmount3
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process3
+
+grep -e '"name":"prod"' "$OVERLAY" >/dev/null 2>&1 || fail overlay_name
+grep -e '"user":"admin"' "$OVERLAY" >/dev/null 2>&1 || fail overlay_user
+grep -e '"host"' "$OVERLAY" >/dev/null 2>&1 && fail overlay_host
+grep -e '"name":"base"' "$BASE" >/dev/null 2>&1 || fail base_unchanged
+
+rmdir "$MNT" || fail mount
+rm "$BASE" "$OVERLAY" "$OUT"
@@ -0,0 +1,44 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+# under rename (the default), the empty key gets a name and keeps its own
+echo '{"":"empty","ok":"fine"}' | ffs -m "$MNT" --source json -o "$OUT" &
+PID=$!
+sleep 2
+case $(ls "$MNT") in
+    (_EMPTY_*ok) ;;
+    (*) fail ls_rename;;
+esac
+[ "$(cat "$MNT"/_EMPTY_)" = "empty" ] || fail empty
+echo edited >"$MNT"/_EMPTY_
+umount "$MNT" || fail unmount_rename
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process_rename
+grep -e '"":"edited"' "$OUT" >/dev/null 2>&1 || fail roundtrip
+
+# under filter, it's left out
+echo '{"":"empty","ok":"fine"}' | ffs -m "$MNT" --source json --munge filter -o "$OUT" &
+PID=$!
+sleep 2
+[ "$(ls "$MNT")" = "ok" ] || fail ls_filter
+umount "$MNT" || fail unmount_filter
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process_filter
+[ "$(cat "$OUT")" = '{"ok":"fine"}' ] || fail filtered
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
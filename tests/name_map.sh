@@ -0,0 +1,43 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+ffs --compact --name-map '/glossary/GlossDiv/GlossList/GlossEntry/GlossDef=def' -m "$MNT" -o "$OUT" ../json/json_eg1.json &
+PID=$!
+sleep 2
+ENTRY="$MNT"/glossary/GlossDiv/GlossList/GlossEntry
+[ -d "$ENTRY"/def ] || fail renamed
+[ -e "$ENTRY"/GlossDef ] && fail original
+[ "$(cat "$ENTRY"/def/para)" = "A meta-markup language, used to create markup languages such as DocBook." ] || fail contents
+echo edited >"$ENTRY"/def/para
+
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+grep -e '"GlossDef":{[^}]*"para":"edited"' "$OUT" >/dev/null 2>&1 || fail roundtrip
+grep -e '"def"' "$OUT" >/dev/null 2>&1 && fail leaked
+
+# a name that's already in use can't be mapped to
+ffs --name-map '/glossary/GlossDiv/GlossList/GlossEntry/GlossDef=Abbrev' -m "$MNT" --no-output ../json/json_eg1.json 2>/dev/null &
+PID=$!
+sleep 2
+kill -0 $PID >/dev/null 2>&1 && fail collision
+mount | grep -q "$MNT" && fail collision_mounted
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
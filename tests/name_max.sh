@@ -0,0 +1,51 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT" "$MSG"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+MSG=$(mktemp)
+
+LONG=$(head -c 300 /dev/zero | tr '\0' x)
+SHORT=$(head -c 200 /dev/zero | tr '\0' x)
+
+echo '{"a":"small"}' | ffs -m "$MNT" --source json -o "$OUT" &
+PID=$!
+sleep 2
+{ echo hi >"$MNT"/"$LONG"; } 2>"$MSG" && fail create
+grep -i -e "too long" "$MSG" >/dev/null 2>&1 || fail create_enametoolong
+mkdir "$MNT"/"$LONG" 2>"$MSG" && fail mkdir
+grep -i -e "too long" "$MSG" >/dev/null 2>&1 || fail mkdir_enametoolong
+mv "$MNT"/a "$MNT"/"$LONG" 2>"$MSG" && fail rename
+grep -i -e "too long" "$MSG" >/dev/null 2>&1 || fail rename_enametoolong
+[ "$(cat "$MNT"/a)" = "small" ] || fail unchanged
+echo hi >"$MNT"/"$SHORT" || fail short
+umount "$MNT" || fail unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+grep -e "$LONG" "$OUT" >/dev/null 2>&1 && fail output_long
+grep -e "\"$SHORT\":\"hi\"" "$OUT" >/dev/null 2>&1 || fail output_short
+
+# a lower limit
+echo '{"a":"small"}' | ffs -m "$MNT" --source json --no-output --name-max 8 &
+PID=$!
+sleep 2
+touch "$MNT"/12345678 || fail limit
+touch "$MNT"/123456789 2>/dev/null && fail over_limit
+umount "$MNT" || fail unmount_limit
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process_limit
+
+rmdir "$MNT" || fail mount
+rm "$OUT" "$MSG"
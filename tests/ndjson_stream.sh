@@ -0,0 +1,47 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+ffs -m "$MNT" -o "$OUT" --target ndjson --stream ../ndjson/log.ndjson &
+PID=$!
+sleep 2
+[ "$(wc -l <"$OUT")" -eq 3 ] || fail initial
+
+# each new element is on disk as soon as it's closed
+echo first >"$MNT"/3
+[ "$(wc -l <"$OUT")" -eq 4 ] || fail lines1
+[ "$(tail -n 1 "$OUT")" = '"first"' ] || fail append1
+echo second >"$MNT"/4
+[ "$(wc -l <"$OUT")" -eq 5 ] || fail lines2
+[ "$(tail -n 1 "$OUT")" = '"second"' ] || fail append2
+echo third >"$MNT"/5
+[ "$(wc -l <"$OUT")" -eq 6 ] || fail lines3
+[ "$(tail -n 1 "$OUT")" = '"third"' ] || fail append3
+
+# changes to elements already on disk are saved at unmount
+echo warn >"$MNT"/0/level
+
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+[ "$(wc -l <"$OUT")" -eq 6 ] || fail lines
+head -n 1 "$OUT" | grep -e '"level":"warn"' >/dev/null 2>&1 || fail changed
+[ "$(sed -n 5p "$OUT")" = '"second"' ] || fail order
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
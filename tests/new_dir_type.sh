@@ -0,0 +1,39 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+# by default, new directories are maps, even when empty
+ffs -m "$MNT" -o "$OUT" --target json ../json/object.json &
+PID=$!
+sleep 2
+mkdir "$MNT"/pockets || fail mkdir1
+umount "$MNT" || fail unmount1
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process1
+grep -e '"pockets":{}' "$OUT" >/dev/null 2>&1 || fail named
+
+# ...or lists
+ffs --new-dir-type list -m "$MNT" -o "$OUT" --target json ../json/object.json &
+PID=$!
+sleep 2
+mkdir "$MNT"/pockets || fail mkdir2
+umount "$MNT" || fail unmount2
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process2
+grep -e '"pockets":\[\]' "$OUT" >/dev/null 2>&1 || fail list
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
@@ -0,0 +1,36 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm -r "$TMP"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+TMP=$(mktemp -d)
+
+echo precious >"$TMP"/out.json
+
+# an existing output file is an error, before anything is mounted
+ffs -m "$MNT" --no-clobber -o "$TMP"/out.json ../json/single.json 2>"$TMP"/msg
+[ $? -eq 2 ] || fail status
+grep -e "already exists; use --force" "$TMP"/msg >/dev/null 2>&1 || fail message
+[ "$(cat "$TMP"/out.json)" = "precious" ] || fail clobbered
+[ "$(ls "$MNT")" ] && fail mounted
+
+# saving in place is fine
+cp ../json/single.json "$TMP"/in.json
+ffs --convert --no-clobber -i "$TMP"/in.json || fail in_place
+
+# --force undoes --no-clobber
+ffs --convert --no-clobber --force -o "$TMP"/out.json ../json/single.json || fail force
+grep -e highlander "$TMP"/out.json >/dev/null 2>&1 || fail forced
+
+rmdir "$MNT" || fail mount
+rm -r "$TMP"
@@ -0,0 +1,36 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$ERR"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+ERR=$(mktemp)
+
+ffs -m "$MNT" --target json ../json/object.json &
+PID=$!
+sleep 2
+BAD=$(printf 'bad\377name')
+cat "$MNT/$BAD" 2>"$ERR" && fail lookup
+grep -i "invalid argument" "$ERR" >/dev/null 2>&1 || fail lookup_einval
+touch "$MNT/$BAD" 2>"$ERR" && fail create
+grep -i "invalid argument" "$ERR" >/dev/null 2>&1 || fail create_einval
+mkdir "$MNT/$BAD" 2>"$ERR" && fail mkdir
+grep -i "invalid argument" "$ERR" >/dev/null 2>&1 || fail mkdir_einval
+mv "$MNT/name" "$MNT/$BAD" 2>"$ERR" && fail rename
+grep -i "invalid argument" "$ERR" >/dev/null 2>&1 || fail rename_einval
+[ "$(cat "$MNT"/name)" = "Michael Greenberg" ] || fail name
+umount "$MNT" || fail unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+rmdir "$MNT" || fail mount
+rm "$ERR"
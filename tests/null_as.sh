@@ -0,0 +1,44 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+# missing: no file at all, but still saved
+ffs --null-as missing -m "$MNT" -o "$OUT" --target json ../json/object_null.json &
+PID=$!
+sleep 2
+[ -e "$MNT"/problems ] && fail missing
+echo 3 >"$MNT"/eyes
+umount "$MNT" || fail unmount1
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process1
+grep -e '"problems":null' "$OUT" >/dev/null 2>&1 || fail missing_save
+grep -e '"eyes":3' "$OUT" >/dev/null 2>&1 || fail missing_edit
+
+# symlink: a link, which is saved as null even when renamed
+ffs --null-as symlink -m "$MNT" -o "$OUT" --target json ../json/object_null.json &
+PID=$!
+sleep 2
+[ -L "$MNT"/problems ] || fail symlink
+[ "$(readlink "$MNT"/problems)" = "null" ] || fail readlink
+mv "$MNT"/problems "$MNT"/worries || fail rename
+umount "$MNT" || fail unmount2
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process2
+grep -e '"worries":null' "$OUT" >/dev/null 2>&1 || fail symlink_save
+grep -e '"problems"' "$OUT" >/dev/null 2>&1 && fail symlink_rename
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
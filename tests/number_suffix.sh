@@ -0,0 +1,34 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+echo '{"f": 1.0, "i": 1, "g": 2.5}' | ffs -m "$MNT" --source json --target json -o "$OUT" --number-suffix &
+PID=$!
+sleep 2
+[ "$(cat "$MNT"/f)" = "1.0" ] || fail float
+[ "$(cat "$MNT"/i)" = "1" ] || fail integer
+echo 3 >"$MNT"/g || fail write
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+grep -e '"f": *1\.0' "$OUT" >/dev/null 2>&1 || fail "f collapsed"
+grep -E '"i": *1([^.0-9]|$)' "$OUT" >/dev/null 2>&1 || fail "i saved"
+grep -e '"g": *3\.0' "$OUT" >/dev/null 2>&1 || fail "g collapsed"
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
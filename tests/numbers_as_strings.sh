@@ -0,0 +1,36 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+echo '{"id": 12345678901234567890, "big": 123456789012345678901234567890, "precise": 0.1000000000000000000001}' |
+    ffs --numbers-as-strings -m "$MNT" -o "$OUT" --source json --target json &
+PID=$!
+sleep 2
+[ "$(cat "$MNT"/id)" = "12345678901234567890" ] || fail id
+[ "$(cat "$MNT"/big)" = "123456789012345678901234567890" ] || fail big
+[ "$(cat "$MNT"/precise)" = "0.1000000000000000000001" ] || fail precise
+echo 98765432109876543210987654321 >"$MNT"/new
+umount "$MNT" || fail unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+grep -e '"id":12345678901234567890[,}]' "$OUT" >/dev/null 2>&1 || fail save_id
+grep -e '"big":123456789012345678901234567890[,}]' "$OUT" >/dev/null 2>&1 || fail save_big
+grep -e '"precise":0.1000000000000000000001[,}]' "$OUT" >/dev/null 2>&1 || fail save_precise
+grep -e '"new":98765432109876543210987654321[,}]' "$OUT" >/dev/null 2>&1 || fail save_new
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
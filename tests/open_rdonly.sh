@@ -0,0 +1,26 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+
+ffs -m "$MNT" --no-output ../json/object.json &
+PID=$!
+sleep 2
+# open read-only, then try to write through that descriptor
+(echo oops >&3) 3<"$MNT"/name 2>/dev/null && fail write
+[ "$(cat "$MNT"/name)" = "Michael Greenberg" ] || fail contents
+umount "$MNT" || fail unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+rmdir "$MNT" || fail mount
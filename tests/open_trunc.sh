@@ -0,0 +1,33 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+ffs -m "$MNT" -o "$OUT" --target json --exact ../json/object.json &
+PID=$!
+sleep 2
+[ "$(wc -c <"$MNT"/name)" -gt 3 ] || fail setup
+# the shell opens with O_WRONLY|O_TRUNC
+printf 'abc' >"$MNT"/name
+[ "$(wc -c <"$MNT"/name)" -eq 3 ] || fail size
+[ "$(cat "$MNT"/name)" = "abc" ] || fail contents
+umount "$MNT" || fail unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+grep -e '"name":"abc"' "$OUT" >/dev/null 2>&1 || fail output
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
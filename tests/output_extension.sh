@@ -0,0 +1,50 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm -r "$TMP"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+TMP=$(mktemp -d)
+
+# save FLAGS...: mounts ../json/single.json and saves it as FLAGS direct
+save() {
+    ffs -m "$MNT" "$@" ../json/single.json &
+    PID=$!
+    sleep 2
+    umount "$MNT" || fail "unmount $*"
+    sleep 1
+    kill -0 $PID >/dev/null 2>&1 && fail "process $*"
+}
+
+# each extension picks the same serializer that --target does
+for ext in json json5 yaml yml toml ron plist bson
+do
+    case $ext in
+        (yml) target=yaml;;
+        (*) target=$ext;;
+    esac
+    save -o "$TMP"/out.$ext
+    save -o "$TMP"/explicit --target $target
+    cmp "$TMP"/out.$ext "$TMP"/explicit || fail $ext
+done
+
+# --target wins over the extension
+save -o "$TMP"/override.json --target yaml
+cmp "$TMP"/override.json "$TMP"/out.yaml || fail override
+
+# an unknown extension is an error, before anything is mounted
+ffs -m "$MNT" -o "$TMP"/out.unknown ../json/single.json 2>"$TMP"/msg && fail unknown
+grep -e "Unrecognized output format 'unknown'" "$TMP"/msg >/dev/null 2>&1 || fail unknown_message
+[ -e "$TMP"/out.unknown ] && fail unknown_output
+
+rmdir "$MNT" || fail mount
+rm -r "$TMP"
@@ -0,0 +1,37 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+ffs -m "$MNT" -o "$OUT" --target json --pointer /menu/popup ../json/json_eg2.json &
+PID=$!
+sleep 2
+case $(ls "$MNT") in
+    (menuitem) ;;
+    (*) fail ls;;
+esac
+[ "$(cat "$MNT"/menuitem/0/value)" = "New" ] || fail value
+echo Save >"$MNT"/menuitem/2/value
+umount "$MNT" || fail unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+grep -e '"id": *"file"' "$OUT" >/dev/null 2>&1 || fail id
+grep -e '"value": *"File"' "$OUT" >/dev/null 2>&1 || fail outer_value
+grep -e '"value": *"Save"' "$OUT" >/dev/null 2>&1 || fail edit
+grep -e '"onclick": *"OpenDoc()"' "$OUT" >/dev/null 2>&1 || fail onclick
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
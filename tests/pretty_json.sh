@@ -24,7 +24,8 @@ umount "$MNT" || fail unmount
 sleep 1
 kill -0 $PID >/dev/null 2>&1 && fail process
 
-[ "$(cat $OUT | wc -l)" -eq 6 ] || fail lines
+# object.json ends in a newline, so the output does, too
+[ "$(cat $OUT | wc -l)" -eq 7 ] || fail lines
 grep '^\s*"handle": "mgree",$' "$OUT" >/dev/null 2>&1 || fail handle
 
 rmdir "$MNT" || fail mount
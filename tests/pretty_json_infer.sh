@@ -0,0 +1,47 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+# json_eg2.json is pretty printed with a two-space indent
+ffs -m "$MNT" -o "$OUT" ../json/json_eg2.json &
+PID=$!
+sleep 2
+umount "$MNT" || fail unmount1
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process1
+
+grep '^    "id": "file",$' "$OUT" >/dev/null 2>&1 || fail inferred
+
+ffs -m "$MNT" -o "$OUT" --indent 4 ../json/json_eg2.json &
+PID=$!
+sleep 2
+umount "$MNT" || fail unmount2
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process2
+
+grep '^        "id": "file",$' "$OUT" >/dev/null 2>&1 || fail indent
+
+ffs -m "$MNT" -o "$OUT" --compact ../json/json_eg2.json &
+PID=$!
+sleep 2
+umount "$MNT" || fail unmount3
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process3
+
+# one line, ending in a newline like json_eg2.json does
+[ "$(cat $OUT | wc -l)" -eq 1 ] || fail compact
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
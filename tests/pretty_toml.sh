@@ -29,7 +29,8 @@ sleep 1
 kill -0 $PID >/dev/null 2>&1 && fail process
 
 [ "$(cat $OUT | wc -l)" -eq 5 ] || fail lines
-[ "$(head -n 1 $OUT)" = "info = '''" ] || fail multi
+# the new key goes after the ones already in the input
+[ "$(sed -n 2p $OUT)" = "info = '''" ] || fail multi
 
 rmdir "$MNT" || fail mount
 rm "$OUT"
@@ -0,0 +1,40 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+echo 42 | ffs -m "$MNT" --source json -o "$OUT" --allow-primitive-root &
+PID=$!
+sleep 2
+[ "$(ls "$MNT")" = "value" ] || fail ls
+[ "$(cat "$MNT"/value)" = "42" ] || fail read
+echo 47 >"$MNT"/value
+umount "$MNT" || fail unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+[ "$(cat "$OUT")" = "47" ] || fail output
+
+echo '"hi"' | ffs -m "$MNT" --source json --no-output --primitive-name greeting &
+PID=$!
+sleep 2
+[ "$(ls "$MNT")" = "greeting" ] || fail name
+[ "$(cat "$MNT"/greeting)" = "hi" ] || fail read2
+umount "$MNT" || fail unmount2
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process2
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
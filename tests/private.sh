@@ -0,0 +1,38 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+
+# this test needs another user and permission to use allow_other
+if ! sudo -n -u nobody true >/dev/null 2>&1 ||
+   { [ "$(id -u)" -ne 0 ] && ! grep -q '^user_allow_other$' /etc/fuse.conf 2>/dev/null; }
+then
+    rmdir "$MNT"
+    exit 0
+fi
+
+# the mountpoint's parent needs to be visible to nobody
+chmod o+rx "$MNT"
+
+ffs --allow-other --private -m "$MNT" --no-output ../json/object.json &
+PID=$!
+sleep 2
+[ "$(cat "$MNT"/name)" = "Michael Greenberg" ] || fail owner
+sudo -n -u nobody cat "$MNT"/name >/dev/null 2>&1 && fail read
+sudo -n -u nobody ls "$MNT" >/dev/null 2>&1 && fail ls
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+rmdir "$MNT" || fail mount
@@ -0,0 +1,61 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT" "$MSG"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+MSG=$(mktemp)
+
+# no schema, no mount
+ffs -m "$MNT" ../protobuf/person.pb 2>"$MSG"
+[ $? -ne 0 ] || fail "no schema status"
+grep -e "--proto-schema" "$MSG" >/dev/null 2>&1 || fail "no schema error"
+
+ffs -m "$MNT" --proto-schema ../protobuf/person.proto -t protobuf -o "$OUT" ../protobuf/person.pb &
+PID=$!
+sleep 2
+case $(ls "$MNT") in
+    (address*emails*id*kind*name*scores) ;;
+    (*) fail ls;;
+esac
+[ "$(cat $MNT/name)" = "Michael Greenberg" ] || fail string
+[ "$(cat $MNT/id)" -eq 7 ] || fail integer
+[ "$(cat $MNT/kind)" = "HUMAN" ] || fail enum
+[ "$(cat $MNT/emails/1)" = "michael@example.org" ] || fail repeated
+[ "$(cat $MNT/scores/ffs)" -eq 10 ] || fail map
+[ "$(cat $MNT/address/city)" = "Hoboken" ] || fail message
+echo fuse >"$MNT"/name
+echo ROBOT >"$MNT"/kind
+
+umount "$MNT" || fail unmount1
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process1
+
+# the saved message should have the edits, and everything else intact
+ffs --readonly -s protobuf --proto-schema ../protobuf/person.proto --proto-message ffs.example.Person -m "$MNT" "$OUT" &
+PID=$!
+sleep 2
+[ "$(cat $MNT/name)" = "fuse" ] || fail edited
+[ "$(cat $MNT/kind)" = "ROBOT" ] || fail "edited enum"
+[ "$(cat $MNT/emails/0)" = "mgree@example.com" ] || fail "saved repeated"
+[ "$(cat $MNT/scores/json)" -eq 3 ] || fail "saved map"
+[ "$(cat $MNT/address/zip)" = "07030" ] || fail "saved message"
+
+umount "$MNT" || fail unmount2
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process2
+
+rmdir "$MNT" || fail mount
+rm "$OUT" "$MSG"
@@ -0,0 +1,46 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT" "$IN"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+IN=$(mktemp)
+mv "$IN" "$IN".json
+IN="$IN".json
+
+printf '{"a":{},"b":[1]}' >"$IN"
+
+ffs -m "$MNT" -o "$OUT" --prune-empty "$IN" &
+PID=$!
+sleep 2
+[ -d "$MNT"/a ] || fail a
+mkdir "$MNT"/c
+mkdir "$MNT"/c/d
+umount "$MNT" || fail unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+[ "$(tr -d ' \n' <"$OUT")" = '{"b":[1]}' ] || fail output
+
+# without the flag, empty directories are kept
+ffs -m "$MNT" -o "$OUT" "$IN" &
+PID=$!
+sleep 2
+umount "$MNT" || fail unmount2
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process2
+
+[ "$(tr -d ' \n' <"$OUT")" = '{"a":{},"b":[1]}' ] || fail unpruned
+
+rmdir "$MNT" || fail mount
+rm "$OUT" "$IN"
@@ -0,0 +1,45 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$LS"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+LS=$(mktemp)
+
+ffs -m "$MNT" --no-output ../json/object.json &
+PID=$!
+sleep 2
+mkdir "$MNT"/d || fail mkdir
+for i in $(seq 1 500)
+do
+    echo $i >"$MNT"/d/orig$i || fail touch$i
+done
+
+# add and remove entries while listing; every original entry should be listed exactly once
+(for i in $(seq 1 200)
+ do
+     touch "$MNT"/d/new$i
+     rm -f "$MNT"/d/new$((i - 1))
+ done) &
+EDITS=$!
+ls -f "$MNT"/d >"$LS" || fail ls
+wait $EDITS
+
+[ "$(grep -c '^orig' "$LS")" -eq 500 ] || fail count
+[ "$(grep '^orig' "$LS" | sort -u | wc -l)" -eq 500 ] || fail duplicates
+
+umount "$MNT" || fail unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+rmdir "$MNT" || fail mount
+rm "$LS"
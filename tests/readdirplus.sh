@@ -0,0 +1,36 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+
+ffs --no-output -m "$MNT" ../json/object.json &
+PID=$!
+sleep 2
+
+# the attributes `ls -l` gets with each entry agree with the files
+ls -ln "$MNT" | tail -n +2 | while read -r mode links uid gid size month day time name
+do
+    [ "$size" -eq "$(wc -c <"$MNT/$name" | tr -d ' ')" ] || fail "size of $name"
+    [ "$uid" -eq "$(id -u)" ] || fail "owner of $name"
+    case "$mode" in
+        (-rw-r--r--*) ;;
+        (*) fail "mode of $name";;
+    esac
+done || exit 1
+[ "$(ls -l "$MNT" | tail -n +2 | wc -l | tr -d ' ')" -eq 4 ] || fail count
+
+umount "$MNT" || fail unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+rmdir "$MNT" || fail mount
@@ -36,7 +36,8 @@ sleep 2
 
 rmattr user.type $MNT && fail "root user.type"
 rmattr user.fake $MNT && fail "root user.fake"
-rmattr user.type "$MNT/name" && fail "root user.type"
+rmattr user.ffs.type $MNT && fail "root user.ffs.type"
+rmattr user.type "$MNT/name" || fail "name user.type"
 
 umount "$MNT" || fail unmount
 sleep 1
@@ -0,0 +1,37 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+# '.' and 'a/b' get munged to names that other fields already have
+echo '{".":1,"_.":2,"a/b":3,"a_SLASH_b":4}' | ffs -m "$MNT" --source json --target json -o "$OUT" &
+PID=$!
+sleep 2
+[ "$(cat "$MNT"/_.)" -eq 2 ] || fail underscore_dot
+[ "$(cat "$MNT"/_._)" -eq 1 ] || fail dot
+[ "$(cat "$MNT"/a_SLASH_b)" -eq 4 ] || fail underscore_slash
+[ "$(cat "$MNT"/a_SLASH_b_)" -eq 3 ] || fail slash
+echo 5 >"$MNT"/_._
+umount "$MNT" || fail unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+grep -e '"\.":5' "$OUT" >/dev/null 2>&1 || fail write_dot
+grep -e '"_\.":2' "$OUT" >/dev/null 2>&1 || fail write_underscore_dot
+grep -e '"a/b":3' "$OUT" >/dev/null 2>&1 || fail write_slash
+grep -e '"a_SLASH_b":4' "$OUT" >/dev/null 2>&1 || fail write_underscore_slash
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
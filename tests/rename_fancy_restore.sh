@@ -16,7 +16,7 @@ MNT=$(mktemp -d)
 OUT=$(mktemp)
 EXP=$(mktemp)
 
-printf '{"he":{"dot":"shlishi"},"imnewhere":"derp","it":{".":"primo","..":"secondo"}}' >"$EXP"
+printf '{"he":{"dot":"shlishi"},"imnewhere":"derp","it":{".":"primo","..":"secondo"}}\n' >"$EXP"
 
 ffs -m "$MNT" -o "$OUT" --target json ../json/obj_rename.json &
 PID=$!
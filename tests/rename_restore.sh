@@ -16,7 +16,7 @@ MNT=$(mktemp -d)
 OUT=$(mktemp)
 EXP=$(mktemp)
 
-printf '{".":"primo","..":"secondo","dot":"terzo","dotdot":"quarto"}' >"$EXP"
+printf '{".":"primo","..":"secondo","dot":"terzo","dotdot":"quarto"}\n' >"$EXP"
 
 ffs -m "$MNT" -o "$OUT" --target json ../json/obj_rename.json &
 PID=$!
@@ -0,0 +1,44 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+# mv refuses to rename a file to itself, so we call rename(2) directly
+rename() {
+    perl -e 'rename($ARGV[0], $ARGV[1]) or exit 1' "$1" "$2"
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+ffs -m "$MNT" --target json -o "$OUT" ../json/nlink.json &
+PID=$!
+sleep 2
+
+rename "$MNT"/child2 "$MNT"/child2 || fail file_self
+[ "$(cat "$MNT"/child2)" -eq 0 ] || fail file_unchanged
+rename "$MNT"/child1 "$MNT"/child1 || fail dir_self
+[ "$(cat "$MNT"/child1/nested)" = "true" ] || fail dir_unchanged
+mkdir "$MNT"/child1/inner
+mv "$MNT"/child1 "$MNT"/child1/inner/moved 2>/dev/null && fail descendant
+mv "$MNT"/child1 "$MNT"/child1/moved 2>/dev/null && fail child
+[ -d "$MNT"/child1/inner ] || fail still_there
+
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+[ "$(tr -d ' \n' <"$OUT")" = '{"child1":{"inner":{},"nested":true},"child2":0,"child3":{"nested":true}}' ] || fail output
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
@@ -0,0 +1,43 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        chmod u+w "$DIR"
+        rm -r "$DIR" "$ERR"
+    fi
+    exit 1
+}
+
+# root can write to read-only directories
+if [ "$(id -u)" -eq 0 ]
+then
+    exit 0
+fi
+
+MNT=$(mktemp -d)
+DIR=$(mktemp -d)
+ERR=$(mktemp)
+
+cp ../json/object.json "$DIR"/object.json
+ffs -i -m "$MNT" "$DIR"/object.json 2>"$ERR" &
+PID=$!
+sleep 2
+# the file itself is writable, but no temporary file can be made next to it
+chmod a-w "$DIR"
+echo 3 >"$MNT"/eyes
+umount "$MNT" || fail unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+cmp ../json/object.json "$DIR"/object.json || fail clobbered
+grep -e "Couldn't save" "$ERR" >/dev/null 2>&1 || fail error
+grep -i -e "panicked" "$ERR" >/dev/null 2>&1 && fail panic
+
+chmod u+w "$DIR"
+rmdir "$MNT" || fail mount
+rm -r "$DIR" "$ERR"
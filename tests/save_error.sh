@@ -0,0 +1,22 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    rm -f "$OUT" "$ERR"
+    exit 1
+}
+
+OUT=$(mktemp)
+ERR=$(mktemp)
+
+# HCL can't hold a list at the top level, so nothing is saved
+echo untouched >"$OUT"
+ffs --convert --target hcl -o "$OUT" ../json/list.json 2>"$ERR" && fail status
+[ "$(cat "$OUT")" = "untouched" ] || fail clobbered
+grep -e "Couldn't save" "$ERR" >/dev/null 2>&1 || fail error
+grep -i -e "panicked" "$ERR" >/dev/null 2>&1 && fail panic
+
+# nor is anything written to STDOUT
+[ -z "$(ffs --convert --target hcl -o - ../json/list.json 2>/dev/null)" ] || fail stdout
+
+rm "$OUT" "$ERR"
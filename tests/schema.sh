@@ -0,0 +1,66 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm -r "$TMP"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+TMP=$(mktemp -d)
+
+cat >"$TMP"/schema.json <<SCHEMA
+{
+  "type": "object",
+  "required": ["name"],
+  "properties": {
+    "name": { "type": "string" },
+    "tags": { "type": "array", "items": { "type": "string" } }
+  }
+}
+SCHEMA
+echo '{"tags": ["a", 2]}' >"$TMP"/in.json
+
+# violations are warnings, with the path to the offending value
+ffs --convert --schema "$TMP"/schema.json -o "$TMP"/out.json "$TMP"/in.json 2>"$TMP"/msg || fail warn
+grep -e "at the root: \"name\" is a required property" "$TMP"/msg >/dev/null 2>&1 || fail root
+grep -e "at '/tags/1'" "$TMP"/msg >/dev/null 2>&1 || fail path
+[ -s "$TMP"/out.json ] || fail saved
+
+# enforcing the schema refuses to save
+rm "$TMP"/out.json
+ffs --convert --schema "$TMP"/schema.json --schema-enforce -o "$TMP"/out.json "$TMP"/in.json 2>"$TMP"/msg && fail enforce
+grep -e "at the root: \"name\" is a required property" "$TMP"/msg >/dev/null 2>&1 || fail enforce_root
+[ -e "$TMP"/out.json ] && fail enforce_saved
+
+# a document that matches is saved quietly
+echo '{"name": "hi", "tags": ["a"]}' >"$TMP"/ok.json
+ffs --convert --schema "$TMP"/schema.json --schema-enforce -o "$TMP"/out.json "$TMP"/ok.json 2>"$TMP"/msg || fail valid
+grep -e "violation" "$TMP"/msg >/dev/null 2>&1 && fail valid_warned
+grep -e hi "$TMP"/out.json >/dev/null 2>&1 || fail valid_saved
+
+# mounted, edits are checked when saving
+ffs -m "$MNT" --schema "$TMP"/schema.json --schema-enforce -o "$TMP"/mnt.json "$TMP"/ok.json 2>"$TMP"/msg &
+PID=$!
+sleep 2
+rm "$MNT"/name || fail rm
+umount "$MNT" || fail unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process
+grep -e "at the root: \"name\" is a required property" "$TMP"/msg >/dev/null 2>&1 || fail mount_root
+[ -e "$TMP"/mnt.json ] && fail mount_saved
+
+# a bad schema is caught before anything is mounted
+echo '{"type": 5}' >"$TMP"/bad.json
+ffs --convert --schema "$TMP"/bad.json "$TMP"/ok.json 2>"$TMP"/msg
+[ $? -eq 2 ] || fail bad_status
+grep -e "Couldn't use the JSON Schema" "$TMP"/msg >/dev/null 2>&1 || fail bad_message
+
+rmdir "$MNT" || fail mount
+rm -r "$TMP"
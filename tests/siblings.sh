@@ -0,0 +1,47 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm -r "$D"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+D=$(mktemp -d)
+
+cp ../json/object.json "$D"/object.json
+printf 'name: ffs\nlist:\n  - 1\n  - 2\n' >"$D"/config.yaml
+cp ../json/list.json "$D"/list.json
+
+ffs -m "$MNT" "$D"/object.json "$D"/list.json "$D"/config.yaml &
+PID=$!
+sleep 2
+case $(ls "$MNT") in
+    (config*list*object) ;;
+    (*) fail ls;;
+esac
+[ "$(cat "$MNT"/object/name)" = "Michael Greenberg" ] || fail json
+[ "$(cat "$MNT"/config/name)" = "ffs" ] || fail yaml
+echo "Mike" >"$MNT"/object/name
+echo 3 >"$MNT"/config/list/2
+umount "$MNT" || fail unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+grep -e '"name":"Mike"' "$D"/object.json >/dev/null 2>&1 || fail object_saved
+grep -e '- 3' "$D"/config.yaml >/dev/null 2>&1 || fail yaml_saved
+grep -e '^name: ffs' "$D"/config.yaml >/dev/null 2>&1 || fail yaml_format
+[ "$(tr -d ' \n' <"$D"/list.json)" = "$(tr -d ' \n' <../json/list.json)" ] || fail list_saved
+
+# the same stem twice is an error
+cp ../json/list.json "$D"/config.json
+ffs -m "$MNT" "$D"/config.json "$D"/config.yaml 2>/dev/null && fail duplicate
+
+rmdir "$MNT" || fail mount
+rm -r "$D"
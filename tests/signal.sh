@@ -0,0 +1,30 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+ffs -m "$MNT" -o "$OUT" --target json --no-sync-on-close ../json/object.json &
+PID=$!
+sleep 2
+echo Mikey >"$MNT"/name || fail write
+kill -TERM $PID || fail kill
+sleep 2
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+grep -e '"name": *"Mikey"' "$OUT" >/dev/null 2>&1 || fail saved
+
+# the mount should be gone, not left dead
+rmdir "$MNT" || fail mount
+rm "$OUT"
@@ -0,0 +1,34 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$ERR"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+ERR=$(mktemp)
+
+echo '{}' | ffs -m "$MNT" --source json --no-output --size 1K &
+PID=$!
+sleep 2
+head -c 1000 /dev/zero | tr '\0' a >"$MNT"/fits || fail fits
+[ "$(wc -c <"$MNT"/fits)" -eq 1000 ] || fail size
+head -c 100 /dev/zero | tr '\0' b >"$MNT"/overflow 2>"$ERR" && fail overflow
+grep -i -e "no space" "$ERR" >/dev/null 2>&1 || fail enospc
+rm "$MNT"/overflow "$MNT"/fits || fail rm
+head -c 1024 /dev/zero | tr '\0' c >"$MNT"/full || fail refill
+touch "$MNT"/empty 2>"$ERR" && fail "full create"
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+rmdir "$MNT" || fail mount
+rm "$ERR"
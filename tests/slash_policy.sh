@@ -0,0 +1,64 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT" "$MSG"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+MSG=$(mktemp)
+
+# escape (the default), with a chosen replacement
+echo '{"a/b":1,"a/c":2,"d":3}' | ffs -m "$MNT" --source json --target json -o "$OUT" --slash-escape %2F &
+PID=$!
+sleep 2
+case $(ls "$MNT") in
+    (a%2Fb*a%2Fc*d) ;;
+    (*) fail ls_escape;;
+esac
+[ "$(cat "$MNT"/a%2Fb)" -eq 1 ] || fail read_escape
+echo 4 >"$MNT"/a%2Fb
+umount "$MNT" || fail unmount_escape
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process_escape
+
+grep -e '"a/b":4' "$OUT" >/dev/null 2>&1 || fail write_escape
+grep -e '"a/c":2' "$OUT" >/dev/null 2>&1 || fail keep_escape
+
+# reject
+echo '{"a/b":1,"d":3}' | ffs -m "$MNT" --source json --slash-policy reject 2>"$MSG"
+[ $? -ne 0 ] || fail status_reject
+grep -e "a/b" "$MSG" >/dev/null 2>&1 || fail error_reject
+grep -i -e "panicked" "$MSG" >/dev/null 2>&1 && fail panic_reject
+
+# split
+echo '{"a/b":1,"a/c":2,"d":3}' | ffs -m "$MNT" --source json --target json -o "$OUT" --slash-policy split &
+PID=$!
+sleep 2
+case $(ls "$MNT") in
+    (a*d) ;;
+    (*) fail ls_split;;
+esac
+[ -d "$MNT"/a ] || fail dir_split
+[ "$(cat "$MNT"/a/b)" -eq 1 ] || fail read_split
+echo 4 >"$MNT"/a/b
+echo 5 >"$MNT"/a/e
+umount "$MNT" || fail unmount_split
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process_split
+
+grep -e '"a/b":4' "$OUT" >/dev/null 2>&1 || fail write_split
+grep -e '"a/c":2' "$OUT" >/dev/null 2>&1 || fail keep_split
+grep -e '"a/e":5' "$OUT" >/dev/null 2>&1 || fail new_split
+grep -e '"a":' "$OUT" >/dev/null 2>&1 && fail nested_split
+
+rmdir "$MNT" || fail mount
+rm "$OUT" "$MSG"
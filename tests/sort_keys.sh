@@ -0,0 +1,30 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+echo '{"c":1,"b":{"z":[{"q":1,"p":2}],"y":2},"a":3}' | ffs --sort-keys --source json -m "$MNT" -o "$OUT" &
+PID=$!
+sleep 2
+echo 4 >"$MNT"/d
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+[ "$(cat $OUT)" = '{"a":3,"b":{"y":2,"z":[{"p":2,"q":1}]},"c":1,"d":4}' ] || fail sorted
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
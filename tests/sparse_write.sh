@@ -0,0 +1,39 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT" "$MSG"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+MSG=$(mktemp)
+
+echo '{"n":12}' | ffs --source json --exact -m "$MNT" -o "$OUT" 2>"$MSG" &
+PID=$!
+sleep 2
+
+# reading past the end is empty, not an error
+[ "$(dd if=$MNT/n bs=1 skip=100 count=1 2>/dev/null | wc -c)" -eq 0 ] || fail read_past_end
+
+# writing past the end zero-fills the gap
+printf 3 | dd of="$MNT"/n bs=1 seek=4 conv=notrunc 2>/dev/null || fail write
+[ "$(od -An -c $MNT/n | tr -d ' ')" = '12\0\03' ] || fail zeroes
+[ "$(dd if=$MNT/n bs=1 skip=2 count=2 2>/dev/null | od -An -tx1 | tr -d ' ')" = "0000" ] || fail read_gap
+
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+grep -e "past its end" "$MSG" >/dev/null 2>&1 || fail warning
+
+rmdir "$MNT" || fail mount
+rm "$OUT" "$MSG"
@@ -0,0 +1,51 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$YAML" "$OUT" "$MSG"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+YAML=$(mktemp)
+OUT=$(mktemp)
+MSG=$(mktemp)
+
+printf 'name: web\nports:\n  80: http\n  443: https\n' >"$YAML"
+
+# JSON keys are strings, so the integer keys can't survive
+ffs -m "$MNT" --source yaml --target json -o "$OUT" --strict-types "$YAML" 2>"$MSG"
+[ $? -ne 0 ] || fail status
+grep -e "'/ports/80' (a non-string key)" "$MSG" >/dev/null 2>&1 || fail error
+grep -e "'/ports/443'" "$MSG" >/dev/null 2>&1 || fail error_all
+grep -i -e "panicked" "$MSG" >/dev/null 2>&1 && fail panic
+
+# by default, the keys become strings, with a warning
+ffs -m "$MNT" --source yaml --target json -o "$OUT" "$YAML" 2>"$MSG" &
+PID=$!
+sleep 2
+[ "$(cat "$MNT"/ports/80)" = "http" ] || fail read
+umount "$MNT" || fail unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+grep -e "at '/ports/80'" "$MSG" >/dev/null 2>&1 || fail warning
+grep -e '"80":"http"' "$OUT" >/dev/null 2>&1 || fail coerced
+
+# YAML to YAML loses nothing
+ffs -m "$MNT" --source yaml --target yaml --no-output --strict-types "$YAML" &
+PID=$!
+sleep 2
+[ "$(cat "$MNT"/name)" = "web" ] || fail same_format
+umount "$MNT" || fail unmount2
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process2
+
+rmdir "$MNT" || fail mount
+rm "$YAML" "$OUT" "$MSG"
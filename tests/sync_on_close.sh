@@ -0,0 +1,54 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+ffs -m "$MNT" -o "$OUT" --target json ../json/object.json &
+PID=$!
+sleep 2
+echo Mikey >"$MNT"/name || fail write
+sleep 2
+grep -e '"name": *"Mikey"' "$OUT" >/dev/null 2>&1 || fail "saved on close"
+echo 3 >"$MNT"/eyes || fail "second write"
+sleep 2
+grep -e '"eyes": *3' "$OUT" >/dev/null 2>&1 || fail "saved again"
+
+# closes in a row share one save, once they've settled down
+echo 4 >"$MNT"/eyes || fail "burst write"
+echo 5 >"$MNT"/eyes || fail "burst write again"
+grep -e '"eyes": *5' "$OUT" >/dev/null 2>&1 && fail "saved right away"
+sleep 2
+grep -e '"eyes": *5' "$OUT" >/dev/null 2>&1 || fail "saved burst"
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+# without syncing on close, nothing is written until unmount
+: >"$OUT"
+ffs -m "$MNT" -o "$OUT" --target json --no-sync-on-close ../json/object.json &
+PID=$!
+sleep 2
+echo Mikey >"$MNT"/name || fail "no sync write"
+sleep 2
+[ -s "$OUT" ] && fail "no sync saved"
+umount "$MNT" || fail "no sync unmount"
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail "no sync process"
+grep -e '"name": *"Mikey"' "$OUT" >/dev/null 2>&1 || fail "no sync output"
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
@@ -0,0 +1,34 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+ffs -m "$MNT" -o "$OUT" --target toml ../toml/comments.toml &
+PID=$!
+sleep 2
+echo 9090 >"$MNT"/owner/port
+umount "$MNT" || fail unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+grep -e '^port = 9090$' "$OUT" >/dev/null 2>&1 || fail edit
+grep -e '^# Server configuration$' "$OUT" >/dev/null 2>&1 || fail top
+grep -e '^title = "demo" # shown in the banner$' "$OUT" >/dev/null 2>&1 || fail trailing
+grep -e '^# Who runs this$' "$OUT" >/dev/null 2>&1 || fail table
+grep -e '^name = "Tom"   # full name$' "$OUT" >/dev/null 2>&1 || fail spacing
+grep -e '^# the first plugin$' "$OUT" >/dev/null 2>&1 || fail array
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
@@ -0,0 +1,42 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+ffs --trim-whitespace -m "$MNT" -o "$OUT" --target json ../json/object.json &
+PID=$!
+sleep 2
+printf ' 42 \n' >"$MNT"/eyes
+printf '\t Mikey Indiana  \n\n' >"$MNT"/name
+umount "$MNT" || fail unmount1
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process1
+
+grep -e '"eyes":42[,}]' "$OUT" >/dev/null 2>&1 || fail number
+grep -e '"name":"Mikey Indiana"' "$OUT" >/dev/null 2>&1 || fail string
+
+# without it, the padding is kept
+ffs -m "$MNT" -o "$OUT" --target json ../json/object.json &
+PID=$!
+sleep 2
+printf ' 42 \n' >"$MNT"/eyes
+umount "$MNT" || fail unmount2
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process2
+
+grep -e '"eyes":" 42 "' "$OUT" >/dev/null 2>&1 || fail untrimmed
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
@@ -0,0 +1,41 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$SRC" "$MSG"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+SRC=$(mktemp)
+MSG=$(mktemp)
+
+echo '{"a":1}' >"$SRC"
+
+ffs --ttl=-1 --source json --no-output -m "$MNT" "$SRC" 2>"$MSG"
+[ $? -eq 2 ] || fail status
+grep -e "Invalid .--ttl" "$MSG" >/dev/null 2>&1 || fail error
+
+# without caching, even files we've already looked up are reloaded
+ffs --watch --ttl 0 --source json --no-output -m "$MNT" "$SRC" &
+PID=$!
+sleep 2
+[ "$(cat $MNT/a)" -eq 1 ] || fail initial
+
+echo '{"a":2}' >"$SRC"
+sleep 1
+[ "$(cat $MNT/a)" -eq 2 ] || fail reload
+
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+rmdir "$MNT" || fail mount
+rm "$SRC" "$MSG"
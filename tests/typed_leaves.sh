@@ -0,0 +1,44 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+ffs --typed-leaves -m "$MNT" -o "$OUT" ../json/object.json &
+PID=$!
+sleep 2
+[ -d "$MNT"/name ] || fail dir
+case $(ls "$MNT"/name) in
+    (type*value) ;;
+    (*) fail ls;;
+esac
+[ "$(cat $MNT/name/value)" = "Michael Greenberg" ] || fail value
+[ "$(cat $MNT/name/type)" = "string" ] || fail string
+[ "$(cat $MNT/human/type)" = "boolean" ] || fail boolean
+echo string >"$MNT"/fingernails/type
+mkdir "$MNT"/toes
+echo 10 >"$MNT"/toes/value
+echo integer >"$MNT"/toes/type
+
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+grep -e '"fingernails":"10"' "$OUT" >/dev/null 2>&1 || fail retype
+grep -e '"toes":10' "$OUT" >/dev/null 2>&1 || fail new
+grep -e '"human":true' "$OUT" >/dev/null 2>&1 || fail unchanged
+
+rmdir "$MNT" || fail mount
+rm "$OUT"
@@ -0,0 +1,41 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT" "$YAML"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+YAML=$(mktemp)
+
+# unpadded names still save in numeric order: 2 before 10
+ffs --unpadded -m "$MNT" -o "$OUT" ../json/list2.json &
+PID=$!
+sleep 2
+echo 30 >"$MNT"/3
+umount "$MNT" || fail unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process
+[ "$(cat "$OUT")" = "[0,1,2,30,4,5,6,7,8,9,10]" ] || fail output
+
+# ...and JSON Pointers count elements the same way
+printf 'l: [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, &x ten]\nr: *x\n' >"$YAML"
+ffs --unpadded -m "$MNT" --no-output --yaml-aliases link "$YAML" &
+PID=$!
+sleep 2
+[ "$(readlink "$MNT"/r)" = "l/10" ] || fail readlink
+[ "$(cat "$MNT"/r)" = "ten" ] || fail read
+umount "$MNT" || fail unmount2
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process2
+
+rmdir "$MNT" || fail mount
+rm "$OUT" "$YAML"
@@ -0,0 +1,38 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$SRC" "$OUT"
+    fi
+    exit 1
+}
+
+which iconv >/dev/null 2>&1 || { echo "SKIPPED: no iconv"; exit 0; }
+
+MNT=$(mktemp -d)
+SRC=$(mktemp)
+OUT=$(mktemp)
+
+# UTF-16LE with a byte order mark
+printf '\377\376' >"$SRC"
+printf '{"greeting":"h\303\251llo"}' | iconv -f UTF-8 -t UTF-16LE >>"$SRC"
+
+ffs -m "$MNT" -o "$OUT" --source json --target json "$SRC" &
+PID=$!
+sleep 2
+[ "$(cat "$MNT"/greeting)" = "$(printf 'h\303\251llo')" ] || fail read
+echo hi >"$MNT"/greeting
+umount "$MNT" || fail unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+[ "$(head -c 2 "$OUT" | od -An -tx1 | tr -d ' ')" = "fffe" ] || fail bom
+tail -c +3 "$OUT" | iconv -f UTF-16LE -t UTF-8 | grep -e '"greeting":"hi"' >/dev/null 2>&1 || fail save
+
+rmdir "$MNT" || fail mount
+rm "$SRC" "$OUT"
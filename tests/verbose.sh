@@ -0,0 +1,55 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$MSG"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+MSG=$(mktemp)
+
+# the flags only decide when RUST_LOG isn't set
+unset RUST_LOG
+
+# -v reports progress
+echo '{"a":1}' | ffs -v --source json --no-output -m "$MNT" 2>"$MSG" &
+PID=$!
+sleep 2
+[ "$(cat $MNT/a)" -eq 1 ] || fail read1
+umount "$MNT" || fail unmount1
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process1
+grep -e "INFO" "$MSG" >/dev/null 2>&1 || fail info
+grep -e "DEBUG" "$MSG" >/dev/null 2>&1 && fail debug
+
+# RUST_LOG takes precedence over -v
+export RUST_LOG=ffs=warn
+echo '{"a":1}' | ffs -v --source json --no-output -m "$MNT" 2>"$MSG" &
+PID=$!
+unset RUST_LOG
+sleep 2
+[ "$(cat $MNT/a)" -eq 1 ] || fail read_env
+umount "$MNT" || fail unmount_env
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process_env
+grep -e "INFO" "$MSG" >/dev/null 2>&1 && fail env
+
+# structured logs
+echo '{"a":1,"a":2}' | ffs --log-format json --source json --no-output -m "$MNT" 2>"$MSG" &
+PID=$!
+sleep 2
+[ "$(cat $MNT/a)" -eq 2 ] || fail read2
+umount "$MNT" || fail unmount2
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process2
+grep -e '^{.*"level":"WARN"' "$MSG" >/dev/null 2>&1 || fail json
+
+rmdir "$MNT" || fail mount
+rm "$MSG"
@@ -0,0 +1,57 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$SRC" "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+SRC=$(mktemp)
+OUT=$(mktemp)
+
+echo '{"a":1}' >"$SRC"
+ffs --watch --source json -m "$MNT" -o "$OUT" "$SRC" &
+PID=$!
+sleep 2
+[ "$(ls $MNT)" = "a" ] || fail initial
+
+echo '{"a":1,"b":2}' >"$SRC"
+sleep 1
+case $(ls "$MNT") in
+    (a*b) ;;
+    (*) fail reload;;
+esac
+[ "$(cat $MNT/b)" -eq 2 ] || fail new
+
+umount "$MNT" || fail unmount
+sleep 1
+
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+[ "$(cat $OUT)" = '{"a":1,"b":2}' ] || fail output
+
+# reloading reuses inodes, so it doesn't run into --max-inodes
+echo '{"a":1,"b":2}' >"$SRC"
+ffs --watch --source json --max-inodes 4 -m "$MNT" --no-output "$SRC" &
+PID=$!
+sleep 2
+for i in 3 4 5 6 7
+do
+    echo "{\"a\":1,\"b\":$i}" >"$SRC"
+    sleep 1
+    [ "$(cat $MNT/b)" -eq $i ] || fail reload$i
+done
+touch "$MNT"/c || fail touch
+umount "$MNT" || fail unmount_max
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process_max
+
+rmdir "$MNT" || fail mount
+rm "$SRC" "$OUT"
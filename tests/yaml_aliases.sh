@@ -0,0 +1,43 @@
+#!/bin/sh
+
+fail() {
+    echo FAILED: $1
+    if [ "$MNT" ]
+    then
+        cd
+        umount "$MNT"
+        rmdir "$MNT"
+        rm "$OUT"
+    fi
+    exit 1
+}
+
+MNT=$(mktemp -d)
+OUT=$(mktemp)
+
+ffs -m "$MNT" -o "$OUT" --target yaml --yaml-aliases link ../yaml/alias.yaml &
+PID=$!
+sleep 2
+[ -L "$MNT"/rbi/0 ] || fail link
+[ "$(readlink "$MNT"/rbi/0)" = "../hr/1" ] || fail readlink
+[ "$(cat "$MNT"/rbi/0)" = "Sammy Sosa" ] || fail read
+[ -L "$MNT"/hr/1 ] && fail anchor
+echo Sammy >"$MNT"/hr/1
+[ "$(cat "$MNT"/rbi/0)" = "Sammy" ] || fail shared
+umount "$MNT" || fail unmount
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process
+
+[ "$(grep -c "^ *- Sammy$" "$OUT")" -eq 2 ] || fail output
+
+ffs -m "$MNT" --no-output ../yaml/alias.yaml &
+PID=$!
+sleep 2
+[ -L "$MNT"/rbi/0 ] && fail expand_link
+[ "$(cat "$MNT"/rbi/0)" = "Sammy Sosa" ] || fail expand_read
+umount "$MNT" || fail unmount2
+sleep 1
+kill -0 $PID >/dev/null 2>&1 && fail process2
+
+rmdir "$MNT" || fail mount
+rm "$OUT"